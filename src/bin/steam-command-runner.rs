@@ -1,28 +1,64 @@
 use clap::Parser;
 use std::process::ExitCode;
+use steam_command_runner::cli::commands::completions::COMPLETE_COMMAND;
 use steam_command_runner::cli::commands::{
-    handle_config, handle_gamescope, handle_install, handle_launch_options, handle_proton,
-    handle_run, handle_search, handle_uninstall,
+    handle_bench, handle_cache, handle_compat_tool, handle_complete, handle_completions, handle_config, handle_doctor, handle_explain, handle_gamescope, handle_games, handle_gpu, handle_heroic, handle_import, handle_info, handle_install, handle_kill,
+    handle_launch_options, handle_logs, handle_modmanager, handle_prefix, handle_proton, handle_run,
+    handle_saves, handle_search, handle_serve, handle_setup, handle_shortcuts, handle_stats, handle_status, handle_uninstall,
 };
 use steam_command_runner::shim;
+use steam_command_runner::steam::refresh_installed_games;
+use steam_command_runner::steam_api::refresh_web_caches;
 use steam_command_runner::{AppError, Cli, Commands};
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use clap::ValueEnum;
+use steam_command_runner::logs::{session_id, JsonFileLayer, LOG_FORMAT_VAR, SESSION_ID_VAR};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
 
 fn main() -> ExitCode {
     // Check if invoked as gamescope shim BEFORE clap parsing
     if shim::is_invoked_as_gamescope() {
         return shim::handle_gamescope_shim();
     }
+    if let Some(wrapper) = shim::invoked_wrapper() {
+        return shim::handle_wrapper_shim(wrapper);
+    }
+
+    // Completion scripts run this on every Tab press; it stays out of --help and the scripts
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some(COMPLETE_COMMAND) {
+        return handle_complete(&args[2..]);
+    }
 
     let cli = Cli::parse();
 
-    // Initialize logging
-    let level = if cli.verbose { Level::DEBUG } else { Level::INFO };
-    FmtSubscriber::builder()
-        .with_max_level(level)
-        .with_target(false)
-        .with_ansi(true)
+    // Exported so every config load, including the shim's in child processes, sees it
+    if let Some(profile) = &cli.profile {
+        std::env::set_var("SCR_PROFILE", profile);
+    }
+    if let Some(format) = cli.log_format {
+        let name = format.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+        std::env::set_var(LOG_FORMAT_VAR, name);
+    }
+    std::env::set_var(SESSION_ID_VAR, session_id());
+    if cli.refresh {
+        refresh_installed_games();
+        refresh_web_caches();
+    }
+
+    // Initialize logging (on stderr, so output like `gamescope args` stays clean); with
+    // logging.format = "json" events also go to the runner log
+    let level = if cli.verbose { LevelFilter::DEBUG } else { LevelFilter::INFO };
+    tracing_subscriber::registry()
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_target(false)
+                .with_ansi(true)
+                .with_filter(level),
+        )
+        .with(JsonFileLayer)
         .init();
 
     let result = run(cli);
@@ -38,33 +74,94 @@ fn main() -> ExitCode {
 
 fn run(cli: Cli) -> Result<ExitCode, AppError> {
     match cli.command {
-        Some(Commands::Run { app_id, command }) => {
-            handle_run(app_id, command, cli.config)?;
+        Some(Commands::Run {
+            app_id,
+            name,
+            heroic,
+            dry_run,
+            command,
+        }) => handle_run(app_id, name, heroic, command, cli.config, dry_run),
+
+        Some(Commands::Explain {
+            app_id,
+            name,
+            command,
+        }) => handle_explain(app_id, name, command, cli.config),
+
+        Some(Commands::Setup) => {
+            handle_setup()?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Doctor) => {
+            handle_doctor(cli.config, cli.json)?;
             Ok(ExitCode::SUCCESS)
         }
 
-        Some(Commands::Install { path }) => {
-            handle_install(path)?;
+        Some(Commands::Install { path, shim }) => {
+            handle_install(path, &shim)?;
             Ok(ExitCode::SUCCESS)
         }
 
-        Some(Commands::Uninstall { path }) => {
-            handle_uninstall(path)?;
+        Some(Commands::Uninstall { path, shim }) => {
+            handle_uninstall(path, &shim)?;
             Ok(ExitCode::SUCCESS)
         }
 
-        Some(Commands::Search { query, limit }) => {
-            handle_search(query, limit)?;
+        Some(Commands::Search {
+            query,
+            limit,
+            store,
+            protondb,
+        }) => {
+            handle_search(query, limit, store, protondb, cli.json)?;
             Ok(ExitCode::SUCCESS)
         }
 
         Some(Commands::Config { action }) => {
-            handle_config(action)?;
+            handle_config(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Proton { action }) => handle_proton(action, cli.json, cli.config),
+
+        Some(Commands::Logs { action }) => {
+            handle_logs(action)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Gpu { action }) => {
+            handle_gpu(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Stats { app_id, recent }) => {
+            handle_stats(app_id, recent, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Status) => {
+            handle_status(cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Kill { app_id }) => {
+            handle_kill(app_id)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Completions { shell }) => {
+            handle_completions(shell)?;
             Ok(ExitCode::SUCCESS)
         }
 
-        Some(Commands::Proton { action }) => {
-            handle_proton(action)?;
+        Some(Commands::Serve { socket }) => {
+            handle_serve(socket)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Bench { action }) => {
+            handle_bench(action, cli.json, cli.config)?;
             Ok(ExitCode::SUCCESS)
         }
 
@@ -73,8 +170,59 @@ fn run(cli: Cli) -> Result<ExitCode, AppError> {
             Ok(ExitCode::SUCCESS)
         }
 
+        Some(Commands::Cache { action }) => {
+            handle_cache(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Games { action }) => {
+            handle_games(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Heroic { action }) => {
+            handle_heroic(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Import { action }) => {
+            handle_import(action)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Info {
+            app_id,
+            name,
+            user_id,
+        }) => {
+            handle_info(app_id, name, user_id, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::CompatTool { action }) => {
+            handle_compat_tool(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
         Some(Commands::LaunchOptions { action }) => {
-            handle_launch_options(action)?;
+            handle_launch_options(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::ModManager { action }) => handle_modmanager(action, cli.config),
+
+        Some(Commands::Prefix { action }) => {
+            handle_prefix(action, cli.json)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Saves { action }) => {
+            handle_saves(action, cli.json, cli.config)?;
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Some(Commands::Shortcuts { action }) => {
+            handle_shortcuts(action)?;
             Ok(ExitCode::SUCCESS)
         }
 
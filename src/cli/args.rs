@@ -1,3 +1,7 @@
+use crate::config::LogFormat;
+use crate::logs::LogTarget;
+use crate::saves::SyncDirection;
+use crate::steam::MergeMode;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -19,6 +23,22 @@ pub struct Cli {
     /// Config file path override
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
+
+    /// Config profile to activate
+    #[arg(long, global = true, env = "SCR_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Print informational output (lists, search results, config) as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Rescan every appmanifest and ask web APIs again instead of using cached results
+    #[arg(long, global = true)]
+    pub refresh: bool,
+
+    /// Line format of the runner and shim logs (overrides logging.format)
+    #[arg(long, global = true, env = "SCR_LOG_FORMAT", value_enum)]
+    pub log_format: Option<LogFormat>,
 }
 
 #[derive(Subcommand)]
@@ -29,23 +49,64 @@ pub enum Commands {
         #[arg(short, long)]
         app_id: Option<u32>,
 
-        /// Command and arguments to run
-        #[arg(trailing_var_arg = true, required = true)]
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Run a game installed by Heroic (store ID or title) instead of a command
+        #[arg(long, conflicts_with_all = ["app_id", "name"])]
+        heroic: Option<String>,
+
+        /// Show the command, environment and wrappers that would be used, without running
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Command and arguments to run (with --heroic, Steam's %command% is replaced by the game's)
+        #[arg(trailing_var_arg = true, required_unless_present = "heroic")]
+        command: Vec<String>,
+    },
+
+    /// Show exactly how a game would be launched, without running anything
+    Explain {
+        /// Steam App ID (optional, for per-game config)
+        #[arg(short, long)]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Game command and arguments (defaults to a %command% placeholder)
+        #[arg(trailing_var_arg = true)]
         command: Vec<String>,
     },
 
+    /// Walk through first-time setup interactively
+    Setup,
+
+    /// Check the Steam, Proton and shim setup and show what config conditions see
+    Doctor,
+
     /// Install the gamescope shim symlink
     Install {
-        /// Custom path for the symlink (default: ~/.local/bin/gamescope)
+        /// Custom path for the symlink (default: ~/.local/bin/<shim>)
         #[arg(short, long)]
         path: Option<PathBuf>,
+
+        /// Program the shim stands in for
+        #[arg(long, default_value = "gamescope", value_parser = ["gamescope", "mangohud", "gamemoderun"])]
+        shim: String,
     },
 
     /// Uninstall the gamescope shim symlink
     Uninstall {
-        /// Path to the symlink (default: ~/.local/bin/gamescope)
+        /// Path to the symlink (default: ~/.local/bin/<shim>)
         #[arg(short, long)]
         path: Option<PathBuf>,
+
+        /// Program the shim stands in for
+        #[arg(long, default_value = "gamescope", value_parser = ["gamescope", "mangohud", "gamemoderun"])]
+        shim: String,
     },
 
     /// Search for a game's Steam App ID
@@ -56,6 +117,14 @@ pub enum Commands {
         /// Maximum number of results
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Search the Steam store instead of installed games and Steam's app cache
+        #[arg(long)]
+        store: bool,
+
+        /// Show each result's ProtonDB rating
+        #[arg(long)]
+        protondb: bool,
     },
 
     /// Configuration management
@@ -70,17 +139,139 @@ pub enum Commands {
         action: ProtonAction,
     },
 
+    /// List GPUs, for picking one with the `gpu` config option
+    Gpu {
+        #[command(subcommand)]
+        action: GpuAction,
+    },
+
+    /// Show, follow or clear steam-command-runner's logs
+    Logs {
+        #[command(subcommand)]
+        action: LogsAction,
+    },
+
+    /// Show playtime per game and recent play sessions
+    Stats {
+        /// Only show sessions of this App ID
+        #[arg(short, long)]
+        app_id: Option<u32>,
+
+        /// Number of recent sessions to show
+        #[arg(short, long, default_value = "10")]
+        recent: usize,
+    },
+
+    /// Show the games running supervised: PID, running time and full command
+    Status,
+
+    /// Stop a game running supervised, as if it had been sent SIGTERM
+    Kill {
+        /// App ID of the game to stop (required when more than one is running)
+        #[arg(short, long)]
+        app_id: Option<u32>,
+    },
+
+    /// Print a shell completion script (completes App IDs and game names too)
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Serve a JSON-RPC API on a unix socket, for frontends such as a Decky plugin
+    Serve {
+        /// Socket path (default: $XDG_RUNTIME_DIR/steam-command-runner/rpc.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+
+    /// Measure how long steam-command-runner takes before handing over to the game
+    Bench {
+        #[command(subcommand)]
+        action: BenchAction,
+    },
+
     /// Gamescope argument management
     Gamescope {
         #[command(subcommand)]
         action: GamescopeAction,
     },
 
+    /// Show or clear shader caches (Steam's shadercache and DXVK/VKD3D state caches)
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Show installed games with their compat tool, config and launch options
+    Games {
+        #[command(subcommand)]
+        action: GamesAction,
+    },
+
+    /// Show everything known about a game: install location, prefix, Proton and launch options
+    Info {
+        /// Steam App ID of the game
+        #[arg(required_unless_present = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Steam user ID whose launch options to show (auto-detected if there is only one)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+    },
+
+    /// Choose the compatibility tool Steam uses for games
+    CompatTool {
+        #[command(subcommand)]
+        action: CompatToolAction,
+    },
+
     /// Manage Steam launch options for games
     LaunchOptions {
         #[command(subcommand)]
         action: LaunchOptionsAction,
     },
+
+    /// Run a mod manager (MO2/Vortex) inside a game's prefix
+    #[command(name = "modmanager")]
+    ModManager {
+        #[command(subcommand)]
+        action: ModManagerAction,
+    },
+
+    /// Manage Proton prefixes (compatdata)
+    Prefix {
+        #[command(subcommand)]
+        action: PrefixAction,
+    },
+
+    /// Back up and restore a game's save files ([saves] in its config)
+    Saves {
+        #[command(subcommand)]
+        action: SavesAction,
+    },
+
+    /// Manage non-Steam game shortcuts
+    Shortcuts {
+        #[command(subcommand)]
+        action: ShortcutsAction,
+    },
+
+    /// Games installed by Heroic Games Launcher (Epic and GOG)
+    Heroic {
+        #[command(subcommand)]
+        action: HeroicAction,
+    },
+
+    /// Turn Lutris or SteamTinkerLaunch settings into game configs
+    Import {
+        #[command(subcommand)]
+        action: ImportAction,
+    },
 }
 
 #[derive(Subcommand)]
@@ -101,11 +292,49 @@ pub enum ConfigAction {
         #[arg(short, long, conflicts_with = "name")]
         app_id: Option<u32>,
 
-        /// Game name to search for (resolves to App ID)
+        /// Game name (resolves to App ID, matching installed games first)
         #[arg(short, long, conflicts_with = "app_id")]
         name: Option<String>,
     },
 
+    /// Set a key in a config file, keeping its comments (e.g. `gamescope.fullscreen true`)
+    Set {
+        /// App ID whose config to change (omit for global config)
+        #[arg(short, long, conflicts_with = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Dotted key, e.g. `gamescope_enabled` or `mangohud.fps_limit`
+        key: String,
+
+        /// Value; parsed as TOML (true, 60, [1, 2]) and otherwise used as a string
+        value: String,
+    },
+
+    /// Print a key from a config file
+    Get {
+        /// App ID whose config to read (omit for global config)
+        #[arg(short, long, conflicts_with = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Dotted key, e.g. `gamescope.width`
+        key: String,
+    },
+
+    /// Check config files for errors, unknown keys and missing Proton versions or programs
+    Validate {
+        /// Only check this game's config (and the global one); default: all game configs
+        #[arg(short, long)]
+        app_id: Option<u32>,
+    },
+
     /// Show configuration file path
     Path {
         /// App ID to show path for (omit for global config)
@@ -122,6 +351,120 @@ pub enum ProtonAction {
         #[arg(short, long)]
         paths: bool,
     },
+
+    /// Download and install a GE-Proton release into compatibilitytools.d
+    Install {
+        /// Release to install (e.g. "GE-Proton9-20", "9-20" or "latest")
+        #[arg(default_value = "latest")]
+        version: String,
+
+        /// Reinstall even if the release is already installed
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Install the latest GE-Proton release if it isn't installed yet
+    Update,
+
+    /// Run a program inside a game's Proton prefix (e.g. winecfg or an installer)
+    Exec {
+        /// Steam App ID of the game whose prefix to use
+        #[arg(short, long, required_unless_present = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Proton version to use instead of the game's
+        #[arg(short, long)]
+        proton: Option<String>,
+
+        /// Program and arguments to run
+        #[arg(trailing_var_arg = true, required = true)]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LogsAction {
+    /// Print a log
+    Show {
+        /// "runner", "shim", or an App ID for the game's latest output log
+        #[arg(default_value = "runner")]
+        log: LogTarget,
+    },
+
+    /// Print the end of a log, optionally following new lines
+    Tail {
+        /// "runner", "shim", or an App ID for the game's latest output log
+        #[arg(default_value = "runner")]
+        log: LogTarget,
+
+        /// Number of lines to show
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+
+        /// Keep printing lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+    },
+
+    /// Show the latest Proton log of a game (written with proton.log = true) and the problems found in it
+    Proton {
+        /// App ID of the game
+        app_id: u32,
+
+        /// Number of lines to show from the end of the log
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+
+        /// Print the whole log
+        #[arg(long, conflicts_with = "lines")]
+        all: bool,
+
+        /// Keep printing lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+    },
+
+    /// Delete a log (with its rotated copy), or every log when none is given
+    Clear {
+        /// "runner", "shim", or an App ID for all of the game's output logs
+        log: Option<LogTarget>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BenchAction {
+    /// Time each step before exec (config load, mode detection, Proton and prefix lookup, gamescope)
+    LaunchOverhead {
+        /// App ID of the game to measure (uses SteamAppId env var if not specified)
+        #[arg(short, long)]
+        app_id: Option<u32>,
+
+        /// Game name to look up instead of an App ID
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// How many times to run the steps; the median is reported
+        #[arg(long, default_value = "20")]
+        iterations: u32,
+
+        /// Fail if the median total goes over this many milliseconds (for CI)
+        #[arg(long)]
+        max_ms: Option<f64>,
+
+        /// Game command and arguments (defaults to a %command% placeholder)
+        #[arg(trailing_var_arg = true)]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GpuAction {
+    /// List the GPUs games can be assigned to
+    List,
 }
 
 #[derive(Subcommand)]
@@ -134,6 +477,10 @@ pub enum GamescopeAction {
         /// App ID to get gamescope args for (uses SteamAppId env var if not specified)
         #[arg(short, long)]
         app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
     },
 
     /// Check if gamescope is enabled for a game
@@ -143,6 +490,82 @@ pub enum GamescopeAction {
         /// App ID to check (uses SteamAppId env var if not specified)
         #[arg(short, long)]
         app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// List shader caches with their sizes, largest first
+    List,
+
+    /// Delete one game's shader caches
+    Clear {
+        /// Steam App ID
+        app_id: u32,
+    },
+
+    /// Delete the shader caches of every game
+    ClearAll {
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GamesAction {
+    /// List installed games
+    List {
+        /// Steam user ID whose launch options to show (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+
+        /// Show each game's ProtonDB rating
+        #[arg(long)]
+        protondb: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CompatToolAction {
+    /// List the compatibility tool chosen for each game
+    List,
+
+    /// Assign a compatibility tool to one or more games
+    Assign {
+        /// Steam App IDs, comma-separated (e.g. 440,570)
+        #[arg(value_delimiter = ',', num_args = 1, required = true)]
+        app_ids: Vec<u32>,
+
+        /// Internal tool name (e.g. "steam-command-runner", "GE-Proton9-20" or "proton_experimental")
+        tool: String,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
+    },
+
+    /// Go back to Steam's default compatibility tool for one or more games
+    Clear {
+        /// Steam App IDs, comma-separated (e.g. 440,570)
+        #[arg(value_delimiter = ',', num_args = 1, required = true)]
+        app_ids: Vec<u32>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
     },
 }
 
@@ -161,13 +584,53 @@ pub enum LaunchOptionsAction {
         /// Steam user ID (auto-detected if not specified)
         #[arg(short, long)]
         user_id: Option<u64>,
+
+        /// Apply to every Steam user on this machine
+        #[arg(long, conflicts_with = "user_id")]
+        all_users: bool,
+
+        /// How to combine with a game's existing launch options
+        #[arg(short, long, value_enum, default_value = "replace")]
+        mode: MergeMode,
+
+        /// Only games Steam runs through Proton
+        #[arg(long, conflicts_with = "only_native")]
+        only_proton: bool,
+
+        /// Only games Steam runs natively
+        #[arg(long)]
+        only_native: bool,
+
+        /// App IDs to leave untouched
+        #[arg(long, num_args = 1..)]
+        exclude: Vec<u32>,
+
+        /// Only games whose name matches this regular expression
+        #[arg(long)]
+        name_regex: Option<String>,
+
+        /// Only games installed in this Steam library folder
+        #[arg(long)]
+        library: Option<PathBuf>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
     },
 
     /// Set launch options for a specific game
     Set {
         /// Steam App ID
-        #[arg(short, long)]
-        app_id: u32,
+        #[arg(short, long, required_unless_present = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
 
         /// Launch options to set (uses default if not specified)
         #[arg(short, long)]
@@ -176,6 +639,18 @@ pub enum LaunchOptionsAction {
         /// Steam user ID (auto-detected if not specified)
         #[arg(short, long)]
         user_id: Option<u64>,
+
+        /// How to combine with a game's existing launch options
+        #[arg(short, long, value_enum, default_value = "replace")]
+        mode: MergeMode,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
     },
 
     /// Clear launch options for all games
@@ -191,13 +666,29 @@ pub enum LaunchOptionsAction {
         /// Steam user ID (auto-detected if not specified)
         #[arg(short, long)]
         user_id: Option<u64>,
+
+        /// Apply to every Steam user on this machine
+        #[arg(long, conflicts_with = "user_id")]
+        all_users: bool,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
     },
 
     /// Show launch options for a specific game
     Show {
         /// Steam App ID
-        #[arg(short, long)]
-        app_id: u32,
+        #[arg(short, long, required_unless_present = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
 
         /// Steam user ID (auto-detected if not specified)
         #[arg(short, long)]
@@ -210,4 +701,300 @@ pub enum LaunchOptionsAction {
         #[arg(short, long)]
         user_id: Option<u64>,
     },
+
+    /// Roll localconfig.vdf back to a backup (the most recent by default)
+    Restore {
+        /// Timestamp of the backup to restore (see --list)
+        #[arg(short, long)]
+        backup: Option<String>,
+
+        /// List available backups instead of restoring
+        #[arg(short, long)]
+        list: bool,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ModManagerAction {
+    /// Start the configured mod manager inside the game's Proton prefix
+    Run {
+        /// Steam App ID of the game the mods are for
+        app_id: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PrefixAction {
+    /// List prefixes, or show one prefix and its backups
+    List {
+        /// App ID to show details for
+        app_id: Option<u32>,
+    },
+
+    /// Show the disk space each prefix uses, largest first
+    Du,
+
+    /// Delete prefixes of games that are no longer installed
+    Prune {
+        /// Only list the prefixes that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Delete without backing each prefix up first
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Delete a prefix so Proton recreates it on the next launch
+    Reset {
+        /// Steam App ID
+        app_id: u32,
+
+        /// Back up the prefix before deleting it
+        #[arg(short, long)]
+        backup: bool,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Archive a prefix to a tar.zst file
+    Backup {
+        /// Steam App ID
+        app_id: u32,
+
+        /// Archive path (default: the data directory, named by date)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Replace a prefix with a backup
+    Restore {
+        /// Steam App ID
+        app_id: u32,
+
+        /// Backup archive to restore (default: the most recent backup)
+        archive: Option<PathBuf>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SavesAction {
+    /// Back up a game's saves to a tar.zst archive
+    Backup {
+        /// Steam App ID
+        app_id: u32,
+    },
+
+    /// Restore a game's saves from a backup (the current saves are backed up first)
+    Restore {
+        /// Steam App ID
+        app_id: u32,
+
+        /// Backup archive to restore (default: the most recent backup)
+        archive: Option<PathBuf>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List a game's save backups, oldest first
+    List {
+        /// Steam App ID
+        app_id: u32,
+    },
+
+    /// Sync a game's saves with its sync_target or sync_command now
+    Sync {
+        /// Steam App ID
+        app_id: u32,
+
+        /// Whether to fetch the saves or send them
+        #[arg(value_enum)]
+        direction: SyncDirection,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShortcutsAction {
+    /// List non-Steam games
+    List {
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+    },
+
+    /// Add a non-Steam game (replaces an existing one with the same name and executable)
+    Add {
+        /// Name shown in the Steam library
+        name: String,
+
+        /// Path to the game executable
+        exe: PathBuf,
+
+        /// Working directory (defaults to the executable's directory)
+        #[arg(long)]
+        start_dir: Option<PathBuf>,
+
+        /// Launch options to set (uses default if not specified)
+        #[arg(short, long)]
+        options: Option<String>,
+
+        /// Compatibility tool to assign (e.g. "GE-Proton9-20" or "proton_experimental")
+        #[arg(long)]
+        compat_tool: Option<String>,
+
+        /// Download library artwork from SteamGridDB (needs steamgriddb.api_key in the config)
+        #[arg(long)]
+        artwork: bool,
+
+        /// Create a backup of shortcuts.vdf before modifying
+        #[arg(short, long, default_value = "true")]
+        backup: bool,
+
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
+    },
+
+    /// Download library artwork for a non-Steam game from SteamGridDB
+    Artwork {
+        /// App ID of the shortcut (see 'shortcuts list')
+        app_id: u32,
+
+        /// Name to search SteamGridDB for (defaults to the shortcut's name)
+        #[arg(long)]
+        search: Option<String>,
+
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+    },
+
+    /// Remove a non-Steam game
+    Remove {
+        /// App ID of the shortcut (see 'shortcuts list')
+        app_id: u32,
+
+        /// Create a backup of shortcuts.vdf before modifying
+        #[arg(short, long, default_value = "true")]
+        backup: bool,
+
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HeroicAction {
+    /// List games installed by Heroic, with the App ID their config goes under
+    List,
+
+    /// Add a Heroic game to Steam, launching through `run --heroic`
+    Add {
+        /// Store ID or title of the game
+        game: String,
+
+        /// Download library artwork from SteamGridDB (needs steamgriddb.api_key in the config)
+        #[arg(long)]
+        artwork: bool,
+
+        /// Create a backup of shortcuts.vdf before modifying
+        #[arg(short, long, default_value = "true")]
+        backup: bool,
+
+        /// Steam user ID (auto-detected if not specified)
+        #[arg(short, long)]
+        user_id: Option<u64>,
+
+        /// If Steam is running, shut it down, apply the changes and start it again
+        #[arg(long)]
+        restart_steam: bool,
+
+        /// Write even if Steam is running (Steam may overwrite the changes)
+        #[arg(long, conflicts_with = "restart_steam")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImportAction {
+    /// Import a Lutris game config (~/.local/share/lutris/games/<game>.yml)
+    Lutris {
+        /// Lutris game config file
+        file: PathBuf,
+
+        /// App ID of the Steam game or shortcut the config is for (read from the file for Steam games)
+        #[arg(short, long, conflicts_with = "name")]
+        app_id: Option<u32>,
+
+        /// Game name (resolves to App ID, matching installed games first)
+        #[arg(short, long, conflicts_with = "app_id")]
+        name: Option<String>,
+
+        /// Print the translated config instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Merge into an existing game config, replacing the keys being imported
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Import SteamTinkerLaunch's settings for a game
+    Stl {
+        /// Steam App ID of the game
+        app_id: u32,
+
+        /// Print the translated config instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Merge into an existing game config, replacing the keys being imported
+        #[arg(short, long)]
+        force: bool,
+    },
 }
@@ -0,0 +1,249 @@
+use super::print_json;
+use super::resolve::resolve_app_id;
+use crate::cli::BenchAction;
+use crate::config::{clear_load_caches, ExecutionMode, MergedConfig};
+use crate::error::AppError;
+use crate::proton::{locate_proton_chain, resolve_compat_data_path};
+use crate::runner::{detect_execution_mode_for_app, gamescope_args_for};
+use crate::shim::gamescope::find_real_gamescope;
+use crate::steam::game_name;
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The steps between being started by Steam and exec()ing the game, in launch order
+const PHASES: [&str; 6] = [
+    "config load",
+    "mode detection",
+    "proton lookup",
+    "prefix lookup",
+    "gamescope args",
+    "gamescope lookup",
+];
+
+/// The steps the gamescope shim runs, as indexes into PHASES
+const SHIM_PHASES: [usize; 3] = [0, 4, 5];
+
+/// Handle the bench command and its subcommands
+pub fn handle_bench(action: BenchAction, json: bool, config_path: Option<PathBuf>) -> Result<(), AppError> {
+    match action {
+        BenchAction::LaunchOverhead {
+            app_id,
+            name,
+            iterations,
+            max_ms,
+            command,
+        } => launch_overhead(app_id, name, iterations, max_ms, command, json, config_path),
+    }
+}
+
+/// Median and slowest time of one step over all runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Timing {
+    median: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    /// Timing of the runs a step ran in; None if it never ran
+    fn of(times: &[Option<Duration>]) -> Option<Self> {
+        let mut times: Vec<Duration> = times.iter().flatten().copied().collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort();
+        // The middle one, or the mean of the middle two
+        let n = times.len();
+        Some(Self {
+            median: (times[(n - 1) / 2] + times[n / 2]) / 2,
+            max: times[n - 1],
+        })
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Run a step, returning its result and how long it took
+fn timed<T>(step: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = step();
+    (result, start.elapsed())
+}
+
+/// Run every step of a launch once, without starting anything
+///
+/// Steps that don't apply to the game (Proton lookups for a native game,
+/// gamescope ones with gamescope off) are None.
+fn run_phases(
+    app_id: Option<u32>,
+    config_path: Option<PathBuf>,
+    game: &str,
+) -> Result<(ExecutionMode, [Option<Duration>; 6]), AppError> {
+    let mut times = [None; 6];
+
+    // Every real launch is a new process, so none may reuse an earlier run's parse
+    clear_load_caches();
+    let (config, elapsed) = timed(|| MergedConfig::load(app_id, config_path));
+    let config = config?;
+    times[0] = Some(elapsed);
+
+    let (mode, elapsed) = timed(|| match config.mode {
+        ExecutionMode::Auto => detect_execution_mode_for_app(game, app_id),
+        mode => mode,
+    });
+    times[1] = Some(elapsed);
+
+    if matches!(mode, ExecutionMode::Proton | ExecutionMode::Umu) {
+        // Only the time matters; a missing Proton fails the real launch, not the benchmark
+        times[2] = Some(timed(|| locate_proton_chain(&config.proton_chain()).ok()).1);
+        times[3] = Some(timed(|| resolve_compat_data_path(config.compat_data_path.as_deref(), app_id, Some(game))).1);
+    }
+
+    if config.gamescope_enabled {
+        times[4] = Some(timed(|| gamescope_args_for(&config).ok()).1);
+        times[5] = Some(timed(find_real_gamescope).1);
+    }
+
+    Ok((mode, times))
+}
+
+/// Time starting this binary and having it exit, as Steam starting the shim or `run` does
+fn process_start() -> Option<Duration> {
+    let exe = std::env::current_exe().ok()?;
+    let (status, elapsed) = timed(|| {
+        Command::new(&exe)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    });
+    status.ok()?.success().then_some(elapsed)
+}
+
+fn launch_overhead(
+    app_id: Option<u32>,
+    name: Option<String>,
+    iterations: u32,
+    max_ms: Option<f64>,
+    command: Vec<String>,
+    json: bool,
+    config_path: Option<PathBuf>,
+) -> Result<(), AppError> {
+    let app_id = resolve_app_id(app_id, name.as_deref())?
+        .or_else(|| std::env::var("SteamAppId").ok().and_then(|s| s.parse().ok()));
+    let game = command.first().map_or("%command%", String::as_str);
+    let iterations = iterations.max(1) as usize;
+
+    let mut runs = Vec::with_capacity(iterations);
+    let mut mode = ExecutionMode::Auto;
+    let mut starts = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let (run_mode, times) = run_phases(app_id, config_path.clone(), game)?;
+        mode = run_mode;
+        runs.push(times);
+        starts.push(process_start());
+    }
+
+    let phases: Vec<Option<Timing>> = (0..PHASES.len())
+        .map(|i| Timing::of(&runs.iter().map(|run| run[i]).collect::<Vec<_>>()))
+        .collect();
+    let sum = |indexes: &[usize]| {
+        let totals: Vec<Option<Duration>> = runs
+            .iter()
+            .map(|run| Some(indexes.iter().filter_map(|&i| run[i]).sum()))
+            .collect();
+        Timing::of(&totals).expect("at least one run")
+    };
+    let all: Vec<usize> = (0..PHASES.len()).collect();
+    let total = sum(&all);
+    let shim = sum(&SHIM_PHASES);
+    let start = Timing::of(&starts);
+
+    let over_budget = max_ms.filter(|&max| millis(total.median) > max);
+
+    if json {
+        let timing = |t: Option<Timing>| {
+            t.map(|t| json!({ "median_ms": millis(t.median), "max_ms": millis(t.max) }))
+        };
+        let steps: Vec<_> = PHASES
+            .iter()
+            .zip(&phases)
+            .map(|(name, t)| json!({ "step": name, "timing": timing(*t) }))
+            .collect();
+        print_json(&json!({
+            "app_id": app_id,
+            "mode": format!("{:?}", mode).to_lowercase(),
+            "iterations": iterations,
+            "steps": steps,
+            "total": timing(Some(total)),
+            "shim": timing(Some(shim)),
+            "process_start": timing(start),
+            "max_ms": max_ms,
+            "within_budget": over_budget.is_none(),
+        }))?;
+    } else {
+        let game = match app_id {
+            Some(id) => match game_name(id) {
+                Some(name) => format!("{} ({})", name, id),
+                None => format!("app {}", id),
+            },
+            None => "a game without an App ID".to_string(),
+        };
+        println!("Launch overhead for {}, {:?} mode, {} runs:", game, mode, iterations);
+        println!();
+        println!("{:<18} {:>10} {:>10}", "Step", "Median", "Max");
+        let row = |name: &str, t: Option<Timing>| match t {
+            Some(t) => println!(
+                "{:<18} {:>7.3} ms {:>7.3} ms",
+                name,
+                millis(t.median),
+                millis(t.max)
+            ),
+            None => println!("{:<18} {:>10}", name, "-"),
+        };
+        for (name, t) in PHASES.iter().zip(&phases) {
+            row(name, *t);
+        }
+        println!();
+        row("total", Some(total));
+        row("shim", Some(shim));
+        row("process start", start);
+        println!();
+        println!("\"shim\" is what the gamescope shim runs: config load and the gamescope steps.");
+        println!("\"process start\" is starting this binary, which comes before every step.");
+    }
+
+    match over_budget {
+        Some(max) => Err(AppError::Bench(format!(
+            "median launch overhead {:.3} ms is over the {} ms budget",
+            millis(total.median),
+            max
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing() {
+        let ms = |n| Some(Duration::from_millis(n));
+        assert_eq!(
+            Timing::of(&[ms(3), None, ms(1), ms(2)]),
+            Some(Timing {
+                median: Duration::from_millis(2),
+                max: Duration::from_millis(3)
+            })
+        );
+        assert_eq!(
+            Timing::of(&[ms(4), ms(1)]).map(|t| t.median),
+            Some(Duration::from_micros(2500))
+        );
+        assert_eq!(Timing::of(&[None, None]), None);
+    }
+}
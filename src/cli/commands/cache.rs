@@ -0,0 +1,98 @@
+use super::{confirm, format_size, print_json};
+use crate::cli::CacheAction;
+use crate::error::AppError;
+use crate::proton::{clear_shader_cache, find_shader_cache, list_shader_caches};
+use crate::steam::{find_installed_games, game_name};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Handle the cache command and its subcommands
+pub fn handle_cache(action: CacheAction, json: bool) -> Result<(), AppError> {
+    match action {
+        CacheAction::List => list_caches(json),
+        CacheAction::Clear { app_id } => clear(app_id),
+        CacheAction::ClearAll { yes } => clear_all(yes),
+    }
+}
+
+/// List each game's shader caches with their size, largest first
+fn list_caches(json: bool) -> Result<(), AppError> {
+    let mut caches = list_shader_caches()?;
+    caches.sort_by_key(|cache| std::cmp::Reverse(cache.size));
+
+    let installed: HashMap<u32, String> = find_installed_games()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|g| (g.app_id, g.name))
+        .collect();
+    let name_of = |app_id: u32| installed.get(&app_id).cloned().or_else(|| game_name(app_id));
+
+    if json {
+        let caches: Vec<_> = caches
+            .iter()
+            .map(|cache| {
+                json!({
+                    "app_id": cache.app_id,
+                    "name": name_of(cache.app_id),
+                    "paths": cache.paths,
+                    "size": cache.size,
+                })
+            })
+            .collect();
+        return print_json(&json!(caches));
+    }
+
+    if caches.is_empty() {
+        println!("No shader caches found.");
+        return Ok(());
+    }
+
+    for cache in &caches {
+        let name = name_of(cache.app_id).unwrap_or_else(|| "(not installed)".to_string());
+        println!("{:>10}  {:<12} {}", format_size(cache.size), cache.app_id, name);
+    }
+    let total: u64 = caches.iter().map(|cache| cache.size).sum();
+    println!();
+    println!("Total: {}", format_size(total));
+
+    Ok(())
+}
+
+/// Delete one game's shader caches
+fn clear(app_id: u32) -> Result<(), AppError> {
+    let Some(cache) = find_shader_cache(app_id)? else {
+        println!("No shader caches found for app {}.", app_id);
+        return Ok(());
+    };
+
+    clear_shader_cache(&cache)?;
+    println!("Cleared {} of shader caches for app {}.", format_size(cache.size), app_id);
+    Ok(())
+}
+
+/// Delete every game's shader caches
+fn clear_all(yes: bool) -> Result<(), AppError> {
+    let caches = list_shader_caches()?;
+    if caches.is_empty() {
+        println!("No shader caches found.");
+        return Ok(());
+    }
+
+    let total: u64 = caches.iter().map(|cache| cache.size).sum();
+    let question = format!(
+        "Delete {} of shader caches for {} games?",
+        format_size(total),
+        caches.len()
+    );
+    if !yes && !confirm(&question)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    for cache in &caches {
+        clear_shader_cache(cache)?;
+    }
+    println!("Cleared {} of shader caches.", format_size(total));
+    println!("Games will rebuild them (with some stutter) the next time they run.");
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use super::launch_options::SteamRunning;
+use super::print_json;
+use crate::cli::CompatToolAction;
+use crate::config::MergedConfig;
+use crate::error::AppError;
+use crate::steam::{
+    assign_compat_tools, create_global_backup, find_installed_games, get_steam_config_path,
+    read_compat_tool_mappings,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Handle the compat-tool command and its subcommands
+pub fn handle_compat_tool(action: CompatToolAction, json: bool) -> Result<(), AppError> {
+    match action {
+        CompatToolAction::List => list_mappings(json),
+
+        CompatToolAction::Assign {
+            app_ids,
+            tool,
+            restart_steam,
+            force,
+        } => update_mappings(&app_ids, Some(&tool), SteamRunning::new(restart_steam, force)),
+
+        CompatToolAction::Clear {
+            app_ids,
+            restart_steam,
+            force,
+        } => update_mappings(&app_ids, None, SteamRunning::new(restart_steam, force)),
+    }
+}
+
+/// List the compatibility tool mapped to each app, with game names where known
+fn list_mappings(json: bool) -> Result<(), AppError> {
+    let mut mappings: Vec<(u32, String)> = read_compat_tool_mappings()?.into_iter().collect();
+    mappings.sort();
+
+    let names: HashMap<u32, String> = find_installed_games()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|game| (game.app_id, game.name))
+        .collect();
+
+    if json {
+        let mappings: Vec<_> = mappings
+            .iter()
+            .map(|(app_id, tool)| {
+                json!({ "app_id": app_id, "name": names.get(app_id), "tool": tool })
+            })
+            .collect();
+        return print_json(&json!(mappings));
+    }
+
+    if mappings.is_empty() {
+        println!("No compatibility tools assigned.");
+        return Ok(());
+    }
+
+    println!("{:<12} {:<28} Game", "App ID", "Tool");
+    for (app_id, tool) in &mappings {
+        // App ID 0 is the tool Steam Play uses for everything else
+        let name = match app_id {
+            0 => "(default for all games)",
+            _ => names.get(app_id).map(String::as_str).unwrap_or("-"),
+        };
+        println!("{:<12} {:<28} {}", app_id, tool, name);
+    }
+
+    Ok(())
+}
+
+/// Assign (or with `None`, clear) a compat tool for several apps, backing up config.vdf first
+fn update_mappings(
+    app_ids: &[u32],
+    tool: Option<&str>,
+    steam: SteamRunning,
+) -> Result<(), AppError> {
+    let config_path = get_steam_config_path()?;
+    let keep = MergedConfig::load(None, None)?.backups.keep;
+
+    steam.apply(|| {
+        create_global_backup(&config_path, keep)?;
+        assign_compat_tools(app_ids, tool)?;
+
+        for app_id in app_ids {
+            match tool {
+                Some(tool) => println!("Assigned {} to app {}", tool, app_id),
+                None => println!("Cleared compatibility tool for app {}", app_id),
+            }
+        }
+        Ok(())
+    })
+}
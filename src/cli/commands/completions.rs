@@ -0,0 +1,152 @@
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::steam::cached_game_names;
+use clap::CommandFactory;
+use clap_complete::{generate, Shell};
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+/// Hidden first argument the completion scripts call to list App IDs and names
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// What `__complete` lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompletionKind {
+    /// App IDs of installed games, each with the game's name after a tab
+    AppId,
+    /// Names of installed games
+    Name,
+}
+
+/// Completes `--app-id` and `--name` from `__complete`, leaving everything else
+/// to the function the generated script registered
+const BASH_GAMES: &str = r#"
+_steam_command_runner_generated="$(complete -p steam-command-runner 2>/dev/null | sed -n 's/.* -F \([^ ]*\) .*/\1/p')"
+_steam_command_runner_games() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        -a|--app-id)
+            mapfile -t COMPREPLY < <("${COMP_WORDS[0]}" __complete app-id "$cur" 2>/dev/null | cut -f1)
+            ;;
+        --name)
+            mapfile -t COMPREPLY < <("${COMP_WORDS[0]}" __complete name "${cur//\\/}" 2>/dev/null \
+                | while IFS= read -r name; do printf '%q\n' "$name"; done)
+            ;;
+        *)
+            "$_steam_command_runner_generated" "$@"
+            ;;
+    esac
+}
+complete -F _steam_command_runner_games -o bashdefault -o default steam-command-runner
+"#;
+
+/// The zsh version; the generated function is `_steam-command-runner` as `#compdef` requires
+const ZSH_GAMES: &str = r#"
+_steam_command_runner_games() {
+    local line
+    local -a values descriptions
+    case "${words[CURRENT-1]}" in
+        -a|--app-id)
+            for line in "${(@f)$(${words[1]} __complete app-id "${words[CURRENT]}" 2>/dev/null)}"; do
+                [[ -n $line ]] || continue
+                values+=("${line%%$'\t'*}")
+                descriptions+=("${line%%$'\t'*}  -- ${line#*$'\t'}")
+            done
+            compadd -U -l -d descriptions -a values
+            ;;
+        --name)
+            for line in "${(@f)$(${words[1]} __complete name "${words[CURRENT]}" 2>/dev/null)}"; do
+                [[ -n $line ]] && values+=("$line")
+            done
+            compadd -U -a values
+            ;;
+        *)
+            _steam-command-runner "$@"
+            ;;
+    esac
+}
+compdef _steam_command_runner_games steam-command-runner
+"#;
+
+const FISH_GAMES: &str = r#"
+complete -c steam-command-runner -s a -l app-id -x -a '(steam-command-runner __complete app-id (commandline -ct) 2>/dev/null)'
+complete -c steam-command-runner -l name -x -a '(steam-command-runner __complete name (commandline -ct) 2>/dev/null)'
+"#;
+
+/// Print a completion script for a shell
+///
+/// The script clap generates covers subcommands and flags; for bash, zsh and
+/// fish it is followed by the hook that asks `__complete` for App IDs and names.
+pub fn handle_completions(shell: Shell) -> Result<(), AppError> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut stdout = io::stdout().lock();
+    generate(shell, &mut command, name, &mut stdout);
+
+    let games = match shell {
+        Shell::Bash => BASH_GAMES,
+        Shell::Zsh => ZSH_GAMES,
+        Shell::Fish => FISH_GAMES,
+        _ => return Ok(()),
+    };
+    stdout.write_all(games.as_bytes())?;
+    Ok(())
+}
+
+/// Print the completion candidates for `__complete <app-id|name> [typed]`, one per line
+///
+/// Called before clap parses anything, so it reads its own arguments.
+pub fn handle_complete(args: &[String]) -> ExitCode {
+    let kind = match args.first().map(String::as_str) {
+        Some("app-id") => CompletionKind::AppId,
+        Some("name") => CompletionKind::Name,
+        _ => {
+            eprintln!("usage: steam-command-runner {} <app-id|name> [typed]", COMPLETE_COMMAND);
+            return ExitCode::FAILURE;
+        }
+    };
+    let prefix = args.get(1).map_or("", String::as_str);
+    for candidate in candidates(kind, prefix, &cached_game_names()) {
+        println!("{}", candidate);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Games matching what has been typed
+///
+/// App IDs match by their digits or by the game's name, so typing part of a
+/// name after `--app-id` completes to the ID. Names match anywhere, ignoring case.
+fn candidates(kind: CompletionKind, prefix: &str, games: &[(u32, String)]) -> Vec<String> {
+    let typed = prefix.to_lowercase();
+    let name_matches = |name: &str| name.to_lowercase().contains(&typed);
+    games
+        .iter()
+        .filter_map(|(app_id, name)| match kind {
+            CompletionKind::AppId => (app_id.to_string().starts_with(prefix) || name_matches(name))
+                .then(|| format!("{}\t{}", app_id, name)),
+            CompletionKind::Name => name_matches(name).then(|| name.clone()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let games = vec![
+            (1145360, "Hades II".to_string()),
+            (1245620, "ELDEN RING".to_string()),
+            (440, "Team Fortress 2".to_string()),
+        ];
+        assert_eq!(
+            candidates(CompletionKind::AppId, "1", &games),
+            ["1145360\tHades II", "1245620\tELDEN RING"]
+        );
+        assert_eq!(candidates(CompletionKind::AppId, "elden", &games), ["1245620\tELDEN RING"]);
+        assert_eq!(candidates(CompletionKind::AppId, "", &games).len(), 3);
+        assert_eq!(candidates(CompletionKind::Name, "ha", &games), ["Hades II"]);
+        assert!(candidates(CompletionKind::Name, "portal", &games).is_empty());
+    }
+}
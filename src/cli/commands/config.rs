@@ -1,24 +1,41 @@
+use super::print_json;
+use super::resolve::resolve_app_id;
 use crate::cli::ConfigAction;
-use crate::config::{get_config_path, get_game_config_path};
+use crate::config::{
+    check_keys, config_fragments, find_game_config_path, get_config_path, get_games_config_dir,
+    get_groups_config_dir, get_key, set_key, validate_config, ConfigError, ConfigKind,
+};
 use crate::error::AppError;
+use serde_json::json;
 use std::fs;
+use std::path::PathBuf;
 use tracing::info;
 
 /// Handle the config command and its subcommands
-pub fn handle_config(action: ConfigAction) -> Result<(), AppError> {
+pub fn handle_config(action: ConfigAction, json: bool) -> Result<(), AppError> {
     match action {
-        ConfigAction::Show { app_id } => show_config(app_id),
+        ConfigAction::Show { app_id } => show_config(app_id, json),
         ConfigAction::Init => init_config(),
         ConfigAction::Edit { app_id, name } => edit_config(app_id, name),
+        ConfigAction::Set {
+            app_id,
+            name,
+            key,
+            value,
+        } => set_config_key(app_id, name, &key, &value),
+        ConfigAction::Get { app_id, name, key } => get_config_key(app_id, name, &key, json),
+        ConfigAction::Validate { app_id } => validate_configs(app_id, json),
         ConfigAction::Path { app_id } => show_path(app_id),
     }
 }
 
-fn show_config(app_id: Option<u32>) -> Result<(), AppError> {
-    let path = match app_id {
-        Some(id) => get_game_config_path(id),
-        None => get_config_path(),
-    };
+fn show_config(app_id: Option<u32>, json: bool) -> Result<(), AppError> {
+    if json {
+        let (path, config) = read_config_file(app_id)?;
+        return print_json(&json!({ "path": path, "exists": config.is_some(), "config": config }));
+    }
+
+    let (path, _) = config_file(app_id);
 
     if path.exists() {
         let content = fs::read_to_string(&path)?;
@@ -32,7 +49,182 @@ fn show_config(app_id: Option<u32>) -> Result<(), AppError> {
     Ok(())
 }
 
-fn init_config() -> Result<(), AppError> {
+/// Config file for an optional game, and which kind of config it holds
+fn config_file(app_id: Option<u32>) -> (PathBuf, ConfigKind) {
+    match app_id {
+        Some(id) => (find_game_config_path(id), ConfigKind::Game),
+        None => (get_config_path(), ConfigKind::Global),
+    }
+}
+
+/// The global config or a game's config file, parsed if it exists
+pub(crate) fn read_config_file(app_id: Option<u32>) -> Result<(PathBuf, Option<toml::Value>), AppError> {
+    let (path, _) = config_file(app_id);
+    if !path.exists() {
+        return Ok((path, None));
+    }
+    let content = fs::read_to_string(&path)?;
+    let value: toml::Value = toml::from_str(&content).map_err(ConfigError::from)?;
+    Ok((path, Some(value)))
+}
+
+/// Set one key without opening an editor, refusing edits that break the config
+fn set_config_key(
+    app_id: Option<u32>,
+    name: Option<String>,
+    key: &str,
+    value: &str,
+) -> Result<(), AppError> {
+    let app_id = resolve_app_id(app_id, name.as_deref())?;
+    let path = write_config_key(app_id, key, value)?;
+    println!("Set {} in {}", key, path.display());
+    Ok(())
+}
+
+/// Set one key in the global config or a game's config, returning the file written
+///
+/// The value is parsed as on the command line: valid TOML values are used as
+/// such, anything else as a string.
+pub(crate) fn write_config_key(app_id: Option<u32>, key: &str, value: &str) -> Result<PathBuf, AppError> {
+    let (path, kind) = config_file(app_id);
+
+    let content = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let updated = set_key(&content, key, value)?;
+
+    // Problems the file already had are left for `config validate` to report
+    let existing = check_keys(&content, kind);
+    let problems = check_keys(&updated, kind);
+    if let Some(problem) = problems
+        .iter()
+        .find(|p| !existing.iter().any(|e| e.message == p.message))
+    {
+        return Err(AppError::InvalidConfig(format!(
+            "setting {} would break {}: {}",
+            key,
+            path.display(),
+            problem.message
+        )));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, updated)?;
+    Ok(path)
+}
+
+/// Print one key's value: strings bare, tables as TOML, anything else as a TOML value
+fn get_config_key(
+    app_id: Option<u32>,
+    name: Option<String>,
+    key: &str,
+    json: bool,
+) -> Result<(), AppError> {
+    let app_id = resolve_app_id(app_id, name.as_deref())?;
+    let value = read_config_key(app_id, key)?;
+
+    if json {
+        return print_json(&serde_json::to_value(&value)?);
+    }
+    match value {
+        toml::Value::String(s) => println!("{}", s),
+        toml::Value::Table(table) => print!("{}", toml::to_string(&table)?),
+        other => println!("{}", other),
+    }
+    Ok(())
+}
+
+/// One key of the global config or a game's config, failing if it isn't set
+pub(crate) fn read_config_key(app_id: Option<u32>, key: &str) -> Result<toml::Value, AppError> {
+    let (path, _) = config_file(app_id);
+    let content = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+
+    get_key(&content, key)?
+        .ok_or_else(|| AppError::InvalidConfig(format!("{} is not set in {}", key, path.display())))
+}
+
+/// Check the global config and game configs, failing if any has a problem
+fn validate_configs(app_id: Option<u32>, json: bool) -> Result<(), AppError> {
+    let mut files: Vec<(PathBuf, ConfigKind)> = vec![(get_config_path(), ConfigKind::Global)];
+    files.extend(config_fragments(&get_config_path()).into_iter().map(|path| (path, ConfigKind::Global)));
+    match app_id {
+        Some(id) => files.push((find_game_config_path(id), ConfigKind::Game)),
+        None => {
+            // Group files use the per-game format
+            for dir in [get_games_config_dir(), get_groups_config_dir()] {
+                let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+                    .map(|entries| {
+                        entries
+                            .flatten()
+                            .map(|entry| entry.path())
+                            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                paths.sort();
+                files.extend(paths.into_iter().map(|path| (path, ConfigKind::Game)));
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+    for (path, kind) in files {
+        if !path.exists() {
+            if kind == ConfigKind::Game {
+                return Err(AppError::InvalidConfig(format!("{} does not exist", path.display())));
+            }
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        results.push((path, validate_config(&content, kind)));
+    }
+    let total: usize = results.iter().map(|(_, problems)| problems.len()).sum();
+
+    if json {
+        let files: Vec<_> = results
+            .iter()
+            .map(|(path, problems)| {
+                let problems: Vec<_> = problems
+                    .iter()
+                    .map(|p| json!({ "line": p.line, "message": p.message }))
+                    .collect();
+                json!({ "path": path, "problems": problems })
+            })
+            .collect();
+        print_json(&json!({ "valid": total == 0, "files": files }))?;
+    } else {
+        for (path, problems) in &results {
+            if problems.is_empty() {
+                println!("{}: OK", path.display());
+            }
+            for problem in problems {
+                match problem.line {
+                    Some(line) => println!("{}:{}: {}", path.display(), line, problem.message),
+                    None => println!("{}: {}", path.display(), problem.message),
+                }
+            }
+        }
+    }
+
+    if total > 0 {
+        return Err(AppError::InvalidConfig(format!(
+            "{} problem(s) in {} file(s)",
+            total,
+            results.iter().filter(|(_, p)| !p.is_empty()).count()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn init_config() -> Result<(), AppError> {
     let path = get_config_path();
 
     if path.exists() {
@@ -47,16 +239,55 @@ fn init_config() -> Result<(), AppError> {
 
     // Write default config with comments
     let template = r#"# Steam Command Runner - Global Configuration
+#
+# String values may use environment variables: ${VAR}, ${VAR:-default}
+# ($${VAR} for a literal ${VAR})
+# *.toml files in config.d/ next to this file are merged on top of it
 
 # Pre-command to prepend to game launches (e.g., gamemoderun, mangohud)
 # pre_command = "gamemoderun"
 
-# Default Proton version (name as shown in Steam, or path)
+# Default Proton version: name as shown in Steam, or a requirement such as
+# "latest-ge" or "GE >=9" resolved against the installed builds at launch
 # default_proton = "Proton 9.0"
 
-# Default execution mode: native | proton | auto
+# Default execution mode: native | proton | umu | wine | auto
 default_mode = "auto"
 
+# Steam installation directory (detected automatically, including Flatpak Steam)
+# steam_root = "~/.var/app/com.valvesoftware.Steam/.local/share/Steam"
+
+# Desktop notifications (notify-send): "off", "errors" (crashes and launch
+# failures) or "all" (also game launches and applied launch options)
+# notifications = "off"
+
+# Warn about missing shared libraries before launching native games
+# library_preflight = true
+
+# Keep running while the game plays (instead of exec'ing into it) so that
+# post_exit hooks run and the game's exit code is passed back to Steam
+# supervise = false
+
+# Relaunch games that crash (non-zero exit or killed by a signal), waiting
+# backoff_secs before the first retry and doubling it each time. Implies supervise.
+# restart_on_crash = { enabled = true, max_retries = 3, backoff_secs = 5 }
+
+# Record play sessions (launch/exit time, exit code, Proton build) for the
+# `stats` command. Implies supervise.
+# track_playtime = false
+
+# Emit io.github.steamcommandrunner.GameStarted/GameStopped signals on the
+# session bus when games start and stop. Implies supervise.
+# dbus_signals = false
+
+# Keep the screen on and the system awake while a game runs, using a
+# systemd-inhibit idle/sleep lock. Implies supervise.
+# inhibit_sleep = false
+
+# Switch power profile while a game runs, restoring the previous one on exit.
+# Uses powerprofilesctl (power-profiles-daemon) or tuned-adm (tuned). Implies supervise.
+# power_profile = "performance"
+
 # Global environment variables applied to all games
 [env]
 # MANGOHUD = "1"
@@ -70,18 +301,137 @@ default_mode = "auto"
 skip_pre_command = true
 # Additional pre_command for Gamescope only
 # pre_command = ""
-# Arguments to pass to gamescope (e.g., "-w 1920 -h 1080 -f")
+# Launch without gamescope when it fails to start (runs the game supervised)
+# fallback = false
+# Typed gamescope options (merged field-by-field with per-game [gamescope] tables)
+# width = 1920
+# height = 1080
+# refresh = 60
+# fsr = false
+# hdr = false          # or "auto" to follow the display
+# fullscreen = true
+# Size the output (-W/-H) and refresh rate to the connected display
+# auto_resolution = false
+# extra_args = "--adaptive-sync"
+# Raw arguments to pass to gamescope, appended after the typed options
 # args = ""
 
-# Pre-launch hook (runs before game starts)
+# Arguments and environment added when Steam runs a wrapper through its shim
+# (steam-command-runner install --shim mangohud|gamemoderun); per game too
+# [wrappers.mangohud]
+# args = "--dlsym"
+# env = { MANGOHUD_DLSYM = "1" }
+
+# Proton tunables for every Proton game (translated to PROTON_* variables)
+[proton]
+# esync = true
+# fsync = true
+# fsr = false
+# fsr_strength = 2
+# nvapi = false
+# log = false
+
+# Game output logs ($XDG_STATE_HOME/steam-command-runner/logs/<appid>-<time>.log)
+[logging]
+# Write each game's stdout/stderr to a log file
+# capture_game_output = false
+# Log files kept per game, and their maximum total size
+# keep_logs = 10
+# max_log_size = "100M"
+# Detail in steam-command-runner's own runner.log and shim.log:
+# off, error, warn, info or debug
+# level = "warn"
+# Size at which runner.log and shim.log are rotated
+# max_file_size = "5M"
+# Line format of runner.log and shim.log: text, or json for log collectors
+# format = "text"
+
+# Backups of Steam's localconfig.vdf and shortcuts.vdf taken before they are modified
+# ($XDG_DATA_HOME/steam-command-runner/steam-backups/<user_id>)
+[backups]
+# Backups kept per file
+# keep = 10
+
+# SteamGridDB API key, for `shortcuts add --artwork`
+# (https://www.steamgriddb.com/profile/preferences/api)
+[steamgriddb]
+# api_key = "${STEAMGRIDDB_API_KEY}"
+
+# MangoHud overlay; a per-game MangoHud config is generated from these settings
+# and mangohud is added to the command (gamescope gets --mangoapp instead)
+[mangohud]
+# enabled = false
+# fps_limit = 144
+# position = "top-left"
+# toggle_hud = "Shift_R+F12"
+# fps = true
+# frametime = true
+# cpu_stats = true
+# gpu_stats = true
+# cpu_temp = false
+# gpu_temp = false
+# ram = false
+# vram = false
+# battery = false
+# Extra MangoHud config lines, written as-is
+# extra = ["background_alpha=0.4"]
+
+# Discord Rich Presence: show the running game (name and elapsed time) in
+# Discord while it runs. Needs an application ID from the Discord developer
+# portal. Implies supervise.
+[discord]
+# enabled = false
+# client_id = "123456789012345678"
+# state = "via steam-command-runner"
+# large_image = "logo"
+
+# cgroup limits, applied by starting games in a transient systemd user scope
+[limits]
+# Always use a scope, and kill anything the game leaves running when it exits
+# scope = false
+# memory_max = "16G"
+# cpu_quota = "800%"
+# io_weight = 100
+
+# Run games in a bubblewrap (bwrap) sandbox that only sees system directories,
+# Steam and the game's own files; home is replaced by a per-game directory
+[sandbox]
+# enabled = false
+# network = true
+# share_pulse = true
+# private_home = true
+# Extra paths the game may write (allow_paths) or only read (read_only_paths)
+# allow_paths = ["~/Games/Mods"]
+# read_only_paths = []
+
+# Wine used by mode = "wine" to run .exe files without Steam or Proton
+[wine]
+# binary = "wine"
+# Default: ~/.local/share/steam-command-runner/wine/<app_id> (or "default")
+# prefix = "~/Games/wine"
+
+# Pre-launch hook (runs before game starts). Commands may use %appid%, %name%,
+# %proton%, %prefix_path% and (after the game exits) %exit_code%
 [hooks]
 # [hooks.pre_launch]
 # command = "/path/to/script.sh"
 # wait = true
+# timeout_secs = 30
 
 # [hooks.post_exit]
 # command = "/path/to/cleanup.sh"
 # wait = false
+
+# Use [[hooks.<phase>]] to run several hooks in order; a hook with
+# continue_on_error = false stops the phase (and the launch, for pre_launch)
+# [[hooks.pre_launch]]
+# command = "powerprofilesctl set performance"
+# continue_on_error = false
+
+# Runs when a supervised game crashes, before any restart
+# [hooks.on_crash]
+# command = "/path/to/report-crash.sh"
+# wait = true
 "#;
     fs::write(&path, template)?;
 
@@ -92,22 +442,10 @@ skip_pre_command = true
 }
 
 fn edit_config(app_id: Option<u32>, name: Option<String>) -> Result<(), AppError> {
-    let app_id = if let Some(name) = name {
-        // Search for the game
-        let results = crate::steam_api::search_games(&name, 1)?;
-        match results.first() {
-            Some((id, found_name)) => {
-                println!("Found game: {} ({})", found_name, id);
-                Some(*id)
-            }
-            None => return Err(AppError::GameNotFound(name)),
-        }
-    } else {
-        app_id
-    };
+    let app_id = resolve_app_id(app_id, name.as_deref())?;
 
     let path = match app_id {
-        Some(id) => get_game_config_path(id),
+        Some(id) => find_game_config_path(id),
         None => get_config_path(),
     };
 
@@ -125,10 +463,18 @@ fn edit_config(app_id: Option<u32>, name: Option<String>) -> Result<(), AppError
 # Display name (for logging)
 # name = "Game Name"
 
-# Execution mode: native | proton | auto
+# Shared settings from [groups.<name>] or groups/<name>.toml (this file overrides them)
+# group = "vr"
+
+# Execution mode: native | proton | umu | wine | auto
 # mode = "proton"
 
-# Specific Proton version (overrides global)
+# Run Steam's command unmodified: no gamescope, env, pre_command or hooks
+# passthrough = true
+
+# Specific Proton version (overrides global), or a requirement like "GE >=9".
+# A list is tried in order, "any" taking whatever is installed:
+# proton = ["GE-Proton9-20", "Proton - Experimental", "any"]
 # proton = "Proton 9.0"
 
 # Pre-command (use "inherit" to include global pre_command)
@@ -140,16 +486,138 @@ fn edit_config(app_id: Option<u32>, name: Option<String>) -> Result<(), AppError
 # Disable gamescope for this game (e.g., for Steam Input compatibility)
 # gamescope_enabled = false
 
+# Directory to start the game in (relative paths are relative to the game executable)
+# working_dir = "bin"
+
+# Proton prefix (compatdata) to use when the game isn't started by Steam
+# compat_data_path = "~/Games/prefixes/this-game"
+
+# Arguments appended to the game command. Like Steam launch options, "%command%"
+# places the whole game command instead; "%args%" is just the game's arguments and
+# "%exe%" its executable, e.g. to launch through a mod loader:
+# launch_args = ["-skipintro"]
+# launch_args = ["/games/mo2/ModOrganizer.exe", "moshortcut://:%exe%", "%args%"]
+
+# Library directories prepended to LD_LIBRARY_PATH (Steam's runtime path is kept)
+# ld_library_path_prepend = ["/opt/openssl-1.1/lib"]
+
+# Extra libraries appended to LD_PRELOAD (after the Steam overlay)
+# ld_preload_extra = ["/usr/lib/libFAudio.so"]
+
+# Winetricks verbs installed into the Proton prefix (once; recorded in compatdata)
+# winetricks = ["vcrun2019", "dotnet48"]
+
+# Kill the game before it exhausts memory (systemd scope, or an rlimit without systemd)
+# memory_max = "12G"
+# More cgroup limits go in a [limits] table (cpu_quota, io_weight, scope)
+# Make the OOM killer prefer the game over the desktop (-1000..1000)
+# oom_score_adj = 500
+
+# Render on a specific GPU: "discrete", "integrated", a PCI address, or an index
+# from 'steam-command-runner gpu list'
+# gpu = "discrete"
+
+# Pin the game to some CPUs (e.g. the P-cores of a hybrid CPU), and adjust its
+# CPU and I/O priority ("idle", "best-effort[:0-7]" or "realtime[:0-7]")
+# cpu_affinity = "0-7"
+# nice = 5
+# ionice = "best-effort:2"
+
+# Run this native game inside the Steam Linux Runtime container, like Steam does
+# when a runtime is picked as its compatibility tool: "off", "sniper" or "soldier"
+# steam_runtime = "sniper"
+
+# Run this game supervised (needed for its post_exit hook)
+# supervise = true
+
+# Relaunch this game if it crashes
+# restart_on_crash = {{ enabled = true, max_retries = 3, backoff_secs = 5 }}
+
+# Record this game's play sessions for `stats`
+# track_playtime = true
+
+# Emit GameStarted/GameStopped D-Bus signals for this game
+# dbus_signals = true
+
+# Keep the system awake while this game runs (e.g. long cutscenes on a controller)
+# inhibit_sleep = true
+
+# Power profile while this game runs ("" keeps the current profile)
+# power_profile = "performance"
+
+# Write this game's stdout/stderr to a log file (overrides [logging] in the global config)
+# capture_game_output = true
+
 # Game-specific environment variables
 [env]
 # MANGOHUD = "1"
 
+# Wine DLL overrides for mods and injectors (n = native, b = builtin, "" = disabled);
+# merged with the global table and passed as WINEDLLOVERRIDES
+# [dll_overrides]
+# dxgi = "n,b"
+# winhttp = "n,b"
+
 # Game-specific hooks
 # [hooks.pre_launch]
 # command = "/path/to/script.sh"
 # wait = true
+
+# Typed gamescope options; each one set here overrides the global value
+# [gamescope]
+# width = 1280
+# height = 800
+# fsr = true
+
+# Proton tunables; each one set here overrides the global value. Use this table
+# instead of the top-level 'proton' key to set the version too.
+# [proton]
+# version = "GE-Proton9-20"
+# wined3d = true
+# large_address_aware = true
+
+# MangoHud for this game (settings override the global [mangohud] field by field)
+# [mangohud]
+# enabled = true
+# fps_limit = 60
+
+# Discord Rich Presence for this game (settings override the global [discord] field by field)
+# [discord]
+# enabled = true
+# large_image = "cover"
+
+# Sandbox this game (settings override the global [sandbox] field by field)
+# [sandbox]
+# enabled = true
+# network = false
+
+# Wine binary and prefix for mode = "wine" (override the global [wine] field by field)
+# [wine]
+# binary = "/opt/wine-staging/bin/wine"
+# prefix = "~/Games/this-game"
+
+# Companion programs started in order before the game and stopped after it exits
+# [[companions]]
+# command = "discord --start-minimized"
+# delay_secs = 2
+
+# Mod manager in this game's prefix (start it with 'modmanager run {}')
+# [modmanager]
+# kind = "mo2"
+# path = "/path/to/MO2/ModOrganizer.exe"
+# Launch the game through MO2's virtual file system
+# route_launch = true
+# executable = "SKSE"
+
+# Save files, for 'saves backup' and 'saves restore' (%prefix_path% is the game's Wine prefix)
+# [saves]
+# paths = ["%prefix_path%/drive_c/users/steamuser/AppData/Roaming/MyGame"]
+# exclude = ["*.log"]
+# backup_on_exit = true
+# Pulled before launch and pushed after exit with rsync (newest file wins)
+# sync_target = "desktop:game-saves"
 "#,
-                id
+                id, id
             );
             fs::write(&path, template)?;
         } else {
@@ -172,7 +640,7 @@ fn edit_config(app_id: Option<u32>, name: Option<String>) -> Result<(), AppError
 
 fn show_path(app_id: Option<u32>) -> Result<(), AppError> {
     let path = match app_id {
-        Some(id) => get_game_config_path(id),
+        Some(id) => find_game_config_path(id),
         None => get_config_path(),
     };
 
@@ -0,0 +1,229 @@
+use super::print_json;
+use crate::config::{get_config_path, hdr_available, validate_config, ConfigKind, MergedConfig};
+use crate::error::AppError;
+use crate::gpu::gpu_vendors;
+use crate::proton::list_proton_versions;
+use crate::runner::installed_flags;
+use crate::shim::{ShimPathReport, WRAPPERS};
+use crate::steam::{get_steam_root, is_steam_running};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+/// How a finding should be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// Working as expected
+    Ok,
+    /// Likely to break launches
+    Warn,
+    /// Worth knowing, nothing to fix
+    Info,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Info => "info",
+        }
+    }
+}
+
+/// One line of the report
+struct Finding {
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Finding {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Handle the doctor command - report on the environment games are launched in
+///
+/// Returns an error when any check warns, so scripts can test the exit status.
+pub fn handle_doctor(config_path: Option<PathBuf>, json: bool) -> Result<(), AppError> {
+    let findings = vec![
+        check_steam(),
+        check_config(config_path.clone()),
+        check_proton(),
+        check_shim(),
+        check_shim_path(),
+        gamescope(),
+        host(config_path),
+        gpus(),
+    ];
+    let warnings = findings.iter().filter(|f| f.status == Status::Warn).count();
+
+    if json {
+        let findings: Vec<_> = findings
+            .iter()
+            .map(|f| json!({ "check": f.name, "status": f.status.label(), "detail": f.detail }))
+            .collect();
+        print_json(&json!({ "ok": warnings == 0, "findings": findings }))?;
+    } else {
+        for finding in &findings {
+            println!("[{:<4}] {:<10} {}", finding.status.label(), finding.name, finding.detail);
+        }
+    }
+
+    if warnings > 0 {
+        return Err(AppError::ExecutionFailed(format!("doctor found {} problem(s)", warnings)));
+    }
+    Ok(())
+}
+
+fn check_steam() -> Finding {
+    match get_steam_root() {
+        Some(root) => {
+            let running = if is_steam_running() { "running" } else { "not running" };
+            Finding::new("steam", Status::Ok, format!("{} ({})", root.display(), running))
+        }
+        None => Finding::new("steam", Status::Warn, "no Steam installation found"),
+    }
+}
+
+fn check_config(config_path: Option<PathBuf>) -> Finding {
+    let path = config_path.unwrap_or_else(get_config_path);
+    if !path.exists() {
+        return Finding::new(
+            "config",
+            Status::Info,
+            format!("{} does not exist; defaults apply", path.display()),
+        );
+    }
+    let problems = fs::read_to_string(&path)
+        .map(|content| validate_config(&content, ConfigKind::Global).len())
+        .unwrap_or(1);
+    if problems == 0 {
+        Finding::new("config", Status::Ok, path.display().to_string())
+    } else {
+        Finding::new(
+            "config",
+            Status::Warn,
+            format!(
+                "{}: {} problem(s); run 'steam-command-runner config validate'",
+                path.display(),
+                problems
+            ),
+        )
+    }
+}
+
+fn check_proton() -> Finding {
+    let versions = list_proton_versions();
+    match versions.len() {
+        0 => Finding::new("proton", Status::Warn, "no Proton versions installed"),
+        n => Finding::new("proton", Status::Ok, format!("{} version(s) installed", n)),
+    }
+}
+
+/// The gamescope shim, if installed in its default place
+fn installed_shim() -> Option<PathBuf> {
+    let shim = dirs::home_dir()?.join(".local/bin/gamescope");
+    fs::read_link(&shim)
+        .is_ok_and(|target| target.to_string_lossy().contains("steam-command-runner"))
+        .then_some(shim)
+}
+
+fn check_shim() -> Finding {
+    let Some(shim) = dirs::home_dir().map(|home| home.join(".local/bin/gamescope")) else {
+        return Finding::new("shim", Status::Info, "no home directory");
+    };
+    // Moving or reinstalling the binary (e.g. `cargo install` to a new path) leaves shims dangling
+    let dangling: Vec<String> = std::iter::once("gamescope")
+        .chain(WRAPPERS.iter().copied())
+        .filter_map(|name| {
+            let path = shim.with_file_name(name);
+            let target = fs::read_link(&path).ok()?;
+            let ours = target.to_string_lossy().contains("steam-command-runner");
+            (ours && !path.exists()).then(|| {
+                let flag = if name == "gamescope" { String::new() } else { format!(" --shim {}", name) };
+                format!(
+                    "{} points to {}, which no longer exists; run 'steam-command-runner install{}'",
+                    path.display(),
+                    target.display(),
+                    flag
+                )
+            })
+        })
+        .collect();
+    if !dangling.is_empty() {
+        return Finding::new("shim", Status::Warn, dangling.join("; "));
+    }
+
+    if installed_shim().is_some() {
+        Finding::new("shim", Status::Ok, format!("installed at {}", shim.display()))
+    } else {
+        Finding::new(
+            "shim",
+            Status::Info,
+            format!("not installed at {}; run 'steam-command-runner install'", shim.display()),
+        )
+    }
+}
+
+/// Whether `gamescope` without a path runs the shim everywhere Steam may get its PATH from
+fn check_shim_path() -> Finding {
+    let Some(shim) = installed_shim() else {
+        return Finding::new("shim-path", Status::Info, "shim not installed");
+    };
+    let report = ShimPathReport::check(&shim);
+    if report.problems.is_empty() {
+        return Finding::new(
+            "shim-path",
+            Status::Ok,
+            format!("found first in PATH of {}", report.ok.join(", ")),
+        );
+    }
+    // Launch options with the shim's full path work regardless; a bare `gamescope` only runs it when first
+    let status = if report.shadowed() { Status::Warn } else { Status::Info };
+    Finding::new("shim-path", status, report.describe(&shim).join("; "))
+}
+
+fn gamescope() -> Finding {
+    match installed_flags() {
+        Some(flags) => Finding::new(
+            "gamescope",
+            Status::Info,
+            flags.version.clone().unwrap_or_else(|| "installed (unknown version)".to_string()),
+        ),
+        None => Finding::new("gamescope", Status::Info, "not installed"),
+    }
+}
+
+/// What config conditions and defaults will see about this machine
+fn host(config_path: Option<PathBuf>) -> Finding {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    match MergedConfig::load(None, config_path) {
+        Ok(config) => Finding::new(
+            "host",
+            Status::Info,
+            format!(
+                "Steam Deck: {}, gamescope session: {}, HDR: {}",
+                yes_no(config.is_steam_deck),
+                yes_no(config.is_gamescope_session),
+                yes_no(hdr_available())
+            ),
+        ),
+        Err(e) => Finding::new("host", Status::Warn, format!("could not load config: {}", e)),
+    }
+}
+
+fn gpus() -> Finding {
+    let vendors = gpu_vendors();
+    if vendors.is_empty() {
+        Finding::new("gpu", Status::Info, "no GPUs found in /sys/class/drm")
+    } else {
+        Finding::new("gpu", Status::Info, vendors.join(", "))
+    }
+}
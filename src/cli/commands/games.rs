@@ -0,0 +1,150 @@
+use super::launch_options::resolve_user_id;
+use super::{format_size, print_json};
+use crate::cli::GamesAction;
+use crate::config::find_game_config_path;
+use crate::error::AppError;
+use crate::steam::{
+    add_platform_info, find_installed_games, get_launch_options, get_localconfig_path,
+    is_our_launch_options, read_localconfig, InstalledGame,
+};
+use crate::steam_api::protondb_tiers;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Handle the games command and its subcommands
+pub fn handle_games(action: GamesAction, json: bool) -> Result<(), AppError> {
+    match action {
+        GamesAction::List { user_id, protondb } => list_games(user_id, protondb, json),
+    }
+}
+
+/// An installed game with what we've configured for it
+pub(crate) struct GameRow {
+    pub game: InstalledGame,
+    /// Its game config file, if there is one
+    pub config: Option<PathBuf>,
+    pub launch_options: Option<String>,
+    pub protondb: Option<String>,
+}
+
+impl GameRow {
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let options = self.launch_options.as_deref();
+        json!({
+            "app_id": self.game.app_id,
+            "name": self.game.name,
+            "library": self.game.library,
+            "install_path": self.game.install_path(),
+            "size_on_disk": self.game.size_on_disk,
+            "compat_tool": self.game.compat_tool,
+            "config": self.config,
+            "launch_options": options,
+            "ours": options.is_some_and(is_our_launch_options),
+            "protondb": self.protondb,
+        })
+    }
+}
+
+/// Every installed game with its config, launch options and optionally ProtonDB tier
+pub(crate) fn game_rows(user_id: Option<u64>, protondb: bool) -> Result<Vec<GameRow>, AppError> {
+    let mut games = find_installed_games()?;
+    add_platform_info(&mut games);
+
+    // Launch options are per Steam user; without one the rest is still worth listing
+    let localconfig = resolve_user_id(user_id)
+        .and_then(get_localconfig_path)
+        .and_then(|path| read_localconfig(&path));
+    let localconfig = match localconfig {
+        Ok(config) => Some(config),
+        Err(e) if user_id.is_none() => {
+            warn!("Not showing launch options: {}", e);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut tiers = if protondb {
+        let app_ids: Vec<u32> = games.iter().map(|game| game.app_id).collect();
+        protondb_tiers(&app_ids)
+    } else {
+        HashMap::new()
+    };
+
+    Ok(games
+        .into_iter()
+        .map(|game| GameRow {
+            config: Some(find_game_config_path(game.app_id)).filter(|path| path.exists()),
+            launch_options: localconfig
+                .as_ref()
+                .and_then(|localconfig| get_launch_options(localconfig, game.app_id)),
+            protondb: tiers.remove(&game.app_id),
+            game,
+        })
+        .collect())
+}
+
+/// List installed games with where they live, how they run and what we've configured
+fn list_games(user_id: Option<u64>, protondb: bool, json: bool) -> Result<(), AppError> {
+    let rows = game_rows(user_id, protondb)?;
+
+    if json {
+        let games: Vec<_> = rows.iter().map(GameRow::to_json).collect();
+        return print_json(&json!(games));
+    }
+
+    if rows.is_empty() {
+        println!("No installed games found.");
+        return Ok(());
+    }
+
+    let protondb_column = |tier: &str| {
+        if protondb {
+            format!("{:<9} ", tier)
+        } else {
+            String::new()
+        }
+    };
+    println!(
+        "{:<10} {:<32} {:>10}  {:<24} {:<6} {}Launch options",
+        "App ID",
+        "Name",
+        "Size",
+        "Compat tool",
+        "Config",
+        protondb_column("ProtonDB")
+    );
+    for row in &rows {
+        let game = &row.game;
+        let launch = match row.launch_options.as_deref() {
+            Some(options) if is_our_launch_options(options) => "ours",
+            Some(_) => "other",
+            None => "-",
+        };
+        println!(
+            "{:<10} {:<32} {:>10}  {:<24} {:<6} {}{}",
+            game.app_id,
+            truncate(&game.name, 32),
+            format_size(game.size_on_disk),
+            game.compat_tool.as_deref().unwrap_or("-"),
+            if row.config.is_some() { "yes" } else { "-" },
+            protondb_column(row.protondb.as_deref().unwrap_or("-")),
+            launch
+        );
+    }
+    println!();
+    println!("Use --json for library paths, config files and the full launch options.");
+
+    Ok(())
+}
+
+/// Shorten a name to fit a column, marking the cut with "…"
+fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let mut short: String = name.chars().take(width - 1).collect();
+    short.push('…');
+    short
+}
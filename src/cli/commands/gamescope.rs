@@ -1,12 +1,18 @@
+use super::resolve::resolve_app_id;
 use crate::cli::GamescopeAction;
 use crate::config::MergedConfig;
 use crate::error::AppError;
+use crate::runner::gamescope_args_for;
 
 /// Handle the gamescope command and its subcommands
 pub fn handle_gamescope(action: GamescopeAction) -> Result<(), AppError> {
     match action {
-        GamescopeAction::Args { app_id } => print_gamescope_args(app_id),
-        GamescopeAction::Enabled { app_id } => print_gamescope_enabled(app_id),
+        GamescopeAction::Args { app_id, name } => {
+            print_gamescope_args(resolve_app_id(app_id, name.as_deref())?)
+        }
+        GamescopeAction::Enabled { app_id, name } => {
+            print_gamescope_enabled(resolve_app_id(app_id, name.as_deref())?)
+        }
     }
 }
 
@@ -30,8 +36,8 @@ fn print_gamescope_args(app_id: Option<u32>) -> Result<(), AppError> {
     }
 
     // Output the gamescope args (just the args, no newline for clean substitution)
-    if let Some(args) = config.gamescope_args {
-        print!("{}", args);
+    if let Some(args) = gamescope_args_for(&config)? {
+        print!("{}", args.join(" "));
     }
 
     Ok(())
@@ -49,7 +55,7 @@ fn print_gamescope_enabled(app_id: Option<u32>) -> Result<(), AppError> {
     let config = MergedConfig::load(app_id, None)?;
 
     // Output true/false
-    if config.gamescope_enabled && config.gamescope_args.is_some() {
+    if config.gamescope_enabled && gamescope_args_for(&config)?.is_some() {
         println!("true");
     } else {
         println!("false");
@@ -0,0 +1,63 @@
+use super::print_json;
+use crate::cli::GpuAction;
+use crate::error::AppError;
+use crate::gpu::{list_gpus, GpuKind};
+use serde_json::json;
+
+/// Handle the gpu command and its subcommands
+pub fn handle_gpu(action: GpuAction, json: bool) -> Result<(), AppError> {
+    match action {
+        GpuAction::List => list(json),
+    }
+}
+
+fn kind_name(kind: GpuKind) -> &'static str {
+    match kind {
+        GpuKind::Integrated => "integrated",
+        GpuKind::Discrete => "discrete",
+        GpuKind::Unknown => "unknown",
+    }
+}
+
+fn list(json: bool) -> Result<(), AppError> {
+    let gpus = list_gpus();
+    if json {
+        let gpus: Vec<_> = gpus
+            .iter()
+            .map(|gpu| {
+                json!({
+                    "index": gpu.index,
+                    "name": gpu.name,
+                    "kind": kind_name(gpu.kind),
+                    "pci_slot": gpu.pci_slot,
+                    "driver": gpu.driver,
+                })
+            })
+            .collect();
+        return print_json(&json!(gpus));
+    }
+
+    if gpus.is_empty() {
+        println!("No GPUs found in /sys/class/drm.");
+        return Ok(());
+    }
+
+    println!("GPUs:\n");
+    for gpu in &gpus {
+        println!("  {}  {}", gpu.index, gpu.display_name());
+        println!(
+            "     {}, {}, driver {}",
+            kind_name(gpu.kind),
+            gpu.pci_slot,
+            gpu.driver.as_deref().unwrap_or("none")
+        );
+    }
+
+    println!();
+    println!("Select one in a game's config with gpu = \"discrete\", \"integrated\", a PCI");
+    println!("address, or an index from this list (e.g. gpu = 1).");
+    if gpus.iter().all(|g| g.name.is_none()) {
+        println!("Install vulkaninfo (vulkan-tools) for device names.");
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use super::launch_options::{resolve_user_id, SteamRunning};
+use super::print_json;
+use super::shortcuts::{add_shortcut, fetch_artwork};
+use crate::cli::HeroicAction;
+use crate::config::find_game_config_path;
+use crate::error::AppError;
+use crate::heroic::{find_heroic_game, find_heroic_games};
+use serde_json::json;
+use tracing::warn;
+
+/// Handle the heroic command and its subcommands
+pub fn handle_heroic(action: HeroicAction, json: bool) -> Result<(), AppError> {
+    match action {
+        HeroicAction::List => list_games(json),
+        HeroicAction::Add {
+            game,
+            artwork,
+            backup,
+            user_id,
+            restart_steam,
+            force,
+        } => add_game(&game, artwork, backup, user_id, SteamRunning::new(restart_steam, force)),
+    }
+}
+
+/// List Heroic's games with the App ID their config goes under
+fn list_games(json: bool) -> Result<(), AppError> {
+    let games = find_heroic_games()?;
+
+    if json {
+        let games: Vec<_> = games
+            .iter()
+            .map(|game| {
+                json!({
+                    "store": game.store.label(),
+                    "app_name": game.app_name,
+                    "title": game.title,
+                    "platform": game.platform,
+                    "app_id": game.app_id(),
+                    "install_path": game.install_path,
+                    "command": game.command(),
+                    "prefix": game.prefix.as_ref().map(|prefix| &prefix.path),
+                })
+            })
+            .collect();
+        return print_json(&json!(games));
+    }
+
+    if games.is_empty() {
+        println!("No games installed by Heroic found.");
+        return Ok(());
+    }
+
+    println!("{:<12} {:<6} {:<8} {:<32} Store ID", "App ID", "Store", "Platform", "Title");
+    for game in &games {
+        println!(
+            "{:<12} {:<6} {:<8} {:<32} {}",
+            game.app_id(),
+            game.store.label(),
+            game.platform,
+            game.title,
+            game.app_name
+        );
+    }
+    println!();
+    println!("Per-game settings go in games/<app_id>.toml; launch with: steam-command-runner run --heroic <title>");
+    Ok(())
+}
+
+/// Add a Heroic game to Steam as a shortcut that starts it through `run --heroic`
+///
+/// The shortcut points at the game's executable, so Steam gives it the same App
+/// ID `run --heroic` uses; the launch options hand the launch over to us.
+fn add_game(
+    query: &str,
+    artwork: bool,
+    backup: bool,
+    user_id: Option<u64>,
+    steam: SteamRunning,
+) -> Result<(), AppError> {
+    let game = find_heroic_game(query)?;
+    let user_id = resolve_user_id(user_id)?;
+
+    let runner = std::env::current_exe()?;
+    let options = format!(
+        "{} run --heroic {} -- %command%",
+        shlex::try_quote(&runner.to_string_lossy())
+            .map_err(|e| AppError::Heroic(e.to_string()))?,
+        shlex::try_quote(&game.app_name).map_err(|e| AppError::Heroic(e.to_string()))?
+    );
+    steam.apply(|| {
+        let app_id = add_shortcut(
+            &game.title,
+            game.exe.clone(),
+            Some(game.working_dir.clone()),
+            Some(options),
+            None,
+            backup,
+            user_id,
+        )?;
+        println!("  Config: {}", find_game_config_path(app_id).display());
+
+        if artwork {
+            if let Err(e) = fetch_artwork(user_id, app_id, &game.title) {
+                warn!("Could not add artwork: {}", e);
+            }
+        }
+        Ok(())
+    })
+}
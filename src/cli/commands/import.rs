@@ -0,0 +1,117 @@
+use super::resolve::resolve_app_id;
+use crate::cli::ImportAction;
+use crate::config::{check_keys, find_game_config_path, set_key, ConfigKind};
+use crate::error::AppError;
+use crate::import::{import_lutris, import_stl, stl_config_path, ImportedConfig};
+use std::fs;
+
+/// Handle the import command and its subcommands
+pub fn handle_import(action: ImportAction) -> Result<(), AppError> {
+    match action {
+        ImportAction::Lutris {
+            file,
+            app_id,
+            name,
+            dry_run,
+            force,
+        } => {
+            let mut imported = import_lutris(&file)?;
+            if let Some(app_id) = resolve_app_id(app_id, name.as_deref())? {
+                imported.app_id = Some(app_id);
+            }
+            write_config(imported, &file.display().to_string(), dry_run, force)
+        }
+        ImportAction::Stl {
+            app_id,
+            dry_run,
+            force,
+        } => {
+            let imported = import_stl(app_id)?;
+            write_config(imported, &stl_config_path(app_id).display().to_string(), dry_run, force)
+        }
+    }
+}
+
+/// Write an imported config as the game's config, or merge it into the existing one
+fn write_config(imported: ImportedConfig, source: &str, dry_run: bool, force: bool) -> Result<(), AppError> {
+    if imported.is_empty() {
+        return Err(AppError::Import(format!("no settings to import from {}", source)));
+    }
+    let content = format!("# Imported from {}\n{}", source, toml::to_string(&imported.config)?);
+
+    if dry_run {
+        print!("{}", content);
+        print_leftovers(&imported);
+        return Ok(());
+    }
+
+    let app_id = imported.app_id.ok_or_else(|| {
+        AppError::Import(format!(
+            "{} doesn't say which game it is for; pass --app-id or --name",
+            source
+        ))
+    })?;
+    let path = find_game_config_path(app_id);
+    let (existing, updated) = if path.exists() {
+        if !force {
+            return Err(AppError::Import(format!(
+                "{} already exists; pass --force to merge the imported settings into it, \
+                 or --dry-run to see them",
+                path.display()
+            )));
+        }
+        let existing = fs::read_to_string(&path)?;
+        let mut updated = existing.clone();
+        for (key, value) in imported.dotted_keys() {
+            updated = set_key(&updated, &key, &value)?;
+        }
+        (existing, updated)
+    } else {
+        (String::new(), content)
+    };
+
+    // Problems the file already had are left for `config validate` to report
+    let known = check_keys(&existing, ConfigKind::Game);
+    if let Some(problem) = check_keys(&updated, ConfigKind::Game)
+        .iter()
+        .find(|p| !known.iter().any(|k| k.message == p.message))
+    {
+        return Err(AppError::Import(format!(
+            "the translated config is invalid: {}",
+            problem.message
+        )));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, updated)?;
+    println!("Imported {} into {}", source, path.display());
+    print_leftovers(&imported);
+    Ok(())
+}
+
+/// List what wasn't carried over and what is left to do by hand
+fn print_leftovers(imported: &ImportedConfig) {
+    if !imported.skipped.is_empty() {
+        println!();
+        println!("Not imported (no equivalent here):");
+        for setting in &imported.skipped {
+            println!("  {}", setting);
+        }
+    }
+    let add_shortcut = match (&imported.exe, imported.app_id) {
+        (Some(exe), None) => Some(format!(
+            "Add the game to Steam with: steam-command-runner shortcuts add <name> {}",
+            shlex::try_quote(exe).map_or(exe.clone(), |quoted| quoted.into_owned())
+        )),
+        _ => None,
+    };
+    let notes: Vec<&String> = imported.notes.iter().chain(add_shortcut.as_ref()).collect();
+    if !notes.is_empty() {
+        println!();
+        for note in notes {
+            println!("Note: {}", note);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::resolve::resolve_app_id;
+use super::{format_size, print_json};
+use crate::error::AppError;
+use crate::playtime::format_time;
+use crate::steam::{game_info, is_our_launch_options};
+use serde_json::json;
+use std::path::Path;
+
+/// Handle the info command
+pub fn handle_info(
+    app_id: Option<u32>,
+    name: Option<String>,
+    user_id: Option<u64>,
+    json: bool,
+) -> Result<(), AppError> {
+    let app_id = resolve_app_id(app_id, name.as_deref())?
+        .ok_or_else(|| AppError::GameNotFound("no game given".to_string()))?;
+    let info = game_info(app_id, user_id)?;
+    let ours = info.launch_options.as_deref().is_some_and(is_our_launch_options);
+
+    if json {
+        let mut value = serde_json::to_value(&info)?;
+        value["ours"] = json!(ours);
+        return print_json(&value);
+    }
+
+    let path = |path: &Option<_>| {
+        path.as_deref()
+            .map(Path::display)
+            .map_or("-".to_string(), |p| p.to_string())
+    };
+    let or_dash = |value: Option<&str>| value.unwrap_or("-").to_string();
+
+    println!("App ID:          {}", info.app_id);
+    println!("Name:            {}", or_dash(info.name.as_deref()));
+    println!("Library:         {}", path(&info.library));
+    println!("Install path:    {}", path(&info.install_path));
+    println!(
+        "Size on disk:    {}",
+        info.size_on_disk.map_or("-".to_string(), format_size)
+    );
+    println!("Compat tool:     {}", or_dash(info.compat_tool.as_deref()));
+    println!("Prefix:          {}", path(&info.compat_data));
+    println!("Config file:     {}", path(&info.config_file));
+    println!(
+        "Proton:          {}",
+        if info.proton.is_empty() {
+            "(auto-detect)".to_string()
+        } else {
+            info.proton.join(", then ")
+        }
+    );
+
+    match info.user_id {
+        Some(user_id) => {
+            let launch = match info.launch_options.as_deref() {
+                Some(options) if ours => format!("{} (ours)", options),
+                Some(options) => options.to_string(),
+                None => "-".to_string(),
+            };
+            println!("Steam user:      {}", user_id);
+            println!("Launch options:  {}", launch);
+            println!(
+                "Last played:     {}",
+                info.last_played.map_or("never".to_string(), format_time)
+            );
+        }
+        None => println!("Launch options:  - (pass --user-id to pick a Steam user)"),
+    }
+
+    Ok(())
+}
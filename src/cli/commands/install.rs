@@ -1,17 +1,21 @@
 use crate::error::AppError;
+use crate::shim::ShimPathReport;
 use std::fs;
 use std::os::unix::fs::symlink;
 use std::path::PathBuf;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-/// Install the gamescope shim symlink
-pub fn handle_install(path: Option<PathBuf>) -> Result<(), AppError> {
-    // Default to ~/.local/bin/gamescope
-    let target_path = path.unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".local/bin/gamescope")
-    });
+/// Default location of a shim symlink: ~/.local/bin/<shim>
+fn default_shim_path(shim: &str) -> PathBuf {
+    dirs::home_dir()
+        .expect("Could not find home directory")
+        .join(".local/bin")
+        .join(shim)
+}
+
+/// Install a shim symlink (gamescope, or a wrapper like mangohud)
+pub fn handle_install(path: Option<PathBuf>, shim: &str) -> Result<(), AppError> {
+    let target_path = path.unwrap_or_else(|| default_shim_path(shim));
 
     // Get current executable path
     let self_path = std::env::current_exe()?;
@@ -38,26 +42,30 @@ pub fn handle_install(path: Option<PathBuf>) -> Result<(), AppError> {
     );
     symlink(&self_path, &target_path)?;
 
-    info!("Installed gamescope shim to: {}", target_path.display());
-    println!("Installed gamescope shim to: {}", target_path.display());
-    println!();
-    println!("Make sure {} is in your PATH before /usr/bin", target_path.parent().unwrap().display());
-    println!("You can add this to your shell profile:");
-    println!("  export PATH=\"{}:$PATH\"", target_path.parent().unwrap().display());
+    info!("Installed {} shim to: {}", shim, target_path.display());
+    println!("Installed {} shim to: {}", shim, target_path.display());
+
+    // Launch options may call the shim by name, so check it isn't shadowed
+    let report = ShimPathReport::check(&target_path);
+    if !report.problems.is_empty() {
+        println!();
+        for line in report.describe(&target_path) {
+            warn!("{}", line);
+        }
+        println!("Make sure {} is in your PATH before /usr/bin", target_path.parent().unwrap().display());
+        println!("You can add this to your shell profile:");
+        println!("  export PATH=\"{}:$PATH\"", target_path.parent().unwrap().display());
+    }
 
     Ok(())
 }
 
-/// Uninstall the gamescope shim symlink
-pub fn handle_uninstall(path: Option<PathBuf>) -> Result<(), AppError> {
-    let target_path = path.unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".local/bin/gamescope")
-    });
+/// Uninstall a shim symlink
+pub fn handle_uninstall(path: Option<PathBuf>, shim: &str) -> Result<(), AppError> {
+    let target_path = path.unwrap_or_else(|| default_shim_path(shim));
 
     if !target_path.exists() && !target_path.is_symlink() {
-        println!("Gamescope shim not installed at: {}", target_path.display());
+        println!("{} shim not installed at: {}", shim, target_path.display());
         return Ok(());
     }
 
@@ -75,7 +83,7 @@ pub fn handle_uninstall(path: Option<PathBuf>) -> Result<(), AppError> {
     }
 
     fs::remove_file(&target_path)?;
-    println!("Removed gamescope shim: {}", target_path.display());
+    println!("Removed {} shim: {}", shim, target_path.display());
 
     Ok(())
 }
@@ -1,48 +1,140 @@
+use super::resolve::resolve_app_id;
+use super::{confirm, print_json};
 use crate::cli::LaunchOptionsAction;
+use crate::config::MergedConfig;
 use crate::error::AppError;
+use crate::notify::{notify, Urgency};
 use crate::steam::{
-    find_installed_games, find_user_ids, generate_default_launch_options, get_launch_options,
-    get_localconfig_path, is_our_launch_options, read_localconfig, set_launch_options,
-    write_localconfig,
+    add_platform_info, backup_timestamp, backups, diff_launch_options, find_backup,
+    default_user_id, find_installed_games, find_user_ids, generate_default_launch_options, get_launch_options,
+    get_localconfig_path, is_our_launch_options, is_steam_running, list_backups,
+    merge_launch_options, read_localconfig, set_launch_options, shutdown_steam, start_steam,
+    write_localconfig, AppInfoCache, InstalledGame, MergeMode,
 };
+use regex::Regex;
+use serde_json::json;
 use std::fs;
-use tracing::{debug, info};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
 
 /// Handle the launch-options command and its subcommands
-pub fn handle_launch_options(action: LaunchOptionsAction) -> Result<(), AppError> {
+pub fn handle_launch_options(action: LaunchOptionsAction, json: bool) -> Result<(), AppError> {
     match action {
         LaunchOptionsAction::SetAll {
             backup,
             dry_run,
             user_id,
-        } => set_all(backup, dry_run, user_id),
+            all_users,
+            only_proton,
+            only_native,
+            exclude,
+            name_regex,
+            library,
+            mode,
+            restart_steam,
+            force,
+        } => {
+            let filter = GameFilter {
+                only_proton,
+                only_native,
+                exclude,
+                name_regex: name_regex.as_deref().map(Regex::new).transpose()?,
+                library,
+            };
+            set_all(
+                backup,
+                dry_run,
+                resolve_user_ids(user_id, all_users)?,
+                &filter,
+                mode,
+                SteamRunning::new(restart_steam, force),
+            )
+        }
 
         LaunchOptionsAction::Set {
             app_id,
+            name,
             options,
             user_id,
-        } => set_single(app_id, options, user_id),
+            mode,
+            restart_steam,
+            force,
+        } => {
+            let app_id = resolve_game(app_id, name)?;
+            SteamRunning::new(restart_steam, force).apply(|| {
+                let launch_options = set_single(app_id, options, user_id, mode)?;
+                println!("Set launch options for app {}:", app_id);
+                println!("  {}", launch_options);
+                notify(
+                    Urgency::Normal,
+                    "Launch options applied",
+                    &format!("App {}: {}", app_id, launch_options),
+                );
+                Ok(())
+            })
+        }
 
         LaunchOptionsAction::ClearAll {
             backup,
             only_ours,
             user_id,
-        } => clear_all(backup, only_ours, user_id),
+            all_users,
+            restart_steam,
+            force,
+        } => clear_all(
+            backup,
+            only_ours,
+            resolve_user_ids(user_id, all_users)?,
+            SteamRunning::new(restart_steam, force),
+        ),
 
-        LaunchOptionsAction::Show { app_id, user_id } => show_single(app_id, user_id),
+        LaunchOptionsAction::Show {
+            app_id,
+            name,
+            user_id,
+        } => show_single(resolve_game(app_id, name)?, user_id, json),
 
-        LaunchOptionsAction::List { user_id } => list_all(user_id),
+        LaunchOptionsAction::List { user_id } => list_all(user_id, json),
+
+        LaunchOptionsAction::Restore {
+            backup,
+            list,
+            yes,
+            user_id,
+            restart_steam,
+            force,
+        } => {
+            if list {
+                list_localconfig_backups(user_id)
+            } else {
+                restore(backup, yes, user_id, SteamRunning::new(restart_steam, force))
+            }
+        }
     }
 }
 
+/// The App ID given by --app-id or --name (clap requires one of them)
+fn resolve_game(app_id: Option<u32>, name: Option<String>) -> Result<u32, AppError> {
+    resolve_app_id(app_id, name.as_deref())?
+        .ok_or_else(|| AppError::GameNotFound("no game given".to_string()))
+}
+
+/// File name of the per-user config holding launch options
+const LOCALCONFIG: &str = "localconfig.vdf";
+
 /// Get the user ID to use, either from arg or auto-detect
-fn resolve_user_id(user_id: Option<u64>) -> Result<u64, AppError> {
+///
+/// With several users, the one loginusers.vdf marks as the most recent login is used.
+pub(crate) fn resolve_user_id(user_id: Option<u64>) -> Result<u64, AppError> {
     match user_id {
         Some(id) => Ok(id),
         None => {
             let user_ids = find_user_ids()?;
-            if user_ids.len() == 1 {
-                Ok(user_ids[0])
+            if let Some(id) = default_user_id() {
+                if user_ids.len() > 1 {
+                    info!("Using the most recently logged in Steam user {}", id);
+                }
+                Ok(id)
             } else {
                 // Try to get user names for better display
                 let user_names = crate::steam::userdata::get_user_names().unwrap_or_default();
@@ -63,135 +155,359 @@ fn resolve_user_id(user_id: Option<u64>) -> Result<u64, AppError> {
     }
 }
 
-/// Create a backup of localconfig.vdf
-fn create_backup(path: &std::path::Path) -> Result<(), AppError> {
-    let backup_path = path.with_extension("vdf.backup");
-    debug!("Creating backup: {}", backup_path.display());
-    fs::copy(path, &backup_path)?;
-    info!("Created backup: {}", backup_path.display());
-    Ok(())
+/// The users to change: every Steam user with --all-users, otherwise one as for resolve_user_id
+fn resolve_user_ids(user_id: Option<u64>, all_users: bool) -> Result<Vec<u64>, AppError> {
+    if all_users {
+        find_user_ids()
+    } else {
+        Ok(vec![resolve_user_id(user_id)?])
+    }
 }
 
-/// Set launch options for all installed games
-fn set_all(backup: bool, dry_run: bool, user_id: Option<u64>) -> Result<(), AppError> {
-    let user_id = resolve_user_id(user_id)?;
-    let config_path = get_localconfig_path(user_id)?;
-    let games = find_installed_games()?;
+/// Back up one of a user's Steam config files, keeping as many backups as configured
+pub(crate) fn create_backup(user_id: u64, path: &Path) -> Result<PathBuf, AppError> {
+    let keep = MergedConfig::load(None, None)?.backups.keep;
+    backups::create_backup(user_id, path, keep)
+}
+
+/// What to do if Steam is running when its config files need writing
+///
+/// Steam keeps its own copy of localconfig.vdf and config.vdf in memory and
+/// writes them back on exit, silently discarding changes made while it was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SteamRunning {
+    Refuse,
+    Restart,
+    Ignore,
+}
+
+impl SteamRunning {
+    pub(crate) fn new(restart_steam: bool, force: bool) -> Self {
+        if restart_steam {
+            SteamRunning::Restart
+        } else if force {
+            SteamRunning::Ignore
+        } else {
+            SteamRunning::Refuse
+        }
+    }
+
+    /// Run an edit of Steam's config files, dealing with a running Steam first
+    pub(crate) fn apply<F>(self, edit: F) -> Result<(), AppError>
+    where
+        F: FnOnce() -> Result<(), AppError>,
+    {
+        let restarted = self.apply_with(edit, |step| println!("{}", step))?;
+        if !restarted {
+            println!();
+            println!("Note: Restart Steam for changes to take effect.");
+        }
+        Ok(())
+    }
+
+    /// `apply` without printing anything, for `serve`
+    pub(crate) fn apply_quietly<F>(self, edit: F) -> Result<(), AppError>
+    where
+        F: FnOnce() -> Result<(), AppError>,
+    {
+        self.apply_with(edit, |step| info!("{}", step)).map(|_| ())
+    }
+
+    /// Run the edit, reporting Steam restart steps; returns whether Steam was restarted
+    fn apply_with<F>(self, edit: F, progress: fn(&str)) -> Result<bool, AppError>
+    where
+        F: FnOnce() -> Result<(), AppError>,
+    {
+        if !is_steam_running() {
+            edit()?;
+            return Ok(false);
+        }
+
+        match self {
+            SteamRunning::Refuse => Err(AppError::SteamRunning(
+                "it would overwrite the changes when it exits. \
+                 Close Steam first, or use --restart-steam (or --force to write anyway)"
+                    .to_string(),
+            )),
+            SteamRunning::Ignore => {
+                warn!("Steam is running and may overwrite these changes when it exits");
+                edit()?;
+                Ok(false)
+            }
+            SteamRunning::Restart => {
+                progress("Shutting down Steam...");
+                shutdown_steam()?;
+                let result = edit();
+                // Bring Steam back even if the edit failed
+                progress("Starting Steam...");
+                start_steam()?;
+                result.map(|()| true)
+            }
+        }
+    }
+}
+
+/// Which installed games `set-all` applies to
+struct GameFilter {
+    only_proton: bool,
+    only_native: bool,
+    exclude: Vec<u32>,
+    name_regex: Option<Regex>,
+    library: Option<PathBuf>,
+}
+
+impl GameFilter {
+    fn needs_platform_info(&self) -> bool {
+        self.only_proton || self.only_native
+    }
+
+    fn matches(&self, game: &InstalledGame) -> bool {
+        if self.exclude.contains(&game.app_id) {
+            return false;
+        }
+        if self.only_proton && !game.runs_proton() {
+            return false;
+        }
+        if self.only_native && game.runs_proton() {
+            return false;
+        }
+        if let Some(regex) = &self.name_regex {
+            if !regex.is_match(&game.name) {
+                return false;
+            }
+        }
+        if let Some(library) = &self.library {
+            // Accept either the library root or its steamapps directory
+            let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+            let game_library = canonical(&game.library);
+            if game_library != canonical(library) && game_library != canonical(&library.join("steamapps")) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Set launch options for all installed games, for each of the given users
+fn set_all(
+    backup: bool,
+    dry_run: bool,
+    user_ids: Vec<u64>,
+    filter: &GameFilter,
+    mode: MergeMode,
+    steam: SteamRunning,
+) -> Result<(), AppError> {
+    let config_paths = user_config_paths(&user_ids)?;
+    let mut games = find_installed_games()?;
 
     if games.is_empty() {
         println!("No installed games found.");
         return Ok(());
     }
 
+    if filter.needs_platform_info() {
+        add_platform_info(&mut games);
+    }
+    let total = games.len();
+    games.retain(|game| filter.matches(game));
+    debug!("{} of {} installed games match the filters", games.len(), total);
+
+    if games.is_empty() {
+        println!("No installed games match the given filters.");
+        return Ok(());
+    }
+
     let default_options = generate_default_launch_options();
 
     if dry_run {
         println!("Dry run - would set launch options for {} games:", games.len());
         println!("Launch options: {}", default_options);
+        if config_paths.len() > 1 {
+            println!("Steam users: {}", join_ids(&user_ids));
+        }
         println!();
+        // Merging depends on each game's current options, so show the results
+        // (for the first user; others may differ)
+        let config = match mode {
+            MergeMode::Replace => None,
+            _ => Some(read_localconfig(&config_paths[0].1)?),
+        };
         for game in &games {
             println!("  {} ({})", game.name, game.app_id);
+            if let Some(config) = &config {
+                let existing = get_launch_options(config, game.app_id);
+                println!(
+                    "    {}",
+                    merge_launch_options(existing.as_deref(), &default_options, mode)
+                );
+            }
         }
         return Ok(());
     }
 
-    if backup {
-        create_backup(&config_path)?;
-    }
+    steam.apply(|| {
+        for (user_id, config_path) in &config_paths {
+            if backup {
+                create_backup(*user_id, config_path)?;
+            }
 
-    let mut config = read_localconfig(&config_path)?;
+            let mut config = read_localconfig(config_path)?;
 
-    let mut count = 0;
-    for game in &games {
-        set_launch_options(&mut config, game.app_id, Some(&default_options));
-        count += 1;
-    }
+            let mut count = 0;
+            for game in &games {
+                let existing = get_launch_options(&config, game.app_id);
+                let options = merge_launch_options(existing.as_deref(), &default_options, mode);
+                set_launch_options(&mut config, game.app_id, Some(&options));
+                count += 1;
+            }
 
-    write_localconfig(&config_path, &config)?;
+            write_localconfig(config_path, &config)?;
 
-    println!(
-        "Set launch options for {} games in {}",
-        count,
-        config_path.display()
-    );
-    println!("Launch options: {}", default_options);
-    println!();
-    println!("Note: Restart Steam for changes to take effect.");
+            println!(
+                "Set launch options for {} games in {}",
+                count,
+                config_path.display()
+            );
+        }
+        println!("Launch options: {}", default_options);
+        notify(
+            Urgency::Normal,
+            "Launch options applied",
+            &format!("Set launch options for {} games", games.len()),
+        );
+        Ok(())
+    })
+}
 
-    Ok(())
+/// Each user's localconfig.vdf, checked before anything is written
+fn user_config_paths(user_ids: &[u64]) -> Result<Vec<(u64, PathBuf)>, AppError> {
+    user_ids
+        .iter()
+        .map(|&user_id| Ok((user_id, get_localconfig_path(user_id)?)))
+        .collect()
 }
 
-/// Set launch options for a single game
-fn set_single(app_id: u32, options: Option<String>, user_id: Option<u64>) -> Result<(), AppError> {
+/// App or user IDs as a comma-separated list
+fn join_ids(ids: &[u64]) -> String {
+    ids.iter().map(u64::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Set launch options for a single game, returning the launch options written
+///
+/// Prints nothing, since `serve` uses it too; callers deal with a running Steam
+/// (see `SteamRunning`) and report the result.
+pub(crate) fn set_single(
+    app_id: u32,
+    options: Option<String>,
+    user_id: Option<u64>,
+    mode: MergeMode,
+) -> Result<String, AppError> {
     let user_id = resolve_user_id(user_id)?;
     let config_path = get_localconfig_path(user_id)?;
-
-    let launch_options = options.unwrap_or_else(generate_default_launch_options);
+    let options = options.unwrap_or_else(generate_default_launch_options);
 
     let mut config = read_localconfig(&config_path)?;
+    let existing = get_launch_options(&config, app_id);
+    let launch_options = merge_launch_options(existing.as_deref(), &options, mode);
     set_launch_options(&mut config, app_id, Some(&launch_options));
     write_localconfig(&config_path, &config)?;
+    Ok(launch_options)
+}
 
-    println!("Set launch options for app {}:", app_id);
-    println!("  {}", launch_options);
-    println!();
-    println!("Note: Restart Steam for changes to take effect.");
+/// Set the default launch options for some games, used by `setup`
+///
+/// Existing launch options are kept (our wrapper goes outside them) and
+/// localconfig.vdf is backed up first.
+pub(crate) fn set_for_games(user_id: u64, app_ids: &[u32], restart_steam: bool) -> Result<(), AppError> {
+    let config_path = get_localconfig_path(user_id)?;
+    let default_options = generate_default_launch_options();
 
-    Ok(())
+    SteamRunning::new(restart_steam, false).apply(|| {
+        create_backup(user_id, &config_path)?;
+        let mut config = read_localconfig(&config_path)?;
+        for &app_id in app_ids {
+            let existing = get_launch_options(&config, app_id);
+            let options = merge_launch_options(existing.as_deref(), &default_options, MergeMode::Prepend);
+            set_launch_options(&mut config, app_id, Some(&options));
+        }
+        write_localconfig(&config_path, &config)?;
+
+        println!("Set launch options for {} games: {}", app_ids.len(), default_options);
+        notify(
+            Urgency::Normal,
+            "Launch options applied",
+            &format!("Set launch options for {} games", app_ids.len()),
+        );
+        Ok(())
+    })
 }
 
-/// Clear launch options for all games
-fn clear_all(backup: bool, only_ours: bool, user_id: Option<u64>) -> Result<(), AppError> {
-    let user_id = resolve_user_id(user_id)?;
-    let config_path = get_localconfig_path(user_id)?;
+/// Clear launch options for all games, for each of the given users
+fn clear_all(
+    backup: bool,
+    only_ours: bool,
+    user_ids: Vec<u64>,
+    steam: SteamRunning,
+) -> Result<(), AppError> {
+    let config_paths = user_config_paths(&user_ids)?;
     let games = find_installed_games()?;
 
-    if backup {
-        create_backup(&config_path)?;
-    }
-
-    let mut config = read_localconfig(&config_path)?;
+    steam.apply(|| {
+        for (user_id, config_path) in &config_paths {
+            if backup {
+                create_backup(*user_id, config_path)?;
+            }
 
-    let mut cleared = 0;
-    let mut skipped = 0;
+            let mut config = read_localconfig(config_path)?;
 
-    for game in &games {
-        if let Some(current_options) = get_launch_options(&config, game.app_id) {
-            if only_ours && !is_our_launch_options(&current_options) {
-                debug!(
-                    "Skipping {} ({}) - not set by us",
-                    game.name, game.app_id
-                );
-                skipped += 1;
-                continue;
-            }
+            let mut cleared = 0;
+            let mut skipped = 0;
 
-            set_launch_options(&mut config, game.app_id, None);
-            debug!("Cleared launch options for {} ({})", game.name, game.app_id);
-            cleared += 1;
-        }
-    }
+            for game in &games {
+                if let Some(current_options) = get_launch_options(&config, game.app_id) {
+                    if only_ours && !is_our_launch_options(&current_options) {
+                        debug!(
+                            "Skipping {} ({}) - not set by us",
+                            game.name, game.app_id
+                        );
+                        skipped += 1;
+                        continue;
+                    }
 
-    write_localconfig(&config_path, &config)?;
+                    set_launch_options(&mut config, game.app_id, None);
+                    debug!("Cleared launch options for {} ({})", game.name, game.app_id);
+                    cleared += 1;
+                }
+            }
 
-    println!("Cleared launch options for {} games.", cleared);
-    if skipped > 0 {
-        println!("Skipped {} games (not set by steam-command-runner).", skipped);
-    }
-    println!();
-    println!("Note: Restart Steam for changes to take effect.");
+            write_localconfig(config_path, &config)?;
 
-    Ok(())
+            if config_paths.len() > 1 {
+                println!("Steam user {}:", user_id);
+            }
+            println!("Cleared launch options for {} games.", cleared);
+            if skipped > 0 {
+                println!("Skipped {} games (not set by steam-command-runner).", skipped);
+            }
+        }
+        Ok(())
+    })
 }
 
 /// Show launch options for a single game
-fn show_single(app_id: u32, user_id: Option<u64>) -> Result<(), AppError> {
+fn show_single(app_id: u32, user_id: Option<u64>, json: bool) -> Result<(), AppError> {
     let user_id = resolve_user_id(user_id)?;
     let config_path = get_localconfig_path(user_id)?;
 
     let config = read_localconfig(&config_path)?;
+    let options = get_launch_options(&config, app_id);
 
-    match get_launch_options(&config, app_id) {
+    if json {
+        let ours = options.as_deref().is_some_and(is_our_launch_options);
+        return print_json(&json!({ "app_id": app_id, "launch_options": options, "ours": ours }));
+    }
+
+    match options {
         Some(options) => {
             println!("Launch options for app {}:", app_id);
             println!("  {}", options);
@@ -208,13 +524,37 @@ fn show_single(app_id: u32, user_id: Option<u64>) -> Result<(), AppError> {
 }
 
 /// List all games with their launch options
-fn list_all(user_id: Option<u64>) -> Result<(), AppError> {
+fn list_all(user_id: Option<u64>, json: bool) -> Result<(), AppError> {
     let user_id = resolve_user_id(user_id)?;
     let config_path = get_localconfig_path(user_id)?;
     let games = find_installed_games()?;
 
     let config = read_localconfig(&config_path)?;
 
+    // Steam's app cache adds platform details; the listing works without it
+    let appinfo = AppInfoCache::load()
+        .map_err(|e| debug!("Steam app cache unavailable: {}", e))
+        .ok();
+
+    if json {
+        let games: Vec<_> = games
+            .iter()
+            .map(|game| {
+                let options = get_launch_options(&config, game.app_id);
+                let app = appinfo.as_ref().and_then(|c| c.get(game.app_id).ok().flatten());
+                json!({
+                    "app_id": game.app_id,
+                    "name": game.name,
+                    "launch_options": options,
+                    "ours": options.as_deref().is_some_and(is_our_launch_options),
+                    "oslist": app.map(|a| a.oslist).unwrap_or_default(),
+                    "default_compat_tool": appinfo.as_ref().and_then(|c| c.default_compat_tool(game.app_id)),
+                })
+            })
+            .collect();
+        return print_json(&json!(games));
+    }
+
     let mut with_options = Vec::new();
     let mut without_options = Vec::new();
 
@@ -233,6 +573,9 @@ fn list_all(user_id: Option<u64>) -> Result<(), AppError> {
             let marker = if *ours { " [ours]" } else { "" };
             println!("  {} ({}){}", game.name, game.app_id, marker);
             println!("    {}", options);
+            if let Some(details) = appinfo.as_ref().and_then(|c| app_details(c, game.app_id)) {
+                println!("    {}", details);
+            }
         }
         println!();
     }
@@ -244,3 +587,79 @@ fn list_all(user_id: Option<u64>) -> Result<(), AppError> {
 
     Ok(())
 }
+
+/// List the localconfig.vdf backups available to restore
+fn list_localconfig_backups(user_id: Option<u64>) -> Result<(), AppError> {
+    let user_id = resolve_user_id(user_id)?;
+    let backups = list_backups(user_id, LOCALCONFIG);
+    if backups.is_empty() {
+        println!("No backups of {} for user {}.", LOCALCONFIG, user_id);
+        return Ok(());
+    }
+
+    println!("Backups of {} (oldest first):", LOCALCONFIG);
+    for backup in &backups {
+        let stamp = backup_timestamp(backup, LOCALCONFIG).unwrap_or_default();
+        println!("  {}  {}", stamp, backup.display());
+    }
+    Ok(())
+}
+
+/// Roll localconfig.vdf back to a backup, showing the launch option changes first
+fn restore(
+    timestamp: Option<String>,
+    yes: bool,
+    user_id: Option<u64>,
+    steam: SteamRunning,
+) -> Result<(), AppError> {
+    let user_id = resolve_user_id(user_id)?;
+    let config_path = get_localconfig_path(user_id)?;
+    let backup_path = find_backup(user_id, LOCALCONFIG, timestamp.as_deref())?;
+
+    let current = read_localconfig(&config_path)?;
+    let backup = read_localconfig(&backup_path)?;
+    let changes = diff_launch_options(&current, &backup);
+
+    println!("Restoring {}", backup_path.display());
+    if changes.is_empty() {
+        if current.content() == backup.content() {
+            println!("The backup matches the current {}.", LOCALCONFIG);
+            return Ok(());
+        }
+        println!("No launch option changes; other settings will be rolled back.");
+    } else {
+        println!("Launch option changes:");
+        for change in &changes {
+            println!("  {}", change.app_id);
+            println!("    - {}", change.before.as_deref().unwrap_or("(none)"));
+            println!("    + {}", change.after.as_deref().unwrap_or("(none)"));
+        }
+    }
+    println!();
+
+    if !yes && !confirm("Restore this backup?")? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    steam.apply(|| {
+        // Keep the current state so the restore itself can be undone
+        create_backup(user_id, &config_path)?;
+        fs::copy(&backup_path, &config_path)?;
+        println!("Restored {} from {}", config_path.display(), backup_path.display());
+        Ok(())
+    })
+}
+
+/// Platform and compatibility tool details for a game from Steam's app cache
+fn app_details(appinfo: &AppInfoCache, app_id: u32) -> Option<String> {
+    let app = appinfo.get(app_id).ok()??;
+    let mut details = Vec::new();
+    if !app.oslist.is_empty() {
+        details.push(format!("platforms: {}", app.oslist.join(", ")));
+    }
+    if let Some(tool) = appinfo.default_compat_tool(app_id) {
+        details.push(format!("default compat tool: {}", tool));
+    }
+    (!details.is_empty()).then(|| details.join("; "))
+}
@@ -0,0 +1,125 @@
+use crate::cli::LogsAction;
+use crate::error::AppError;
+use crate::logs::{all_log_files, last_lines, latest_proton_log, summarize_proton_log, LogTarget};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// How often `logs tail --follow` checks for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Handle the logs command and its subcommands
+pub fn handle_logs(action: LogsAction) -> Result<(), AppError> {
+    match action {
+        LogsAction::Show { log } => show(log),
+        LogsAction::Tail { log, lines, follow } => tail(&existing_path(log)?, lines, follow),
+        LogsAction::Proton {
+            app_id,
+            lines,
+            all,
+            follow,
+        } => proton(app_id, lines, all, follow),
+        LogsAction::Clear { log } => clear(log),
+    }
+}
+
+fn existing_path(log: LogTarget) -> Result<PathBuf, AppError> {
+    log.path()
+        .filter(|path| path.exists())
+        .ok_or_else(|| AppError::LogNotFound(format!("no {} log has been written yet", describe(log))))
+}
+
+fn describe(log: LogTarget) -> String {
+    match log {
+        LogTarget::File(file) => format!("{:?}", file).to_lowercase(),
+        LogTarget::Game(app_id) => format!("game output ({})", app_id),
+    }
+}
+
+fn show(log: LogTarget) -> Result<(), AppError> {
+    let path = existing_path(log)?;
+    io::copy(&mut File::open(path)?, &mut io::stdout().lock())?;
+    Ok(())
+}
+
+fn tail(path: &Path, lines: usize, follow: bool) -> Result<(), AppError> {
+    for line in last_lines(path, lines)? {
+        println!("{}", line);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = fs::metadata(path)?.len();
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        // A rotated or cleared log starts over
+        let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if len < offset {
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut new = Vec::new();
+        file.read_to_end(&mut new)?;
+        offset += new.len() as u64;
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&new)?;
+        stdout.flush()?;
+    }
+}
+
+/// Print a game's latest Proton log, then the problems spotted in it
+fn proton(app_id: u32, lines: usize, all: bool, follow: bool) -> Result<(), AppError> {
+    let path = latest_proton_log(app_id).ok_or_else(|| {
+        AppError::LogNotFound(format!(
+            "no Proton log for {}; set log = true under [proton] in its config and launch it",
+            app_id
+        ))
+    })?;
+    println!("==> {} <==", path.display());
+    if all {
+        io::copy(&mut File::open(&path)?, &mut io::stdout().lock())?;
+    } else {
+        for line in last_lines(&path, lines)? {
+            println!("{}", line);
+        }
+    }
+
+    let issues = summarize_proton_log(&String::from_utf8_lossy(&fs::read(&path)?));
+    println!();
+    if issues.is_empty() {
+        println!("No common problems found");
+    } else {
+        println!("Problems found:");
+        for issue in issues {
+            let count = if issue.count > 1 { format!(" ({}x)", issue.count) } else { String::new() };
+            println!("  {}{}", issue.message, count);
+            if let Some(hint) = issue.hint {
+                println!("    try: {}", hint);
+            }
+        }
+    }
+
+    if follow {
+        tail(&path, 0, true)?;
+    }
+    Ok(())
+}
+
+fn clear(log: Option<LogTarget>) -> Result<(), AppError> {
+    let files = match log {
+        Some(log) => log.files(),
+        None => all_log_files(),
+    };
+    for file in &files {
+        fs::remove_file(file)?;
+    }
+    println!("Removed {} log file(s)", files.len());
+    Ok(())
+}
@@ -1,15 +1,93 @@
+pub mod bench;
+pub mod cache;
+pub mod compat_tool;
+pub mod completions;
 pub mod config;
+pub mod doctor;
 pub mod gamescope;
+pub mod games;
+pub mod gpu;
+pub mod heroic;
+pub mod import;
+pub mod info;
 pub mod install;
 pub mod launch_options;
+pub mod logs;
+pub mod modmanager;
+pub mod prefix;
 pub mod proton;
+pub(crate) mod resolve;
 pub mod run;
+pub mod saves;
 pub mod search;
+pub mod serve;
+pub mod session;
+pub mod setup;
+pub mod shortcuts;
+pub mod stats;
 
+pub use bench::handle_bench;
+pub use cache::handle_cache;
+pub use compat_tool::handle_compat_tool;
+pub use completions::{handle_complete, handle_completions};
 pub use config::handle_config;
+pub use doctor::handle_doctor;
 pub use gamescope::handle_gamescope;
+pub use games::handle_games;
+pub use gpu::handle_gpu;
+pub use heroic::handle_heroic;
+pub use import::handle_import;
+pub use info::handle_info;
 pub use install::{handle_install, handle_uninstall};
 pub use launch_options::handle_launch_options;
+pub use logs::handle_logs;
+pub use modmanager::handle_modmanager;
+pub use prefix::handle_prefix;
 pub use proton::handle_proton;
-pub use run::handle_run;
+pub use run::{handle_explain, handle_run};
+pub use saves::handle_saves;
 pub use search::handle_search;
+pub use serve::handle_serve;
+pub use session::{handle_kill, handle_status};
+pub use setup::handle_setup;
+pub use shortcuts::handle_shortcuts;
+pub use stats::handle_stats;
+
+use crate::error::AppError;
+use std::io::{self, BufRead, Write};
+
+/// Print a value as pretty-printed JSON, for --json output
+pub(crate) fn print_json(value: &serde_json::Value) -> Result<(), AppError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Format a byte count for display ("1.4 GiB")
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Ask for a line of input on the terminal
+pub(crate) fn prompt(question: &str) -> Result<String, AppError> {
+    print!("{} ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Ask a yes/no question on the terminal
+pub(crate) fn confirm(question: &str) -> Result<bool, AppError> {
+    let answer = prompt(&format!("{} [y/N]", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
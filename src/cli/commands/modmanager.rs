@@ -0,0 +1,57 @@
+use crate::cli::ModManagerAction;
+use crate::config::MergedConfig;
+use crate::error::AppError;
+use crate::modmanager::session_command;
+use crate::proton::compat_environment;
+use crate::runner::ProtonRunner;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::info;
+
+/// Handle the modmanager command and its subcommands
+pub fn handle_modmanager(
+    action: ModManagerAction,
+    config_path: Option<PathBuf>,
+) -> Result<ExitCode, AppError> {
+    match action {
+        ModManagerAction::Run { app_id } => run_manager(app_id, config_path),
+    }
+}
+
+/// Start the mod manager in the game's prefix with the game's Steam environment
+fn run_manager(app_id: u32, config_path: Option<PathBuf>) -> Result<ExitCode, AppError> {
+    let mut config = prefix_tool_config(app_id, config_path)?;
+
+    let manager = config.modmanager.take().ok_or_else(|| {
+        AppError::ModManager(format!(
+            "No [modmanager] configured for app {} (see 'config edit --app-id {}')",
+            app_id, app_id
+        ))
+    })?;
+
+    info!("Starting {:?} mod manager for app {}", manager.kind, app_id);
+    let runner = ProtonRunner::new(&config)?;
+    runner.run(session_command(&manager)?)
+}
+
+/// A game's config for running another program in its prefix
+///
+/// Tools like mod managers and winecfg need the game's prefix and Steam identity,
+/// but none of the wrappers or arguments meant for the game itself.
+pub(crate) fn prefix_tool_config(
+    app_id: u32,
+    config_path: Option<PathBuf>,
+) -> Result<MergedConfig, AppError> {
+    let mut config = MergedConfig::load(Some(app_id), config_path)?;
+    for (key, value) in compat_environment(app_id)? {
+        config.env.entry(key).or_insert(value);
+    }
+    config.gamescope_enabled = false;
+    config.pre_command = None;
+    config.gamescope_pre_command = None;
+    config.launch_args.clear();
+    config.game_args = None;
+    config.working_dir = None;
+    config.companions.clear();
+    Ok(config)
+}
@@ -0,0 +1,245 @@
+use super::{confirm, format_size, print_json};
+use crate::cli::PrefixAction;
+use crate::error::AppError;
+use crate::proton::{
+    backup_dir, backup_prefix, dir_size, find_compat_data_path, list_backups, list_prefixes,
+    orphaned_prefixes, reset_prefix, restore_prefix,
+};
+use crate::steam::{find_installed_games, game_name};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Handle the prefix command and its subcommands
+pub fn handle_prefix(action: PrefixAction, json: bool) -> Result<(), AppError> {
+    match action {
+        PrefixAction::List { app_id: None } => list_all(),
+        PrefixAction::List { app_id: Some(id) } => show_prefix(id),
+        PrefixAction::Du => disk_usage(json),
+        PrefixAction::Prune {
+            dry_run,
+            no_backup,
+            yes,
+        } => prune(dry_run, !no_backup, yes),
+        PrefixAction::Reset {
+            app_id,
+            backup,
+            yes,
+        } => reset(app_id, backup, yes),
+        PrefixAction::Backup { app_id, output } => backup(app_id, output),
+        PrefixAction::Restore {
+            app_id,
+            archive,
+            yes,
+        } => restore(app_id, archive, yes),
+    }
+}
+
+/// List all prefixes with game names where known
+fn list_all() -> Result<(), AppError> {
+    let prefixes = list_prefixes()?;
+    if prefixes.is_empty() {
+        println!("No Proton prefixes found.");
+        return Ok(());
+    }
+
+    let names: HashMap<u32, String> = find_installed_games()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|g| (g.app_id, g.name))
+        .collect();
+
+    println!("Proton prefixes:");
+    for prefix in prefixes {
+        let name = names.get(&prefix.app_id).map(String::as_str).unwrap_or("(not installed)");
+        println!("  {} ({})", name, prefix.app_id);
+        println!("    {}", prefix.path.display());
+    }
+
+    Ok(())
+}
+
+/// Show the size of every prefix, largest first, flagging those of uninstalled games
+fn disk_usage(json: bool) -> Result<(), AppError> {
+    let installed: HashMap<u32, String> = find_installed_games()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|g| (g.app_id, g.name))
+        .collect();
+
+    let mut prefixes: Vec<_> = list_prefixes()?
+        .into_iter()
+        .map(|prefix| {
+            // Non-Steam shortcuts aren't in an appmanifest but still own their prefix
+            let name = installed
+                .get(&prefix.app_id)
+                .cloned()
+                .or_else(|| game_name(prefix.app_id));
+            let size = dir_size(&prefix.path);
+            (prefix, name, size)
+        })
+        .collect();
+    prefixes.sort_by_key(|(_, _, size)| std::cmp::Reverse(*size));
+
+    if json {
+        let prefixes: Vec<_> = prefixes
+            .iter()
+            .map(|(prefix, name, size)| {
+                json!({
+                    "app_id": prefix.app_id,
+                    "name": name,
+                    "installed": name.is_some(),
+                    "path": prefix.path,
+                    "size": size,
+                })
+            })
+            .collect();
+        return print_json(&json!(prefixes));
+    }
+
+    if prefixes.is_empty() {
+        println!("No Proton prefixes found.");
+        return Ok(());
+    }
+
+    let mut orphaned = 0;
+    for (prefix, name, size) in &prefixes {
+        if name.is_none() {
+            orphaned += size;
+        }
+        let name = name.as_deref().unwrap_or("(not installed)");
+        println!("{:>10}  {:<12} {}", format_size(*size), prefix.app_id, name);
+    }
+
+    let total: u64 = prefixes.iter().map(|(_, _, size)| size).sum();
+    println!();
+    println!("Total: {}", format_size(total));
+    if orphaned > 0 {
+        println!("Not installed: {} (see 'prefix reset <app_id>')", format_size(orphaned));
+    }
+
+    Ok(())
+}
+
+/// Delete the prefixes of games that are no longer installed, backing them up first unless told not to
+fn prune(dry_run: bool, backup_first: bool, yes: bool) -> Result<(), AppError> {
+    let orphans: Vec<_> = orphaned_prefixes()?
+        .into_iter()
+        .map(|prefix| {
+            let size = dir_size(&prefix.path);
+            (prefix, size)
+        })
+        .collect();
+    if orphans.is_empty() {
+        println!("No prefixes of uninstalled games found.");
+        return Ok(());
+    }
+
+    println!("Prefixes of games that are no longer installed:");
+    for (prefix, size) in &orphans {
+        println!("  {:>10}  {}", format_size(*size), prefix.path.display());
+    }
+    let total: u64 = orphans.iter().map(|(_, size)| size).sum();
+    println!("Total: {}", format_size(total));
+
+    if dry_run {
+        return Ok(());
+    }
+    let question = if backup_first {
+        format!("Back up and delete {} prefixes?", orphans.len())
+    } else {
+        format!("Delete {} prefixes?", orphans.len())
+    };
+    if !yes && !confirm(&question)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    for (prefix, _) in &orphans {
+        if backup_first {
+            let archive = backup_prefix(prefix.app_id, None)?;
+            println!("Backed up prefix for app {} to {}", prefix.app_id, archive.display());
+        }
+        let path = reset_prefix(prefix.app_id)?;
+        println!("Removed {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Show one prefix and its backups
+fn show_prefix(app_id: u32) -> Result<(), AppError> {
+    let path = find_compat_data_path(app_id)?;
+    if path.is_dir() {
+        println!("Prefix for app {}: {}", app_id, path.display());
+    } else {
+        println!("No prefix for app {} (would be created at {})", app_id, path.display());
+    }
+
+    let backups = list_backups(app_id);
+    if backups.is_empty() {
+        println!("No backups in {}", backup_dir(app_id).display());
+    } else {
+        println!("Backups:");
+        for backup in backups {
+            println!("  {}", backup.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn reset(app_id: u32, backup_first: bool, yes: bool) -> Result<(), AppError> {
+    let path = find_compat_data_path(app_id)?;
+    if !yes && !confirm(&format!("Delete the prefix at {}?", path.display()))? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    if backup_first {
+        let archive = backup_prefix(app_id, None)?;
+        println!("Backed up prefix to {}", archive.display());
+    }
+
+    let path = reset_prefix(app_id)?;
+    println!("Removed {}", path.display());
+    println!("Proton will create a fresh prefix the next time the game starts.");
+
+    Ok(())
+}
+
+fn backup(app_id: u32, output: Option<PathBuf>) -> Result<(), AppError> {
+    let archive = backup_prefix(app_id, output)?;
+    println!("Backed up prefix for app {} to {}", app_id, archive.display());
+    Ok(())
+}
+
+fn restore(app_id: u32, archive: Option<PathBuf>, yes: bool) -> Result<(), AppError> {
+    let archive = match archive {
+        Some(path) => path,
+        None => list_backups(app_id).pop().ok_or_else(|| {
+            AppError::Prefix(format!(
+                "no backups for app {} in {}",
+                app_id,
+                backup_dir(app_id).display()
+            ))
+        })?,
+    };
+
+    let path = find_compat_data_path(app_id)?;
+    if !yes
+        && path.exists()
+        && !confirm(&format!(
+            "Replace the prefix at {} with {}?",
+            path.display(),
+            archive.display()
+        ))?
+    {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    let path = restore_prefix(app_id, &archive)?;
+    println!("Restored {} from {}", path.display(), archive.display());
+    Ok(())
+}
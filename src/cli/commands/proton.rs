@@ -1,22 +1,95 @@
+use super::modmanager::prefix_tool_config;
+use super::print_json;
+use super::resolve::resolve_app_id;
 use crate::cli::ProtonAction;
 use crate::error::AppError;
-use crate::proton::list_proton_versions;
+use crate::proton::{
+    compat_tool_search_dirs, fetch_ge_release, install_ge_release, is_installed,
+    list_proton_versions, normalize_ge_version,
+};
+use crate::runner::ProtonRunner;
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::info;
 
 /// Handle the proton command and its subcommands
-pub fn handle_proton(action: ProtonAction) -> Result<(), AppError> {
+pub fn handle_proton(
+    action: ProtonAction,
+    json: bool,
+    config_path: Option<PathBuf>,
+) -> Result<ExitCode, AppError> {
     match action {
-        ProtonAction::List { paths } => list_versions(paths),
+        ProtonAction::List { paths } => list_versions(paths, json)?,
+        ProtonAction::Install { version, force } => install_version(&version, force)?,
+        ProtonAction::Update => install_version("latest", false)?,
+        ProtonAction::Exec {
+            app_id,
+            name,
+            proton,
+            command,
+        } => {
+            let app_id = resolve_app_id(app_id, name.as_deref())?
+                .ok_or_else(|| AppError::GameNotFound("no game given".to_string()))?;
+            return exec_in_prefix(app_id, proton, command, config_path);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Run a program in a game's prefix with the game's Proton build and environment
+fn exec_in_prefix(
+    app_id: u32,
+    proton: Option<String>,
+    command: Vec<String>,
+    config_path: Option<PathBuf>,
+) -> Result<ExitCode, AppError> {
+    let mut config = prefix_tool_config(app_id, config_path)?;
+    if proton.is_some() {
+        config.proton = proton;
+        config.proton_fallbacks.clear();
+    }
+    // Don't route the program through the game's mod manager
+    config.modmanager = None;
+
+    info!("Running {:?} in the prefix of app {}", command, app_id);
+    ProtonRunner::new(&config)?.run(command)
+}
+
+pub(crate) fn install_version(version: &str, force: bool) -> Result<(), AppError> {
+    let tag = normalize_ge_version(version);
+    let release = fetch_ge_release(tag.as_deref())?;
+
+    if !force && is_installed(&release.tag_name)? {
+        println!("{} is already installed.", release.tag_name);
+        return Ok(());
     }
+
+    let path = install_ge_release(&release)?;
+    println!("Installed {} to {}", release.tag_name, path.display());
+    println!();
+    println!("Note: Restart Steam to make it available as a compatibility tool.");
+
+    Ok(())
 }
 
-fn list_versions(show_paths: bool) -> Result<(), AppError> {
+fn list_versions(show_paths: bool, json: bool) -> Result<(), AppError> {
     let versions = list_proton_versions();
 
+    if json {
+        let versions: Vec<_> = versions
+            .iter()
+            .map(|(name, path)| json!({ "name": name, "path": path }))
+            .collect();
+        return print_json(&json!(versions));
+    }
+
     if versions.is_empty() {
         println!("No Proton versions found.");
         println!("\nSearched locations:");
-        println!("  ~/.steam/root/compatibilitytools.d/");
-        println!("  ~/.local/share/Steam/compatibilitytools.d/");
+        for dir in compat_tool_search_dirs() {
+            println!("  {}/", dir.display());
+        }
         println!("  Steam library paths");
         return Ok(());
     }
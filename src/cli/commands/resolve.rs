@@ -0,0 +1,89 @@
+use crate::error::AppError;
+use crate::steam::{find_installed_games, match_installed_games};
+use crate::steam_api::search_games;
+use std::io::{self, BufRead, IsTerminal, Write};
+use tracing::debug;
+
+/// Most candidates offered when a name is ambiguous
+const MAX_CHOICES: usize = 10;
+
+/// Resolve a `--name` argument to an App ID, or pass `app_id` through
+///
+/// Installed games are matched first; the Steam store is only searched when no
+/// installed game matches. Messages go to stderr so commands whose stdout is
+/// consumed by scripts (e.g. `gamescope args`) stay clean.
+pub(crate) fn resolve_app_id(
+    app_id: Option<u32>,
+    name: Option<&str>,
+) -> Result<Option<u32>, AppError> {
+    let Some(name) = name else {
+        return Ok(app_id);
+    };
+    if let Ok(id) = name.trim().parse::<u32>() {
+        return Ok(Some(id));
+    }
+
+    let games = find_installed_games().unwrap_or_else(|e| {
+        debug!("Could not list installed games: {}", e);
+        Vec::new()
+    });
+    let installed: Vec<(u32, String)> = match_installed_games(name, &games)
+        .into_iter()
+        .map(|game| (game.app_id, game.name.clone()))
+        .collect();
+
+    let candidates = if installed.is_empty() {
+        debug!(
+            "No installed game matches '{}', searching the Steam store",
+            name
+        );
+        search_games(name, MAX_CHOICES)?
+    } else {
+        installed
+    };
+
+    choose(name, &candidates).map(Some)
+}
+
+/// Pick one candidate, asking on the terminal when the choice isn't obvious
+fn choose(name: &str, candidates: &[(u32, String)]) -> Result<u32, AppError> {
+    let exact: Vec<&(u32, String)> = candidates
+        .iter()
+        .filter(|(_, n)| n.eq_ignore_ascii_case(name.trim()))
+        .collect();
+    let chosen = match (candidates, exact.as_slice()) {
+        ([], _) => return Err(AppError::GameNotFound(name.to_string())),
+        ([only], _) => only,
+        (_, [only]) => *only,
+        _ => ask(name, candidates)?,
+    };
+
+    eprintln!("Using {} ({})", chosen.1, chosen.0);
+    Ok(chosen.0)
+}
+
+fn ask<'a>(name: &str, candidates: &'a [(u32, String)]) -> Result<&'a (u32, String), AppError> {
+    let ambiguous =
+        || AppError::GameNotFound(format!("'{}' matches several games; use --app-id", name));
+    if !io::stdin().is_terminal() {
+        return Err(ambiguous());
+    }
+
+    eprintln!("Several games match '{}':", name);
+    for (i, (app_id, game)) in candidates.iter().take(MAX_CHOICES).enumerate() {
+        eprintln!("  {}) {} ({})", i + 1, game, app_id);
+    }
+    eprint!("Which one? [number] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .filter(|&i| i < MAX_CHOICES)
+        .and_then(|i| candidates.get(i))
+        .ok_or_else(ambiguous)
+}
@@ -1,15 +1,34 @@
-use crate::config::MergedConfig;
+use super::resolve::resolve_app_id;
+use crate::config::{LogLevel, MergedConfig};
 use crate::error::AppError;
+use crate::heroic::{find_heroic_game, HeroicGame};
+use crate::logs::{configure, write_log, LogFile};
+use crate::notify::{notify, Urgency};
 use crate::runner::execute_game;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use tracing::{debug, info};
 
 /// Handle the run command - execute a game with configured wrappers
+///
+/// With `heroic`, the Heroic game's own launch command replaces `command`, which
+/// is only Steam's `%command%` when launched from a shortcut added by `heroic add`.
 pub fn handle_run(
     app_id: Option<u32>,
+    name: Option<String>,
+    heroic: Option<String>,
     command: Vec<String>,
     config_path: Option<PathBuf>,
-) -> Result<(), AppError> {
+    dry_run: bool,
+) -> Result<ExitCode, AppError> {
+    let heroic = heroic.as_deref().map(find_heroic_game).transpose()?;
+    let (app_id, command) = match &heroic {
+        Some(game) => {
+            info!("Running {} game {} ({})", game.store.label(), game.title, game.app_name);
+            (Some(game.app_id()), game.command())
+        }
+        None => (resolve_app_id(app_id, name.as_deref())?, command),
+    };
     if command.is_empty() {
         return Err(AppError::NoCommand);
     }
@@ -18,11 +37,64 @@ pub fn handle_run(
     debug!("Command: {:?}", command);
 
     // Load and merge configuration
-    let config = MergedConfig::load(app_id, config_path)?;
+    let mut config = MergedConfig::load(app_id, config_path)?;
+    config.dry_run = dry_run;
+    if let Some(game) = &heroic {
+        use_heroic_settings(&mut config, game);
+    }
     debug!("Loaded config: {:?}", config);
+    configure(&config.logging, config.app_id, LogLevel::Off);
 
-    // Execute the game
-    execute_game(&config, command)?;
+    // Execute the game (only returns when it ran supervised, or for a dry run)
+    let result = execute_game(&config, command);
+    if let Err(e) = &result {
+        if !dry_run {
+            write_log(
+                LogFile::Runner,
+                LogLevel::Error,
+                &format!("Failed to launch {}: {}", config.display_name(), e),
+            );
+            notify(
+                Urgency::Critical,
+                &format!("Failed to launch {}", config.display_name()),
+                &e.to_string(),
+            );
+        }
+    }
+    result
+}
+
+/// Handle the explain command - describe how a game would be launched
+///
+/// Without a command, Steam's `%command%` placeholder stands in for the game, so
+/// the execution mode falls back to what Steam knows about the app.
+pub fn handle_explain(
+    app_id: Option<u32>,
+    name: Option<String>,
+    command: Vec<String>,
+    config_path: Option<PathBuf>,
+) -> Result<ExitCode, AppError> {
+    let command = if command.is_empty() {
+        vec!["%command%".to_string()]
+    } else {
+        command
+    };
+    handle_run(app_id, name, None, command, config_path, true)
+}
 
-    Ok(())
+/// Start where Heroic starts the game, in its prefix, unless the config says otherwise
+fn use_heroic_settings(config: &mut MergedConfig, game: &HeroicGame) {
+    if config.working_dir.is_none() {
+        config.working_dir = Some(game.working_dir.to_string_lossy().into_owned());
+    }
+    match &game.prefix {
+        // Heroic's Proton prefixes are compatdata directories, so saves and installs carry over
+        Some(prefix) if prefix.proton && config.compat_data_path.is_none() => {
+            config.compat_data_path = Some(prefix.path.to_string_lossy().into_owned());
+        }
+        Some(prefix) if !prefix.proton && config.wine.prefix.is_none() => {
+            config.wine.prefix = Some(prefix.path.to_string_lossy().into_owned());
+        }
+        _ => {}
+    }
 }
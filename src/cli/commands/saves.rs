@@ -0,0 +1,105 @@
+use super::{confirm, format_size, print_json};
+use crate::cli::SavesAction;
+use crate::config::MergedConfig;
+use crate::error::AppError;
+use crate::saves::{
+    backup_saves, list_save_backups, restore_saves, save_paths, saves_backup_dir, sync_saves, SyncDirection,
+};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+/// Handle the saves command and its subcommands
+pub fn handle_saves(action: SavesAction, json: bool, config_path: Option<PathBuf>) -> Result<(), AppError> {
+    match action {
+        SavesAction::Backup { app_id } => backup(app_id, config_path),
+        SavesAction::Restore { app_id, archive, yes } => restore(app_id, archive, yes, config_path),
+        SavesAction::List { app_id } => list(app_id, json),
+        SavesAction::Sync { app_id, direction } => sync(app_id, direction, config_path),
+    }
+}
+
+fn backup(app_id: u32, config_path: Option<PathBuf>) -> Result<(), AppError> {
+    let config = MergedConfig::load(Some(app_id), config_path)?;
+    let backup = backup_saves(&config)?;
+    println!("Backed up {} save files for app {} to {}", backup.files, app_id, backup.path.display());
+    Ok(())
+}
+
+fn restore(app_id: u32, archive: Option<PathBuf>, yes: bool, config_path: Option<PathBuf>) -> Result<(), AppError> {
+    let config = MergedConfig::load(Some(app_id), config_path)?;
+    let archive = match archive {
+        Some(path) => path,
+        None => list_save_backups(app_id).pop().ok_or_else(|| {
+            AppError::Saves(format!(
+                "no save backups for app {} in {}",
+                app_id,
+                saves_backup_dir(app_id).display()
+            ))
+        })?,
+    };
+
+    if !yes {
+        println!("Restoring {} replaces the files it holds in:", archive.display());
+        for path in save_paths(&config)? {
+            println!("  {}", path.display());
+        }
+        if !confirm("Continue?")? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let (current, restored) = restore_saves(&config, &archive)?;
+    if let Some(current) = current {
+        println!("Backed up the current saves to {}", current.path.display());
+    }
+    println!("Restored {} save files from {}", restored, archive.display());
+    Ok(())
+}
+
+fn list(app_id: u32, json: bool) -> Result<(), AppError> {
+    let backups: Vec<(PathBuf, u64)> = list_save_backups(app_id)
+        .into_iter()
+        .map(|path| {
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            (path, size)
+        })
+        .collect();
+
+    if json {
+        let backups: Vec<_> = backups
+            .iter()
+            .map(|(path, size)| json!({ "path": path, "size": size }))
+            .collect();
+        return print_json(&json!(backups));
+    }
+
+    if backups.is_empty() {
+        println!("No save backups for app {}.", app_id);
+        return Ok(());
+    }
+    println!("Save backups for app {} ({}):", app_id, saves_backup_dir(app_id).display());
+    for (path, size) in &backups {
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        println!("  {}  {}", name, format_size(*size));
+    }
+    Ok(())
+}
+
+fn sync(app_id: u32, direction: SyncDirection, config_path: Option<PathBuf>) -> Result<(), AppError> {
+    let config = MergedConfig::load(Some(app_id), config_path)?;
+    let report = sync_saves(&config, direction)?;
+    if let Some(backup) = &report.backup {
+        println!("Backed up the local saves to {}", backup.display());
+    }
+    println!("Synced saves for app {} ({})", app_id, direction.label());
+    if !report.conflicts.is_empty() {
+        println!();
+        println!("Changed here and on the sync target since the last sync; replaced by the newer copies:");
+        for path in &report.conflicts {
+            println!("  {}", path.display());
+        }
+    }
+    Ok(())
+}
@@ -1,22 +1,184 @@
+use super::print_json;
 use crate::error::AppError;
-use crate::steam_api::search_games;
-use tracing::info;
+use crate::steam::{find_installed_games, match_installed_games, AppInfoCache};
+use crate::steam_api::{protondb_tiers, search_games};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+/// One search result
+struct SearchHit {
+    app_id: u32,
+    name: String,
+    oslist: Vec<String>,
+    installed: bool,
+    /// ProtonDB tier, when asked for and the game has been rated
+    protondb: Option<String>,
+}
 
 /// Handle the search command - search for Steam App IDs by game name
-pub fn handle_search(query: String, limit: usize) -> Result<(), AppError> {
+///
+/// By default installed games are fuzzy-matched, followed by name matches from
+/// Steam's local app cache; both work offline. `--store` queries the Steam store
+/// instead, falling back to the local results if the request fails. `--protondb`
+/// adds each result's ProtonDB rating.
+pub fn handle_search(
+    query: String,
+    limit: usize,
+    store: bool,
+    protondb: bool,
+    json: bool,
+) -> Result<(), AppError> {
     info!("Searching for: {}", query);
 
-    let results = search_games(&query, limit)?;
+    let cache = match AppInfoCache::load() {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            debug!("Steam app cache unavailable: {}", e);
+            None
+        }
+    };
+
+    let (mut hits, source) = if store {
+        match search_store(&query, limit, cache.as_ref()) {
+            Ok(hits) => (hits, "the Steam store"),
+            Err(e) => {
+                warn!("Steam store search failed ({}); showing local results", e);
+                (
+                    search_local(&query, limit, cache.as_ref()),
+                    "installed games and Steam's app cache",
+                )
+            }
+        }
+    } else {
+        (
+            search_local(&query, limit, cache.as_ref()),
+            "installed games and Steam's app cache",
+        )
+    };
+
+    if protondb {
+        let app_ids: Vec<u32> = hits.iter().map(|hit| hit.app_id).collect();
+        let mut tiers = protondb_tiers(&app_ids);
+        for hit in &mut hits {
+            hit.protondb = tiers.remove(&hit.app_id);
+        }
+    }
 
-    if results.is_empty() {
-        println!("No games found matching '{}'", query);
+    if json {
+        let results: Vec<_> = hits
+            .iter()
+            .map(|hit| {
+                json!({
+                    "app_id": hit.app_id,
+                    "name": hit.name,
+                    "oslist": hit.oslist,
+                    "installed": hit.installed,
+                    "protondb": hit.protondb,
+                })
+            })
+            .collect();
+        return print_json(&json!(results));
+    }
+
+    if hits.is_empty() {
+        println!("No games found matching '{}' in {}", query, source);
+        if !store {
+            println!("Use --store to search the Steam store.");
+        }
         return Ok(());
     }
 
-    println!("Found {} result(s) for '{}':\n", results.len(), query);
-    for (app_id, name) in results {
-        println!("  {:>8}  {}", app_id, name);
+    println!(
+        "Found {} result(s) for '{}' in {}:\n",
+        hits.len(),
+        query,
+        source
+    );
+    for hit in &hits {
+        println!(
+            "  {:>8}  {}{}{}{}",
+            hit.app_id,
+            hit.name,
+            platforms(&hit.oslist),
+            hit.protondb
+                .as_deref()
+                .map_or(String::new(), |tier| format!("  ProtonDB: {}", tier)),
+            if hit.installed { "  (installed)" } else { "" }
+        );
     }
 
     Ok(())
 }
+
+/// Installed games by fuzzy name match, then other apps Steam knows about
+fn search_local(query: &str, limit: usize, cache: Option<&AppInfoCache>) -> Vec<SearchHit> {
+    let games = find_installed_games().unwrap_or_else(|e| {
+        debug!("Could not list installed games: {}", e);
+        Vec::new()
+    });
+    let oslist = |app_id: u32| {
+        cache
+            .and_then(|c| c.get(app_id).ok().flatten())
+            .map(|app| app.oslist)
+            .unwrap_or_default()
+    };
+
+    let mut hits: Vec<SearchHit> = match_installed_games(query, &games)
+        .into_iter()
+        .map(|game| SearchHit {
+            app_id: game.app_id,
+            name: game.name.clone(),
+            oslist: oslist(game.app_id),
+            installed: true,
+            protondb: None,
+        })
+        .collect();
+
+    if let Some(cache) = cache {
+        for app in cache.search(query, limit) {
+            if !hits.iter().any(|hit| hit.app_id == app.app_id) {
+                hits.push(SearchHit {
+                    app_id: app.app_id,
+                    name: app.name.unwrap_or_else(|| "(unknown)".to_string()),
+                    oslist: app.oslist,
+                    installed: false,
+                    protondb: None,
+                });
+            }
+        }
+    }
+
+    hits.truncate(limit);
+    hits
+}
+
+fn search_store(
+    query: &str,
+    limit: usize,
+    cache: Option<&AppInfoCache>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let games = find_installed_games().unwrap_or_default();
+    let hits = search_games(query, limit)?
+        .into_iter()
+        .map(|(app_id, name)| SearchHit {
+            app_id,
+            name,
+            oslist: cache
+                .and_then(|c| c.get(app_id).ok().flatten())
+                .map(|app| app.oslist)
+                .unwrap_or_default(),
+            installed: games.iter().any(|g| g.app_id == app_id),
+            protondb: None,
+        })
+        .collect();
+    Ok(hits)
+}
+
+/// Supported platforms suffix, e.g. "  [windows, linux]"
+fn platforms(oslist: &[String]) -> String {
+    if oslist.is_empty() {
+        String::new()
+    } else {
+        format!("  [{}]", oslist.join(", "))
+    }
+}
@@ -0,0 +1,121 @@
+use super::config::{read_config_file, read_config_key, write_config_key};
+use super::games::{game_rows, GameRow};
+use super::launch_options::{set_single, SteamRunning};
+use crate::error::AppError;
+use crate::proton::list_proton_versions;
+use crate::rpc::{bind, default_socket_path, serve, RpcError, METHOD_NOT_FOUND};
+use crate::steam::MergeMode;
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tracing::info;
+
+/// Serve JSON-RPC on a unix socket until stopped
+pub fn handle_serve(socket: Option<PathBuf>) -> Result<(), AppError> {
+    let path = socket.unwrap_or_else(default_socket_path);
+    let listener = bind(&path)?;
+    info!("Serving JSON-RPC on {}", path.display());
+    println!("Listening on {}", path.display());
+    serve(listener, dispatch);
+    Ok(())
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct GamesListParams {
+    user_id: Option<u64>,
+    protondb: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigGetParams {
+    /// Omitted for the global config
+    app_id: Option<u32>,
+    /// Omitted for the whole file
+    key: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigSetParams {
+    app_id: Option<u32>,
+    key: String,
+    value: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LaunchOptionsSetParams {
+    app_id: u32,
+    /// Omitted for our default launch options
+    options: Option<String>,
+    user_id: Option<u64>,
+    mode: Option<String>,
+    #[serde(default)]
+    restart_steam: bool,
+    #[serde(default)]
+    force: bool,
+}
+
+/// Run one method
+fn dispatch(method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "games.list" => {
+            let params: GamesListParams = parse_params(params)?;
+            let rows = game_rows(params.user_id, params.protondb)?;
+            Ok(json!(rows.iter().map(GameRow::to_json).collect::<Vec<_>>()))
+        }
+        "config.get" => {
+            let params: ConfigGetParams = parse_params(params)?;
+            match params.key {
+                Some(key) => Ok(json!(read_config_key(params.app_id, &key)?)),
+                None => {
+                    let (path, config) = read_config_file(params.app_id)?;
+                    Ok(json!({ "path": path, "exists": config.is_some(), "config": config }))
+                }
+            }
+        }
+        "config.set" => {
+            let params: ConfigSetParams = parse_params(params)?;
+            let value = toml_literal(params.value)?;
+            let path = write_config_key(params.app_id, &params.key, &value)?;
+            Ok(json!({ "path": path }))
+        }
+        "launch_options.set" => {
+            let params: LaunchOptionsSetParams = parse_params(params)?;
+            let mode = match params.mode.as_deref() {
+                Some(mode) => MergeMode::from_str(mode, true).map_err(RpcError::invalid_params)?,
+                None => MergeMode::default(),
+            };
+            let mut options = String::new();
+            SteamRunning::new(params.restart_steam, params.force).apply_quietly(|| {
+                options = set_single(params.app_id, params.options, params.user_id, mode)?;
+                Ok(())
+            })?;
+            Ok(json!({ "launch_options": options }))
+        }
+        "proton.list" => {
+            let versions: Vec<_> = list_proton_versions()
+                .into_iter()
+                .map(|(name, path)| json!({ "name": name, "path": path }))
+                .collect();
+            Ok(json!(versions))
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+    }
+}
+
+/// Params as a method's struct; omitted params count as an empty object
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+/// A JSON value written as TOML, so `"60"` stays a string and `60` a number
+fn toml_literal(value: Value) -> Result<String, RpcError> {
+    let value = toml::Value::try_from(value).map_err(RpcError::invalid_params)?;
+    Ok(value.to_string())
+}
@@ -0,0 +1,111 @@
+use super::print_json;
+use crate::control::{running_sessions, sockets_dir, stop_session};
+use crate::error::AppError;
+use crate::playtime::{format_duration, format_time, now};
+use serde_json::json;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `kill` waits for the session to end before giving up on reporting it
+const STOP_WAIT: Duration = Duration::from_secs(10);
+
+/// Show every game running supervised
+pub fn handle_status(json: bool) -> Result<(), AppError> {
+    let sessions = running_sessions();
+
+    if json {
+        let sessions: Vec<_> = sessions
+            .iter()
+            .map(|(_, status)| {
+                let mut value = json!(status);
+                value["running_secs"] = json!(now().saturating_sub(status.started));
+                value
+            })
+            .collect();
+        return print_json(&json!(sessions));
+    }
+
+    if sessions.is_empty() {
+        println!("No game is running supervised.");
+        println!("(Games that steam-command-runner exec()s into have no session to query.)");
+        return Ok(());
+    }
+
+    for (i, (_, status)) in sessions.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match (&status.name, status.app_id) {
+            (Some(name), Some(app_id)) => println!("{} ({})", name, app_id),
+            _ => println!("{}", status.display_name()),
+        }
+        match status.game_pid {
+            Some(pid) => println!("  PID:      {} (runner {})", pid, status.pid),
+            None => println!("  PID:      not running (runner {})", status.pid),
+        }
+        println!(
+            "  Running:  {} (since {})",
+            format_duration(now().saturating_sub(status.started)),
+            format_time(status.started)
+        );
+        if let Some(proton) = &status.proton {
+            println!("  Proton:   {}", proton);
+        }
+        if let Some(profile) = &status.profile {
+            println!("  Profile:  {}", profile);
+        }
+        let command = shlex::try_join(status.command.iter().map(String::as_str))
+            .unwrap_or_else(|_| status.command.join(" "));
+        println!("  Command:  {}", command);
+    }
+    Ok(())
+}
+
+/// Stop a supervised game and wait briefly for its session to end
+pub fn handle_kill(app_id: Option<u32>) -> Result<(), AppError> {
+    let sessions = running_sessions();
+    let mut matching: Vec<_> = sessions
+        .iter()
+        .filter(|(_, status)| app_id.is_none() || status.app_id == app_id)
+        .collect();
+
+    let (socket, status) = match (matching.len(), app_id) {
+        (0, Some(app_id)) => {
+            return Err(AppError::Control(format!("no supervised session for app {}", app_id)))
+        }
+        (0, None) => {
+            return Err(AppError::Control(format!(
+                "no game is running supervised (no sessions in {})",
+                sockets_dir().display()
+            )))
+        }
+        (1, _) => matching.remove(0),
+        (_, _) => {
+            let running: Vec<String> = matching
+                .iter()
+                .map(|(_, status)| match (&status.name, status.app_id) {
+                    (Some(name), Some(app_id)) => format!("{} ({})", name, app_id),
+                    _ => status.display_name(),
+                })
+                .collect();
+            return Err(AppError::Control(format!(
+                "{} games are running ({}); pick one with --app-id",
+                running.len(),
+                running.join(", ")
+            )));
+        }
+    };
+
+    stop_session(socket)?;
+    println!("Stopping {}...", status.display_name());
+    let deadline = Instant::now() + STOP_WAIT;
+    while socket.exists() {
+        if Instant::now() >= deadline {
+            println!("{} is still shutting down.", status.display_name());
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    println!("Stopped {}.", status.display_name());
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use super::config::init_config;
+use super::install::handle_install;
+use super::launch_options::set_for_games;
+use super::proton::install_version;
+use super::{confirm, prompt};
+use crate::config::get_config_path;
+use crate::error::AppError;
+use crate::proton::list_proton_versions;
+use crate::steam::userdata::get_user_names;
+use crate::steam::{find_installed_games, find_user_ids, is_steam_running};
+use std::fs;
+
+/// Handle the setup command - walk through first-time setup step by step
+///
+/// Each step checks whether it is already done and asks before changing anything,
+/// so running setup again is harmless.
+pub fn handle_setup() -> Result<(), AppError> {
+    println!("steam-command-runner setup\n");
+
+    println!("[1/5] Compatibility tool");
+    setup_proton()?;
+
+    println!("\n[2/5] Gamescope shim");
+    setup_shim()?;
+
+    println!("\n[3/5] Configuration");
+    setup_config()?;
+
+    println!("\n[4/5] Steam user");
+    let user_id = select_user()?;
+
+    println!("\n[5/5] Launch options");
+    match user_id {
+        Some(user_id) => setup_launch_options(user_id)?,
+        None => println!("Skipped: no Steam user selected."),
+    }
+
+    println!("\nSetup complete.");
+    Ok(())
+}
+
+fn setup_proton() -> Result<(), AppError> {
+    let versions = list_proton_versions();
+    if let Some((name, _)) = versions.iter().find(|(name, _)| name.starts_with("GE-Proton")) {
+        println!("GE-Proton is installed ({}).", name);
+        return Ok(());
+    }
+
+    println!("Found {} Proton version(s), none of them GE-Proton.", versions.len());
+    if confirm("Download and install the latest GE-Proton?")? {
+        install_version("latest", false)?;
+    }
+    Ok(())
+}
+
+fn setup_shim() -> Result<(), AppError> {
+    let shim = dirs::home_dir()
+        .ok_or_else(|| AppError::ExecutionFailed("could not find home directory".to_string()))?
+        .join(".local/bin/gamescope");
+
+    let ours = fs::read_link(&shim).is_ok_and(|target| target.to_string_lossy().contains("steam-command-runner"));
+    if ours {
+        println!("The shim is installed at {}.", shim.display());
+        return Ok(());
+    }
+
+    if shim.exists() {
+        println!("{} exists but isn't our shim; leaving it alone.", shim.display());
+        println!("Run 'steam-command-runner install --path <path>' to install it elsewhere.");
+        return Ok(());
+    }
+
+    if confirm(&format!("Install the gamescope shim to {}?", shim.display()))? {
+        handle_install(Some(shim), "gamescope")?;
+    }
+    Ok(())
+}
+
+fn setup_config() -> Result<(), AppError> {
+    let path = get_config_path();
+    if path.exists() {
+        println!("Config exists at {}.", path.display());
+        return Ok(());
+    }
+
+    if confirm(&format!("Create a default config at {}?", path.display()))? {
+        init_config()?;
+    }
+    Ok(())
+}
+
+/// Pick the Steam user to configure, asking when there is more than one
+fn select_user() -> Result<Option<u64>, AppError> {
+    let user_ids = match find_user_ids() {
+        Ok(ids) if !ids.is_empty() => ids,
+        Ok(_) => {
+            println!("No Steam users found. Log in to Steam once, then run setup again.");
+            return Ok(None);
+        }
+        Err(e) => {
+            println!("Could not find Steam users: {}", e);
+            return Ok(None);
+        }
+    };
+    let names = get_user_names().unwrap_or_default();
+    let label = |id: &u64| match names.get(id) {
+        Some(name) => format!("{} ({})", id, name),
+        None => id.to_string(),
+    };
+
+    if let [user_id] = user_ids[..] {
+        println!("Using Steam user {}.", label(&user_id));
+        return Ok(Some(user_id));
+    }
+
+    println!("Steam users:");
+    for (i, id) in user_ids.iter().enumerate() {
+        println!("  {}) {}", i + 1, label(id));
+    }
+    let answer = prompt("Which user? [number, empty to skip]")?;
+    let user_id = answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| user_ids.get(i).copied());
+    if user_id.is_none() && !answer.is_empty() {
+        println!("Not a listed user.");
+    }
+    Ok(user_id)
+}
+
+fn setup_launch_options(user_id: u64) -> Result<(), AppError> {
+    let games = find_installed_games()?;
+    if games.is_empty() {
+        println!("No installed games found.");
+        return Ok(());
+    }
+
+    println!("Installed games:");
+    for game in &games {
+        println!("  {:>8}  {}", game.app_id, game.name);
+    }
+    let answer = prompt("Set launch options for which games? [all, App IDs separated by spaces, empty to skip]")?;
+
+    let app_ids: Vec<u32> = if answer.eq_ignore_ascii_case("all") {
+        games.iter().map(|g| g.app_id).collect()
+    } else {
+        answer
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| match s.parse::<u32>() {
+                Ok(id) if games.iter().any(|g| g.app_id == id) => Some(id),
+                _ => {
+                    println!("Ignoring '{}': not an installed game's App ID", s);
+                    None
+                }
+            })
+            .collect()
+    };
+    if app_ids.is_empty() {
+        println!("No launch options changed.");
+        return Ok(());
+    }
+
+    let restart_steam = is_steam_running();
+    if restart_steam && !confirm("Steam has to be restarted to change launch options. Restart it now?")? {
+        println!("No launch options changed; run 'steam-command-runner launch-options set-all' later.");
+        return Ok(());
+    }
+    set_for_games(user_id, &app_ids, restart_steam)
+}
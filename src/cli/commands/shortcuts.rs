@@ -0,0 +1,182 @@
+use super::launch_options::{create_backup, resolve_user_id, SteamRunning};
+use crate::cli::ShortcutsAction;
+use crate::config::MergedConfig;
+use crate::error::AppError;
+use crate::steam::{
+    assign_compat_tool, generate_default_launch_options, get_grid_dir, get_shortcuts_path,
+    read_shortcuts, write_shortcuts, Shortcut,
+};
+use crate::steam_api::download_artwork;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Handle the shortcuts command and its subcommands
+pub fn handle_shortcuts(action: ShortcutsAction) -> Result<(), AppError> {
+    match action {
+        ShortcutsAction::List { user_id } => list_shortcuts(user_id),
+
+        ShortcutsAction::Add {
+            name,
+            exe,
+            start_dir,
+            options,
+            compat_tool,
+            artwork,
+            backup,
+            user_id,
+            restart_steam,
+            force,
+        } => {
+            let user_id = resolve_user_id(user_id)?;
+            SteamRunning::new(restart_steam, force).apply(|| {
+                let app_id = add_shortcut(&name, exe, start_dir, options, compat_tool, backup, user_id)?;
+                if artwork {
+                    // The shortcut is already added; missing art shouldn't fail the command
+                    if let Err(e) = fetch_artwork(user_id, app_id, &name) {
+                        warn!("Could not add artwork: {}", e);
+                    }
+                }
+                Ok(())
+            })
+        }
+
+        ShortcutsAction::Artwork {
+            app_id,
+            search,
+            user_id,
+        } => {
+            let user_id = resolve_user_id(user_id)?;
+            let name = match search {
+                Some(name) => name,
+                None => read_shortcuts(get_shortcuts_path(user_id)?)?
+                    .iter()
+                    .find(|shortcut| shortcut.app_id() == app_id)
+                    .map(|shortcut| shortcut.name().to_string())
+                    .ok_or_else(|| AppError::GameNotFound(format!("non-Steam game {}", app_id)))?,
+            };
+            fetch_artwork(user_id, app_id, &name)?;
+            println!();
+            println!("Note: Restart Steam for changes to take effect.");
+            Ok(())
+        }
+
+        ShortcutsAction::Remove {
+            app_id,
+            backup,
+            user_id,
+            restart_steam,
+            force,
+        } => remove_shortcut(app_id, backup, user_id, SteamRunning::new(restart_steam, force)),
+    }
+}
+
+/// List all non-Steam games
+fn list_shortcuts(user_id: Option<u64>) -> Result<(), AppError> {
+    let user_id = resolve_user_id(user_id)?;
+    let path = get_shortcuts_path(user_id)?;
+    let shortcuts = read_shortcuts(&path)?;
+
+    if shortcuts.iter().next().is_none() {
+        println!("No non-Steam games found.");
+        return Ok(());
+    }
+
+    println!("Non-Steam games:");
+    for shortcut in shortcuts.iter() {
+        println!("  {} ({})", shortcut.name(), shortcut.app_id());
+        println!("    Exe: {}", shortcut.exe());
+        if !shortcut.launch_options().is_empty() {
+            println!("    Launch options: {}", shortcut.launch_options());
+        }
+    }
+
+    Ok(())
+}
+
+/// Add (or replace) a non-Steam game, returning its App ID
+pub(crate) fn add_shortcut(
+    name: &str,
+    exe: PathBuf,
+    start_dir: Option<PathBuf>,
+    options: Option<String>,
+    compat_tool: Option<String>,
+    backup: bool,
+    user_id: u64,
+) -> Result<u32, AppError> {
+    let path = get_shortcuts_path(user_id)?;
+
+    let exe = std::path::absolute(&exe)?;
+    let start_dir = match start_dir {
+        Some(dir) => std::path::absolute(dir)?,
+        None => exe.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+    let launch_options = options.unwrap_or_else(generate_default_launch_options);
+
+    if backup && path.exists() {
+        create_backup(user_id, &path)?;
+    }
+
+    let mut shortcuts = read_shortcuts(&path)?;
+    let mut shortcut = Shortcut::new(name, &exe, &start_dir);
+    shortcut.set_launch_options(&launch_options);
+    let app_id = shortcut.app_id();
+    shortcuts.add(shortcut);
+    write_shortcuts(&path, &shortcuts)?;
+
+    println!("Added non-Steam game {} ({}):", name, app_id);
+    println!("  Launch options: {}", launch_options);
+
+    if let Some(tool) = compat_tool {
+        match assign_compat_tool(app_id, Some(&tool)) {
+            Ok(()) => println!("  Compatibility tool: {}", tool),
+            Err(e) => warn!("Could not assign compatibility tool: {}", e),
+        }
+    }
+
+    Ok(app_id)
+}
+
+/// Download SteamGridDB artwork for a non-Steam game into the user's grid directory
+pub(crate) fn fetch_artwork(user_id: u64, app_id: u32, name: &str) -> Result<(), AppError> {
+    let api_key = MergedConfig::load(None, None)?
+        .steamgriddb
+        .api_key
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| {
+            AppError::SteamGridDb("no API key; set api_key in the [steamgriddb] section of config.toml".to_string())
+        })?;
+
+    let written = download_artwork(&api_key, name, app_id, &get_grid_dir(user_id)?)?;
+    if written.is_empty() {
+        println!("  SteamGridDB has no artwork for {}", name);
+    }
+    for (kind, path) in &written {
+        println!("  Artwork ({}): {}", kind.label(), path.display());
+    }
+    Ok(())
+}
+
+/// Remove a non-Steam game and its compat tool mapping
+fn remove_shortcut(app_id: u32, backup: bool, user_id: Option<u64>, steam: SteamRunning) -> Result<(), AppError> {
+    let user_id = resolve_user_id(user_id)?;
+    let path = get_shortcuts_path(user_id)?;
+
+    steam.apply(|| {
+        let mut shortcuts = read_shortcuts(&path)?;
+        let Some(removed) = shortcuts.remove(app_id) else {
+            return Err(AppError::GameNotFound(format!("non-Steam game {}", app_id)));
+        };
+
+        if backup {
+            create_backup(user_id, &path)?;
+        }
+        write_shortcuts(&path, &shortcuts)?;
+
+        if let Err(e) = assign_compat_tool(app_id, None) {
+            warn!("Could not clear compatibility tool mapping: {}", e);
+        }
+
+        println!("Removed non-Steam game {} ({}).", removed.name(), app_id);
+        Ok(())
+    })
+}
@@ -0,0 +1,70 @@
+use super::print_json;
+use crate::error::AppError;
+use crate::playtime::{format_duration, format_time, load_sessions, playtime_path, summarize};
+use serde_json::json;
+
+/// Handle the stats command
+pub fn handle_stats(app_id: Option<u32>, recent: usize, json: bool) -> Result<(), AppError> {
+    let sessions: Vec<_> = load_sessions()
+        .into_iter()
+        .filter(|s| app_id.is_none() || s.app_id == app_id)
+        .collect();
+
+    if json {
+        let games: Vec<_> = summarize(&sessions)
+            .iter()
+            .map(|game| {
+                json!({
+                    "app_id": game.app_id,
+                    "name": game.name,
+                    "sessions": game.sessions,
+                    "total_secs": game.total_secs,
+                    "last_played": game.last_played,
+                })
+            })
+            .collect();
+        let recent: Vec<_> = sessions.iter().rev().take(recent).collect();
+        return print_json(&json!({ "games": games, "recent": recent }));
+    }
+
+    if sessions.is_empty() {
+        println!("No play sessions recorded in {}.", playtime_path().display());
+        println!("Set track_playtime = true in the config to record them.");
+        return Ok(());
+    }
+
+    let game_name = |app_id: Option<u32>, name: Option<&str>| match (name, app_id) {
+        (Some(name), Some(id)) => format!("{} ({})", name, id),
+        (Some(name), None) => name.to_string(),
+        (None, Some(id)) => id.to_string(),
+        (None, None) => "(unknown)".to_string(),
+    };
+
+    println!("Playtime:\n");
+    for game in summarize(&sessions) {
+        println!(
+            "  {:>10}  {:>4} sessions  last {}  {}",
+            format_duration(game.total_secs),
+            game.sessions,
+            format_time(game.last_played),
+            game_name(game.app_id, game.name.as_deref())
+        );
+    }
+
+    println!("\nRecent sessions:\n");
+    for session in sessions.iter().rev().take(recent) {
+        let proton = session
+            .proton
+            .as_deref()
+            .map_or(String::new(), |p| format!(" [{}]", p));
+        println!(
+            "  {}  {:>10}  exit {:>3}  {}{}",
+            format_time(session.start),
+            format_duration(session.duration_secs()),
+            session.exit_code,
+            game_name(session.app_id, session.name.as_deref()),
+            proton
+        );
+    }
+    Ok(())
+}
@@ -1,4 +1,8 @@
 pub mod args;
 pub mod commands;
 
-pub use args::{Cli, Commands, ConfigAction, GamescopeAction, LaunchOptionsAction, ProtonAction};
+pub use args::{
+    BenchAction, CacheAction, Cli, Commands, CompatToolAction, ConfigAction, GamescopeAction, GamesAction, GpuAction,
+    HeroicAction, ImportAction, LaunchOptionsAction, LogsAction, ModManagerAction, PrefixAction, ProtonAction,
+    SavesAction, ShortcutsAction,
+};
@@ -0,0 +1,474 @@
+use super::overlay::ConfigOverlay;
+use crate::gpu::gpu_vendors;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// A conditional block: overrides applied only when all its conditions hold
+///
+/// ```toml
+/// [[when]]
+/// schedule = { after = "22:00", before = "06:00" }
+/// gamescope_args = "-w 1280 -h 720 -r 30"
+///
+/// [[when]]
+/// hostname = "steamdeck"
+/// battery = true
+/// gamescope = { refresh = 40 }
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WhenBlock {
+    /// Time window in which the block applies
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+
+    /// Host name the block applies on (case-insensitive)
+    #[serde(default)]
+    pub hostname: Option<String>,
+
+    /// Applies when a GPU from this vendor is present: "amd", "intel" or "nvidia"
+    #[serde(default)]
+    pub gpu_vendor: Option<String>,
+
+    /// Applies when running on battery (true) or on mains power (false)
+    #[serde(default)]
+    pub battery: Option<bool>,
+
+    /// Applies on a Steam Deck (true) or anywhere else (false)
+    #[serde(default)]
+    pub steam_deck: Option<bool>,
+
+    /// Profile to activate when the block matches (unless one was chosen explicitly)
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Settings applied when the block matches
+    #[serde(flatten)]
+    pub overlay: ConfigOverlay,
+}
+
+impl WhenBlock {
+    /// Check whether every condition in the block holds
+    pub fn matches(&self, facts: &HostFacts) -> bool {
+        if let Some(schedule) = &self.schedule {
+            if !schedule.matches(&facts.now) {
+                return false;
+            }
+        }
+        if let Some(hostname) = &self.hostname {
+            if !facts.hostname.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(hostname)) {
+                return false;
+            }
+        }
+        if let Some(vendor) = &self.gpu_vendor {
+            if !facts.gpu_vendors.iter().any(|v| v.eq_ignore_ascii_case(vendor)) {
+                return false;
+            }
+        }
+        if let Some(battery) = self.battery {
+            if facts.on_battery != battery {
+                return false;
+            }
+        }
+        if let Some(steam_deck) = self.steam_deck {
+            if facts.steam_deck != steam_deck {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A time-of-day and day-of-week window
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Start time ("HH:MM", inclusive)
+    #[serde(default)]
+    pub after: Option<String>,
+
+    /// End time ("HH:MM", exclusive); may be earlier than `after` to wrap past midnight
+    #[serde(default)]
+    pub before: Option<String>,
+
+    /// Days the window applies on ("mon".."sun", "weekdays", "weekends"); empty means every day
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+impl Schedule {
+    /// Check whether the given local time falls within this schedule
+    pub fn matches(&self, now: &LocalTime) -> bool {
+        if !self.days.is_empty() && !self.days.iter().any(|d| day_matches(d, now.weekday)) {
+            return false;
+        }
+
+        let after = match self.after.as_deref().map(parse_time) {
+            Some(Some(t)) => Some(t),
+            Some(None) => return false,
+            None => None,
+        };
+        let before = match self.before.as_deref().map(parse_time) {
+            Some(Some(t)) => Some(t),
+            Some(None) => return false,
+            None => None,
+        };
+
+        let t = now.minutes;
+        match (after, before) {
+            (Some(a), Some(b)) if a <= b => t >= a && t < b,
+            (Some(a), Some(b)) => t >= a || t < b,
+            (Some(a), None) => t >= a,
+            (None, Some(b)) => t < b,
+            (None, None) => true,
+        }
+    }
+}
+
+/// Parse "HH:MM" into minutes since midnight
+fn parse_time(value: &str) -> Option<u32> {
+    let parsed = value.split_once(':').and_then(|(h, m)| {
+        let hours: u32 = h.trim().parse().ok()?;
+        let minutes: u32 = m.trim().parse().ok()?;
+        (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+    });
+
+    if parsed.is_none() {
+        warn!("Invalid schedule time '{}', expected HH:MM", value);
+    }
+    parsed
+}
+
+/// Check a configured day name against a weekday (0 = Sunday)
+fn day_matches(day: &str, weekday: u32) -> bool {
+    let day = day.to_lowercase();
+    match day.as_str() {
+        "weekdays" => (1..=5).contains(&weekday),
+        "weekends" => weekday == 0 || weekday == 6,
+        _ => {
+            let names = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+            match names.iter().position(|n| day.starts_with(n)) {
+                Some(index) => index as u32 == weekday,
+                None => {
+                    warn!("Invalid schedule day '{}'", day);
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Local wall-clock time used for schedule conditions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    /// Minutes since local midnight
+    pub minutes: u32,
+    /// Day of the week (0 = Sunday)
+    pub weekday: u32,
+}
+
+impl LocalTime {
+    /// Current local time according to the system timezone
+    pub fn now() -> Self {
+        // SAFETY: time() and localtime_r() only write to the tm struct we own
+        unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&now, &mut tm);
+            Self {
+                minutes: (tm.tm_hour * 60 + tm.tm_min) as u32,
+                weekday: tm.tm_wday as u32,
+            }
+        }
+    }
+}
+
+/// Facts about the current host that conditions are evaluated against
+#[derive(Debug, Default, Clone)]
+pub struct HostFacts {
+    /// Current local time
+    pub now: LocalTime,
+    /// Host name
+    pub hostname: Option<String>,
+    /// Vendors of the GPUs present ("AMD", "Intel", "NVIDIA")
+    pub gpu_vendors: Vec<String>,
+    /// Whether the machine is running on battery
+    pub on_battery: bool,
+    /// Whether the machine is a Steam Deck
+    pub steam_deck: bool,
+    /// Whether the session or a connected display can show HDR; None until
+    /// `hdr = "auto"` needs it, as reading every display's EDID is the slowest check
+    pub hdr: Option<bool>,
+}
+
+impl HostFacts {
+    /// Gather facts about the current host
+    pub fn detect() -> Self {
+        let facts = Self {
+            now: LocalTime::now(),
+            hostname: fs::read_to_string("/proc/sys/kernel/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty()),
+            gpu_vendors: gpu_vendors().into_iter().map(str::to_string).collect(),
+            on_battery: on_battery(Path::new("/sys/class/power_supply")),
+            steam_deck: is_steam_deck(),
+            hdr: None,
+        };
+        debug!("Host facts: {:?}", facts);
+        facts
+    }
+
+    /// Gather the facts a config can consult
+    ///
+    /// Only `[[when]]` blocks look at the time, host name, GPUs and battery, so
+    /// without any those are left at their defaults. Whether this is a Steam
+    /// Deck is always needed for the gamescope defaults.
+    pub fn detect_for(has_conditions: bool) -> Self {
+        if has_conditions {
+            return Self::detect();
+        }
+        Self {
+            steam_deck: is_steam_deck(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether HDR output is available, detecting it unless already known
+    pub fn hdr(&self) -> bool {
+        self.hdr.unwrap_or_else(hdr_available)
+    }
+}
+
+/// Whether this is a Steam Deck
+///
+/// `SteamDeck=1` (set by Steam in Game Mode) or `SteamDeck=0` decide; otherwise
+/// Valve's board is looked for in the DMI tables.
+pub fn is_steam_deck() -> bool {
+    match std::env::var("SteamDeck").as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => {
+            let read = |name: &str| fs::read_to_string(Path::new("/sys/class/dmi/id").join(name)).unwrap_or_default();
+            is_deck_dmi(&read("sys_vendor"), &read("product_name"))
+        }
+    }
+}
+
+/// Valve's DMI identifiers: "Jupiter" is the LCD model, "Galileo" the OLED one
+fn is_deck_dmi(vendor: &str, product: &str) -> bool {
+    vendor.trim() == "Valve" && matches!(product.trim(), "Jupiter" | "Galileo")
+}
+
+/// Whether HDR output is available
+///
+/// True when the session already advertises HDR to games (DXVK_HDR or
+/// ENABLE_HDR_WSI set to 1), or when a connected display's EDID supports PQ.
+pub fn hdr_available() -> bool {
+    let on = |name: &str| std::env::var(name).as_deref() == Ok("1");
+    on("DXVK_HDR") || on("ENABLE_HDR_WSI") || drm_hdr(Path::new("/sys/class/drm"))
+}
+
+/// Whether any connected connector in /sys/class/drm has an HDR-capable display
+fn drm_hdr(drm: &Path) -> bool {
+    fs::read_dir(drm).into_iter().flatten().flatten().any(|entry| {
+        let path = entry.path();
+        fs::read_to_string(path.join("status")).is_ok_and(|s| s.trim() == "connected")
+            && fs::read(path.join("edid")).is_ok_and(|edid| edid_supports_hdr(&edid))
+    })
+}
+
+/// Look for an HDR static metadata block advertising the SMPTE ST 2084 (PQ) EOTF
+///
+/// The block lives in a CTA-861 extension: data blocks run from byte 4 up to the
+/// detailed timing offset in byte 2, each with a tag in the top 3 bits of its
+/// header and the length in the bottom 5. Extended tag 6 is HDR static metadata.
+fn edid_supports_hdr(edid: &[u8]) -> bool {
+    edid.chunks_exact(128).skip(1).any(|block| {
+        if block[0] != 0x02 {
+            return false;
+        }
+        let end = (block[2] as usize).clamp(4, 127);
+        let mut i = 4;
+        while i < end {
+            let tag = block[i] >> 5;
+            let len = (block[i] & 0x1f) as usize;
+            if tag == 7 && len >= 2 && i + 2 < block.len() && block[i + 1] == 6 {
+                return block[i + 2] & 0x04 != 0;
+            }
+            i += len + 1;
+        }
+        false
+    })
+}
+
+/// Whether the machine runs on battery: it has a battery and no AC adapter is online
+///
+/// Without an AC adapter entry (some handhelds), a discharging battery counts.
+fn on_battery(power_supply: &Path) -> bool {
+    let read = |path: &Path| fs::read_to_string(path).map(|s| s.trim().to_string()).unwrap_or_default();
+    let mut has_battery = false;
+    let mut discharging = false;
+    let mut has_mains = false;
+    let mut mains_online = false;
+
+    for entry in fs::read_dir(power_supply).into_iter().flatten().flatten() {
+        let path = entry.path();
+        match read(&path.join("type")).as_str() {
+            "Battery" => {
+                has_battery = true;
+                discharging |= read(&path.join("status")) == "Discharging";
+            }
+            "Mains" => {
+                has_mains = true;
+                mains_online |= read(&path.join("online")) == "1";
+            }
+            _ => {}
+        }
+    }
+
+    has_battery && if has_mains { !mains_online } else { discharging }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32, weekday: u32) -> LocalTime {
+        LocalTime {
+            minutes: hour * 60 + minute,
+            weekday,
+        }
+    }
+
+    fn window(after: Option<&str>, before: Option<&str>, days: &[&str]) -> Schedule {
+        Schedule {
+            after: after.map(str::to_string),
+            before: before.map(str::to_string),
+            days: days.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_schedule_same_day_window() {
+        let schedule = window(Some("09:00"), Some("17:30"), &[]);
+        assert!(schedule.matches(&at(9, 0, 1)));
+        assert!(schedule.matches(&at(17, 29, 1)));
+        assert!(!schedule.matches(&at(17, 30, 1)));
+        assert!(!schedule.matches(&at(8, 59, 1)));
+    }
+
+    #[test]
+    fn test_schedule_wraps_midnight() {
+        let schedule = window(Some("22:00"), Some("06:00"), &[]);
+        assert!(schedule.matches(&at(23, 15, 3)));
+        assert!(schedule.matches(&at(2, 0, 3)));
+        assert!(!schedule.matches(&at(12, 0, 3)));
+    }
+
+    #[test]
+    fn test_schedule_open_ended() {
+        assert!(window(Some("22:00"), None, &[]).matches(&at(22, 0, 0)));
+        assert!(!window(Some("22:00"), None, &[]).matches(&at(21, 59, 0)));
+        assert!(window(None, Some("06:00"), &[]).matches(&at(5, 0, 0)));
+    }
+
+    #[test]
+    fn test_schedule_days() {
+        let weekends = window(None, None, &["weekends"]);
+        assert!(weekends.matches(&at(12, 0, 0)));
+        assert!(weekends.matches(&at(12, 0, 6)));
+        assert!(!weekends.matches(&at(12, 0, 3)));
+
+        let named = window(None, None, &["Mon", "friday"]);
+        assert!(named.matches(&at(12, 0, 1)));
+        assert!(named.matches(&at(12, 0, 5)));
+        assert!(!named.matches(&at(12, 0, 2)));
+    }
+
+    #[test]
+    fn test_schedule_invalid_time_never_matches() {
+        assert!(!window(Some("25:00"), None, &[]).matches(&at(12, 0, 0)));
+    }
+
+    #[test]
+    fn test_when_block_from_toml() {
+        let block: WhenBlock = toml::from_str(
+            r#"
+            schedule = { after = "22:00" }
+            gamescope_args = "-r 30"
+            [env]
+            DXVK_FRAME_RATE = "30"
+            "#,
+        )
+        .unwrap();
+        assert!(block.matches(&HostFacts { now: at(23, 0, 2), ..HostFacts::default() }));
+        assert!(!block.matches(&HostFacts { now: at(20, 0, 2), ..HostFacts::default() }));
+        assert_eq!(block.overlay.gamescope_args.as_deref(), Some("-r 30"));
+        assert_eq!(block.overlay.env.get("DXVK_FRAME_RATE").map(String::as_str), Some("30"));
+    }
+
+    #[test]
+    fn test_host_conditions() {
+        let block: WhenBlock = toml::from_str(
+            r#"
+            hostname = "SteamDeck"
+            gpu_vendor = "amd"
+            battery = true
+            "#,
+        )
+        .unwrap();
+        let deck = HostFacts {
+            hostname: Some("steamdeck".to_string()),
+            gpu_vendors: vec!["AMD".to_string()],
+            on_battery: true,
+            ..HostFacts::default()
+        };
+        assert!(block.matches(&deck));
+        assert!(!block.matches(&HostFacts { on_battery: false, ..deck.clone() }));
+        assert!(!block.matches(&HostFacts { hostname: Some("desktop".to_string()), ..deck.clone() }));
+        assert!(!block.matches(&HostFacts { gpu_vendors: vec!["NVIDIA".to_string()], ..deck }));
+
+        let block: WhenBlock = toml::from_str("steam_deck = true").unwrap();
+        assert!(block.matches(&HostFacts { steam_deck: true, ..HostFacts::default() }));
+        assert!(!block.matches(&HostFacts::default()));
+        assert!(is_deck_dmi("Valve\n", "Galileo\n"));
+        assert!(!is_deck_dmi("Valve", "Index"));
+    }
+
+    #[test]
+    fn test_on_battery() {
+        let dir = tempfile::tempdir().unwrap();
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            for (file, value) in files {
+                fs::write(path.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        assert!(on_battery(dir.path()));
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert!(!on_battery(dir.path()));
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        assert!(on_battery(dir.path()));
+    }
+
+    #[test]
+    fn test_edid_supports_hdr() {
+        let mut edid = vec![0u8; 256];
+        let cta = &mut edid[128..];
+        cta[0] = 0x02;
+        cta[2] = 12;
+        // Video data block (tag 2, 3 bytes), then HDR static metadata (extended tag 6)
+        cta[4..8].copy_from_slice(&[0x43, 0x10, 0x04, 0x03]);
+        cta[8..12].copy_from_slice(&[0xe3, 0x06, 0x05, 0x01]);
+        assert!(edid_supports_hdr(&edid));
+
+        // SDR and HLG only
+        edid[128 + 10] = 0x09;
+        assert!(!edid_supports_hdr(&edid));
+        assert!(!edid_supports_hdr(&edid[..128]));
+    }
+}
@@ -0,0 +1,158 @@
+use super::error::ConfigError;
+use super::global::GlobalConfig;
+use super::interpolate::{from_value, parse_config};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use tracing::debug;
+
+/// Size and modification time of each file a config was read from, so a cached
+/// parse can be reused until one of them changes
+type Stamps = Vec<(PathBuf, Option<(u64, SystemTime)>)>;
+
+/// Configs already parsed by this process, by main file path
+static PARSED: OnceLock<Mutex<HashMap<PathBuf, (Stamps, GlobalConfig)>>> = OnceLock::new();
+
+/// Forget the configs parsed so far, so the next load reads the files again
+pub(crate) fn clear_parsed() {
+    if let Some(parsed) = PARSED.get() {
+        parsed.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// Drop-in fragments for a global config file: `*.toml` in the `config.d`
+/// directory next to it, in lexical order
+pub fn config_fragments(config_path: &Path) -> Vec<PathBuf> {
+    let dir = config_path.with_file_name("config.d");
+    let mut fragments: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+                .collect()
+        })
+        .unwrap_or_default();
+    fragments.sort();
+    fragments
+}
+
+/// Load the global config, with any `config.d` fragments merged on top
+///
+/// A missing main file is treated as empty, so fragments alone are enough.
+/// A launch asks for the global config several times, so the parse is kept for
+/// the rest of the process and only redone once a file has changed.
+pub fn load_global_config(config_path: &Path) -> Result<GlobalConfig, ConfigError> {
+    let fragments = config_fragments(config_path);
+    let stamps: Stamps = std::iter::once(config_path)
+        .chain(fragments.iter().map(PathBuf::as_path))
+        .map(|path| (path.to_path_buf(), stamp(path)))
+        .collect();
+
+    let parsed = PARSED.get_or_init(Default::default);
+    if let Some((cached, config)) = parsed.lock().unwrap_or_else(|e| e.into_inner()).get(config_path) {
+        if *cached == stamps {
+            debug!("Using already loaded global config: {}", config_path.display());
+            return Ok(config.clone());
+        }
+    }
+
+    let config = read_global_config(config_path, fragments)?;
+    parsed
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(config_path.to_path_buf(), (stamps, config.clone()));
+    Ok(config)
+}
+
+/// Size and modification time of a file, or None if it can't be read
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+fn read_global_config(config_path: &Path, fragments: Vec<PathBuf>) -> Result<GlobalConfig, ConfigError> {
+    if fragments.is_empty() {
+        if !config_path.exists() {
+            debug!("No global config found, using defaults");
+            return Ok(GlobalConfig::default());
+        }
+        debug!("Loading global config from: {}", config_path.display());
+        return parse_config(&fs::read_to_string(config_path)?);
+    }
+
+    let mut merged = if config_path.exists() {
+        debug!("Loading global config from: {}", config_path.display());
+        fs::read_to_string(config_path)?.parse::<toml::Table>()?
+    } else {
+        toml::Table::new()
+    };
+    for fragment in fragments {
+        debug!("Merging config fragment: {}", fragment.display());
+        merge_tables(&mut merged, fs::read_to_string(&fragment)?.parse::<toml::Table>()?);
+    }
+    from_value(toml::Value::Table(merged))
+}
+
+/// Merge `overlay` into `base`
+///
+/// Tables are merged key by key and arrays of tables (`[[when]]`, `[[hooks.pre_launch]]`)
+/// are appended to; any other value replaces the one in `base`.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge_tables(base, overlay),
+            (Some(toml::Value::Array(base)), toml::Value::Array(overlay))
+                if is_table_array(base) && is_table_array(&overlay) =>
+            {
+                base.extend(overlay)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn is_table_array(array: &[toml::Value]) -> bool {
+    array.iter().all(|value| value.is_table())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_tables() {
+        let mut base: toml::Table = r#"
+            pre_command = "gamemoderun"
+            ld_preload_extra = ["a.so"]
+            [mangohud]
+            enabled = true
+            position = "top-left"
+            [[when]]
+            hostname = "deck"
+        "#
+        .parse()
+        .unwrap();
+        let fragment: toml::Table = r#"
+            ld_preload_extra = ["b.so"]
+            [mangohud]
+            position = "top-right"
+            [[when]]
+            battery = true
+        "#
+        .parse()
+        .unwrap();
+        merge_tables(&mut base, fragment);
+
+        let config: GlobalConfig = from_value(toml::Value::Table(base)).unwrap();
+        assert_eq!(config.pre_command.as_deref(), Some("gamemoderun"));
+        assert_eq!(config.ld_preload_extra, vec!["b.so"]);
+        assert_eq!(config.mangohud.enabled, Some(true));
+        assert_eq!(config.mangohud.position.as_deref(), Some("top-right"));
+        assert_eq!(config.when.len(), 2);
+    }
+}
@@ -0,0 +1,87 @@
+use super::error::ConfigError;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Split a dotted key ("gamescope.fullscreen") into its parts
+fn key_parts(key: &str) -> Result<Vec<&str>, ConfigError> {
+    let parts: Vec<&str> = key.split('.').map(str::trim).collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(ConfigError::Edit(format!("invalid key '{}'", key)));
+    }
+    Ok(parts)
+}
+
+/// Parse a value given on the command line
+///
+/// Anything that is a valid TOML value (true, 60, "text", [1, 2]) is used as
+/// such; everything else is taken as a string, so quoting is rarely needed.
+fn parse_value(value: &str) -> Value {
+    value.parse::<Value>().unwrap_or_else(|_| Value::from(value))
+}
+
+/// Set a dotted key in a config file's contents, keeping comments and layout
+///
+/// Missing tables along the way are created.
+pub fn set_key(content: &str, key: &str, value: &str) -> Result<String, ConfigError> {
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| ConfigError::Edit(e.to_string()))?;
+    let parts = key_parts(key)?;
+    let (last, tables) = parts.split_last().expect("key_parts returns at least one part");
+
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for (i, part) in tables.iter().enumerate() {
+        let item = table.entry(part).or_insert(toml_edit::table());
+        table = item.as_table_like_mut().ok_or_else(|| {
+            ConfigError::Edit(format!("'{}' is not a table", parts[..=i].join(".")))
+        })?;
+    }
+    let mut value = parse_value(value);
+    // Keep the spacing and trailing comment of a value being replaced
+    if let Some(old) = table.get(last).and_then(Item::as_value) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    table.insert(last, Item::Value(value));
+    Ok(doc.to_string())
+}
+
+/// Read a dotted key from a config file's contents
+pub fn get_key(content: &str, key: &str) -> Result<Option<toml::Value>, ConfigError> {
+    let mut value = toml::Value::Table(toml::from_str(content)?);
+    for part in key_parts(key)? {
+        let next = match (&mut value, part.parse::<usize>()) {
+            (toml::Value::Array(array), Ok(index)) if index < array.len() => array.swap_remove(index),
+            (toml::Value::Table(table), _) => match table.remove(part) {
+                Some(next) => next,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        value = next;
+    }
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_get_key() {
+        let content = "# My game\nmode = \"proton\" # always\n\n[gamescope]\nwidth = 1920\n";
+
+        let updated = set_key(content, "gamescope.fullscreen", "true").unwrap();
+        assert_eq!(
+            updated,
+            "# My game\nmode = \"proton\" # always\n\n[gamescope]\nwidth = 1920\nfullscreen = true\n"
+        );
+        let updated = set_key(&updated, "mode", "native").unwrap();
+        assert!(updated.contains("mode = \"native\" # always\n"));
+        let updated = set_key(&updated, "mangohud.fps_limit", "60").unwrap();
+        assert!(updated.ends_with("[mangohud]\nfps_limit = 60\n"));
+        assert!(set_key(&updated, "mode.x", "1").is_err());
+
+        assert_eq!(get_key(&updated, "gamescope.width").unwrap(), Some(toml::Value::Integer(1920)));
+        assert_eq!(get_key(&updated, "mode").unwrap(), Some(toml::Value::String("native".to_string())));
+        assert_eq!(get_key(&updated, "gamescope.height").unwrap(), None);
+    }
+}
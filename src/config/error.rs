@@ -10,4 +10,7 @@ pub enum ConfigError {
 
     #[error("Failed to serialize config: {0}")]
     SerializeError(#[from] toml::ser::Error),
+
+    #[error("Could not edit config: {0}")]
+    Edit(String),
 }
@@ -1,7 +1,21 @@
-use super::global::{ExecutionMode, HooksConfig};
+use super::conditions::WhenBlock;
+use super::global::{
+    DiscordConfig, ExecutionMode, GamescopeOptions, HookConfig, HooksConfig, LimitsConfig, MangoHudConfig,
+    ProtonSetting, RestartConfig, SandboxConfig, SteamRuntime, WineConfig, WrapperConfig, merge_wrappers,
+};
+use super::overlay::ConfigOverlay;
+use crate::gpu::GpuSelector;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Which games a name-keyed config file applies to (`match = { name = "Hades*" }`)
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GameMatch {
+    /// Game name pattern, case-insensitive; `*` matches any run of characters and `?` one
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
 /// Per-game configuration, overrides global settings
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
@@ -9,13 +23,27 @@ pub struct GameConfig {
     #[serde(default)]
     pub name: Option<String>,
 
+    /// Games this file applies to, for files not named after an App ID
+    #[serde(default, rename = "match")]
+    pub match_rule: Option<GameMatch>,
+
+    /// Group whose settings sit between the global config and this one
+    /// (`[groups.<name>]` in the global config, or `groups/<name>.toml`)
+    #[serde(default)]
+    pub group: Option<String>,
+
     /// Override execution mode for this game
     #[serde(default)]
     pub mode: Option<ExecutionMode>,
 
-    /// Specific Proton version (overrides global default)
+    /// Run Steam's command exactly as given: no gamescope, environment, pre_command,
+    /// hooks or anything else from the config (an escape hatch when wrapping breaks a game)
     #[serde(default)]
-    pub proton: Option<String>,
+    pub passthrough: Option<bool>,
+
+    /// Specific Proton version (overrides global default), or a `[proton]` table of tunables
+    #[serde(default)]
+    pub proton: Option<ProtonSetting>,
 
     /// Override/extend pre_command
     /// Use "inherit" to include global pre_command, or specify full command
@@ -26,7 +54,12 @@ pub struct GameConfig {
     #[serde(default)]
     pub env: HashMap<String, String>,
 
-    /// Game-specific launch arguments
+    /// Wine DLL overrides for Proton and Wine launches, e.g. { dxgi = "n,b" } (merged with global)
+    #[serde(default)]
+    pub dll_overrides: HashMap<String, String>,
+
+    /// Game-specific launch arguments, appended to the game command unless they
+    /// place it with `%command%` (see also `%args%` and `%exe%`)
     #[serde(default)]
     pub launch_args: Vec<String>,
 
@@ -38,11 +71,340 @@ pub struct GameConfig {
     #[serde(default)]
     pub gamescope_args: Option<String>,
 
+    /// Typed gamescope options (each set field overrides the global one)
+    #[serde(default)]
+    pub gamescope: GamescopeOptions,
+
     /// Whether to enable gamescope for this game (overrides global)
     #[serde(default)]
     pub gamescope_enabled: Option<bool>,
 
+    /// Whether to launch without gamescope when it fails to start (overrides global)
+    #[serde(default)]
+    pub gamescope_fallback: Option<bool>,
+
     /// Arguments to append to the game command
     #[serde(default)]
     pub game_args: Option<String>,
+
+    /// Wrapper shim settings for this game (merged per wrapper with the global ones)
+    #[serde(default)]
+    pub wrappers: HashMap<String, WrapperConfig>,
+
+    /// Directory the game is started in ("~/" expands to home; relative paths are
+    /// relative to the game executable's directory)
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Proton compatdata directory when Steam doesn't provide one ("~/" expands to home)
+    #[serde(default)]
+    pub compat_data_path: Option<String>,
+
+    /// Directories prepended to LD_LIBRARY_PATH (placed before global entries)
+    #[serde(default)]
+    pub ld_library_path_prepend: Vec<String>,
+
+    /// Libraries appended to LD_PRELOAD (placed after global entries)
+    #[serde(default)]
+    pub ld_preload_extra: Vec<String>,
+
+    /// Check the native binary for missing shared libraries (overrides global)
+    #[serde(default)]
+    pub library_preflight: Option<bool>,
+
+    /// Capture the game's stdout/stderr to a log file (overrides global)
+    #[serde(default)]
+    pub capture_game_output: Option<bool>,
+
+    /// Run the game supervised instead of exec'ing (overrides global)
+    #[serde(default)]
+    pub supervise: Option<bool>,
+
+    /// Relaunch the game after a crash (overrides global)
+    #[serde(default)]
+    pub restart_on_crash: Option<RestartConfig>,
+
+    /// Record this game's play sessions (overrides global)
+    #[serde(default)]
+    pub track_playtime: Option<bool>,
+
+    /// Emit D-Bus signals when this game starts and stops (overrides global)
+    #[serde(default)]
+    pub dbus_signals: Option<bool>,
+
+    /// Block idle and sleep while this game runs (overrides global)
+    #[serde(default)]
+    pub inhibit_sleep: Option<bool>,
+
+    /// Power profile while this game runs (overrides global; "" keeps the current profile)
+    #[serde(default)]
+    pub power_profile: Option<String>,
+
+    /// Companion programs started (in order) before the game and stopped after it exits
+    #[serde(default)]
+    pub companions: Vec<CompanionConfig>,
+
+    /// Mod manager (MO2/Vortex) installed in this game's prefix
+    #[serde(default)]
+    pub modmanager: Option<ModManagerConfig>,
+
+    /// Winetricks verbs installed into the game's prefix before it is launched
+    #[serde(default)]
+    pub winetricks: Vec<String>,
+
+    /// Where the game keeps its saves, for `saves backup` and `saves restore`
+    #[serde(default)]
+    pub saves: Option<SavesConfig>,
+
+    /// Conditional overrides, applied in order after the global ones
+    #[serde(default)]
+    pub when: Vec<WhenBlock>,
+
+    /// Memory limit for this game (overrides global)
+    #[serde(default)]
+    pub memory_max: Option<String>,
+
+    /// OOM killer score adjustment for this game (overrides global)
+    #[serde(default)]
+    pub oom_score_adj: Option<i32>,
+
+    /// MangoHud settings for this game (each set field overrides the global one)
+    #[serde(default)]
+    pub mangohud: MangoHudConfig,
+
+    /// Discord Rich Presence settings for this game (each set field overrides the global one)
+    #[serde(default)]
+    pub discord: DiscordConfig,
+
+    /// GPU to run on: "discrete", "integrated", a PCI address or an index from `gpu list`
+    #[serde(default)]
+    pub gpu: Option<GpuSelector>,
+
+    /// cgroup resource limits for this game (each set field overrides the global one)
+    #[serde(default)]
+    pub limits: LimitsConfig,
+
+    /// CPUs the game may run on, e.g. "0-7" or "0-5,12" (pins older games to P-cores)
+    #[serde(default)]
+    pub cpu_affinity: Option<String>,
+
+    /// Scheduling niceness for the game (-20..19, negative values need CAP_SYS_NICE)
+    #[serde(default)]
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class and level: "idle", "best-effort[:0-7]" or "realtime[:0-7]"
+    #[serde(default)]
+    pub ionice: Option<String>,
+
+    /// Sandbox settings for this game (each set field overrides the global one)
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+
+    /// Steam Linux Runtime container to run this native game in: "off", "sniper" or "soldier"
+    #[serde(default)]
+    pub steam_runtime: Option<SteamRuntime>,
+
+    /// Wine binary and prefix for `mode = "wine"` (each set field overrides the global one)
+    #[serde(default)]
+    pub wine: WineConfig,
+
+    /// Per-game profile settings, layered on top of the global profile of the same name
+    #[serde(default)]
+    pub profiles: HashMap<String, ConfigOverlay>,
+}
+
+impl GameConfig {
+    /// Layer this config over a group's: set fields win, "inherit" in `pre_command`
+    /// refers to the group's, a non-empty hook phase or `launch_args` replaces the
+    /// group's, and other lists and maps are combined
+    pub fn or(self, base: GameConfig) -> Self {
+        let pre_command = match (self.pre_command, base.pre_command) {
+            (Some(cmd), Some(base)) if cmd.contains("inherit") => Some(cmd.replace("inherit", &base)),
+            (cmd, base) => cmd.or(base),
+        };
+        let proton = match (self.proton, base.proton) {
+            (Some(proton), Some(base)) => Some(ProtonSetting::Options(proton.into_options().or(base.into_options()))),
+            (proton, base) => proton.or(base),
+        };
+        let replace = |game: Vec<HookConfig>, base: Vec<HookConfig>| {
+            if game.is_empty() {
+                base
+            } else {
+                game
+            }
+        };
+        let hooks = HooksConfig {
+            pre_launch: replace(self.hooks.pre_launch, base.hooks.pre_launch),
+            post_exit: replace(self.hooks.post_exit, base.hooks.post_exit),
+            on_crash: replace(self.hooks.on_crash, base.hooks.on_crash),
+        };
+        let mut env = base.env;
+        env.extend(self.env);
+        let mut dll_overrides = base.dll_overrides;
+        dll_overrides.extend(self.dll_overrides);
+        let mut profiles = base.profiles;
+        profiles.extend(self.profiles);
+
+        Self {
+            name: self.name,
+            match_rule: self.match_rule,
+            group: self.group,
+            mode: self.mode.or(base.mode),
+            passthrough: self.passthrough.or(base.passthrough),
+            proton,
+            pre_command,
+            env,
+            dll_overrides,
+            launch_args: if self.launch_args.is_empty() {
+                base.launch_args
+            } else {
+                self.launch_args
+            },
+            hooks,
+            gamescope_args: self.gamescope_args.or(base.gamescope_args),
+            gamescope: self.gamescope.or(base.gamescope),
+            gamescope_enabled: self.gamescope_enabled.or(base.gamescope_enabled),
+            gamescope_fallback: self.gamescope_fallback.or(base.gamescope_fallback),
+            game_args: self.game_args.or(base.game_args),
+            wrappers: merge_wrappers(self.wrappers, base.wrappers),
+            working_dir: self.working_dir.or(base.working_dir),
+            compat_data_path: self.compat_data_path.or(base.compat_data_path),
+            ld_library_path_prepend: concat(self.ld_library_path_prepend, base.ld_library_path_prepend),
+            ld_preload_extra: concat(base.ld_preload_extra, self.ld_preload_extra),
+            library_preflight: self.library_preflight.or(base.library_preflight),
+            capture_game_output: self.capture_game_output.or(base.capture_game_output),
+            supervise: self.supervise.or(base.supervise),
+            restart_on_crash: self.restart_on_crash.or(base.restart_on_crash),
+            track_playtime: self.track_playtime.or(base.track_playtime),
+            dbus_signals: self.dbus_signals.or(base.dbus_signals),
+            inhibit_sleep: self.inhibit_sleep.or(base.inhibit_sleep),
+            power_profile: self.power_profile.or(base.power_profile),
+            companions: concat(base.companions, self.companions),
+            modmanager: self.modmanager.or(base.modmanager),
+            winetricks: concat(base.winetricks, self.winetricks),
+            saves: self.saves.or(base.saves),
+            when: concat(base.when, self.when),
+            memory_max: self.memory_max.or(base.memory_max),
+            oom_score_adj: self.oom_score_adj.or(base.oom_score_adj),
+            mangohud: self.mangohud.or(base.mangohud),
+            discord: self.discord.or(base.discord),
+            gpu: self.gpu.or(base.gpu),
+            limits: self.limits.or(base.limits),
+            cpu_affinity: self.cpu_affinity.or(base.cpu_affinity),
+            nice: self.nice.or(base.nice),
+            ionice: self.ionice.or(base.ionice),
+            sandbox: self.sandbox.or(base.sandbox),
+            steam_runtime: self.steam_runtime.or(base.steam_runtime),
+            wine: self.wine.or(base.wine),
+            profiles,
+        }
+    }
+}
+
+/// `first` followed by `then`
+fn concat<T>(mut first: Vec<T>, then: Vec<T>) -> Vec<T> {
+    first.extend(then);
+    first
+}
+
+/// A companion program that runs alongside the game (overlay, voice chat, etc.)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionConfig {
+    /// Command to execute
+    pub command: String,
+
+    /// Seconds to wait after starting this companion before starting the next one
+    #[serde(default)]
+    pub delay_secs: u64,
+
+    /// Working directory for the companion
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Extra environment variables for the companion
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Supported mod managers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModManagerKind {
+    /// Mod Organizer 2 (virtual file system, launches games via moshortcut://)
+    #[default]
+    Mo2,
+    /// Vortex (deploys with links, games launch normally)
+    Vortex,
+    /// Any other manager started as-is
+    Other,
+}
+
+/// Mod manager running inside the game's Proton prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModManagerConfig {
+    /// Which mod manager this is
+    #[serde(default)]
+    pub kind: ModManagerKind,
+
+    /// Path to the mod manager executable (e.g. ModOrganizer.exe)
+    pub path: String,
+
+    /// Extra arguments passed to the mod manager
+    #[serde(default)]
+    pub args: Option<String>,
+
+    /// MO2 instance name (empty for a portable instance)
+    #[serde(default)]
+    pub instance: Option<String>,
+
+    /// MO2 profile to activate
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// MO2 executable entry to start when routing game launches (e.g. "SKSE")
+    #[serde(default)]
+    pub executable: Option<String>,
+
+    /// Launch the game through the mod manager so its VFS is active
+    #[serde(default)]
+    pub route_launch: bool,
+}
+
+/// A game's save files, backed up to tar.zst archives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavesConfig {
+    /// Files and directories holding the saves ("~/" expands to home, and
+    /// `%prefix_path%` is the game's Wine prefix)
+    pub paths: Vec<String>,
+
+    /// Only back up files matching one of these patterns, relative to the save
+    /// directory (`*` is any run of characters, including `/`; default: every file)
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Leave out files matching any of these patterns
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Back up the saves every time the game exits; implies supervise
+    #[serde(default)]
+    pub backup_on_exit: bool,
+
+    /// Number of backups kept; older ones are deleted (default: 10)
+    #[serde(default = "default_keep_saves")]
+    pub keep: usize,
+
+    /// rsync destination to sync the saves with (e.g. "deck:saves/hades"): pulled
+    /// before launch and pushed after exit, keeping the newest copy of each file
+    #[serde(default)]
+    pub sync_target: Option<String>,
+
+    /// Command that syncs the saves instead of rsync, run before launch and after
+    /// exit; `%direction%` expands to "pull" or "push"
+    #[serde(default)]
+    pub sync_command: Option<String>,
+}
+
+fn default_keep_saves() -> usize {
+    10
 }
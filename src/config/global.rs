@@ -1,25 +1,43 @@
-use serde::{Deserialize, Serialize};
+use super::conditions::WhenBlock;
+use super::expand_home;
+use super::game::GameConfig;
+use super::overlay::ConfigOverlay;
+use crate::gpu::GpuSelector;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// Global configuration for steam-command-runner
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalConfig {
     /// Pre-command to prepend (e.g., gamemoderun, mangohud)
     #[serde(default)]
     pub pre_command: Option<String>,
 
-    /// Default Proton version (name as shown in Steam, or path)
+    /// Default Proton version (name as shown in Steam, or path), or versions to try in order
     #[serde(default)]
-    pub default_proton: Option<String>,
+    pub default_proton: Option<ProtonVersion>,
 
     /// Default execution mode
     #[serde(default)]
     pub default_mode: ExecutionMode,
 
+    /// Steam installation directory (auto-detected, including Flatpak Steam, when unset)
+    #[serde(default)]
+    pub steam_root: Option<String>,
+
+    /// Desktop notifications to show: "off", "errors" or "all" (default: off)
+    #[serde(default)]
+    pub notifications: Notifications,
+
     /// Global environment variables applied to all games
     #[serde(default)]
     pub env: HashMap<String, String>,
 
+    /// Wine DLL overrides (e.g. dxgi = "n,b"), passed as WINEDLLOVERRIDES
+    #[serde(default)]
+    pub dll_overrides: HashMap<String, String>,
+
     /// Hook configuration
     #[serde(default)]
     pub hooks: HooksConfig,
@@ -28,6 +46,10 @@ pub struct GlobalConfig {
     #[serde(default)]
     pub gamescope: GamescopeConfig,
 
+    /// Proton tunables applied to every Proton game (`version` falls back after default_proton)
+    #[serde(default)]
+    pub proton: ProtonOptions,
+
     /// Arguments to append to the game command
     #[serde(default)]
     pub game_args: Option<String>,
@@ -35,6 +57,155 @@ pub struct GlobalConfig {
     /// Enable debug logging for the shim (default: false)
     #[serde(default)]
     pub shim_debug: bool,
+
+    /// Arguments and environment the wrapper shims (`[wrappers.mangohud]`,
+    /// `[wrappers.gamemoderun]`) add when Steam runs them
+    #[serde(default)]
+    pub wrappers: HashMap<String, WrapperConfig>,
+
+    /// Directories prepended to LD_LIBRARY_PATH (Steam's runtime value is kept after them)
+    #[serde(default)]
+    pub ld_library_path_prepend: Vec<String>,
+
+    /// Libraries appended to LD_PRELOAD (after the Steam overlay)
+    #[serde(default)]
+    pub ld_preload_extra: Vec<String>,
+
+    /// Check native game binaries for missing shared libraries before launch (default: true)
+    #[serde(default = "default_library_preflight")]
+    pub library_preflight: bool,
+
+    /// Conditional overrides (e.g. time-of-day schedules), applied in order
+    #[serde(default)]
+    pub when: Vec<WhenBlock>,
+
+    /// Memory limit for games (e.g. "8G"), enforced via a systemd scope when available
+    #[serde(default)]
+    pub memory_max: Option<String>,
+
+    /// OOM killer score adjustment for games (-1000..1000, higher is killed first)
+    #[serde(default)]
+    pub oom_score_adj: Option<i32>,
+
+    /// Named alternate settings (e.g. docked, handheld), selected with --profile or SCR_PROFILE
+    #[serde(default)]
+    pub profiles: HashMap<String, ConfigOverlay>,
+
+    /// Settings shared by several games, which opt in with `group = "<name>"`
+    #[serde(default)]
+    pub groups: HashMap<String, GameConfig>,
+
+    /// Run games as a child process instead of exec'ing, so post_exit hooks run (default: false)
+    #[serde(default)]
+    pub supervise: bool,
+
+    /// Relaunch games that crash
+    #[serde(default)]
+    pub restart_on_crash: RestartConfig,
+
+    /// Record play sessions for `stats`; implies supervise (default: false)
+    #[serde(default)]
+    pub track_playtime: bool,
+
+    /// Emit GameStarted/GameStopped D-Bus signals; implies supervise (default: false)
+    #[serde(default)]
+    pub dbus_signals: bool,
+
+    /// Block idle and sleep with systemd-inhibit while the game runs; implies supervise (default: false)
+    #[serde(default)]
+    pub inhibit_sleep: bool,
+
+    /// Power profile to switch to while a game runs (e.g. "performance"); implies supervise
+    #[serde(default)]
+    pub power_profile: Option<String>,
+
+    /// Game output logging settings
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Backups of Steam config files taken before we modify them
+    #[serde(default)]
+    pub backups: BackupConfig,
+
+    /// SteamGridDB access, for artwork of non-Steam games
+    #[serde(default)]
+    pub steamgriddb: SteamGridDbConfig,
+
+    /// MangoHud overlay settings
+    #[serde(default)]
+    pub mangohud: MangoHudConfig,
+
+    /// Discord Rich Presence settings
+    #[serde(default)]
+    pub discord: DiscordConfig,
+
+    /// GPU games run on by default (see `gpu list`)
+    #[serde(default)]
+    pub gpu: Option<GpuSelector>,
+
+    /// Bubblewrap sandbox for games
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+
+    /// cgroup resource limits for games
+    #[serde(default)]
+    pub limits: LimitsConfig,
+
+    /// Steam Linux Runtime container native games run in (default: off)
+    #[serde(default)]
+    pub steam_runtime: SteamRuntime,
+
+    /// Wine binary and prefix for `mode = "wine"`
+    #[serde(default)]
+    pub wine: WineConfig,
+}
+
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        Self {
+            pre_command: None,
+            default_proton: None,
+            default_mode: ExecutionMode::default(),
+            steam_root: None,
+            notifications: Notifications::default(),
+            env: HashMap::new(),
+            dll_overrides: HashMap::new(),
+            hooks: HooksConfig::default(),
+            gamescope: GamescopeConfig::default(),
+            proton: ProtonOptions::default(),
+            game_args: None,
+            shim_debug: false,
+            wrappers: HashMap::new(),
+            ld_library_path_prepend: Vec::new(),
+            ld_preload_extra: Vec::new(),
+            library_preflight: true,
+            when: Vec::new(),
+            memory_max: None,
+            oom_score_adj: None,
+            profiles: HashMap::new(),
+            groups: HashMap::new(),
+            supervise: false,
+            restart_on_crash: RestartConfig::default(),
+            track_playtime: false,
+            dbus_signals: false,
+            inhibit_sleep: false,
+            power_profile: None,
+            logging: LoggingConfig::default(),
+            backups: BackupConfig::default(),
+            steamgriddb: SteamGridDbConfig::default(),
+            mangohud: MangoHudConfig::default(),
+            discord: DiscordConfig::default(),
+            gpu: None,
+            sandbox: SandboxConfig::default(),
+            limits: LimitsConfig::default(),
+            steam_runtime: SteamRuntime::default(),
+            wine: WineConfig::default(),
+        }
+    }
+}
+
+fn default_library_preflight() -> bool {
+    true
 }
 
 /// Execution mode for games
@@ -45,21 +216,534 @@ pub enum ExecutionMode {
     Native,
     /// Always use Proton/Wine
     Proton,
+    /// Run Windows games through umu-run, which applies protonfixes
+    Umu,
+    /// Run Windows games with a plain Wine install, outside Steam
+    Wine,
     /// Auto-detect based on executable type
     #[default]
     Auto,
 }
 
+/// Steam Linux Runtime container for native games
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SteamRuntime {
+    /// Run the game directly
+    #[default]
+    Off,
+    /// Steam Linux Runtime 3.0 (sniper)
+    Sniper,
+    /// Steam Linux Runtime 2.0 (soldier)
+    Soldier,
+}
+
+impl SteamRuntime {
+    /// Directory the runtime is installed in, under steamapps/common
+    pub fn dir_name(self) -> Option<&'static str> {
+        match self {
+            SteamRuntime::Off => None,
+            SteamRuntime::Sniper => Some("SteamLinuxRuntime_sniper"),
+            SteamRuntime::Soldier => Some("SteamLinuxRuntime_soldier"),
+        }
+    }
+
+    /// Steam app ID the runtime is installed as
+    pub fn app_id(self) -> Option<u32> {
+        match self {
+            SteamRuntime::Off => None,
+            SteamRuntime::Sniper => Some(1628350),
+            SteamRuntime::Soldier => Some(1391110),
+        }
+    }
+}
+
+/// Which events show a desktop notification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Notifications {
+    /// No notifications
+    #[default]
+    Off,
+    /// Only failures: crashes and launch errors
+    Errors,
+    /// Failures plus launches and applied launch options
+    All,
+}
+
+/// Game output logging settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Write the game's stdout/stderr to a per-game log file (default: false)
+    #[serde(default)]
+    pub capture_game_output: bool,
+
+    /// Number of log files kept per game (default: 10)
+    #[serde(default = "default_keep_logs")]
+    pub keep_logs: usize,
+
+    /// Total size of a game's logs before the oldest are removed (default: "100M")
+    #[serde(default = "default_max_log_size")]
+    pub max_log_size: String,
+
+    /// Messages written to the runner and shim logs (default: warn)
+    #[serde(default)]
+    pub level: LogLevel,
+
+    /// Size at which the runner and shim logs are rotated (default: "5M")
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: String,
+
+    /// Line format of the runner and shim logs (default: text)
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            capture_game_output: false,
+            keep_logs: default_keep_logs(),
+            max_log_size: default_max_log_size(),
+            level: LogLevel::default(),
+            max_file_size: default_max_file_size(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+fn default_max_file_size() -> String {
+    "5M".to_string()
+}
+
+/// Line format of the runner and shim logs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Timestamped plain text
+    #[default]
+    Text,
+    /// JSON objects, one per line, including tracing events
+    Json,
+}
+
+/// How much goes into the runner and shim logs, from nothing to everything
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+        }
+    }
+}
+
+fn default_keep_logs() -> usize {
+    10
+}
+
+fn default_max_log_size() -> String {
+    "100M".to_string()
+}
+
+/// MangoHud overlay settings, written to a per-game MangoHud config file
+///
+/// Merged field-by-field between global and game configs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MangoHudConfig {
+    /// Show MangoHud in games (default: false)
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Frame rate limit (fps_limit)
+    #[serde(default)]
+    pub fps_limit: Option<u32>,
+
+    /// Where the HUD is drawn: top-left, top-right, bottom-left, bottom-right, top-center, ...
+    #[serde(default)]
+    pub position: Option<String>,
+
+    /// Font size in pixels
+    #[serde(default)]
+    pub font_size: Option<u32>,
+
+    /// Start with the HUD hidden (toggle it with the toggle_hud key)
+    #[serde(default)]
+    pub hidden: Option<bool>,
+
+    /// Key combination that shows/hides the HUD (e.g. "Shift_R+F12")
+    #[serde(default)]
+    pub toggle_hud: Option<String>,
+
+    /// Frame rate
+    #[serde(default)]
+    pub fps: Option<bool>,
+
+    /// Frame time graph
+    #[serde(default)]
+    pub frametime: Option<bool>,
+
+    /// CPU load
+    #[serde(default)]
+    pub cpu_stats: Option<bool>,
+
+    /// CPU temperature
+    #[serde(default)]
+    pub cpu_temp: Option<bool>,
+
+    /// GPU load
+    #[serde(default)]
+    pub gpu_stats: Option<bool>,
+
+    /// GPU temperature
+    #[serde(default)]
+    pub gpu_temp: Option<bool>,
+
+    /// System memory usage
+    #[serde(default)]
+    pub ram: Option<bool>,
+
+    /// Video memory usage
+    #[serde(default)]
+    pub vram: Option<bool>,
+
+    /// Battery charge and power draw
+    #[serde(default)]
+    pub battery: Option<bool>,
+
+    /// Wine/Proton version
+    #[serde(default)]
+    pub wine: Option<bool>,
+
+    /// Whether GameMode is active
+    #[serde(default)]
+    pub gamemode: Option<bool>,
+
+    /// Extra MangoHud config lines, written as-is (global entries first)
+    #[serde(default)]
+    pub extra: Vec<String>,
+}
+
+impl MangoHudConfig {
+    /// Fill any unset fields from `base`; extra lines are combined
+    pub fn or(self, base: MangoHudConfig) -> Self {
+        let mut extra = base.extra;
+        extra.extend(self.extra);
+        Self {
+            enabled: self.enabled.or(base.enabled),
+            fps_limit: self.fps_limit.or(base.fps_limit),
+            position: self.position.or(base.position),
+            font_size: self.font_size.or(base.font_size),
+            hidden: self.hidden.or(base.hidden),
+            toggle_hud: self.toggle_hud.or(base.toggle_hud),
+            fps: self.fps.or(base.fps),
+            frametime: self.frametime.or(base.frametime),
+            cpu_stats: self.cpu_stats.or(base.cpu_stats),
+            cpu_temp: self.cpu_temp.or(base.cpu_temp),
+            gpu_stats: self.gpu_stats.or(base.gpu_stats),
+            gpu_temp: self.gpu_temp.or(base.gpu_temp),
+            ram: self.ram.or(base.ram),
+            vram: self.vram.or(base.vram),
+            battery: self.battery.or(base.battery),
+            wine: self.wine.or(base.wine),
+            gamemode: self.gamemode.or(base.gamemode),
+            extra,
+        }
+    }
+
+    /// Whether MangoHud should be shown
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+}
+
+/// Discord Rich Presence, published while a game runs
+///
+/// Merged field-by-field between global and game configs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscordConfig {
+    /// Show the running game in Discord; implies supervision (default: false)
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Application ID from the Discord developer portal; its name is shown as "Playing ..."
+    #[serde(default)]
+    pub client_id: Option<String>,
+
+    /// Second line of the presence (the first is the game name)
+    #[serde(default)]
+    pub state: Option<String>,
+
+    /// Asset key of an image uploaded to the Discord application
+    #[serde(default)]
+    pub large_image: Option<String>,
+}
+
+impl DiscordConfig {
+    /// Fill any unset fields from `base`
+    pub fn or(self, base: DiscordConfig) -> Self {
+        Self {
+            enabled: self.enabled.or(base.enabled),
+            client_id: self.client_id.or(base.client_id),
+            state: self.state.or(base.state),
+            large_image: self.large_image.or(base.large_image),
+        }
+    }
+
+    /// Whether Rich Presence should be published
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+}
+
+/// Standalone Wine settings for `mode = "wine"`
+///
+/// Merged field-by-field between global and game configs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WineConfig {
+    /// Wine binary: a name looked up in PATH or a path (default: "wine")
+    #[serde(default)]
+    pub binary: Option<String>,
+
+    /// WINEPREFIX directory (default: one per game under ~/.local/share/steam-command-runner/wine)
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+impl WineConfig {
+    /// Fill any unset fields from `base`
+    pub fn or(self, base: WineConfig) -> Self {
+        Self {
+            binary: self.binary.or(base.binary),
+            prefix: self.prefix.or(base.prefix),
+        }
+    }
+
+    /// The Wine binary to run, with a leading "~/" expanded
+    pub fn binary_path(&self) -> String {
+        match self.binary.as_deref() {
+            Some(binary) => expand_home(binary).to_string_lossy().to_string(),
+            None => "wine".to_string(),
+        }
+    }
+
+    /// The prefix for a game, with a leading "~/" expanded
+    pub fn prefix_path(&self, app_id: Option<u32>) -> PathBuf {
+        match self.prefix.as_deref() {
+            Some(prefix) => expand_home(prefix),
+            None => {
+                let name = app_id.map_or("default".to_string(), |id| id.to_string());
+                dirs::data_dir()
+                    .unwrap_or_else(|| PathBuf::from(".local/share"))
+                    .join("steam-command-runner")
+                    .join("wine")
+                    .join(name)
+            }
+        }
+    }
+}
+
+/// cgroup resource limits, applied by starting the game in a transient systemd scope
+///
+/// Merged field-by-field between global and game configs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Always start the game in its own scope, and stop the scope (killing anything
+    /// the game left behind) when it exits. Implies supervision. (default: false)
+    #[serde(default)]
+    pub scope: Option<bool>,
+
+    /// Memory limit (MemoryMax, e.g. "8G"); same as the top-level memory_max
+    #[serde(default)]
+    pub memory_max: Option<String>,
+
+    /// CPU time limit (CPUQuota, e.g. "400%" for four cores' worth)
+    #[serde(default)]
+    pub cpu_quota: Option<String>,
+
+    /// Relative I/O weight (IOWeight, 1..10000, default 100)
+    #[serde(default)]
+    pub io_weight: Option<u32>,
+}
+
+impl LimitsConfig {
+    /// Fill any unset fields from `base`
+    pub fn or(self, base: LimitsConfig) -> Self {
+        Self {
+            scope: self.scope.or(base.scope),
+            memory_max: self.memory_max.or(base.memory_max),
+            cpu_quota: self.cpu_quota.or(base.cpu_quota),
+            io_weight: self.io_weight.or(base.io_weight),
+        }
+    }
+}
+
+/// Bubblewrap sandbox settings, merged field-by-field between global and game configs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Run games inside a bwrap sandbox (default: false)
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Allow network access (default: true)
+    #[serde(default)]
+    pub network: Option<bool>,
+
+    /// Share the PulseAudio/PipeWire sockets for sound (default: true)
+    #[serde(default)]
+    pub share_pulse: Option<bool>,
+
+    /// Give each game a persistent home directory of its own instead of an empty
+    /// temporary one (default: true)
+    #[serde(default)]
+    pub private_home: Option<bool>,
+
+    /// Extra paths the game may read and write (global entries first)
+    #[serde(default)]
+    pub allow_paths: Vec<String>,
+
+    /// Extra paths the game may only read (global entries first)
+    #[serde(default)]
+    pub read_only_paths: Vec<String>,
+
+    /// Additional bwrap arguments, placed before the command
+    #[serde(default)]
+    pub extra_args: Option<String>,
+}
+
+impl SandboxConfig {
+    /// Fill any unset fields from `base`; path lists are combined
+    pub fn or(self, base: SandboxConfig) -> Self {
+        let mut allow_paths = base.allow_paths;
+        allow_paths.extend(self.allow_paths);
+        let mut read_only_paths = base.read_only_paths;
+        read_only_paths.extend(self.read_only_paths);
+        Self {
+            enabled: self.enabled.or(base.enabled),
+            network: self.network.or(base.network),
+            share_pulse: self.share_pulse.or(base.share_pulse),
+            private_home: self.private_home.or(base.private_home),
+            allow_paths,
+            read_only_paths,
+            extra_args: self.extra_args.or(base.extra_args),
+        }
+    }
+
+    /// Whether games should be sandboxed
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+}
+
+/// Relaunching a game that crashes (requires supervision, which it turns on)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestartConfig {
+    /// Relaunch the game when it exits with a non-zero status or a signal (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Relaunches before giving up (default: 3)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Seconds to wait before the first relaunch, doubled for each one after (default: 5)
+    #[serde(default = "default_backoff_secs")]
+    pub backoff_secs: u64,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: default_max_retries(),
+            backoff_secs: default_backoff_secs(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_backoff_secs() -> u64 {
+    5
+}
+
+/// Steam config file backup settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Number of backups kept per Steam config file (default: 10)
+    #[serde(default = "default_keep_backups")]
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            keep: default_keep_backups(),
+        }
+    }
+}
+
+fn default_keep_backups() -> usize {
+    10
+}
+
+/// SteamGridDB settings
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SteamGridDbConfig {
+    /// API key from https://www.steamgriddb.com/profile/preferences/api
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
 /// Hook configuration for pre-launch and post-exit commands
+///
+/// Each phase takes a single hook table or an array of them, run in order.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct HooksConfig {
-    /// Pre-launch hook (runs before game starts)
-    #[serde(default)]
-    pub pre_launch: Option<HookConfig>,
+    /// Pre-launch hooks (run before game starts)
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub pre_launch: Vec<HookConfig>,
 
-    /// Post-exit hook (runs after game exits)
-    #[serde(default)]
-    pub post_exit: Option<HookConfig>,
+    /// Post-exit hooks (run after game exits)
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub post_exit: Vec<HookConfig>,
+
+    /// Crash hooks (run when a supervised game exits with an error or a signal)
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub on_crash: Vec<HookConfig>,
+}
+
+/// Deserialize either a single hook table or an array of hook tables
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<HookConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(HookConfig),
+        Many(Vec<HookConfig>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(hook) => vec![hook],
+        OneOrMany::Many(hooks) => hooks,
+    })
 }
 
 /// Individual hook configuration
@@ -75,12 +759,60 @@ pub struct HookConfig {
     /// Working directory for the hook
     #[serde(default)]
     pub working_dir: Option<String>,
+
+    /// Keep going with the phase's remaining hooks (and the launch) if this one fails
+    #[serde(default = "default_continue_on_error")]
+    pub continue_on_error: bool,
+
+    /// Kill the hook if it is still running after this many seconds (waited hooks only)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_continue_on_error() -> bool {
+    true
 }
 
 fn default_wait() -> bool {
     true
 }
 
+/// What a wrapper shim adds to a wrapper's invocation
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrapperConfig {
+    /// Arguments inserted before the ones Steam passed (shell-quoted string)
+    #[serde(default)]
+    pub args: Option<String>,
+
+    /// Environment variables set for the wrapper, and so for the game
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl WrapperConfig {
+    /// Fill unset fields from `base`; environment variables are combined
+    pub fn or(self, base: WrapperConfig) -> Self {
+        let mut env = base.env;
+        env.extend(self.env);
+        Self {
+            args: self.args.or(base.args),
+            env,
+        }
+    }
+}
+
+/// Layer per-wrapper settings over base ones, wrapper by wrapper
+pub fn merge_wrappers(
+    wrappers: HashMap<String, WrapperConfig>,
+    mut base: HashMap<String, WrapperConfig>,
+) -> HashMap<String, WrapperConfig> {
+    for (name, wrapper) in wrappers {
+        let merged = wrapper.or(base.remove(&name).unwrap_or_default());
+        base.insert(name, merged);
+    }
+    base
+}
+
 /// Gamescope-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GamescopeConfig {
@@ -96,9 +828,17 @@ pub struct GamescopeConfig {
     #[serde(default)]
     pub pre_command: Option<String>,
 
-    /// Arguments to pass to gamescope
+    /// Raw arguments to pass to gamescope (legacy; appended after the typed options)
     #[serde(default)]
     pub args: Option<String>,
+
+    /// Launch without gamescope when it fails to start (runs the game supervised)
+    #[serde(default)]
+    pub fallback: bool,
+
+    /// Typed gamescope options (width, height, refresh, ...)
+    #[serde(flatten)]
+    pub options: GamescopeOptions,
 }
 
 impl Default for GamescopeConfig {
@@ -108,6 +848,277 @@ impl Default for GamescopeConfig {
             skip_pre_command: true,
             pre_command: None,
             args: None,
+            fallback: false,
+            options: GamescopeOptions::default(),
+        }
+    }
+}
+
+/// Typed gamescope options, merged field-by-field between global and game configs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamescopeOptions {
+    /// Game width in pixels (-w)
+    #[serde(default)]
+    pub width: Option<u32>,
+
+    /// Game height in pixels (-h)
+    #[serde(default)]
+    pub height: Option<u32>,
+
+    /// Frame rate limit (-r)
+    #[serde(default)]
+    pub refresh: Option<u32>,
+
+    /// Upscale with AMD FSR (-F fsr)
+    #[serde(default)]
+    pub fsr: Option<bool>,
+
+    /// Enable HDR output (--hdr-enabled): true, false or "auto"
+    #[serde(default)]
+    pub hdr: Option<HdrSetting>,
+
+    /// Start fullscreen (-f)
+    #[serde(default)]
+    pub fullscreen: Option<bool>,
+
+    /// Match the output size and refresh rate to the connected display (-W/-H/-r)
+    #[serde(default)]
+    pub auto_resolution: Option<bool>,
+
+    /// Additional arguments appended after the typed options
+    #[serde(default)]
+    pub extra_args: Option<String>,
+}
+
+impl GamescopeOptions {
+    /// Fill any unset fields from `base`
+    pub fn or(self, base: GamescopeOptions) -> Self {
+        Self {
+            width: self.width.or(base.width),
+            height: self.height.or(base.height),
+            refresh: self.refresh.or(base.refresh),
+            fsr: self.fsr.or(base.fsr),
+            hdr: self.hdr.or(base.hdr),
+            fullscreen: self.fullscreen.or(base.fullscreen),
+            auto_resolution: self.auto_resolution.or(base.auto_resolution),
+            extra_args: self.extra_args.or(base.extra_args),
+        }
+    }
+
+    /// Whether no option is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The gamescope `hdr` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HdrSetting {
+    /// `hdr = true` / `hdr = false`
+    Enabled(bool),
+    /// `hdr = "auto"`: on when the session or display supports HDR
+    Auto(Auto),
+}
+
+/// The `"auto"` keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Auto {
+    Auto,
+}
+
+impl HdrSetting {
+    /// Decide "auto" from whether HDR is available
+    pub fn resolve(self, available: bool) -> bool {
+        match self {
+            Self::Enabled(enabled) => enabled,
+            Self::Auto(_) => available,
+        }
+    }
+}
+
+/// Typed Proton tunables, translated into PROTON_* environment variables
+///
+/// Merged field-by-field like the gamescope options. Only set fields produce a
+/// variable, so Proton's own defaults apply to everything else.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtonOptions {
+    /// Proton version to use (game config and profiles; same as `proton = "..."`)
+    #[serde(default)]
+    pub version: Option<ProtonVersion>,
+
+    /// eventfd-based synchronization (false sets PROTON_NO_ESYNC)
+    #[serde(default)]
+    pub esync: Option<bool>,
+
+    /// futex-based synchronization (false sets PROTON_NO_FSYNC)
+    #[serde(default)]
+    pub fsync: Option<bool>,
+
+    /// NT synchronization driver, where the kernel supports it (PROTON_USE_NTSYNC)
+    #[serde(default)]
+    pub ntsync: Option<bool>,
+
+    /// Use OpenGL-based wined3d instead of DXVK/VKD3D (PROTON_USE_WINED3D)
+    #[serde(default)]
+    pub wined3d: Option<bool>,
+
+    /// Direct3D 11 support (false sets PROTON_NO_D3D11)
+    #[serde(default)]
+    pub d3d11: Option<bool>,
+
+    /// Direct3D 12 support (false sets PROTON_NO_D3D12)
+    #[serde(default)]
+    pub d3d12: Option<bool>,
+
+    /// Upscale fullscreen games with AMD FSR (WINE_FULLSCREEN_FSR)
+    #[serde(default)]
+    pub fsr: Option<bool>,
+
+    /// FSR sharpening strength, 0 (sharpest) to 5 (WINE_FULLSCREEN_FSR_STRENGTH)
+    #[serde(default)]
+    pub fsr_strength: Option<u8>,
+
+    /// Write a Proton log to ~/steam-<app_id>.log (PROTON_LOG)
+    #[serde(default)]
+    pub log: Option<bool>,
+
+    /// Force the large-address-aware flag on 32-bit games (PROTON_FORCE_LARGE_ADDRESS_AWARE)
+    #[serde(default)]
+    pub large_address_aware: Option<bool>,
+
+    /// NVIDIA's NVAPI, needed for DLSS (PROTON_ENABLE_NVAPI / PROTON_DISABLE_NVAPI)
+    #[serde(default)]
+    pub nvapi: Option<bool>,
+
+    /// Report NVIDIA GPUs as AMD to the game (PROTON_HIDE_NVIDIA_GPU)
+    #[serde(default)]
+    pub hide_nvidia_gpu: Option<bool>,
+
+    /// Native Wayland driver instead of XWayland (PROTON_ENABLE_WAYLAND)
+    #[serde(default)]
+    pub wayland: Option<bool>,
+
+    /// HDR output, with the Wayland driver or under gamescope (PROTON_ENABLE_HDR)
+    #[serde(default)]
+    pub hdr: Option<bool>,
+}
+
+impl ProtonOptions {
+    /// Fill any unset fields from `base`
+    pub fn or(self, base: ProtonOptions) -> Self {
+        Self {
+            version: self.version.or(base.version),
+            esync: self.esync.or(base.esync),
+            fsync: self.fsync.or(base.fsync),
+            ntsync: self.ntsync.or(base.ntsync),
+            wined3d: self.wined3d.or(base.wined3d),
+            d3d11: self.d3d11.or(base.d3d11),
+            d3d12: self.d3d12.or(base.d3d12),
+            fsr: self.fsr.or(base.fsr),
+            fsr_strength: self.fsr_strength.or(base.fsr_strength),
+            log: self.log.or(base.log),
+            large_address_aware: self.large_address_aware.or(base.large_address_aware),
+            nvapi: self.nvapi.or(base.nvapi),
+            hide_nvidia_gpu: self.hide_nvidia_gpu.or(base.hide_nvidia_gpu),
+            wayland: self.wayland.or(base.wayland),
+            hdr: self.hdr.or(base.hdr),
+        }
+    }
+
+    /// Environment variables for the set options
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let flag = |b: bool| if b { "1" } else { "0" }.to_string();
+        let mut vars = Vec::new();
+
+        // Options Proton enables by default are turned off with PROTON_NO_*
+        let disables = [
+            (self.esync, "PROTON_NO_ESYNC"),
+            (self.fsync, "PROTON_NO_FSYNC"),
+            (self.d3d11, "PROTON_NO_D3D11"),
+            (self.d3d12, "PROTON_NO_D3D12"),
+        ];
+        for (value, var) in disables {
+            if value == Some(false) {
+                vars.push((var, "1".to_string()));
+            }
+        }
+
+        let toggles = [
+            (self.ntsync, "PROTON_USE_NTSYNC"),
+            (self.wined3d, "PROTON_USE_WINED3D"),
+            (self.fsr, "WINE_FULLSCREEN_FSR"),
+            (self.log, "PROTON_LOG"),
+            (self.large_address_aware, "PROTON_FORCE_LARGE_ADDRESS_AWARE"),
+            (self.hide_nvidia_gpu, "PROTON_HIDE_NVIDIA_GPU"),
+            (self.wayland, "PROTON_ENABLE_WAYLAND"),
+            (self.hdr, "PROTON_ENABLE_HDR"),
+        ];
+        for (value, var) in toggles {
+            if let Some(value) = value {
+                vars.push((var, flag(value)));
+            }
+        }
+
+        if let Some(strength) = self.fsr_strength {
+            vars.push(("WINE_FULLSCREEN_FSR_STRENGTH", strength.min(5).to_string()));
+        }
+        match self.nvapi {
+            Some(true) => vars.push(("PROTON_ENABLE_NVAPI", "1".to_string())),
+            Some(false) => vars.push(("PROTON_DISABLE_NVAPI", "1".to_string())),
+            None => {}
+        }
+
+        vars
+    }
+}
+
+/// A Proton version, or a fallback chain of versions tried in order
+///
+/// `"any"` in a chain accepts whatever Proton is installed, so
+/// `["GE-Proton9-20", "Proton - Experimental", "any"]` works on any machine with Proton.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProtonVersion {
+    /// `proton = "GE-Proton9-20"`
+    One(String),
+    /// `proton = ["GE-Proton9-20", "any"]`
+    Chain(Vec<String>),
+}
+
+impl ProtonVersion {
+    /// The preferred version and the fallbacks after it
+    pub fn split(self) -> (Option<String>, Vec<String>) {
+        match self {
+            Self::One(version) => (Some(version), Vec::new()),
+            Self::Chain(versions) => {
+                let mut versions = versions.into_iter();
+                (versions.next(), versions.collect())
+            }
+        }
+    }
+}
+
+/// The `proton` key of a game config or profile: a version (or chain), or a table of options
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProtonSetting {
+    /// `proton = "GE-Proton9-20"` or `proton = ["GE-Proton9-20", "any"]`
+    Version(ProtonVersion),
+    /// `[proton]` table (which may also set `version`)
+    Options(ProtonOptions),
+}
+
+impl ProtonSetting {
+    /// The setting as options (a bare version becomes `version`)
+    pub fn into_options(self) -> ProtonOptions {
+        match self {
+            Self::Version(version) => ProtonOptions {
+                version: Some(version),
+                ..ProtonOptions::default()
+            },
+            Self::Options(options) => options,
         }
     }
 }
@@ -0,0 +1,94 @@
+use super::error::ConfigError;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+/// Expand `${VAR}` references in a string
+///
+/// `${VAR:-default}` uses `default` when VAR is unset or empty, and `$${` is a
+/// literal `${`. Unset variables without a default expand to nothing. A lone `$`
+/// is left alone, so shell-style text such as `$HOME` passes through.
+pub fn expand_env(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos..];
+
+        if let Some(escaped) = after.strip_prefix("$${") {
+            out.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(body) = after.strip_prefix("${") else {
+            out.push('$');
+            rest = &after[1..];
+            continue;
+        };
+        let Some(end) = body.find('}') else {
+            // Unterminated: keep the text as written
+            out.push_str(after);
+            rest = "";
+            break;
+        };
+
+        let (name, default) = match body[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&body[..end], None),
+        };
+        match (lookup(name).filter(|v| !v.is_empty()), default) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => debug!("${{{}}} is not set, expanding to nothing", name),
+        }
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Expand environment references in every string in a TOML value
+fn interpolate(value: &mut toml::Value, lookup: &dyn Fn(&str) -> Option<String>) {
+    match value {
+        toml::Value::String(s) => *s = expand_env(s, lookup),
+        toml::Value::Array(items) => items.iter_mut().for_each(|item| interpolate(item, lookup)),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, item)| interpolate(item, lookup)),
+        _ => {}
+    }
+}
+
+/// Parse a config file, expanding `${VAR}` references in its string values
+pub fn parse_config<T: DeserializeOwned>(content: &str) -> Result<T, ConfigError> {
+    // Deserializing straight from the text keeps line numbers in error messages
+    if !content.contains("${") {
+        return Ok(toml::from_str(content)?);
+    }
+    from_value(toml::from_str(content)?)
+}
+
+/// Deserialize an already parsed config, expanding `${VAR}` references first
+pub(crate) fn from_value<T: DeserializeOwned>(mut value: toml::Value) -> Result<T, ConfigError> {
+    interpolate(&mut value, &|name| std::env::var(name).ok());
+    Ok(value.try_into()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_env() {
+        let lookup = |name: &str| match name {
+            "XDG_DATA_HOME" => Some("/home/me/.local/share".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let expand = |s: &str| expand_env(s, &lookup);
+
+        assert_eq!(expand("${XDG_DATA_HOME}/mods"), "/home/me/.local/share/mods");
+        assert_eq!(expand("${MISSING}/mods"), "/mods");
+        assert_eq!(expand("${EMPTY:-/fallback}"), "/fallback");
+        assert_eq!(expand("${MISSING:-~/.local/share}/mods"), "~/.local/share/mods");
+        assert_eq!(expand("$${XDG_DATA_HOME} and $HOME"), "${XDG_DATA_HOME} and $HOME");
+        assert_eq!(expand("cost: 5$ ${unterminated"), "cost: 5$ ${unterminated");
+    }
+}
@@ -0,0 +1,184 @@
+use super::error::ConfigError;
+use super::game::{GameConfig, GameMatch};
+use super::interpolate::parse_config;
+use super::{get_game_config_path, get_games_config_dir, get_groups_config_dir};
+use crate::steam::game_name;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Just the `match` table of a game config
+#[derive(Deserialize)]
+struct MatchOnly {
+    #[serde(default, rename = "match")]
+    rule: Option<GameMatch>,
+}
+
+/// Config file for a game: `games/<app_id>.toml` when it exists, otherwise the
+/// first name-keyed file (in lexical order) that applies to the game
+///
+/// A name-keyed file applies when its `match` rule matches the game's name, or,
+/// without a rule, when its file name is the game's name as a slug
+/// (`games/hades.toml` for "Hades"). Falls back to the App ID path, so callers
+/// creating a config get the usual file.
+pub fn find_game_config_path(app_id: u32) -> PathBuf {
+    let by_id = get_game_config_path(app_id);
+    if by_id.exists() {
+        return by_id;
+    }
+
+    let candidates = named_game_configs(&get_games_config_dir());
+    if candidates.is_empty() {
+        return by_id;
+    }
+    let Some(name) = game_name(app_id) else {
+        debug!("No name known for app_id {}, skipping name-keyed configs", app_id);
+        return by_id;
+    };
+
+    for path in candidates {
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| toml::from_str::<MatchOnly>(&content).map_err(|e| e.to_string()));
+        let rule = match parsed {
+            Ok(config) => config.rule,
+            Err(e) => {
+                warn!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        if applies_to(&stem, rule.as_ref(), &name) {
+            debug!("Using {} for '{}' ({})", path.display(), name, app_id);
+            return path;
+        }
+    }
+    by_id
+}
+
+/// Layer a game config over the group it names, if any
+///
+/// The group comes from `groups/<name>.toml` layered over `[groups.<name>]` in the
+/// global config; either one alone is enough.
+pub fn with_group(
+    game: GameConfig,
+    groups: &mut HashMap<String, GameConfig>,
+) -> Result<GameConfig, ConfigError> {
+    let Some(name) = game.group.clone() else {
+        return Ok(game);
+    };
+
+    let path = get_groups_config_dir().join(format!("{}.toml", name));
+    let from_file = if path.exists() {
+        debug!("Loading group config from: {}", path.display());
+        Some(parse_config::<GameConfig>(&fs::read_to_string(&path)?)?)
+    } else {
+        None
+    };
+    let group = match (from_file, groups.remove(&name)) {
+        (Some(file), Some(inline)) => Some(file.or(inline)),
+        (file, inline) => file.or(inline),
+    };
+
+    match group {
+        Some(group) => {
+            debug!("Applying group '{}'", name);
+            Ok(game.or(group))
+        }
+        None => {
+            warn!("Group '{}' is not defined in the global config or {}", name, path.display());
+            Ok(game)
+        }
+    }
+}
+
+/// `*.toml` files in the games directory that aren't named after an App ID, sorted
+fn named_game_configs(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .filter(|path| {
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .is_some_and(|stem| stem.parse::<u32>().is_err())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn applies_to(stem: &str, rule: Option<&GameMatch>, name: &str) -> bool {
+    match rule.and_then(|rule| rule.name.as_deref()) {
+        Some(pattern) => glob_match(&pattern.to_lowercase(), &name.to_lowercase()),
+        None => slug(stem) == slug(name),
+    }
+}
+
+/// Lowercase a name and join its words with dashes ("Half-Life 2" -> "half-life-2")
+fn slug(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and `?` one character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies_to() {
+        let rule = |name: &str| GameMatch {
+            name: Some(name.to_string()),
+        };
+        assert!(applies_to("hades", None, "Hades"));
+        assert!(applies_to("Half-Life 2", None, "Half-Life 2"));
+        assert!(!applies_to("hades", None, "Hades II"));
+        assert!(applies_to("hades", Some(&rule("Hades*")), "Hades II"));
+        assert!(applies_to("anything", Some(&rule("*souls ?")), "Dark Souls 3"));
+        assert!(!applies_to("hades", Some(&rule("Hades?")), "Hades"));
+
+        assert!(glob_match("a*b*c", "aXXbYbc"));
+        assert!(!glob_match("a*b", "aXXbc"));
+    }
+}
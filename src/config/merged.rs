@@ -1,11 +1,34 @@
+use super::conditions::{HostFacts, WhenBlock};
 use super::error::ConfigError;
-use super::game::GameConfig;
-use super::global::{ExecutionMode, GlobalConfig, HookConfig};
-use super::{get_config_path, get_game_config_path};
+use super::dropin::load_global_config;
+use super::game::{CompanionConfig, GameConfig, ModManagerConfig, SavesConfig};
+use super::interpolate::parse_config;
+use super::global::{
+    BackupConfig, ExecutionMode, GamescopeOptions, GlobalConfig, HdrSetting, HookConfig, LimitsConfig,
+    DiscordConfig, LoggingConfig, MangoHudConfig, ProtonOptions, ProtonSetting, ProtonVersion, RestartConfig,
+    SandboxConfig, SteamGridDbConfig, SteamRuntime, WineConfig, WrapperConfig, merge_wrappers,
+};
+use super::lookup::{find_game_config_path, with_group};
+use super::session::is_gamescope_session;
+use super::get_config_path;
+use crate::gpu::GpuSelector;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// Environment variable handing the config files a launch has already read to
+/// a nested invocation (`run` started by the gamescope shim), as JSON
+pub const RESOLVED_CONFIG_ENV: &str = "SCR_RESOLVED_CONFIG";
+
+/// The parsed config files behind a launch, as handed over in `RESOLVED_CONFIG_ENV`
+#[derive(Serialize, Deserialize)]
+struct ResolvedFiles {
+    app_id: Option<u32>,
+    global: GlobalConfig,
+    game: Option<GameConfig>,
+}
 
 /// Merged configuration for a specific game launch
 #[derive(Debug, Clone)]
@@ -19,93 +42,237 @@ pub struct MergedConfig {
     /// Execution mode (native, proton, auto)
     pub mode: ExecutionMode,
 
+    /// Whether to run the game's command unmodified
+    pub passthrough: bool,
+
     /// Proton version to use
     pub proton: Option<String>,
 
+    /// Versions to try, in order, when `proton` isn't installed
+    pub proton_fallbacks: Vec<String>,
+
+    /// Proton tunables (`version` is resolved into `proton` and left unset here)
+    pub proton_options: ProtonOptions,
+
     /// Pre-command to prepend
     pub pre_command: Option<String>,
 
     /// Environment variables to set
     pub env: HashMap<String, String>,
 
+    /// Wine DLL overrides (game entries win over global ones)
+    pub dll_overrides: HashMap<String, String>,
+
     /// Additional launch arguments
     pub launch_args: Vec<String>,
 
-    /// Pre-launch hook
-    pub pre_launch_hook: Option<HookConfig>,
+    /// Pre-launch hooks, in order
+    pub pre_launch_hooks: Vec<HookConfig>,
+
+    /// Post-exit hooks, in order
+    pub post_exit_hooks: Vec<HookConfig>,
 
-    /// Post-exit hook
-    pub post_exit_hook: Option<HookConfig>,
+    /// Hooks run when a supervised game crashes, in order
+    pub on_crash_hooks: Vec<HookConfig>,
 
     /// Whether we're in a Gamescope session
     pub is_gamescope_session: bool,
 
+    /// Whether we're running on a Steam Deck
+    pub is_steam_deck: bool,
+
     /// Gamescope-specific pre_command
     pub gamescope_pre_command: Option<String>,
 
     /// Skip pre_command in Gamescope
     pub skip_pre_command_in_gamescope: bool,
 
-    /// Arguments to pass to gamescope
+    /// Raw arguments to pass to gamescope (appended after the typed options)
     pub gamescope_args: Option<String>,
 
+    /// Typed gamescope options
+    pub gamescope: GamescopeOptions,
+
     /// Whether gamescope is enabled
     pub gamescope_enabled: bool,
 
+    /// Whether to launch without gamescope when it fails to start
+    pub gamescope_fallback: bool,
+
     /// Whether shim debug logging is enabled
     pub shim_debug: bool,
+
+    /// What the wrapper shims add, by wrapper name
+    pub wrappers: HashMap<String, WrapperConfig>,
     
     /// Arguments to append to the game command
     pub game_args: Option<String>,
+
+    /// Directory the game is started in
+    pub working_dir: Option<String>,
+
+    /// Proton compatdata directory to use when Steam doesn't set one
+    pub compat_data_path: Option<String>,
+
+    /// Directories prepended to LD_LIBRARY_PATH (game entries first)
+    pub ld_library_path_prepend: Vec<String>,
+
+    /// Libraries appended to LD_PRELOAD (global entries first)
+    pub ld_preload_extra: Vec<String>,
+
+    /// Whether to check native binaries for missing shared libraries
+    pub library_preflight: bool,
+
+    /// Whether to run the game as a supervised child process
+    pub supervise: bool,
+
+    /// Relaunching the game when it crashes
+    pub restart_on_crash: RestartConfig,
+
+    /// Whether to record play sessions
+    pub track_playtime: bool,
+
+    /// Whether to emit D-Bus signals on game start and stop
+    pub dbus_signals: bool,
+
+    /// Whether to hold an idle/sleep inhibitor lock while the game runs
+    pub inhibit_sleep: bool,
+
+    /// Power profile to switch to for the session (restored on exit)
+    pub power_profile: Option<String>,
+
+    /// Game output logging (capture already resolved against the game config)
+    pub logging: LoggingConfig,
+
+    /// Steam config file backup settings (global only)
+    pub backups: BackupConfig,
+
+    /// SteamGridDB access (global only)
+    pub steamgriddb: SteamGridDbConfig,
+
+    /// Describe the launch instead of running anything (set by `run --dry-run` and `explain`)
+    pub dry_run: bool,
+
+    /// Companion programs to run alongside the game
+    pub companions: Vec<CompanionConfig>,
+
+    /// Mod manager installed in the game's prefix
+    pub modmanager: Option<ModManagerConfig>,
+
+    /// Winetricks verbs to install into the game's prefix
+    pub winetricks: Vec<String>,
+
+    /// Where the game keeps its saves
+    pub saves: Option<SavesConfig>,
+
+    /// Memory limit for the game (e.g. "8G")
+    pub memory_max: Option<String>,
+
+    /// Other cgroup limits (`memory_max` is resolved into the field above)
+    pub limits: LimitsConfig,
+
+    /// OOM killer score adjustment for the game
+    pub oom_score_adj: Option<i32>,
+
+    /// MangoHud overlay settings
+    pub mangohud: MangoHudConfig,
+
+    /// Discord Rich Presence settings
+    pub discord: DiscordConfig,
+
+    /// GPU the game should render on
+    pub gpu: Option<GpuSelector>,
+
+    /// CPUs the game is pinned to (CPU list syntax, e.g. "0-7")
+    pub cpu_affinity: Option<String>,
+
+    /// Scheduling niceness for the game
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class and level for the game
+    pub ionice: Option<String>,
+
+    /// Bubblewrap sandbox settings
+    pub sandbox: SandboxConfig,
+
+    /// Steam Linux Runtime container native games run in
+    pub steam_runtime: SteamRuntime,
+
+    /// Wine binary and prefix for `mode = "wine"`
+    pub wine: WineConfig,
+
+    /// Name of the active profile, if any
+    pub profile: Option<String>,
 }
 
 impl MergedConfig {
+    /// The Proton version followed by its fallbacks, in the order to try them
+    pub fn proton_chain(&self) -> Vec<&str> {
+        self.proton
+            .iter()
+            .chain(&self.proton_fallbacks)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Name to show for the game in messages: its name, else its App ID
+    pub fn display_name(&self) -> String {
+        match (&self.name, self.app_id) {
+            (Some(name), _) => name.clone(),
+            (None, Some(app_id)) => format!("app {}", app_id),
+            (None, None) => "game".to_string(),
+        }
+    }
+
     /// Load and merge configuration for a game
+    ///
+    /// Config files handed over by the launch that started this process are used
+    /// instead of reading them again, unless a config file is given explicitly.
     pub fn load(app_id: Option<u32>, config_path: Option<PathBuf>) -> Result<Self, ConfigError> {
-        let is_gamescope = is_gamescope_session();
-        debug!("Gamescope session: {}", is_gamescope);
-
-        // Load global config
-        let global_path = config_path.unwrap_or_else(get_config_path);
-        let global = if global_path.exists() {
-            debug!("Loading global config from: {}", global_path.display());
-            let content = fs::read_to_string(&global_path)?;
-            toml::from_str(&content)?
-        } else {
-            debug!("No global config found, using defaults");
-            GlobalConfig::default()
-        };
+        let (global, game) = load_files(app_id, config_path)?;
+        Ok(Self::resolve(global, game, app_id))
+    }
 
-        // Load game-specific config if app_id is provided
-        let game = if let Some(id) = app_id {
-            let game_path = get_game_config_path(id);
-            if game_path.exists() {
-                debug!("Loading game config from: {}", game_path.display());
-                let content = fs::read_to_string(&game_path)?;
-                Some(toml::from_str(&content)?)
-            } else {
-                debug!("No game config found for app_id: {}", id);
-                None
-            }
-        } else {
-            None
-        };
+    /// Load like `load`, also returning the parsed files encoded for `RESOLVED_CONFIG_ENV`
+    pub fn load_for_handoff(app_id: Option<u32>) -> Result<(Self, Option<String>), ConfigError> {
+        let (global, game) = load_files(app_id, None)?;
+        let files = ResolvedFiles { app_id, global, game };
+        let handoff = serde_json::to_string(&files)
+            .map_err(|e| warn!("Could not encode the config for {}: {}", RESOLVED_CONFIG_ENV, e))
+            .ok();
+        Ok((Self::resolve(files.global, files.game, app_id), handoff))
+    }
 
-        Ok(Self::merge(global, game, is_gamescope, app_id))
+    /// Merge the loaded files with what is detected about this host and session
+    fn resolve(global: GlobalConfig, game: Option<GameConfig>, app_id: Option<u32>) -> Self {
+        let is_gamescope = is_gamescope_session();
+        let has_conditions = !global.when.is_empty() || game.as_ref().is_some_and(|game| !game.when.is_empty());
+        let facts = HostFacts::detect_for(has_conditions);
+        let profile = std::env::var("SCR_PROFILE").ok().filter(|p| !p.is_empty());
+        Self::merge(global, game, is_gamescope, app_id, &facts, profile.as_deref())
     }
 
     /// Merge global and game configurations
-    fn merge(
+    pub(crate) fn merge(
         global: GlobalConfig,
         game: Option<GameConfig>,
         is_gamescope: bool,
         app_id: Option<u32>,
+        facts: &HostFacts,
+        profile: Option<&str>,
     ) -> Self {
-        let game = game.unwrap_or_default();
+        let mut global = global;
+        let mut game = game.unwrap_or_default();
+        let when_blocks: Vec<WhenBlock> =
+            global.when.iter().chain(game.when.iter()).cloned().collect();
+        let mut global_profiles = std::mem::take(&mut global.profiles);
+        let mut game_profiles = std::mem::take(&mut game.profiles);
 
         // Merge environment variables (game overrides global)
         let mut env = global.env.clone();
         env.extend(game.env);
+        let mut dll_overrides = global.dll_overrides;
+        dll_overrides.extend(game.dll_overrides);
 
         // Handle pre_command with "inherit" keyword
         let pre_command = match &game.pre_command {
@@ -118,40 +285,162 @@ impl MergedConfig {
             None => global.pre_command.clone(),
         };
 
-        // Merge hooks (game overrides global)
-        let pre_launch_hook = game
-            .hooks
-            .pre_launch
-            .or(global.hooks.pre_launch);
-        let post_exit_hook = game
-            .hooks
-            .post_exit
-            .or(global.hooks.post_exit);
+        // Merge hooks (a game's hooks for a phase replace the global ones)
+        let phase = |game: Vec<HookConfig>, global: Vec<HookConfig>| {
+            if game.is_empty() {
+                global
+            } else {
+                game
+            }
+        };
+        let pre_launch_hooks = phase(game.hooks.pre_launch, global.hooks.pre_launch);
+        let post_exit_hooks = phase(game.hooks.post_exit, global.hooks.post_exit);
+        let on_crash_hooks = phase(game.hooks.on_crash, global.hooks.on_crash);
+
+        // Proton: the game's version wins, then default_proton, then the global [proton]
+        // version; tunables merge field-by-field
+        let mut game_proton = game.proton.map(ProtonSetting::into_options).unwrap_or_default();
+        let mut global_proton = global.proton;
+        let (proton, proton_fallbacks) = game_proton
+            .version
+            .take()
+            .or(global.default_proton)
+            .or(global_proton.version.take())
+            .map(ProtonVersion::split)
+            .unwrap_or_default();
+        let proton_options = game_proton.or(global_proton);
 
         // Gamescope args: game overrides global
         let gamescope_args = game.gamescope_args.or(global.gamescope.args);
 
+        // Typed gamescope options merge field-by-field
+        let mut gamescope = game.gamescope.or(global.gamescope.options);
+
+        // On a Deck, gamescope options without a size get the built-in panel's
+        if facts.steam_deck && !gamescope.is_empty() && gamescope.width.is_none() && gamescope.height.is_none() {
+            gamescope.width = Some(1280);
+            gamescope.height = Some(800);
+        }
+
         // Gamescope enabled: game overrides global
         let gamescope_enabled = game.gamescope_enabled.unwrap_or(global.gamescope.enabled);
 
-        Self {
+        // Library injection: game directories take priority in the search path,
+        // game preloads load after the global ones
+        let mut ld_library_path_prepend = game.ld_library_path_prepend;
+        ld_library_path_prepend.extend(global.ld_library_path_prepend);
+        let mut ld_preload_extra = global.ld_preload_extra;
+        ld_preload_extra.extend(game.ld_preload_extra);
+
+        // [limits] memory_max and the top-level memory_max are the same setting
+        let game_memory_max = game.limits.memory_max.take().or(game.memory_max);
+        let global_memory_max = global.limits.memory_max.take().or(global.memory_max);
+        let limits = game.limits.or(global.limits);
+
+        let mut logging = global.logging;
+        if let Some(capture) = game.capture_game_output {
+            logging.capture_game_output = capture;
+        }
+
+        let mut merged = Self {
             app_id,
             name: game.name,
             mode: game.mode.unwrap_or(global.default_mode),
-            proton: game.proton.or(global.default_proton),
+            passthrough: game.passthrough.unwrap_or(false),
+            proton,
+            proton_fallbacks,
+            proton_options,
             pre_command,
             env,
+            dll_overrides,
             launch_args: game.launch_args,
-            pre_launch_hook,
-            post_exit_hook,
+            pre_launch_hooks,
+            post_exit_hooks,
+            on_crash_hooks,
             is_gamescope_session: is_gamescope,
+            is_steam_deck: facts.steam_deck,
             gamescope_pre_command: global.gamescope.pre_command,
             skip_pre_command_in_gamescope: global.gamescope.skip_pre_command,
             gamescope_args,
+            gamescope,
             gamescope_enabled,
+            gamescope_fallback: game.gamescope_fallback.unwrap_or(global.gamescope.fallback),
             shim_debug: global.shim_debug,
+            wrappers: merge_wrappers(game.wrappers, global.wrappers),
             game_args: game.game_args.or(global.game_args),
+            working_dir: game.working_dir,
+            compat_data_path: game.compat_data_path,
+            ld_library_path_prepend,
+            ld_preload_extra,
+            library_preflight: game.library_preflight.unwrap_or(global.library_preflight),
+            supervise: game.supervise.unwrap_or(global.supervise),
+            restart_on_crash: game.restart_on_crash.unwrap_or(global.restart_on_crash),
+            track_playtime: game.track_playtime.unwrap_or(global.track_playtime),
+            dbus_signals: game.dbus_signals.unwrap_or(global.dbus_signals),
+            inhibit_sleep: game.inhibit_sleep.unwrap_or(global.inhibit_sleep),
+            power_profile: game
+                .power_profile
+                .or(global.power_profile)
+                .filter(|profile| !profile.is_empty()),
+            logging,
+            backups: global.backups,
+            steamgriddb: global.steamgriddb,
+            companions: game.companions,
+            modmanager: game.modmanager,
+            winetricks: game.winetricks,
+            saves: game.saves,
+            memory_max: game_memory_max.or(global_memory_max),
+            limits,
+            oom_score_adj: game.oom_score_adj.or(global.oom_score_adj),
+            mangohud: game.mangohud.or(global.mangohud),
+            discord: game.discord.or(global.discord),
+            gpu: game.gpu.or(global.gpu),
+            cpu_affinity: game.cpu_affinity,
+            nice: game.nice,
+            ionice: game.ionice,
+            sandbox: game.sandbox.or(global.sandbox),
+            steam_runtime: game.steam_runtime.unwrap_or(global.steam_runtime),
+            wine: game.wine.or(global.wine),
+            profile: None,
+            dry_run: false,
+        };
+
+        // Conditional overrides go on top: global blocks first, then game blocks
+        let mut conditional_profile = None;
+        for block in when_blocks.iter().filter(|b| b.matches(facts)) {
+            debug!("Applying matching [[when]] block");
+            block.overlay.apply(&mut merged);
+            if block.profile.is_some() {
+                conditional_profile = block.profile.clone();
+            }
+        }
+
+        // The selected profile goes last: an explicit choice wins over one picked by a
+        // [[when]] block, and the game's profile is layered over the global one
+        if let Some(name) = profile.map(str::to_string).or(conditional_profile) {
+            let global_profile = global_profiles.remove(&name);
+            let game_profile = game_profiles.remove(&name);
+            if global_profile.is_none() && game_profile.is_none() {
+                warn!("Profile '{}' is not defined in the global or game config", name);
+            }
+            for overlay in global_profile.iter().chain(game_profile.iter()) {
+                overlay.apply(&mut merged);
+            }
+            debug!("Active profile: {}", name);
+            merged.profile = Some(name);
+        }
+
+        // hdr = "auto" is decided once every layer has had its say
+        if let Some(hdr) = merged.gamescope.hdr {
+            merged.gamescope.hdr = Some(HdrSetting::Enabled(hdr.resolve(facts.hdr())));
         }
+
+        merged
+    }
+
+    /// Whether the game should get HDR output
+    pub fn hdr_enabled(&self) -> bool {
+        self.gamescope.hdr == Some(HdrSetting::Enabled(true))
     }
 
     /// Get the effective pre_command considering Gamescope session
@@ -168,15 +457,461 @@ impl MergedConfig {
     }
 }
 
-/// Check if running in a Gamescope session or if the wrapper script is handling gamescope
-fn is_gamescope_session() -> bool {
-    // Check if we're inside a gamescope session
-    let in_gamescope = std::env::var("XDG_CURRENT_DESKTOP")
-        .map(|v| v.to_lowercase() == "gamescope")
-        .unwrap_or(false);
+/// Read the global config and the game's config (with its group applied)
+fn load_files(app_id: Option<u32>, config_path: Option<PathBuf>) -> Result<(GlobalConfig, Option<GameConfig>), ConfigError> {
+    if config_path.is_none() {
+        if let Some(files) = std::env::var(RESOLVED_CONFIG_ENV).ok().and_then(|encoded| decode_handoff(&encoded, app_id)) {
+            debug!("Using the config handed over in {}", RESOLVED_CONFIG_ENV);
+            return Ok((files.global, files.game));
+        }
+    }
+
+    // Load global config
+    let global_path = config_path.unwrap_or_else(get_config_path);
+    let mut global = load_global_config(&global_path)?;
+
+    // Load game-specific config if app_id is provided
+    let game = if let Some(id) = app_id {
+        let game_path = find_game_config_path(id);
+        if game_path.exists() {
+            debug!("Loading game config from: {}", game_path.display());
+            let content = fs::read_to_string(&game_path)?;
+            Some(with_group(parse_config(&content)?, &mut global.groups)?)
+        } else {
+            debug!("No game config found for app_id: {}", id);
+            None
+        }
+    } else {
+        None
+    };
+    Ok((global, game))
+}
+
+/// Handed-over config files, if they are for this game
+fn decode_handoff(encoded: &str, app_id: Option<u32>) -> Option<ResolvedFiles> {
+    match serde_json::from_str::<ResolvedFiles>(encoded) {
+        Ok(files) if files.app_id == app_id => Some(files),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Ignoring {}: {}", RESOLVED_CONFIG_ENV, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LocalTime;
+
+    fn facts_at(hour: u32) -> HostFacts {
+        HostFacts {
+            now: LocalTime {
+                minutes: hour * 60,
+                weekday: 3,
+            },
+            hdr: Some(false),
+            ..HostFacts::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_applies_matching_when_blocks_in_order() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            [gamescope]
+            args = "-w 2560 -h 1440"
+
+            [[when]]
+            schedule = { after = "22:00", before = "06:00" }
+            gamescope_args = "-w 1920 -h 1080 -r 60"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [[when]]
+            schedule = { after = "23:00" }
+            gamescope_args = "-w 1280 -h 720 -r 30"
+            "#,
+        )
+        .unwrap();
+
+        let day = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(12), None);
+        assert_eq!(day.gamescope_args.as_deref(), Some("-w 2560 -h 1440"));
+
+        let night = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(22), None);
+        assert_eq!(night.gamescope_args.as_deref(), Some("-w 1920 -h 1080 -r 60"));
+
+        let late = MergedConfig::merge(global, Some(game), false, None, &facts_at(23), None);
+        assert_eq!(late.gamescope_args.as_deref(), Some("-w 1280 -h 720 -r 30"));
+    }
+
+    #[test]
+    fn test_merge_layers_selected_profile() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            default_proton = "Proton 9.0"
+
+            [profiles.battery]
+            gamescope_args = "-w 1280 -h 800 -r 40"
+            [profiles.battery.env]
+            DXVK_FRAME_RATE = "40"
+
+            [[when]]
+            schedule = { after = "22:00" }
+            profile = "battery"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [profiles.battery]
+            proton = "GE-Proton9-20"
+
+            [profiles.streaming.env]
+            OBS_VKCAPTURE = "1"
+            "#,
+        )
+        .unwrap();
+
+        let none = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(12), None);
+        assert_eq!(none.profile, None);
+        assert_eq!(none.proton.as_deref(), Some("Proton 9.0"));
+
+        let battery = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(12), Some("battery"));
+        assert_eq!(battery.profile.as_deref(), Some("battery"));
+        assert_eq!(battery.proton.as_deref(), Some("GE-Proton9-20"));
+        assert_eq!(battery.gamescope_args.as_deref(), Some("-w 1280 -h 800 -r 40"));
+        assert_eq!(battery.env.get("DXVK_FRAME_RATE").map(String::as_str), Some("40"));
+
+        let scheduled = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(23), None);
+        assert_eq!(scheduled.profile.as_deref(), Some("battery"));
+
+        let explicit = MergedConfig::merge(global, Some(game), false, None, &facts_at(23), Some("streaming"));
+        assert_eq!(explicit.profile.as_deref(), Some("streaming"));
+        assert_eq!(explicit.env.get("OBS_VKCAPTURE").map(String::as_str), Some("1"));
+        assert_eq!(explicit.gamescope_args, None);
+    }
+
+    #[test]
+    fn test_handoff_round_trip() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            default_proton = "Proton 9.0"
+            pre_command = "gamemoderun"
+            [gamescope]
+            args = "-w 2560 -h 1440"
+            [env]
+            A = "global"
+            [profiles.battery]
+            gamescope_args = "-w 1280 -h 800 -r 40"
+            [[when]]
+            schedule = { after = "22:00" }
+            profile = "battery"
+            [[hooks.pre_launch]]
+            command = "echo start"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            proton = "GE-Proton9-20"
+            launch_args = ["-novid"]
+            [env]
+            B = "game"
+            "#,
+        )
+        .unwrap();
+        let files = ResolvedFiles { app_id: Some(620), global, game: Some(game) };
+        let encoded = serde_json::to_string(&files).unwrap();
+
+        let decoded = decode_handoff(&encoded, Some(620)).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&files).unwrap());
+        let merged = MergedConfig::merge(decoded.global, decoded.game, false, Some(620), &facts_at(23), None);
+        assert_eq!(merged.profile.as_deref(), Some("battery"));
+        assert_eq!(merged.gamescope_args.as_deref(), Some("-w 1280 -h 800 -r 40"));
+        assert_eq!(merged.pre_launch_hooks.len(), 1);
+
+        assert!(decode_handoff(&encoded, Some(440)).is_none());
+        assert!(decode_handoff("{", Some(620)).is_none());
+    }
+
+    #[test]
+    fn test_merge_limits() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            memory_max = "16G"
+            [limits]
+            cpu_quota = "800%"
+            io_weight = 50
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [limits]
+            memory_max = "8G"
+            io_weight = 200
+            "#,
+        )
+        .unwrap();
+
+        let merged = MergedConfig::merge(global.clone(), Some(game), false, None, &facts_at(12), None);
+        assert_eq!(merged.memory_max.as_deref(), Some("8G"));
+        assert_eq!(merged.limits.cpu_quota.as_deref(), Some("800%"));
+        assert_eq!(merged.limits.io_weight, Some(200));
+        assert_eq!(merged.limits.memory_max, None);
+
+        let merged = MergedConfig::merge(global, None, false, None, &facts_at(12), None);
+        assert_eq!(merged.memory_max.as_deref(), Some("16G"));
+    }
 
-    // Check if the wrapper script is handling gamescope for us
-    let wrapper_handling = std::env::var("STEAM_CMD_RUNNER_GAMESCOPE_HANDLED").is_ok();
+    #[test]
+    fn test_merge_hooks() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            [[hooks.pre_launch]]
+            command = "powerprofilesctl set performance"
 
-    in_gamescope || wrapper_handling
+            [[hooks.pre_launch]]
+            command = "syncthing cli config folders pause"
+            continue_on_error = false
+
+            [hooks.post_exit]
+            command = "powerprofilesctl set balanced"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [hooks.post_exit]
+            command = "backup-saves"
+            "#,
+        )
+        .unwrap();
+
+        let merged = MergedConfig::merge(global, Some(game), false, None, &facts_at(12), None);
+        let commands: Vec<&str> = merged.pre_launch_hooks.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(commands, vec!["powerprofilesctl set performance", "syncthing cli config folders pause"]);
+        assert!(merged.pre_launch_hooks[0].continue_on_error);
+        assert!(!merged.pre_launch_hooks[1].continue_on_error);
+        assert_eq!(merged.post_exit_hooks.len(), 1);
+        assert_eq!(merged.post_exit_hooks[0].command, "backup-saves");
+        assert!(merged.on_crash_hooks.is_empty());
+    }
+
+    #[test]
+    fn test_merge_steam_deck_gamescope_size() {
+        let global: GlobalConfig = toml::from_str("[gamescope]\nrefresh = 60").unwrap();
+        let deck = HostFacts {
+            steam_deck: true,
+            ..facts_at(12)
+        };
+        let merged = MergedConfig::merge(global.clone(), None, false, None, &deck, None);
+        assert!(merged.is_steam_deck);
+        assert_eq!((merged.gamescope.width, merged.gamescope.height), (Some(1280), Some(800)));
+
+        let merged = MergedConfig::merge(global, None, false, None, &facts_at(12), None);
+        assert_eq!(merged.gamescope.width, None);
+    }
+
+    #[test]
+    fn test_merge_hdr_auto() {
+        let global: GlobalConfig = toml::from_str("[gamescope]\nhdr = \"auto\"").unwrap();
+        let hdr = HostFacts {
+            hdr: Some(true),
+            ..facts_at(12)
+        };
+        let merged = MergedConfig::merge(global.clone(), None, false, None, &hdr, None);
+        assert_eq!(merged.gamescope.hdr, Some(HdrSetting::Enabled(true)));
+        assert!(merged.hdr_enabled());
+
+        let merged = MergedConfig::merge(global, None, false, None, &facts_at(12), None);
+        assert!(!merged.hdr_enabled());
+
+        let game: GameConfig = toml::from_str("[gamescope]\nhdr = true").unwrap();
+        let merged = MergedConfig::merge(GlobalConfig::default(), Some(game), false, None, &facts_at(12), None);
+        assert!(merged.hdr_enabled());
+    }
+
+    #[test]
+    fn test_merge_wrappers() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            [wrappers.mangohud]
+            args = "--dlsym"
+            env = { MANGOHUD_DLSYM = "1", A = "global" }
+
+            [wrappers.gamemoderun.env]
+            GAMEMODERUNEXEC = "nvidia-offload"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str("[wrappers.mangohud.env]\nA = \"game\"").unwrap();
+
+        let merged = MergedConfig::merge(global, Some(game), false, None, &facts_at(12), None);
+        let mangohud = &merged.wrappers["mangohud"];
+        assert_eq!(mangohud.args.as_deref(), Some("--dlsym"));
+        assert_eq!(mangohud.env["A"], "game");
+        assert_eq!(mangohud.env["MANGOHUD_DLSYM"], "1");
+        assert_eq!(merged.wrappers["gamemoderun"].env["GAMEMODERUNEXEC"], "nvidia-offload");
+    }
+
+    #[test]
+    fn test_merge_wine() {
+        let global: GlobalConfig = toml::from_str("[wine]\nbinary = \"wine64\"\nprefix = \"/games/wine\"").unwrap();
+        let game: GameConfig = toml::from_str("mode = \"wine\"\n[wine]\nprefix = \"/games/this\"").unwrap();
+
+        let merged = MergedConfig::merge(global, Some(game), false, Some(440), &facts_at(12), None);
+        assert_eq!(merged.mode, ExecutionMode::Wine);
+        assert_eq!(merged.wine.binary_path(), "wine64");
+        assert_eq!(merged.wine.prefix_path(merged.app_id), PathBuf::from("/games/this"));
+
+        let merged = MergedConfig::merge(GlobalConfig::default(), None, false, Some(440), &facts_at(12), None);
+        assert_eq!(merged.wine.binary_path(), "wine");
+        assert!(merged.wine.prefix_path(Some(440)).ends_with("steam-command-runner/wine/440"));
+    }
+
+    #[test]
+    fn test_merge_power_profile() {
+        let global: GlobalConfig = toml::from_str(r#"power_profile = "performance""#).unwrap();
+        let merged = MergedConfig::merge(global.clone(), None, false, None, &facts_at(12), None);
+        assert_eq!(merged.power_profile.as_deref(), Some("performance"));
+
+        let game: GameConfig = toml::from_str(r#"power_profile = """#).unwrap();
+        let merged = MergedConfig::merge(global, Some(game), false, None, &facts_at(12), None);
+        assert_eq!(merged.power_profile, None);
+    }
+
+    #[test]
+    fn test_merge_group() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            pre_command = "gamemoderun"
+            [env]
+            A = "global"
+            [groups.vr]
+            pre_command = "inherit vr-wrapper"
+            launch_args = ["-vr"]
+            [groups.vr.env]
+            A = "group"
+            B = "group"
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            group = "vr"
+            pre_command = "inherit --fast"
+            [env]
+            B = "game"
+            "#,
+        )
+        .unwrap();
+        let mut groups = global.groups.clone();
+        let game = with_group(game, &mut groups).unwrap();
+        let merged = MergedConfig::merge(global, Some(game), false, None, &facts_at(12), None);
+        assert_eq!(merged.pre_command.as_deref(), Some("gamemoderun vr-wrapper --fast"));
+        assert_eq!(merged.launch_args, vec!["-vr"]);
+        assert_eq!(merged.env["A"], "group");
+        assert_eq!(merged.env["B"], "game");
+    }
+
+    #[test]
+    fn test_merge_proton_options() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            [proton]
+            version = "Proton 9.0"
+            esync = false
+            fsr = true
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [proton]
+            version = "GE-Proton9-20"
+            fsr = false
+            nvapi = true
+
+            [profiles.debug.proton]
+            log = true
+            "#,
+        )
+        .unwrap();
+
+        let merged = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(12), None);
+        assert_eq!(merged.proton.as_deref(), Some("GE-Proton9-20"));
+        assert_eq!(merged.proton_options.version, None);
+        assert_eq!(
+            merged.proton_options.env_vars(),
+            vec![
+                ("PROTON_NO_ESYNC", "1".to_string()),
+                ("WINE_FULLSCREEN_FSR", "0".to_string()),
+                ("PROTON_ENABLE_NVAPI", "1".to_string()),
+            ]
+        );
+
+        let debug = MergedConfig::merge(global.clone(), Some(game), false, None, &facts_at(12), Some("debug"));
+        assert!(debug.proton_options.env_vars().contains(&("PROTON_LOG", "1".to_string())));
+
+        let version_only: GameConfig = toml::from_str(r#"proton = "Proton Experimental""#).unwrap();
+        let merged = MergedConfig::merge(global, Some(version_only), false, None, &facts_at(12), None);
+        assert_eq!(merged.proton.as_deref(), Some("Proton Experimental"));
+        assert_eq!(merged.proton_options.fsr, Some(true));
+    }
+
+    #[test]
+    fn test_merge_proton_fallback_chain() {
+        let global: GlobalConfig = toml::from_str(r#"default_proton = "Proton 9.0""#).unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            proton = ["GE-Proton9-20", "Proton - Experimental", "any"]
+
+            [profiles.stable]
+            proton = "Proton 8.0"
+            "#,
+        )
+        .unwrap();
+
+        let merged = MergedConfig::merge(global.clone(), Some(game.clone()), false, None, &facts_at(12), None);
+        assert_eq!(merged.proton.as_deref(), Some("GE-Proton9-20"));
+        assert_eq!(merged.proton_chain(), vec!["GE-Proton9-20", "Proton - Experimental", "any"]);
+
+        // A profile's single version replaces the whole chain
+        let stable = MergedConfig::merge(global.clone(), Some(game), false, None, &facts_at(12), Some("stable"));
+        assert_eq!(stable.proton_chain(), vec!["Proton 8.0"]);
+
+        let merged = MergedConfig::merge(global, None, false, None, &facts_at(12), None);
+        assert_eq!(merged.proton_chain(), vec!["Proton 9.0"]);
+    }
+
+    #[test]
+    fn test_merge_gamescope_options_field_by_field() {
+        let global: GlobalConfig = toml::from_str(
+            r#"
+            [gamescope]
+            width = 2560
+            height = 1440
+            fullscreen = true
+            "#,
+        )
+        .unwrap();
+        let game: GameConfig = toml::from_str(
+            r#"
+            [gamescope]
+            height = 1080
+            fsr = true
+            "#,
+        )
+        .unwrap();
+
+        let merged = MergedConfig::merge(global, Some(game), false, None, &facts_at(12), None);
+        assert_eq!(merged.gamescope.width, Some(2560));
+        assert_eq!(merged.gamescope.height, Some(1080));
+        assert_eq!(merged.gamescope.fullscreen, Some(true));
+        assert_eq!(merged.gamescope.fsr, Some(true));
+    }
 }
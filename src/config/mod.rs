@@ -1,14 +1,45 @@
+mod conditions;
+mod dropin;
+mod edit;
 mod error;
 mod game;
 mod global;
+mod interpolate;
+mod lookup;
 mod merged;
+mod overlay;
+mod session;
+mod validate;
 
+pub use conditions::{hdr_available, is_steam_deck, HostFacts, LocalTime, Schedule, WhenBlock};
+pub use dropin::{config_fragments, load_global_config};
+pub use edit::{get_key, set_key};
 pub use error::ConfigError;
-pub use game::GameConfig;
-pub use global::{ExecutionMode, GlobalConfig, GamescopeConfig, HookConfig, HooksConfig};
-pub use merged::MergedConfig;
+pub use game::{CompanionConfig, GameConfig, GameMatch, ModManagerConfig, ModManagerKind, SavesConfig};
+pub use global::{
+    BackupConfig, DiscordConfig, ExecutionMode, GamescopeConfig, GamescopeOptions, GlobalConfig, HdrSetting, HookConfig, HooksConfig,
+    LimitsConfig, LogFormat, LogLevel, LoggingConfig, MangoHudConfig, Notifications, ProtonOptions, ProtonSetting, ProtonVersion, RestartConfig,
+    SandboxConfig, SteamGridDbConfig, SteamRuntime, WineConfig, WrapperConfig,
+};
+pub use interpolate::{expand_env, parse_config};
+pub use lookup::{find_game_config_path, with_group};
+pub(crate) use lookup::glob_match;
+pub use merged::{MergedConfig, RESOLVED_CONFIG_ENV};
+pub use overlay::ConfigOverlay;
+pub use session::{is_gamescope_session, GAMESCOPE_SESSION_OVERRIDE};
+pub use validate::{check_keys, validate_config, ConfigKind, Problem};
 
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Forget the configs parsed and the session detected so far in this process
+///
+/// The next `MergedConfig::load` then does all its work again, as a freshly
+/// started launch would; `bench launch-overhead` relies on this to time cold loads.
+pub fn clear_load_caches() {
+    dropin::clear_parsed();
+    session::clear_detected();
+}
 
 /// Get the global config file path
 pub fn get_config_path() -> PathBuf {
@@ -33,3 +64,46 @@ pub fn get_games_config_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(".config"));
     config_dir.join("steam-command-runner").join("games")
 }
+
+/// Get the group config directory
+pub fn get_groups_config_dir() -> PathBuf {
+    let config_dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from(".config"));
+    config_dir.join("steam-command-runner").join("groups")
+}
+
+/// Expand a leading "~/" in a configured path against the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Steam installation directory set with `steam_root` in the global config
+///
+/// Read once per process; Steam path lookups happen in many places that don't
+/// otherwise have the config loaded.
+pub fn configured_steam_root() -> Option<PathBuf> {
+    static STEAM_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    STEAM_ROOT
+        .get_or_init(|| {
+            let global = load_global_config(&get_config_path()).ok()?;
+            Some(expand_home(&global.steam_root?))
+        })
+        .clone()
+}
+
+/// Desktop notification verbosity set with `notifications` in the global config
+///
+/// Read once per process, like `steam_root`, so commands that never load the
+/// merged config can still notify.
+pub fn configured_notifications() -> Notifications {
+    static NOTIFICATIONS: OnceLock<Notifications> = OnceLock::new();
+    *NOTIFICATIONS.get_or_init(|| {
+        load_global_config(&get_config_path())
+            .map(|global| global.notifications)
+            .ok()
+            .unwrap_or_default()
+    })
+}
@@ -0,0 +1,68 @@
+use super::global::{GamescopeOptions, ProtonSetting};
+use super::merged::MergedConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// A set of settings layered on top of the merged configuration
+///
+/// Unset fields leave the underlying value alone; `env` entries are added on top.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ConfigOverlay {
+    /// Replace the pre-command ("inherit" expands to the underlying pre-command)
+    #[serde(default)]
+    pub pre_command: Option<String>,
+
+    /// Replace the Proton version, or set Proton tunables (`[profiles.<name>.proton]`)
+    #[serde(default)]
+    pub proton: Option<ProtonSetting>,
+
+    /// Extra environment variables
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Replace the gamescope arguments
+    #[serde(default)]
+    pub gamescope_args: Option<String>,
+
+    /// Typed gamescope options (each set field replaces the underlying one)
+    #[serde(default)]
+    pub gamescope: GamescopeOptions,
+
+    /// Enable or disable gamescope
+    #[serde(default)]
+    pub gamescope_enabled: Option<bool>,
+
+    /// Replace the arguments appended to the game command
+    #[serde(default)]
+    pub game_args: Option<String>,
+}
+
+impl ConfigOverlay {
+    /// Layer these settings on top of a merged configuration
+    pub fn apply(&self, config: &mut MergedConfig) {
+        if let Some(cmd) = &self.pre_command {
+            let underlying = config.pre_command.as_deref().unwrap_or("");
+            config.pre_command = Some(cmd.replace("inherit", underlying).trim().to_string());
+        }
+        if let Some(proton) = &self.proton {
+            let mut options = proton.clone().into_options();
+            if let Some(version) = options.version.take() {
+                (config.proton, config.proton_fallbacks) = version.split();
+            }
+            config.proton_options = options.or(config.proton_options.clone());
+        }
+        config.env.extend(self.env.clone());
+        if let Some(args) = &self.gamescope_args {
+            config.gamescope_args = Some(args.clone());
+        }
+        config.gamescope = self.gamescope.clone().or(config.gamescope.clone());
+        if let Some(enabled) = self.gamescope_enabled {
+            config.gamescope_enabled = enabled;
+        }
+        if let Some(args) = &self.game_args {
+            config.game_args = Some(args.clone());
+        }
+        debug!("Applied config overlay: {:?}", self);
+    }
+}
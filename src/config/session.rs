@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Environment variable that forces gamescope session detection on ("1") or off ("0")
+pub const GAMESCOPE_SESSION_OVERRIDE: &str = "SCR_GAMESCOPE_SESSION";
+
+/// How many parent processes to look through for a gamescope ancestor
+const MAX_ANCESTORS: usize = 32;
+
+/// Result of the first detection in this process
+static DETECTED: Mutex<Option<bool>> = Mutex::new(None);
+
+/// Check if we're running inside gamescope, so the game must not be wrapped again
+///
+/// Holds for a gamescope desktop session (Steam Deck Game Mode), for games nested
+/// in a gamescope window on another desktop, and when the wrapper script is
+/// handling gamescope for us. Detected once per process, since neither the
+/// environment we were started with nor our ancestors change.
+pub fn is_gamescope_session() -> bool {
+    *DETECTED.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(|| {
+        let env = |name: &str| std::env::var(name).ok();
+        let detected = detect_gamescope_session(&env, &ancestor_names);
+        debug!("Gamescope session: {}", detected);
+        detected
+    })
+}
+
+/// Forget the detected session, so the next check looks again
+pub(crate) fn clear_detected() {
+    *DETECTED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn detect_gamescope_session(
+    env: &dyn Fn(&str) -> Option<String>,
+    ancestors: &dyn Fn() -> Vec<String>,
+) -> bool {
+    match env(GAMESCOPE_SESSION_OVERRIDE).as_deref() {
+        Some("1") => return true,
+        Some("0") => return false,
+        _ => {}
+    }
+
+    let set = |name: &str| env(name).is_some_and(|v| !v.is_empty());
+    let desktop_is_gamescope =
+        env("XDG_CURRENT_DESKTOP").is_some_and(|v| v.eq_ignore_ascii_case("gamescope"));
+
+    // gamescope exports its Wayland display to everything it runs, and
+    // gamescope-session sets STEAM_MULTIPLE_XWAYLANDS for Steam
+    desktop_is_gamescope
+        || env("STEAM_CMD_RUNNER_GAMESCOPE_HANDLED").is_some()
+        || set("GAMESCOPE_WAYLAND_DISPLAY")
+        || env("STEAM_MULTIPLE_XWAYLANDS").as_deref() == Some("1")
+        || ancestors().iter().any(|name| name.starts_with("gamescope"))
+}
+
+/// Process names of our parent, its parent, and so on up to init
+fn ancestor_names() -> Vec<String> {
+    let mut names = Vec::new();
+    let mut pid = std::os::unix::process::parent_id();
+    while pid > 1 && names.len() < MAX_ANCESTORS {
+        let Some((name, parent)) = read_stat(&Path::new("/proc").join(pid.to_string())) else {
+            break;
+        };
+        names.push(name);
+        pid = parent;
+    }
+    names
+}
+
+/// Name and parent PID from a /proc/<pid>/stat file
+fn read_stat(proc_dir: &Path) -> Option<(String, u32)> {
+    parse_stat(&fs::read_to_string(proc_dir.join("stat")).ok()?)
+}
+
+/// Parse "pid (name) state ppid ..."; the name may itself contain spaces and parentheses
+fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    let ppid = stat.get(close + 1..)?.split_whitespace().nth(1)?.parse().ok()?;
+    Some((name, ppid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn detect(vars: &[(&str, &str)], ancestors: &[&str]) -> bool {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let ancestors: Vec<String> = ancestors.iter().map(|a| a.to_string()).collect();
+        detect_gamescope_session(&|name| vars.get(name).cloned(), &|| ancestors.clone())
+    }
+
+    #[test]
+    fn test_detect_gamescope_session() {
+        assert!(!detect(&[("XDG_CURRENT_DESKTOP", "KDE")], &["steam", "plasmashell"]));
+        assert!(detect(&[("XDG_CURRENT_DESKTOP", "gamescope")], &[]));
+        assert!(detect(&[("GAMESCOPE_WAYLAND_DISPLAY", "gamescope-0")], &[]));
+        assert!(detect(&[("STEAM_MULTIPLE_XWAYLANDS", "1")], &[]));
+        assert!(detect(&[], &["reaper", "gamescope-wl", "kwin_wayland"]));
+
+        assert!(!detect(&[(GAMESCOPE_SESSION_OVERRIDE, "0")], &["gamescope"]));
+        assert!(detect(&[(GAMESCOPE_SESSION_OVERRIDE, "1")], &[]));
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        assert_eq!(
+            parse_stat("4242 (Web Content (x)) S 17 4242 4242 0 -1"),
+            Some(("Web Content (x)".to_string(), 17))
+        );
+        assert_eq!(parse_stat("garbage"), None);
+    }
+}
@@ -0,0 +1,296 @@
+use super::game::GameConfig;
+use super::global::{
+    ExecutionMode, GlobalConfig, HooksConfig, ProtonSetting, ProtonVersion, WineConfig,
+};
+use super::overlay::ConfigOverlay;
+use crate::proton::{locate_proton, locate_proton_chain};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::Path;
+use toml_edit::{ImDocument, Item};
+
+/// Which kind of config file is being checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    Global,
+    Game,
+}
+
+/// A problem found in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 1-based line number, when the problem can be pinned to one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Check a config file's contents
+///
+/// Reports syntax and type errors, keys that aren't settings (usually typos, which
+/// would otherwise be silently ignored), Proton versions that aren't installed,
+/// and hook or companion commands that can't be found.
+pub fn validate_config(content: &str, kind: ConfigKind) -> Vec<Problem> {
+    match kind {
+        ConfigKind::Global => match parse_checked::<GlobalConfig>(content) {
+            Ok((config, mut problems)) => {
+                problems.extend(check_global(content, &config));
+                problems
+            }
+            Err(problem) => vec![problem],
+        },
+        ConfigKind::Game => match parse_checked::<GameConfig>(content) {
+            Ok((config, mut problems)) => {
+                problems.extend(check_game(content, &config));
+                problems
+            }
+            Err(problem) => vec![problem],
+        },
+    }
+}
+
+/// Check only that a config parses and has no unknown keys
+///
+/// Unlike `validate_config` this doesn't look at the host (installed Proton
+/// versions, programs in PATH), so it is used to vet edits before writing them.
+pub fn check_keys(content: &str, kind: ConfigKind) -> Vec<Problem> {
+    let result = match kind {
+        ConfigKind::Global => parse_checked::<GlobalConfig>(content).map(|(_, problems)| problems),
+        ConfigKind::Game => parse_checked::<GameConfig>(content).map(|(_, problems)| problems),
+    };
+    result.unwrap_or_else(|problem| vec![problem])
+}
+
+/// Parse a config, collecting keys the config type doesn't know
+fn parse_checked<T: DeserializeOwned>(content: &str) -> Result<(T, Vec<Problem>), Problem> {
+    let mut unknown: Vec<Vec<String>> = Vec::new();
+    let config = serde_ignored::deserialize(toml::Deserializer::new(content), |path| {
+        unknown.push(path_segments(&path));
+    })
+    .map_err(|e| Problem {
+        line: e.span().map(|span| line_of(content, span.start)),
+        message: e.message().to_string(),
+    })?;
+
+    let doc = ImDocument::parse(content).ok();
+    let problems = unknown
+        .into_iter()
+        .map(|segments| Problem {
+            line: doc.as_ref().and_then(|doc| line_of_key(content, doc, &segments)),
+            message: format!("unknown key '{}'", segments.join(".")),
+        })
+        .collect();
+    Ok((config, problems))
+}
+
+fn path_segments(path: &serde_ignored::Path) -> Vec<String> {
+    let mut segments = match path {
+        serde_ignored::Path::Root => return Vec::new(),
+        serde_ignored::Path::Seq { parent, .. }
+        | serde_ignored::Path::Map { parent, .. }
+        | serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => path_segments(parent),
+    };
+    match path {
+        serde_ignored::Path::Seq { index, .. } => segments.push(index.to_string()),
+        serde_ignored::Path::Map { key, .. } => segments.push(key.clone()),
+        _ => {}
+    }
+    segments
+}
+
+/// Line of the deepest part of a dotted key path that exists in the document
+fn line_of_key(content: &str, doc: &ImDocument<&str>, segments: &[String]) -> Option<usize> {
+    let mut item: &Item = doc.as_item();
+    let mut span = None;
+    for segment in segments {
+        let next = match segment.parse::<usize>() {
+            Ok(index) => item.get(index),
+            Err(_) => item.get(segment.as_str()),
+        };
+        let Some(next) = next else { break };
+        span = next.span().or(span);
+        item = next;
+    }
+    span.map(|span| line_of(content, span.start))
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+fn check_global(content: &str, config: &GlobalConfig) -> Vec<Problem> {
+    let doc = ImDocument::parse(content).ok();
+    let line = |segments: &[&str]| {
+        let segments: Vec<String> = segments.iter().map(|s| s.to_string()).collect();
+        doc.as_ref().and_then(|doc| line_of_key(content, doc, &segments))
+    };
+
+    let mut problems = Vec::new();
+    let mut versions = Vec::new();
+    if let Some(version) = &config.default_proton {
+        versions.push((version.clone(), line(&["default_proton"])));
+    }
+    if let Some(version) = &config.proton.version {
+        versions.push((version.clone(), line(&["proton", "version"])));
+    }
+    versions.extend(profile_versions(&config.profiles, &line));
+    problems.extend(check_proton_versions(versions));
+    problems.extend(check_hooks(&config.hooks, &line));
+    problems.extend(check_mode(config.default_mode, &config.wine, line(&["default_mode"])));
+    problems
+}
+
+fn check_game(content: &str, config: &GameConfig) -> Vec<Problem> {
+    let doc = ImDocument::parse(content).ok();
+    let line = |segments: &[&str]| {
+        let segments: Vec<String> = segments.iter().map(|s| s.to_string()).collect();
+        doc.as_ref().and_then(|doc| line_of_key(content, doc, &segments))
+    };
+
+    let mut problems = Vec::new();
+    let mut versions = Vec::new();
+    if let Some(version) = config.proton.clone().and_then(|p| p.into_options().version) {
+        versions.push((version, line(&["proton"])));
+    }
+    versions.extend(profile_versions(&config.profiles, &line));
+    problems.extend(check_proton_versions(versions));
+    problems.extend(check_hooks(&config.hooks, &line));
+    if let Some(mode) = config.mode {
+        problems.extend(check_mode(mode, &config.wine, line(&["mode"])));
+    }
+
+    for (i, companion) in config.companions.iter().enumerate() {
+        if let Some(message) = check_command(&companion.command) {
+            let index = i.to_string();
+            problems.push(Problem {
+                line: line(&["companions", &index, "command"]),
+                message: format!("companion {}", message),
+            });
+        }
+    }
+    problems
+}
+
+fn profile_versions(
+    profiles: &HashMap<String, ConfigOverlay>,
+    line: &dyn Fn(&[&str]) -> Option<usize>,
+) -> Vec<(ProtonVersion, Option<usize>)> {
+    let mut names: Vec<&String> = profiles.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let version = match profiles[name].proton.clone()? {
+                ProtonSetting::Version(version) => version,
+                ProtonSetting::Options(options) => options.version?,
+            };
+            Some((version, line(&["profiles", name, "proton"])))
+        })
+        .collect()
+}
+
+/// Report versions that aren't installed; a fallback chain only needs one of its entries
+fn check_proton_versions(versions: Vec<(ProtonVersion, Option<usize>)>) -> Vec<Problem> {
+    versions
+        .into_iter()
+        .filter_map(|(version, line)| {
+            let message = match version {
+                ProtonVersion::One(version) => {
+                    locate_proton(Some(&version)).is_err().then(|| {
+                        format!("Proton version '{}' is not installed", version)
+                    })?
+                }
+                ProtonVersion::Chain(chain) => {
+                    let chain: Vec<&str> = chain.iter().map(String::as_str).collect();
+                    locate_proton_chain(&chain).is_err().then(|| {
+                        format!("none of the Proton versions '{}' are installed", chain.join("', '"))
+                    })?
+                }
+            };
+            Some(Problem { line, message })
+        })
+        .collect()
+}
+
+fn check_hooks(hooks: &HooksConfig, line: &dyn Fn(&[&str]) -> Option<usize>) -> Vec<Problem> {
+    let phases = [
+        ("pre_launch", &hooks.pre_launch),
+        ("post_exit", &hooks.post_exit),
+        ("on_crash", &hooks.on_crash),
+    ];
+    let mut problems = Vec::new();
+    for (phase, phase_hooks) in phases {
+        for (i, hook) in phase_hooks.iter().enumerate() {
+            if let Some(message) = check_command(&hook.command) {
+                let index = i.to_string();
+                problems.push(Problem {
+                    line: line(&["hooks", phase, &index]).or_else(|| line(&["hooks", phase])),
+                    message: format!("{} hook {}", phase, message),
+                });
+            }
+        }
+    }
+    problems
+}
+
+/// Check the program an execution mode runs games through
+///
+/// umu mode falls back to plain Proton without umu-run, which is easy to miss.
+fn check_mode(mode: ExecutionMode, wine: &WineConfig, line: Option<usize>) -> Option<Problem> {
+    let (program, name) = match mode {
+        ExecutionMode::Umu => ("umu-run".to_string(), "umu"),
+        ExecutionMode::Wine => (wine.binary_path(), "wine"),
+        _ => return None,
+    };
+    check_command(&shlex::try_quote(&program).ok()?).map(|message| Problem {
+        line,
+        message: format!("mode \"{}\": {}", name, message),
+    })
+}
+
+/// Check that a command line parses and its program exists
+fn check_command(command: &str) -> Option<String> {
+    let Some(program) = shlex::split(command).and_then(|args| args.into_iter().next()) else {
+        return Some(format!("'{}' could not be parsed", command));
+    };
+    // Placeholders are only known at launch time
+    if program.contains('%') {
+        return None;
+    }
+
+    let found = if program.contains('/') {
+        Path::new(&program).exists()
+    } else {
+        std::env::var("PATH")
+            .map(|path| path.split(':').any(|dir| Path::new(dir).join(&program).exists()))
+            .unwrap_or(false)
+    };
+    (!found).then(|| format!("program '{}' not found", program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_keys_and_errors() {
+        let content = "pre_command = \"gamemoderun\"\nsupervize = true\n\n[mangohud]\nenabled = true\nfps_limt = 60\n";
+        let problems = validate_config(content, ConfigKind::Global);
+        assert_eq!(
+            problems,
+            vec![
+                Problem { line: Some(2), message: "unknown key 'supervize'".to_string() },
+                Problem { line: Some(6), message: "unknown key 'mangohud.fps_limt'".to_string() },
+            ]
+        );
+
+        let problems = validate_config("nice = \"high\"\n", ConfigKind::Game);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(1));
+
+        let problems = validate_config("[[hooks.pre_launch]]\ncommand = \"/nonexistent/hook\"\n", ConfigKind::Game);
+        assert_eq!(problems[0].message, "pre_launch hook program '/nonexistent/hook' not found");
+        assert_eq!(problems[0].line, Some(1));
+    }
+}
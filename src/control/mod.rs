@@ -0,0 +1,299 @@
+//! Control socket for supervised game sessions
+//!
+//! While a game runs supervised, the runner listens on
+//! `$XDG_RUNTIME_DIR/steam-command-runner/<pid>.sock`. Clients send one line,
+//! `status` or `stop`, and get one line of JSON back. The directory is only
+//! used when it belongs to the current user and nobody else can write to it.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How long a client waits for a session to answer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a running session reports about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub app_id: Option<u32>,
+    pub name: Option<String>,
+    /// PID of the steam-command-runner process supervising the game
+    pub pid: u32,
+    /// PID of the outermost process of the game (gamescope or a wrapper when used), if running
+    pub game_pid: Option<u32>,
+    /// Unix time the session started
+    pub started: u64,
+    /// Proton build the game runs with, if any
+    pub proton: Option<String>,
+    /// Active profile, if any
+    pub profile: Option<String>,
+    /// The full command the game runs with: wrappers, gamescope, Proton and the game
+    pub command: Vec<String>,
+}
+
+impl SessionStatus {
+    /// Name to show for the session: the game's name, else its App ID
+    pub fn display_name(&self) -> String {
+        match (&self.name, self.app_id) {
+            (Some(name), _) => name.clone(),
+            (None, Some(app_id)) => format!("app {}", app_id),
+            (None, None) => "game".to_string(),
+        }
+    }
+}
+
+/// Directory holding the control sockets of running sessions
+///
+/// Without `XDG_RUNTIME_DIR` this is a per-user directory directly in /tmp, so
+/// `private_dir` checking it is enough.
+pub fn sockets_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("steam-command-runner"),
+        None => PathBuf::from(format!("/tmp/steam-command-runner-{}", current_uid())),
+    }
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid() has no preconditions
+    unsafe { libc::getuid() }
+}
+
+/// Create a directory for sockets with mode 0700, or check the one that exists
+///
+/// A directory someone else owns, or can write to, is refused: under /tmp
+/// another user could create it first and then control the sockets in it.
+pub fn private_dir(dir: &Path) -> io::Result<()> {
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    check_private(dir)
+}
+
+/// Fail unless a directory is owned by the current user and only writable by them
+fn check_private(dir: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() || metadata.mode() & 0o022 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a directory only you can write to; not using it for sockets", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// The control socket of a running session, removed again when dropped
+pub struct ControlServer {
+    path: PathBuf,
+}
+
+impl ControlServer {
+    /// Start answering on this process's socket; `game_pid` holds the game's PID (0 when none)
+    ///
+    /// A `stop` request sends SIGTERM to this process, so it goes through the
+    /// same forwarding to the game as a `kill` from outside would. Failing to
+    /// create the socket only logs a warning.
+    pub fn start(status: SessionStatus, game_pid: &'static AtomicI32) -> Option<Self> {
+        Self::start_in(&sockets_dir(), status, game_pid)
+    }
+
+    fn start_in(dir: &Path, status: SessionStatus, game_pid: &'static AtomicI32) -> Option<Self> {
+        let path = dir.join(format!("{}.sock", std::process::id()));
+        let listener = private_dir(dir)
+            .and_then(|_| {
+                let _ = fs::remove_file(&path);
+                UnixListener::bind(&path)
+            })
+            .map_err(|e| warn!("Could not create control socket {}: {}", path.display(), e))
+            .ok()?;
+        debug!("Control socket listening on {}", path.display());
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut status = status.clone();
+                let pid = game_pid.load(Ordering::SeqCst);
+                status.game_pid = (pid > 0).then_some(pid as u32);
+                if let Err(e) = answer(stream, &status) {
+                    debug!("Control socket request failed: {}", e);
+                }
+            }
+        });
+        Some(Self { path })
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Read one request and write its reply
+fn answer(stream: UnixStream, status: &SessionStatus) -> Result<(), AppError> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let reply = match request.trim() {
+        "status" => serde_json::to_value(status)?,
+        "stop" => {
+            debug!("Stop requested over the control socket");
+            // SAFETY: kill() on our own PID; the handler forwards the signal to the game
+            unsafe {
+                libc::kill(libc::getpid(), libc::SIGTERM);
+            }
+            serde_json::json!({ "ok": true })
+        }
+        other => serde_json::json!({ "error": format!("unknown request '{}'", other) }),
+    };
+    let mut stream = stream;
+    writeln!(stream, "{}", reply)?;
+    Ok(())
+}
+
+/// Send a request to a session's socket and read the reply
+fn request(socket: &Path, request: &str) -> Result<String, AppError> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", request)?;
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    Ok(reply)
+}
+
+/// Every running session with its socket, oldest first
+///
+/// Sockets left behind by a runner that died are removed.
+pub fn running_sessions() -> Vec<(PathBuf, SessionStatus)> {
+    sessions_in(&sockets_dir())
+}
+
+fn sessions_in(dir: &Path) -> Vec<(PathBuf, SessionStatus)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    if let Err(e) = check_private(dir) {
+        warn!("{}", e);
+        return Vec::new();
+    }
+    let mut sessions: Vec<(PathBuf, SessionStatus)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        // Session sockets are named by PID; `serve` keeps its socket here too
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sock"))
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.parse::<u32>().is_ok())
+        })
+        .filter_map(|socket| match request(&socket, "status") {
+            Ok(reply) => match serde_json::from_str(&reply) {
+                Ok(status) => Some((socket, status)),
+                Err(e) => {
+                    debug!("Unexpected reply from {}: {}", socket.display(), e);
+                    None
+                }
+            },
+            Err(AppError::Io(e)) if is_stale(&e) => {
+                debug!("Removing stale control socket {}: {}", socket.display(), e);
+                let _ = fs::remove_file(&socket);
+                None
+            }
+            // A session that is slow to answer is still running; keep its socket
+            Err(e) => {
+                debug!("No status from {}: {}", socket.display(), e);
+                None
+            }
+        })
+        .collect();
+    sessions.sort_by_key(|(_, status)| status.started);
+    sessions
+}
+
+/// Whether a failed request means nothing is listening on the socket any more
+fn is_stale(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::NotFound)
+}
+
+/// Ask a session to stop its game
+pub fn stop_session(socket: &Path) -> Result<(), AppError> {
+    let reply = request(socket, "stop")?;
+    let reply: serde_json::Value = serde_json::from_str(&reply)?;
+    match reply.get("error").and_then(|e| e.as_str()) {
+        Some(error) => Err(AppError::Control(error.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// A command line as shown in status output
+pub fn command_line(command: &Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    static GAME_PID: AtomicI32 = AtomicI32::new(4242);
+
+    #[test]
+    fn test_status_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = SessionStatus {
+            app_id: Some(1145360),
+            name: Some("Hades II".to_string()),
+            pid: std::process::id(),
+            game_pid: None,
+            started: 1_700_000_000,
+            proton: None,
+            profile: None,
+            command: vec!["gamemoderun".to_string(), "./Hades2".to_string()],
+        };
+        let server = ControlServer::start_in(dir.path(), status, &GAME_PID).unwrap();
+
+        let sessions = sessions_in(dir.path());
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].1.game_pid, Some(4242));
+        assert_eq!(sessions[0].1.command, ["gamemoderun", "./Hades2"]);
+
+        drop(server);
+        assert!(sessions_in(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_only_stale_sockets_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let stale = dir.path().join("1001.sock");
+        drop(UnixListener::bind(&stale).unwrap());
+        // Bound but never answering, like a session busy enough to time out
+        let busy = dir.path().join("1002.sock");
+        let _listener = UnixListener::bind(&busy).unwrap();
+
+        assert!(sessions_in(dir.path()).is_empty());
+        assert!(!stale.exists());
+        assert!(busy.exists());
+    }
+
+    #[test]
+    fn test_private_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let sockets = dir.path().join("sockets");
+        private_dir(&sockets).unwrap();
+        assert_eq!(fs::metadata(&sockets).unwrap().mode() & 0o777, 0o700);
+
+        // Others being able to add sockets is as bad as someone else owning it
+        let shared = dir.path().join("shared");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(private_dir(&shared).is_err());
+    }
+}
@@ -20,6 +20,9 @@ pub enum AppError {
     #[error("Could not parse gamescope args: {0}")]
     GamescopeArgsParse(String),
 
+    #[error("Gamescope failed to start: {0}")]
+    GamescopeFailed(String),
+
     #[error("Steam installation not found. Checked: {0:?}")]
     SteamNotFound(Vec<std::path::PathBuf>),
 
@@ -32,6 +35,12 @@ pub enum AppError {
     #[error("Steam API error: {0}")]
     SteamApi(String),
 
+    #[error("Heroic error: {0}")]
+    Heroic(String),
+
+    #[error("SteamGridDB error: {0}")]
+    SteamGridDb(String),
+
     #[error("HTTP request failed: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -44,6 +53,9 @@ pub enum AppError {
     #[error("TOML serialization error: {0}")]
     TomlSerialize(#[from] toml::ser::Error),
 
+    #[error("JSON serialization error: {0}")]
+    JsonSerialize(#[from] serde_json::Error),
+
     #[error("Hook execution failed: {0}")]
     HookFailed(String),
 
@@ -53,6 +65,15 @@ pub enum AppError {
     #[error("Failed to parse localconfig.vdf: {0}")]
     LocalConfigParseFailed(String),
 
+    #[error("{0} was changed by another program while being edited; run the command again")]
+    ChangedOnDisk(String),
+
+    #[error("Not writing localconfig.vdf, it was left unchanged: {0}")]
+    RewriteVerification(String),
+
+    #[error("VDF parse error: {0}")]
+    VdfParse(String),
+
     #[error("VDF serialization error: {0}")]
     VdfSerialize(String),
 
@@ -64,4 +85,61 @@ pub enum AppError {
 
     #[error("Game not found: {0}")]
     GameNotFound(String),
+
+    #[error("Log not found: {0}")]
+    LogNotFound(String),
+
+    #[error("Mod manager error: {0}")]
+    ModManager(String),
+
+    #[error("Proton installation failed: {0}")]
+    ProtonInstall(String),
+
+    #[error("Prefix error: {0}")]
+    Prefix(String),
+
+    #[error("Winetricks failed: {0}")]
+    Winetricks(String),
+
+    #[error("Sandbox error: {0}")]
+    Sandbox(String),
+
+    #[error("{0} not found in any Steam library. Install it with: steam steam://install/{1}")]
+    SteamRuntimeNotFound(String, u32),
+
+    #[error("Failed to parse shortcuts.vdf: {0}")]
+    ShortcutsParse(String),
+
+    #[error("Failed to parse appinfo.vdf: {0}")]
+    AppInfoParse(String),
+
+    #[error("Steam is running: {0}")]
+    SteamRunning(String),
+
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+
+    #[error("Backup error: {0}")]
+    Backup(String),
+
+    #[error("Saves error: {0}")]
+    Saves(String),
+
+    #[error("Session control error: {0}")]
+    Control(String),
+
+    #[error("RPC server error: {0}")]
+    Rpc(String),
+
+    #[error("Benchmark failed: {0}")]
+    Bench(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Playtime store error: {0}")]
+    Playtime(String),
+
+    #[error("Import failed: {0}")]
+    Import(String),
 }
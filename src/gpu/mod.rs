@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{debug, warn};
+
+const VENDOR_AMD: u16 = 0x1002;
+const VENDOR_INTEL: u16 = 0x8086;
+const VENDOR_NVIDIA: u16 = 0x10de;
+
+/// Directories Vulkan loaders read ICD manifests from
+const ICD_DIRS: &[&str] = &[
+    "/usr/share/vulkan/icd.d",
+    "/etc/vulkan/icd.d",
+    "/run/opengl-driver/share/vulkan/icd.d",
+    "/run/opengl-driver-32/share/vulkan/icd.d",
+];
+
+/// Which GPU a game should run on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GpuSelector {
+    /// Index from `gpu list`
+    Index(usize),
+    /// "discrete", "integrated", or a PCI address like "0000:01:00.0"
+    Name(String),
+}
+
+/// Integrated or discrete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuKind {
+    Integrated,
+    Discrete,
+    Unknown,
+}
+
+/// A GPU found in /sys/class/drm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuDevice {
+    pub index: usize,
+    /// PCI address, e.g. "0000:01:00.0"
+    pub pci_slot: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    /// Kernel driver (amdgpu, i915, xe, nvidia, nouveau, ...)
+    pub driver: Option<String>,
+    /// Vulkan device name, when vulkaninfo is available
+    pub name: Option<String>,
+    pub kind: GpuKind,
+    /// The GPU the firmware used for the boot display
+    pub boot_vga: bool,
+}
+
+impl GpuDevice {
+    pub fn vendor_name(&self) -> &'static str {
+        match self.vendor_id {
+            VENDOR_AMD => "AMD",
+            VENDOR_INTEL => "Intel",
+            VENDOR_NVIDIA => "NVIDIA",
+            _ => "Unknown vendor",
+        }
+    }
+
+    /// Name to show: the Vulkan device name, or vendor and PCI IDs
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            format!(
+                "{} GPU [{:04x}:{:04x}]",
+                self.vendor_name(),
+                self.vendor_id,
+                self.device_id
+            )
+        })
+    }
+
+    fn uses_nvidia_driver(&self) -> bool {
+        self.driver.as_deref() == Some("nvidia")
+    }
+}
+
+/// A device reported by `vulkaninfo --summary`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct VulkanDevice {
+    vendor_id: u16,
+    device_id: u16,
+    device_type: String,
+    name: String,
+}
+
+fn parse_hex_id(value: &str) -> Option<u16> {
+    u16::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// Parse the per-GPU blocks of `vulkaninfo --summary`
+fn parse_vulkaninfo_summary(output: &str) -> Vec<VulkanDevice> {
+    let mut devices = Vec::new();
+    let mut current: Option<VulkanDevice> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("GPU") && trimmed.ends_with(':') {
+            devices.extend(current.take());
+            current = Some(VulkanDevice::default());
+            continue;
+        }
+        let (Some(device), Some((key, value))) = (current.as_mut(), trimmed.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "vendorID" => device.vendor_id = parse_hex_id(value).unwrap_or(0),
+            "deviceID" => device.device_id = parse_hex_id(value).unwrap_or(0),
+            "deviceType" => device.device_type = value.to_string(),
+            "deviceName" => device.name = value.to_string(),
+            _ => {}
+        }
+    }
+    devices.extend(current);
+    devices
+}
+
+fn vulkan_devices() -> Vec<VulkanDevice> {
+    let output = Command::new("vulkaninfo")
+        .arg("--summary")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) => parse_vulkaninfo_summary(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            debug!("vulkaninfo unavailable: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn read_sysfs_id(path: &Path) -> Option<u16> {
+    parse_hex_id(&fs::read_to_string(path).ok()?)
+}
+
+/// DRM card directories (card0, card1, ...), in card order
+fn drm_cards(drm: &Path) -> Vec<PathBuf> {
+    let mut cards: Vec<(u32, PathBuf)> = fs::read_dir(drm)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    let number = name.strip_prefix("card")?.parse::<u32>().ok()?;
+                    Some((number, e.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    cards.sort();
+    cards.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Vendors of the GPUs in the system ("AMD", "Intel", "NVIDIA"), without running vulkaninfo
+///
+/// Cheap enough to call on every launch, e.g. for `[[when]] gpu_vendor` conditions.
+pub fn gpu_vendors() -> Vec<&'static str> {
+    let mut vendors = Vec::new();
+    for card in drm_cards(Path::new("/sys/class/drm")) {
+        let vendor = match read_sysfs_id(&card.join("device/vendor")) {
+            Some(VENDOR_AMD) => "AMD",
+            Some(VENDOR_INTEL) => "Intel",
+            Some(VENDOR_NVIDIA) => "NVIDIA",
+            _ => continue,
+        };
+        if !vendors.contains(&vendor) {
+            vendors.push(vendor);
+        }
+    }
+    vendors
+}
+
+/// List the system's GPUs
+///
+/// Devices come from /sys/class/drm; vulkaninfo, when installed, supplies names
+/// and whether each GPU is integrated or discrete.
+pub fn list_gpus() -> Vec<GpuDevice> {
+    let vulkan = vulkan_devices();
+    let mut gpus = Vec::new();
+
+    for card in drm_cards(Path::new("/sys/class/drm")) {
+        let device = card.join("device");
+        let (Some(vendor_id), Some(device_id)) = (
+            read_sysfs_id(&device.join("vendor")),
+            read_sysfs_id(&device.join("device")),
+        ) else {
+            continue;
+        };
+        let Some(pci_slot) = fs::canonicalize(&device)
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        else {
+            continue;
+        };
+        let driver = fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+        let boot_vga = fs::read_to_string(device.join("boot_vga"))
+            .map(|s| s.trim() == "1")
+            .unwrap_or(false);
+
+        let vk = vulkan
+            .iter()
+            .find(|d| d.vendor_id == vendor_id && d.device_id == device_id);
+        let kind = match vk.map(|d| d.device_type.as_str()) {
+            Some(t) if t.contains("INTEGRATED") => GpuKind::Integrated,
+            Some(t) if t.contains("DISCRETE") => GpuKind::Discrete,
+            _ => GpuKind::Unknown,
+        };
+
+        gpus.push(GpuDevice {
+            index: gpus.len(),
+            pci_slot,
+            vendor_id,
+            device_id,
+            driver,
+            name: vk.map(|d| d.name.clone()),
+            kind,
+            boot_vga,
+        });
+    }
+
+    guess_kinds(&mut gpus);
+    gpus
+}
+
+/// Fill in integrated/discrete where vulkaninfo couldn't tell us
+///
+/// NVIDIA GPUs are discrete and Intel ones almost always integrated; with more
+/// than one GPU, the one driving the boot display is taken to be the integrated one.
+fn guess_kinds(gpus: &mut [GpuDevice]) {
+    let multiple = gpus.len() > 1;
+    for gpu in gpus.iter_mut().filter(|g| g.kind == GpuKind::Unknown) {
+        gpu.kind = match gpu.vendor_id {
+            VENDOR_NVIDIA => GpuKind::Discrete,
+            VENDOR_INTEL => GpuKind::Integrated,
+            _ if multiple && gpu.boot_vga => GpuKind::Integrated,
+            _ if multiple => GpuKind::Discrete,
+            _ => GpuKind::Unknown,
+        };
+    }
+}
+
+/// Pick the GPU a selector refers to
+pub fn select_gpu<'a>(gpus: &'a [GpuDevice], selector: &GpuSelector) -> Option<&'a GpuDevice> {
+    match selector {
+        GpuSelector::Index(index) => gpus.get(*index),
+        GpuSelector::Name(name) => match name.to_lowercase().as_str() {
+            "discrete" | "dgpu" => gpus.iter().find(|g| g.kind == GpuKind::Discrete),
+            "integrated" | "igpu" => gpus.iter().find(|g| g.kind == GpuKind::Integrated),
+            slot => gpus.iter().find(|g| {
+                g.pci_slot == slot || g.pci_slot.strip_prefix("0000:") == Some(slot)
+            }),
+        },
+    }
+}
+
+/// Vulkan ICD manifests whose file name contains one of `patterns`
+fn icd_files(patterns: &[&str]) -> Vec<String> {
+    let mut files: Vec<String> = ICD_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .is_some_and(|n| n.ends_with(".json") && patterns.iter().any(|pat| n.contains(pat)))
+        })
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Environment that makes OpenGL and Vulkan games render on `gpu`
+///
+/// Mesa drivers are steered with DRI_PRIME and the device-select layer. With the
+/// NVIDIA driver in the system, PRIME render offload is switched on or off, and the
+/// Vulkan loader is limited to the chosen vendor's ICDs.
+pub fn gpu_env(gpu: &GpuDevice, gpus: &[GpuDevice]) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    let mut set = |key: &str, value: String| env.push((key.to_string(), value));
+
+    let nvidia_present = gpus.iter().any(GpuDevice::uses_nvidia_driver);
+    if gpu.uses_nvidia_driver() {
+        set("__NV_PRIME_RENDER_OFFLOAD", "1".to_string());
+        set("__GLX_VENDOR_LIBRARY_NAME", "nvidia".to_string());
+        set("__VK_LAYER_NV_optimus", "NVIDIA_only".to_string());
+    } else {
+        set("DRI_PRIME", format!("pci-{}", gpu.pci_slot.replace([':', '.'], "_")));
+        set(
+            "MESA_VK_DEVICE_SELECT",
+            format!("{:04x}:{:04x}", gpu.vendor_id, gpu.device_id),
+        );
+        if nvidia_present {
+            set("__VK_LAYER_NV_optimus", "non_NVIDIA_only".to_string());
+        }
+    }
+
+    // Only narrow the Vulkan loader's view when GPUs of different vendors are present
+    let vendors_differ = gpus.iter().any(|g| g.vendor_id != gpu.vendor_id);
+    if vendors_differ {
+        let patterns: &[&str] = match (gpu.vendor_id, gpu.driver.as_deref()) {
+            (_, Some("nvidia")) => &["nvidia"],
+            (VENDOR_AMD, _) => &["radeon", "amd"],
+            (VENDOR_INTEL, _) => &["intel"],
+            (VENDOR_NVIDIA, _) => &["nouveau"],
+            _ => &[],
+        };
+        let files = icd_files(patterns);
+        if !files.is_empty() {
+            let files = files.join(":");
+            set("VK_DRIVER_FILES", files.clone());
+            set("VK_ICD_FILENAMES", files);
+        }
+    }
+
+    // DXVK/VKD3D pick the first suitable device otherwise
+    if let Some(name) = &gpu.name {
+        set("DXVK_FILTER_DEVICE_NAME", name.clone());
+        set("VKD3D_FILTER_DEVICE_NAME", name.clone());
+    }
+
+    env
+}
+
+/// Environment for the configured GPU, or nothing if it can't be found
+pub fn gpu_env_for(selector: &GpuSelector) -> Vec<(String, String)> {
+    let gpus = list_gpus();
+    match select_gpu(&gpus, selector) {
+        Some(gpu) => {
+            debug!("Using GPU {} ({})", gpu.display_name(), gpu.pci_slot);
+            gpu_env(gpu, &gpus)
+        }
+        None => {
+            warn!("No GPU matches gpu = {:?}; using the default GPU", selector);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpu(index: usize, slot: &str, vendor_id: u16, driver: &str, kind: GpuKind) -> GpuDevice {
+        GpuDevice {
+            index,
+            pci_slot: slot.to_string(),
+            vendor_id,
+            device_id: 0x1234,
+            driver: Some(driver.to_string()),
+            name: None,
+            kind,
+            boot_vga: index == 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_vulkaninfo_summary() {
+        let output = "Devices:\n========\nGPU0:\n\tapiVersion         = 1.3.274\n\tvendorID           = 0x1002\n\tdeviceID           = 0x73bf\n\tdeviceType         = PHYSICAL_DEVICE_TYPE_DISCRETE_GPU\n\tdeviceName         = AMD Radeon RX 6800 XT (RADV NAVI21)\nGPU1:\n\tvendorID           = 0x10005\n\tdeviceType         = PHYSICAL_DEVICE_TYPE_CPU\n\tdeviceName         = llvmpipe\n";
+        let devices = parse_vulkaninfo_summary(output);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].vendor_id, VENDOR_AMD);
+        assert_eq!(devices[0].device_id, 0x73bf);
+        assert_eq!(devices[0].name, "AMD Radeon RX 6800 XT (RADV NAVI21)");
+        assert_eq!(devices[1].device_type, "PHYSICAL_DEVICE_TYPE_CPU");
+    }
+
+    #[test]
+    fn test_select_gpu_and_env() {
+        let gpus = vec![
+            gpu(0, "0000:00:02.0", VENDOR_INTEL, "i915", GpuKind::Integrated),
+            gpu(1, "0000:01:00.0", VENDOR_NVIDIA, "nvidia", GpuKind::Discrete),
+        ];
+        let discrete = select_gpu(&gpus, &GpuSelector::Name("discrete".to_string())).unwrap();
+        assert_eq!(discrete.index, 1);
+        assert_eq!(select_gpu(&gpus, &GpuSelector::Name("00:02.0".to_string())).unwrap().index, 0);
+        assert_eq!(select_gpu(&gpus, &GpuSelector::Index(2)), None);
+
+        let env = gpu_env(discrete, &gpus);
+        assert!(env.contains(&("__NV_PRIME_RENDER_OFFLOAD".to_string(), "1".to_string())));
+
+        let env = gpu_env(&gpus[0], &gpus);
+        assert!(env.contains(&("DRI_PRIME".to_string(), "pci-0000_00_02_0".to_string())));
+        assert!(env.contains(&("__VK_LAYER_NV_optimus".to_string(), "non_NVIDIA_only".to_string())));
+    }
+}
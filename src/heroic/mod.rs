@@ -0,0 +1,305 @@
+use crate::error::AppError;
+use crate::steam::{name_match_score, Shortcut};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Heroic's config directory inside its Flatpak sandbox
+const FLATPAK_HEROIC_CONFIG: &str = ".var/app/com.heroicgameslauncher.hgl/config/heroic";
+
+/// Store a Heroic game was installed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeroicStore {
+    /// Epic Games Store, installed through legendary
+    Epic,
+    /// GOG, installed through gogdl
+    Gog,
+}
+
+impl HeroicStore {
+    pub fn label(self) -> &'static str {
+        match self {
+            HeroicStore::Epic => "Epic",
+            HeroicStore::Gog => "GOG",
+        }
+    }
+}
+
+/// The Wine prefix Heroic runs a Windows game in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeroicPrefix {
+    pub path: PathBuf,
+    /// Whether Heroic runs the game with Proton, in which case `path` holds `pfx`
+    pub proton: bool,
+}
+
+/// A game installed by Heroic Games Launcher
+#[derive(Debug, Clone)]
+pub struct HeroicGame {
+    pub store: HeroicStore,
+    /// Store ID of the game (legendary's app name, or the GOG product ID)
+    pub app_name: String,
+    pub title: String,
+    /// "windows" or "linux"
+    pub platform: String,
+    pub install_path: PathBuf,
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub prefix: Option<HeroicPrefix>,
+}
+
+impl HeroicGame {
+    pub fn is_windows(&self) -> bool {
+        self.platform.eq_ignore_ascii_case("windows")
+    }
+
+    /// App ID the game's config is found under
+    ///
+    /// The ID Steam gives a shortcut to the game's executable, so `heroic add`,
+    /// `shortcuts add` and `run --heroic` all use the same game config.
+    pub fn app_id(&self) -> u32 {
+        Shortcut::new(&self.title, &self.exe, &self.working_dir).app_id()
+    }
+
+    /// Command that starts the game
+    pub fn command(&self) -> Vec<String> {
+        let mut command = vec![self.exe.to_string_lossy().into_owned()];
+        command.extend(self.args.iter().cloned());
+        command
+    }
+}
+
+/// Heroic's config directory, native or Flatpak
+pub fn heroic_config_dir() -> Option<PathBuf> {
+    let native = dirs::config_dir().map(|dir| dir.join("heroic"));
+    let flatpak = dirs::home_dir().map(|home| home.join(FLATPAK_HEROIC_CONFIG));
+    [native, flatpak].into_iter().flatten().find(|dir| dir.is_dir())
+}
+
+/// Find every game Heroic has installed, sorted by title
+///
+/// DLC and games whose executable can't be worked out are left out.
+pub fn find_heroic_games() -> Result<Vec<HeroicGame>, AppError> {
+    let dir = heroic_config_dir()
+        .ok_or_else(|| AppError::Heroic("Heroic Games Launcher config not found".to_string()))?;
+    debug!("Reading Heroic games from {}", dir.display());
+
+    let mut games = epic_games(&dir.join("legendaryConfig/legendary/installed.json"));
+    games.extend(gog_games(&dir.join("gog_store")));
+    for game in &mut games {
+        game.prefix = game_prefix(&dir, &game.app_name);
+    }
+    games.sort_by_key(|game| game.title.to_lowercase());
+    Ok(games)
+}
+
+/// Find an installed Heroic game by store ID or (loosely matched) title
+pub fn find_heroic_game(query: &str) -> Result<HeroicGame, AppError> {
+    let games = find_heroic_games()?;
+    if let Some(game) = games.iter().find(|game| game.app_name == query) {
+        return Ok(game.clone());
+    }
+    games
+        .iter()
+        .filter_map(|game| name_match_score(query, &game.title).map(|score| (score, game)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, game)| game.clone())
+        .ok_or_else(|| AppError::GameNotFound(format!("no Heroic game matching '{}'", query)))
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| debug!("Could not parse {}: {}", path.display(), e))
+        .ok()
+}
+
+#[derive(Deserialize)]
+struct EpicInstall {
+    app_name: String,
+    title: String,
+    install_path: PathBuf,
+    executable: String,
+    #[serde(default)]
+    launch_parameters: String,
+    #[serde(default)]
+    platform: String,
+    #[serde(default)]
+    is_dlc: bool,
+}
+
+/// Epic games from legendary's installed.json, keyed by app name
+fn epic_games(installed: &Path) -> Vec<HeroicGame> {
+    let Some(Value::Object(entries)) = read_json(installed) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter_map(|(_, entry)| serde_json::from_value::<EpicInstall>(entry).ok())
+        .filter(|install| !install.is_dlc && !install.executable.is_empty())
+        .map(|install| {
+            let exe = install.install_path.join(&install.executable);
+            HeroicGame {
+                store: HeroicStore::Epic,
+                app_name: install.app_name,
+                title: install.title,
+                platform: install.platform.to_lowercase(),
+                working_dir: exe.parent().map(Path::to_path_buf).unwrap_or_default(),
+                exe,
+                args: shlex::split(&install.launch_parameters).unwrap_or_default(),
+                install_path: install.install_path,
+                prefix: None,
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct GogInstall {
+    #[serde(rename = "appName")]
+    app_name: String,
+    install_path: PathBuf,
+    #[serde(default)]
+    platform: String,
+    #[serde(default)]
+    is_dlc: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GogPlayTask {
+    #[serde(default)]
+    is_primary: bool,
+    path: Option<String>,
+    working_dir: Option<String>,
+    arguments: Option<String>,
+}
+
+/// GOG games from Heroic's gog_store/installed.json, titled from its library.json
+fn gog_games(store: &Path) -> Vec<HeroicGame> {
+    let installs: Vec<GogInstall> = read_json(&store.join("installed.json"))
+        .and_then(|json| serde_json::from_value(json.get("installed")?.clone()).ok())
+        .unwrap_or_default();
+    let titles: HashMap<String, String> = read_json(&store.join("library.json"))
+        .and_then(|json| json.get("games")?.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|game| {
+            Some((
+                game.get("app_name")?.as_str()?.to_string(),
+                game.get("title")?.as_str()?.to_string(),
+            ))
+        })
+        .collect();
+
+    installs
+        .into_iter()
+        .filter(|install| !install.is_dlc)
+        .filter_map(|install| {
+            let info = read_json(&install.install_path.join(format!("goggame-{}.info", install.app_name)));
+            let title = titles
+                .get(&install.app_name)
+                .cloned()
+                .or_else(|| info.as_ref()?.get("name")?.as_str().map(String::from))
+                .unwrap_or_else(|| install.app_name.clone());
+            let platform = install.platform.to_lowercase();
+
+            let (exe, args, working_dir) = if platform == "linux" {
+                let exe = install.install_path.join("start.sh");
+                (exe, Vec::new(), install.install_path.clone())
+            } else {
+                let task = gog_primary_task(info.as_ref()?)?;
+                let exe = install.install_path.join(task.path?.replace('\\', "/"));
+                let working_dir = match task.working_dir.filter(|dir| !dir.is_empty()) {
+                    Some(dir) => install.install_path.join(dir.replace('\\', "/")),
+                    None => exe.parent().map(Path::to_path_buf).unwrap_or_default(),
+                };
+                let args = task.arguments.and_then(|a| shlex::split(&a)).unwrap_or_default();
+                (exe, args, working_dir)
+            };
+
+            Some(HeroicGame {
+                store: HeroicStore::Gog,
+                app_name: install.app_name,
+                title,
+                platform,
+                install_path: install.install_path,
+                exe,
+                args,
+                working_dir,
+                prefix: None,
+            })
+        })
+        .collect()
+}
+
+/// The primary play task of a goggame-<id>.info file
+fn gog_primary_task(info: &Value) -> Option<GogPlayTask> {
+    let tasks: Vec<GogPlayTask> = serde_json::from_value(info.get("playTasks")?.clone()).ok()?;
+    let mut tasks = tasks.into_iter().filter(|task| task.path.is_some());
+    let first = tasks.next()?;
+    if first.is_primary {
+        return Some(first);
+    }
+    Some(tasks.find(|task| task.is_primary).unwrap_or(first))
+}
+
+/// Wine prefix from Heroic's per-game settings (GamesConfig/<app_name>.json)
+fn game_prefix(dir: &Path, app_name: &str) -> Option<HeroicPrefix> {
+    let config = read_json(&dir.join("GamesConfig").join(format!("{}.json", app_name)))?;
+    let settings = config.get(app_name)?;
+    let path = settings.get("winePrefix")?.as_str().filter(|p| !p.is_empty())?;
+    let proton = settings
+        .get("wineVersion")
+        .and_then(|version| version.get("type"))
+        .and_then(Value::as_str)
+        == Some("proton");
+    Some(HeroicPrefix {
+        path: PathBuf::from(path),
+        proton,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gog_games() {
+        let dir = tempfile::tempdir().unwrap();
+        let install = dir.path().join("Games/Witcher 3");
+        fs::create_dir_all(&install).unwrap();
+        fs::write(
+            install.join("goggame-1207664663.info"),
+            r#"{"name": "The Witcher 3", "playTasks": [
+                {"type": "FileTask", "path": "REDprelauncher.exe"},
+                {"isPrimary": true, "type": "FileTask", "path": "bin\\x64\\witcher3.exe", "workingDir": "bin\\x64", "arguments": "-nolauncher"}
+            ]}"#,
+        )
+        .unwrap();
+        let store = dir.path().join("gog_store");
+        fs::create_dir_all(&store).unwrap();
+        fs::write(
+            store.join("installed.json"),
+            format!(
+                r#"{{"installed": [{{"appName": "1207664663", "install_path": "{}", "platform": "windows", "is_dlc": false}}]}}"#,
+                install.display()
+            ),
+        )
+        .unwrap();
+
+        let games = gog_games(&store);
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+        assert_eq!(game.title, "The Witcher 3");
+        assert!(game.is_windows());
+        assert_eq!(game.exe, install.join("bin/x64/witcher3.exe"));
+        assert_eq!(game.working_dir, install.join("bin/x64"));
+        assert_eq!(game.command()[1..], ["-nolauncher"]);
+        assert!(game.app_id() & 0x8000_0000 != 0);
+    }
+}
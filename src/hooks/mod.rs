@@ -1,10 +1,110 @@
-use crate::config::HookConfig;
+use crate::config::{HookConfig, MergedConfig};
 use crate::error::AppError;
-use std::process::Command;
+use crate::logs::{hook_log_path, timestamp};
+use crate::proton::resolve_compat_data_path;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
+/// Placeholders hooks can use; unset ones expand to an empty string
+const VARIABLES: &[&str] = &["appid", "name", "proton", "prefix_path", "exit_code", "signal"];
+
+/// Runtime values substituted into hook commands as %variable% placeholders
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub app_id: Option<u32>,
+    pub name: Option<String>,
+    /// Name of the Proton build (Proton games only)
+    pub proton: Option<String>,
+    /// The game's Wine prefix (Proton games only)
+    pub prefix_path: Option<PathBuf>,
+    /// The game's exit code, 128 + signal number if killed (after it exits)
+    pub exit_code: Option<u8>,
+    /// Signal that killed the game, if it was killed by one
+    pub signal: Option<i32>,
+}
+
+impl HookContext {
+    /// Context for launching a game, with `proton` the Proton build directory if any
+    pub fn for_launch(config: &MergedConfig, proton: Option<&Path>) -> Self {
+        let prefix_path = proton.and_then(|_| {
+            resolve_compat_data_path(config.compat_data_path.as_deref(), config.app_id, None)
+                .map(|compat_data| compat_data.join("pfx"))
+        });
+        Self {
+            app_id: config.app_id,
+            name: config.name.clone(),
+            proton: proton
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string()),
+            prefix_path,
+            exit_code: None,
+            signal: None,
+        }
+    }
+
+    fn value(&self, variable: &str) -> String {
+        match variable {
+            "appid" => self.app_id.map(|id| id.to_string()),
+            "name" => self.name.clone(),
+            "proton" => self.proton.clone(),
+            "prefix_path" => self.prefix_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            "exit_code" => self.exit_code.map(|code| code.to_string()),
+            "signal" => self.signal.map(|signal| signal.to_string()),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+
+    /// The same values as environment variables (SCR_APP_ID, SCR_EXIT_CODE, ...),
+    /// leaving out unset ones
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        [
+            ("SCR_APP_ID", "appid"),
+            ("SCR_GAME_NAME", "name"),
+            ("SCR_PROTON", "proton"),
+            ("SCR_PREFIX_PATH", "prefix_path"),
+            ("SCR_EXIT_CODE", "exit_code"),
+            ("SCR_SIGNAL", "signal"),
+        ]
+        .into_iter()
+        .map(|(key, variable)| (key, self.value(variable)))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+
+    /// Replace known %variable% placeholders in `text`; other text is left alone
+    pub fn expand(&self, text: &str) -> String {
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('%') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            match after.find('%').map(|end| &after[..end]) {
+                Some(variable) if VARIABLES.contains(&variable) => {
+                    out.push_str(&self.value(variable));
+                    rest = &after[variable.len() + 1..];
+                }
+                _ => {
+                    out.push('%');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
 /// Execute a hook command
-pub fn execute(hook: &HookConfig) -> Result<(), AppError> {
+///
+/// Placeholders are expanded per argument after splitting, so values containing
+/// spaces (game names, paths) stay single arguments.
+pub fn execute(hook: &HookConfig, context: &HookContext) -> Result<(), AppError> {
     info!("Executing hook: {}", hook.command);
 
     let args = shlex::split(&hook.command)
@@ -14,19 +114,33 @@ pub fn execute(hook: &HookConfig) -> Result<(), AppError> {
         return Err(AppError::HookFailed("Empty hook command".to_string()));
     }
 
+    let args: Vec<String> = args.iter().map(|arg| context.expand(arg)).collect();
     let (cmd, cmd_args) = args.split_first().unwrap();
+    debug!("Hook command line: {:?}", args);
 
     let mut command = Command::new(cmd);
-    command.args(cmd_args);
+    command.args(cmd_args).envs(context.env_vars());
 
     // Set working directory if specified
     if let Some(ref dir) = hook.working_dir {
-        command.current_dir(dir);
+        command.current_dir(context.expand(dir));
+    }
+
+    // Keep hook output out of Steam's console
+    if let Some(log) = open_hook_log(context.app_id, &hook.command) {
+        if let Ok(stderr) = log.try_clone() {
+            command.stdout(Stdio::from(log)).stderr(Stdio::from(stderr));
+        }
     }
 
     if hook.wait {
         debug!("Waiting for hook to complete");
-        let status = command.status()?;
+        let mut child = command.spawn()?;
+        let status = match hook.timeout_secs {
+            Some(secs) => wait_with_timeout(child, Duration::from_secs(secs))
+                .map_err(|e| AppError::HookFailed(format!("Hook '{}' {}", hook.command, e)))?,
+            None => child.wait()?,
+        };
         if !status.success() {
             let code = status.code().unwrap_or(-1);
             warn!("Hook exited with non-zero status: {}", code);
@@ -42,3 +156,93 @@ pub fn execute(hook: &HookConfig) -> Result<(), AppError> {
 
     Ok(())
 }
+
+/// Open the game's hook log for appending, with a header line for this hook
+fn open_hook_log(app_id: Option<u32>, command: &str) -> Option<File> {
+    let path = hook_log_path(app_id);
+    let file = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+    match file {
+        Ok(mut file) => {
+            let _ = writeln!(file, "==> {} {}", timestamp(), command);
+            Some(file)
+        }
+        Err(e) => {
+            warn!("Cannot open hook log {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Wait for a hook, killing it once the timeout passes
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<ExitStatus, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {}s and was killed", timeout.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Run a phase's hooks in order
+///
+/// A failing hook is logged and the rest still run, unless it has
+/// `continue_on_error = false`; then the phase stops and the error is returned.
+pub fn run_all(phase: &str, hooks: &[HookConfig], context: &HookContext) -> Result<(), AppError> {
+    for hook in hooks {
+        match execute(hook, context) {
+            Ok(()) => {}
+            Err(e) if hook.continue_on_error => warn!("{} hook failed: {}", phase, e),
+            Err(e) => {
+                warn!("{} hook failed, skipping the remaining {} hooks", phase, phase);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let context = HookContext {
+            app_id: Some(440),
+            name: Some("Team Fortress 2".to_string()),
+            exit_code: Some(0),
+            ..HookContext::default()
+        };
+        assert_eq!(
+            context.expand("%appid%-%name%: %exit_code%"),
+            "440-Team Fortress 2: 0"
+        );
+        assert_eq!(context.expand("[%proton%]"), "[]");
+        assert_eq!(context.expand("100% %unknown% 50%"), "100% %unknown% 50%");
+
+        let env = context.env_vars();
+        assert!(env.contains(&("SCR_APP_ID", "440".to_string())));
+        assert!(env.contains(&("SCR_EXIT_CODE", "0".to_string())));
+        assert!(!env.iter().any(|(key, _)| *key == "SCR_SIGNAL"));
+    }
+
+    #[test]
+    fn test_wait_with_timeout() {
+        let quick = Command::new("true").spawn().unwrap();
+        assert!(wait_with_timeout(quick, Duration::from_secs(5)).unwrap().success());
+
+        let hung = Command::new("sleep").arg("5").spawn().unwrap();
+        let started = Instant::now();
+        assert!(wait_with_timeout(hung, Duration::from_millis(100)).is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,245 @@
+use super::{is_on, parse_resolution, proton_version, ImportedConfig};
+use crate::error::AppError;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lutris system options that only make sense inside Lutris
+const LUTRIS_ONLY: &[&str] = &[
+    "disable_compositor",
+    "disable_screen_saver",
+    "reset_desktop",
+    "restore_gamma",
+    "terminal",
+    "manual_command",
+    "prelaunch_command",
+    "prelaunch_wait",
+    "exclude_processes",
+    "xephyr",
+];
+
+/// Translate a Lutris game config (`~/.local/share/lutris/games/<slug>-<id>.yml`)
+///
+/// Handles the Wine, Linux and Steam runners: the executable's arguments and
+/// working directory, environment, DLL overrides, gamemode, MangoHud,
+/// gamescope and the Wine or Proton build.
+pub fn import_lutris(path: &Path) -> Result<ImportedConfig, AppError> {
+    let content = fs::read_to_string(path)?;
+    let yaml: Value = serde_yaml::from_str(&content)
+        .map_err(|e| AppError::Import(format!("{}: {}", path.display(), e)))?;
+    Ok(translate(&yaml))
+}
+
+fn translate(yaml: &Value) -> ImportedConfig {
+    let mut imported = ImportedConfig::default();
+    let section = |name: &str| yaml.get(name).and_then(Value::as_mapping);
+    let empty = Mapping::new();
+    let game = section("game").unwrap_or(&empty);
+    let system = section("system").unwrap_or(&empty);
+
+    imported.app_id = get_str(game, "appid").and_then(|id| id.parse().ok());
+    if let Some(args) = get_str(game, "args").filter(|args| !args.is_empty()) {
+        imported.set("game_args", args);
+    }
+    if let Some(dir) = get_str(game, "working_dir").filter(|dir| !dir.is_empty()) {
+        imported.set("working_dir", dir);
+    }
+
+    if let Some(wine) = section("wine") {
+        translate_wine(&mut imported, game, wine);
+    } else if section("linux").is_some() {
+        imported.set("mode", "native");
+    }
+    translate_system(&mut imported, system);
+
+    imported.exe = get_str(game, "exe");
+    imported
+}
+
+/// Wine runner: Proton builds run through umu like Lutris does, others through Wine
+fn translate_wine(imported: &mut ImportedConfig, game: &Mapping, wine: &Mapping) {
+    let version = get_str(wine, "version");
+    match version.as_deref().and_then(proton_version) {
+        Some(proton) => {
+            imported.set("mode", "umu");
+            imported.set("proton.version", proton);
+            if let Some(prefix) = get_str(game, "prefix") {
+                imported.notes.push(format!(
+                    "Proton keeps its prefix in a compatdata directory, so {} isn't reused",
+                    prefix
+                ));
+            }
+            if get_str(wine, "esync").is_some_and(|v| !is_on(&v)) {
+                imported.set("proton.esync", false);
+            }
+            if get_str(wine, "fsync").is_some_and(|v| !is_on(&v)) {
+                imported.set("proton.fsync", false);
+            }
+            if get_str(wine, "dxvk").is_some_and(|v| !is_on(&v)) {
+                imported.set("proton.wined3d", true);
+            }
+            if get_str(wine, "dxvk_nvapi").is_some_and(|v| is_on(&v)) {
+                imported.set("proton.nvapi", true);
+            }
+            if get_str(wine, "fsr").is_some_and(|v| is_on(&v)) {
+                imported.set("proton.fsr", true);
+            }
+        }
+        None => {
+            imported.set("mode", "wine");
+            if let Some(prefix) = get_str(game, "prefix") {
+                imported.set("wine.prefix", prefix);
+            }
+            if let Some(binary) = version.as_deref().and_then(lutris_wine_binary) {
+                imported.set("wine.binary", binary.to_string_lossy().into_owned());
+            } else if let Some(version) = version {
+                imported.skipped.push(format!("wine.version = {} (not found in Lutris' runners)", version));
+            }
+            for (key, var) in [("esync", "WINEESYNC"), ("fsync", "WINEFSYNC")] {
+                if get_str(wine, key).is_some_and(|v| is_on(&v)) {
+                    imported.insert("env", var, "1");
+                }
+            }
+            for key in ["dxvk", "vkd3d", "dxvk_nvapi"] {
+                if get_str(wine, key).is_some_and(|v| is_on(&v)) {
+                    imported.skipped.push(format!(
+                        "wine.{} (install it into the prefix, e.g. with winetricks)",
+                        key
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(overrides) = wine.get("overrides").and_then(Value::as_mapping) {
+        for (dll, mode) in overrides {
+            if let (Some(dll), Some(mode)) = (scalar(dll), scalar(mode)) {
+                imported.insert("dll_overrides", &dll, mode);
+            }
+        }
+    }
+}
+
+/// System options shared by every runner
+fn translate_system(imported: &mut ImportedConfig, system: &Mapping) {
+    if let Some(env) = system.get("env").and_then(Value::as_mapping) {
+        for (name, value) in env {
+            if let (Some(name), Some(value)) = (scalar(name), scalar(value)) {
+                imported.insert("env", &name, value);
+            }
+        }
+    }
+
+    if let Some(command) = get_str(system, "prefix_command").filter(|c| !c.is_empty()) {
+        imported.wrap(&command);
+    }
+    if get_str(system, "gamemode").is_some_and(|v| is_on(&v)) {
+        imported.wrap("gamemoderun");
+    }
+    if get_str(system, "mangohud").is_some_and(|v| is_on(&v)) {
+        imported.set("mangohud.enabled", true);
+    }
+    if get_str(system, "pulse_latency").is_some_and(|v| is_on(&v)) {
+        // What Lutris sets for its "Reduce PulseAudio latency" option
+        imported.insert("env", "PULSE_LATENCY_MSEC", "60");
+    }
+    if get_str(system, "single_cpu").is_some_and(|v| is_on(&v)) {
+        imported.set("cpu_affinity", "0");
+    }
+
+    match get_str(system, "gamescope").map(|v| is_on(&v)) {
+        Some(true) => {
+            imported.set("gamescope_enabled", true);
+            translate_gamescope(imported, system);
+        }
+        Some(false) => imported.set("gamescope_enabled", false),
+        None => {}
+    }
+
+    for key in LUTRIS_ONLY {
+        if let Some(value) = get_str(system, key).filter(|v| !v.is_empty() && v != "false") {
+            imported.skipped.push(format!("system.{} = {}", key, value));
+        }
+    }
+}
+
+/// Lutris' gamescope options, as typed options where we have them
+fn translate_gamescope(imported: &mut ImportedConfig, system: &Mapping) {
+    let mut extra = Vec::new();
+    if let Some((width, height)) = get_str(system, "gamescope_game_res").as_deref().and_then(parse_resolution) {
+        imported.set("gamescope.width", width);
+        imported.set("gamescope.height", height);
+    }
+    if let Some((width, height)) = get_str(system, "gamescope_output_res").as_deref().and_then(parse_resolution) {
+        extra.push(format!("-W {} -H {}", width, height));
+    }
+    if let Some(fps) = get_str(system, "gamescope_fps_limiter").and_then(|fps| fps.parse::<u32>().ok()) {
+        imported.set("gamescope.refresh", fps);
+    }
+    match get_str(system, "gamescope_window_mode").as_deref() {
+        Some("fullscreen") => imported.set("gamescope.fullscreen", true),
+        Some("borderless") => extra.push("-b".to_string()),
+        _ => {}
+    }
+    if get_str(system, "gamescope_hdr").is_some_and(|v| is_on(&v)) {
+        imported.set("gamescope.hdr", true);
+    }
+    if get_str(system, "gamescope_fsr_sharpness").is_some_and(|v| !v.is_empty()) {
+        imported.set("gamescope.fsr", true);
+    }
+    if let Some(flags) = get_str(system, "gamescope_flags").filter(|flags| !flags.is_empty()) {
+        extra.push(flags);
+    }
+    if !extra.is_empty() {
+        imported.set("gamescope.extra_args", extra.join(" "));
+    }
+}
+
+/// Where Lutris installs a Wine runner version
+fn lutris_wine_binary(version: &str) -> Option<PathBuf> {
+    let binary = dirs::data_dir()?
+        .join("lutris/runners/wine")
+        .join(version)
+        .join("bin/wine");
+    binary.is_file().then_some(binary)
+}
+
+/// A scalar YAML value as text (Lutris writes booleans and numbers unquoted)
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn get_str(mapping: &Mapping, key: &str) -> Option<String> {
+    mapping.get(key).and_then(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_lutris() {
+        let yaml: Value = serde_yaml::from_str(
+            "game:\n  exe: /games/witcher/witcher3.exe\n  args: -nolauncher\n  prefix: /games/witcher/prefix\n\
+             system:\n  env:\n    DXVK_HUD: fps\n  gamemode: true\n  mangohud: true\n  gamescope: true\n  \
+             gamescope_game_res: 2560x1440\n  gamescope_window_mode: fullscreen\n  disable_compositor: true\n\
+             wine:\n  version: lutris-GE-Proton8-26-x86_64\n  fsync: false\n  overrides:\n    d3d9: n,b\n",
+        )
+        .unwrap();
+        let imported = translate(&yaml);
+
+        let config = toml::to_string(&imported.config).unwrap();
+        let expected = "game_args = \"-nolauncher\"\ngamescope_enabled = true\nmode = \"umu\"\n\
+                        pre_command = \"gamemoderun\"\n\n[dll_overrides]\nd3d9 = \"n,b\"\n\n[env]\nDXVK_HUD = \"fps\"\n\n\
+                        [gamescope]\nfullscreen = true\nheight = 1440\nwidth = 2560\n\n[mangohud]\nenabled = true\n\n\
+                        [proton]\nfsync = false\nversion = \"GE-Proton8-26\"\n";
+        assert_eq!(config, expected);
+        assert_eq!(imported.skipped, vec!["system.disable_compositor = true"]);
+        assert_eq!(imported.notes.len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+//! Translating per-game settings from other launchers into game configs
+
+mod lutris;
+mod stl;
+
+pub use lutris::import_lutris;
+pub use stl::{import_stl, stl_config_path};
+
+use toml::{Table, Value};
+
+/// A game config translated from another launcher's settings
+#[derive(Debug, Default)]
+pub struct ImportedConfig {
+    /// Steam App ID, when the source records one
+    pub app_id: Option<u32>,
+    /// Executable the source starts, for games Steam doesn't know about yet
+    pub exe: Option<String>,
+    /// Game config keys, ready to be written out
+    pub config: Table,
+    /// Settings with no equivalent here, to carry over by hand
+    pub skipped: Vec<String>,
+    /// Steps the config alone doesn't cover, such as adding a shortcut
+    pub notes: Vec<String>,
+}
+
+impl ImportedConfig {
+    /// Set a dotted key ("mangohud.enabled"), creating tables along the way
+    fn set(&mut self, key: &str, value: impl Into<Value>) {
+        let mut table = &mut self.config;
+        let mut parts = key.split('.').peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                table.insert(part.to_string(), value.into());
+                return;
+            }
+            let entry = table
+                .entry(part)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            table = entry.as_table_mut().expect("just made a table");
+        }
+    }
+
+    /// Add an entry to a table key such as `env` or `dll_overrides`
+    fn insert(&mut self, table: &str, key: &str, value: impl Into<Value>) {
+        let entry = self
+            .config
+            .entry(table)
+            .or_insert_with(|| Value::Table(Table::new()));
+        if let Some(entries) = entry.as_table_mut() {
+            entries.insert(key.to_string(), value.into());
+        }
+    }
+
+    /// Prepend a program to `pre_command`, so wrappers run in the order the source ran them
+    fn wrap(&mut self, program: &str) {
+        let command = match self.config.get("pre_command").and_then(Value::as_str) {
+            Some(existing) => format!("{} {}", program, existing),
+            None => program.to_string(),
+        };
+        self.set("pre_command", command);
+    }
+
+    /// Whether nothing could be translated
+    pub fn is_empty(&self) -> bool {
+        self.config.is_empty()
+    }
+
+    /// Every key as a dotted path and its TOML value, for merging into an existing file
+    pub fn dotted_keys(&self) -> Vec<(String, String)> {
+        fn walk(prefix: &str, table: &Table, out: &mut Vec<(String, String)>) {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match value {
+                    Value::Table(table) => walk(&key, table, out),
+                    value => out.push((key, value.to_string())),
+                }
+            }
+        }
+        let mut keys = Vec::new();
+        walk("", &self.config, &mut keys);
+        keys
+    }
+}
+
+/// Whether a launcher setting is switched on ("1", "true", "yes", true)
+fn is_on(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+/// Width and height from "1920x1080"
+fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.trim().split_once(['x', 'X'])?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+/// A Proton build name as it appears under compatibilitytools.d, or None for Wine builds
+///
+/// Launchers name their copies differently: Lutris has "lutris-GE-Proton8-26-x86_64"
+/// or just "GE-Proton" for its latest umu-managed build.
+fn proton_version(name: &str) -> Option<String> {
+    if !name.contains("Proton") {
+        return None;
+    }
+    let name = name.strip_prefix("lutris-").unwrap_or(name);
+    let name = name.strip_suffix("-x86_64").unwrap_or(name);
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imported_config_keys() {
+        let mut imported = ImportedConfig::default();
+        imported.set("mangohud.enabled", true);
+        imported.insert("env", "DXVK_HUD", "fps");
+        imported.wrap("mangohud");
+        imported.wrap("gamemoderun");
+
+        assert_eq!(
+            imported.dotted_keys(),
+            vec![
+                ("env.DXVK_HUD".to_string(), "\"fps\"".to_string()),
+                ("mangohud.enabled".to_string(), "true".to_string()),
+                ("pre_command".to_string(), "\"gamemoderun mangohud\"".to_string()),
+            ]
+        );
+        assert_eq!(parse_resolution("2560x1440"), Some((2560, 1440)));
+        assert_eq!(proton_version("lutris-GE-Proton8-26-x86_64").as_deref(), Some("GE-Proton8-26"));
+        assert_eq!(proton_version("lutris-7.2-2-x86_64"), None);
+    }
+}
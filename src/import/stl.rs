@@ -0,0 +1,168 @@
+use super::{is_on, ImportedConfig};
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// STL variables holding Proton settings, with the `[proton]` option each maps to
+/// and the value that option takes when the variable is "1"
+const PROTON_FLAGS: &[(&str, &str, bool)] = &[
+    ("PROTON_NO_ESYNC", "esync", false),
+    ("PROTON_NO_FSYNC", "fsync", false),
+    ("PROTON_USE_WINED3D", "wined3d", true),
+    ("PROTON_NO_D3D11", "d3d11", false),
+    ("PROTON_NO_D3D12", "d3d12", false),
+    ("PROTON_LOG", "log", true),
+    ("PROTON_FORCE_LARGE_ADDRESS_AWARE", "large_address_aware", true),
+    ("PROTON_ENABLE_NVAPI", "nvapi", true),
+    ("PROTON_HIDE_NVIDIA_GPU", "hide_nvidia_gpu", true),
+    ("WINE_FULLSCREEN_FSR", "fsr", true),
+];
+
+/// STL features we have no equivalent for, reported when switched on
+const STL_ONLY: &[&str] = &[
+    "USERESHADE",
+    "USEVKBASALT",
+    "USEBOXTRON",
+    "USEROBERTA",
+    "USELUXTORPEDA",
+    "USEOBSCAP",
+    "USENETMON",
+    "VIRTUALDESKTOP",
+    "USEWINE",
+    "RUN_REPLAY",
+];
+
+/// SteamTinkerLaunch's per-game config file
+pub fn stl_config_path(app_id: u32) -> PathBuf {
+    stl_dir().join("gamecfgs/id").join(format!("{}.conf", app_id))
+}
+
+fn stl_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from(".config"))
+        .join("steamtinkerlaunch")
+}
+
+/// Translate SteamTinkerLaunch's settings for a game
+///
+/// Reads `gamecfgs/id/<app_id>.conf` and the game's custom variables from
+/// `gamecfgs/customvars/<app_id>.conf`: Proton version and flags, game
+/// arguments, gamemode, MangoHud, gamescope, winetricks verbs and environment.
+pub fn import_stl(app_id: u32) -> Result<ImportedConfig, AppError> {
+    let path = stl_config_path(app_id);
+    let content = fs::read_to_string(&path).map_err(|e| {
+        AppError::Import(format!("no SteamTinkerLaunch config for {} at {}: {}", app_id, path.display(), e))
+    })?;
+    let custom = fs::read_to_string(stl_dir().join("gamecfgs/customvars").join(format!("{}.conf", app_id)))
+        .unwrap_or_default();
+
+    let mut imported = translate(&parse_vars(&content), &parse_vars(&custom));
+    imported.app_id = Some(app_id);
+    Ok(imported)
+}
+
+/// Parse shell-style `KEY="value"` lines, skipping comments
+fn parse_vars(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = shlex::split(value)
+                .map(|words| words.join(" "))
+                .unwrap_or_else(|| value.to_string());
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// Whether an STL value means "not set"
+fn is_unset(value: &str) -> bool {
+    matches!(value, "" | "none" | "default" | "0")
+}
+
+fn translate(vars: &HashMap<String, String>, custom: &HashMap<String, String>) -> ImportedConfig {
+    let mut imported = ImportedConfig::default();
+    let var = |key: &str| vars.get(key).map(String::as_str).filter(|value| !is_unset(value));
+
+    if let Some(version) = var("USEPROTON") {
+        imported.set("proton.version", version);
+    }
+    for (key, option, when_on) in PROTON_FLAGS {
+        if var(key).is_some_and(is_on) {
+            imported.set(&format!("proton.{}", option), *when_on);
+        }
+    }
+    if let Some(strength) = var("WINE_FULLSCREEN_FSR_STRENGTH").and_then(|s| s.parse::<u8>().ok()) {
+        imported.set("proton.fsr_strength", strength as i64);
+    }
+
+    if let Some(args) = var("GAMEARGS") {
+        imported.set("game_args", args);
+    }
+    if var("USEGAMEMODERUN").is_some_and(is_on) {
+        imported.wrap("gamemoderun");
+    }
+    if var("USEMANGOHUD").is_some_and(is_on) {
+        imported.set("mangohud.enabled", true);
+    }
+    if var("USEGAMESCOPE").is_some_and(is_on) {
+        imported.set("gamescope_enabled", true);
+        if let Some(args) = var("GAMESCOPE_ARGS") {
+            // STL ends the arguments with the separator before the game command
+            let args = args.trim().trim_end_matches("--").trim_end();
+            if !args.is_empty() {
+                imported.set("gamescope_args", args);
+            }
+        }
+    }
+    if let Some(verbs) = var("WINETRICKSPAKS") {
+        let verbs: Vec<toml::Value> = verbs.split_whitespace().map(toml::Value::from).collect();
+        imported.set("winetricks", verbs);
+    }
+    if let Some(hud) = var("DXVK_HUD") {
+        imported.insert("env", "DXVK_HUD", hud);
+    }
+    for (name, value) in custom {
+        imported.insert("env", name, value.as_str());
+    }
+
+    if let Some(command) = var("CUSTOMCMD") {
+        imported.skipped.push(format!(
+            "CUSTOMCMD = {} (add it as a companion or pre-launch hook)",
+            command
+        ));
+    }
+    for key in STL_ONLY {
+        if var(key).is_some_and(is_on) {
+            imported.skipped.push(format!("{} = 1", key));
+        }
+    }
+    imported
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_stl() {
+        let vars = parse_vars(
+            "#GAMENAME=\"Elden Ring\"\nUSEPROTON=\"GE-Proton9-20\"\nPROTON_NO_FSYNC=\"1\"\nPROTON_LOG=\"0\"\n\
+             GAMEARGS=\"none\"\nUSEGAMEMODERUN=\"1\"\nUSEGAMESCOPE=\"1\"\nGAMESCOPE_ARGS=\"-W 2560 -H 1440 -f --\"\n\
+             WINETRICKSPAKS=\"vcrun2019 d3dx9\"\nUSEVKBASALT=\"1\"\n",
+        );
+        let custom = parse_vars("export SteamDeck=0\n");
+        let imported = translate(&vars, &custom);
+
+        let config = toml::to_string(&imported.config).unwrap();
+        let expected = "gamescope_args = \"-W 2560 -H 1440 -f\"\ngamescope_enabled = true\n\
+                        pre_command = \"gamemoderun\"\nwinetricks = [\"vcrun2019\", \"d3dx9\"]\n\n\
+                        [env]\nSteamDeck = \"0\"\n\n[proton]\nfsync = false\nversion = \"GE-Proton9-20\"\n";
+        assert_eq!(config, expected);
+        assert_eq!(imported.skipped, vec!["USEVKBASALT = 1"]);
+    }
+}
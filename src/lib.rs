@@ -1,9 +1,19 @@
 pub mod cli;
 pub mod config;
+pub mod control;
 pub mod error;
+pub mod gpu;
+pub mod heroic;
 pub mod hooks;
+pub mod import;
+pub mod logs;
+pub mod modmanager;
+pub mod notify;
+pub mod playtime;
 pub mod proton;
+pub mod rpc;
 pub mod runner;
+pub mod saves;
 pub mod shim;
 pub mod steam;
 pub mod steam_api;
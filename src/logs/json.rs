@@ -0,0 +1,141 @@
+use super::{append_lines, settings, LogFile};
+use crate::config::{LogFormat, LogLevel};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Environment variable carrying the session ID to processes we start
+pub const SESSION_ID_VAR: &str = "SCR_SESSION_ID";
+
+/// ID shared by every log line of one launch
+///
+/// Inherited from SCR_SESSION_ID when a parent set it, so hooks and companions
+/// that call back into steam-command-runner log under the same session.
+pub fn session_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        std::env::var(SESSION_ID_VAR)
+            .ok()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| {
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis())
+                    .unwrap_or(0);
+                format!("{:x}-{:x}", millis, std::process::id())
+            })
+    })
+}
+
+/// One JSON log line: timestamp, level, log name, session and app ID, then `fields`
+pub(super) fn line(level: LogLevel, log: &str, fields: Map<String, Value>, app_id: Option<u32>) -> String {
+    let mut object = Map::new();
+    object.insert("timestamp".into(), utc_timestamp().into());
+    object.insert("level".into(), level.label().to_lowercase().into());
+    object.insert("log".into(), log.into());
+    object.insert("session_id".into(), session_id().into());
+    if let Some(app_id) = app_id {
+        object.insert("app_id".into(), app_id.into());
+    }
+    object.extend(fields);
+    Value::Object(object).to_string()
+}
+
+/// Current UTC time in RFC 3339 format with milliseconds
+fn utc_timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs() as libc::time_t;
+    // SAFETY: gmtime_r() only writes to the tm struct we own
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::gmtime_r(&secs, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+        now.subsec_millis()
+    )
+}
+
+/// Tracing layer copying events into the runner log as JSON lines
+///
+/// Only active with `logging.format = "json"` (or --log-format json), and
+/// filtered by `logging.level` rather than --verbose.
+pub struct JsonFileLayer;
+
+impl<S: Subscriber> Layer<S> for JsonFileLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let settings = settings();
+        let level = match *event.metadata().level() {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        };
+        if settings.format != LogFormat::Json || level > settings.level {
+            return;
+        }
+
+        let mut fields = FieldMap(Map::new());
+        fields.0.insert("target".into(), event.metadata().target().into());
+        event.record(&mut fields);
+        let line = line(level, LogFile::Runner.name(), fields.0, settings.app_id);
+        append_lines(LogFile::Runner, &[line], settings.max_size);
+    }
+}
+
+/// Collects an event's fields as JSON values
+struct FieldMap(Map<String, Value>);
+
+impl Visit for FieldMap {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let fields = Map::from_iter([("message".to_string(), Value::from("Launching"))]);
+        let line: Value = serde_json::from_str(&line(LogLevel::Info, "runner", fields, Some(440))).unwrap();
+        assert_eq!(line["level"], "info");
+        assert_eq!(line["log"], "runner");
+        assert_eq!(line["app_id"], 440);
+        assert_eq!(line["message"], "Launching");
+        assert_eq!(line["session_id"], session_id());
+        assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+}
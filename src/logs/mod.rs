@@ -0,0 +1,360 @@
+mod json;
+mod proton;
+
+pub use json::{session_id, JsonFileLayer, SESSION_ID_VAR};
+pub use proton::{latest_proton_log, proton_logs_dir, summarize_proton_log, ProtonIssue};
+
+use crate::config::{LogFormat, LogLevel, LoggingConfig};
+use crate::runner::parse_size;
+use clap::ValueEnum;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Environment variable overriding `logging.format` ("text" or "json")
+pub const LOG_FORMAT_VAR: &str = "SCR_LOG_FORMAT";
+
+/// Rotation size of the runner and shim logs when the config doesn't give one
+const DEFAULT_MAX_FILE_SIZE: u64 = 5 << 20;
+
+/// Our state directory ($XDG_STATE_HOME/steam-command-runner)
+pub fn state_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from(".local/state"))
+        .join("steam-command-runner")
+}
+
+/// Directory game output logs are written to ($XDG_STATE_HOME/steam-command-runner/logs)
+pub fn logs_dir() -> PathBuf {
+    state_dir().join("logs")
+}
+
+/// Logs steam-command-runner writes about its own work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFile {
+    /// Launches through `run`
+    Runner,
+    /// The gamescope and wrapper shims
+    Shim,
+}
+
+impl LogFile {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Runner => "runner",
+            Self::Shim => "shim",
+        }
+    }
+
+    pub fn path(self) -> PathBuf {
+        state_dir().join(format!("{}.log", self.name()))
+    }
+
+    /// Where the previous contents go when the log is rotated
+    pub fn rotated_path(self) -> PathBuf {
+        let mut path = self.path().into_os_string();
+        path.push(".1");
+        PathBuf::from(path)
+    }
+}
+
+/// How the runner and shim logs are written
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    level: LogLevel,
+    max_size: u64,
+    format: LogFormat,
+    /// Game the process is launching, added to JSON lines
+    app_id: Option<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            max_size: DEFAULT_MAX_FILE_SIZE,
+            format: format_override().unwrap_or_default(),
+            app_id: None,
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// The format asked for with --log-format / SCR_LOG_FORMAT
+fn format_override() -> Option<LogFormat> {
+    let value = std::env::var(LOG_FORMAT_VAR).ok()?;
+    LogFormat::from_str(&value, true).ok()
+}
+
+/// Apply the config's log settings; the first call in a process wins
+///
+/// `at_least` raises the level, for the legacy `shim_debug` switch.
+pub fn configure(logging: &LoggingConfig, app_id: Option<u32>, at_least: LogLevel) {
+    let _ = SETTINGS.set(Settings {
+        level: logging.level.max(at_least),
+        max_size: parse_size(&logging.max_file_size).unwrap_or(DEFAULT_MAX_FILE_SIZE),
+        format: format_override().unwrap_or(logging.format),
+        app_id,
+    });
+}
+
+/// The configured settings, or the defaults until the config has been loaded
+fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+/// Append a message to one of our logs, if the configured level lets it through
+pub fn write_log(file: LogFile, level: LogLevel, message: &str) {
+    let settings = settings();
+    if level == LogLevel::Off || level > settings.level {
+        return;
+    }
+    let lines: Vec<String> = match settings.format {
+        LogFormat::Text => message
+            .lines()
+            .map(|line| format!("{} {:<5} {}", timestamp(), level.label(), line))
+            .collect(),
+        LogFormat::Json => {
+            let fields = serde_json::Map::from_iter([("message".to_string(), message.into())]);
+            vec![json::line(level, file.name(), fields, settings.app_id)]
+        }
+    };
+    append_lines(file, &lines, settings.max_size);
+}
+
+/// Append lines to a log, rotating it first once it has reached `max_size`
+fn append_lines(file: LogFile, lines: &[String], max_size: u64) {
+    let path = file.path();
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= max_size) {
+        let _ = fs::rename(&path, file.rotated_path());
+    }
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut out) = OpenOptions::new().create(true).append(true).open(&path) {
+        for line in lines {
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+}
+
+/// A log the `logs` command can show: ours, or a game's latest output log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogTarget {
+    File(LogFile),
+    Game(u32),
+}
+
+impl LogTarget {
+    /// The log's path; None for a game without output logs
+    pub fn path(self) -> Option<PathBuf> {
+        match self {
+            Self::File(file) => Some(file.path()),
+            Self::Game(app_id) => game_logs(Some(app_id)).pop(),
+        }
+    }
+
+    /// Every file belonging to the target, including rotated and older logs
+    pub fn files(self) -> Vec<PathBuf> {
+        match self {
+            Self::File(file) => vec![file.path(), file.rotated_path()],
+            Self::Game(app_id) => game_logs(Some(app_id)),
+        }
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect()
+    }
+}
+
+impl FromStr for LogTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "runner" => Ok(Self::File(LogFile::Runner)),
+            "shim" => Ok(Self::File(LogFile::Shim)),
+            _ => s
+                .parse()
+                .map(Self::Game)
+                .map_err(|_| format!("'{}' is not 'runner', 'shim' or an App ID", s)),
+        }
+    }
+}
+
+/// Every log file we have written: runner and shim logs, game output, hook and Proton logs
+pub fn all_log_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = [LogFile::Runner, LogFile::Shim]
+        .into_iter()
+        .flat_map(|file| LogTarget::File(file).files())
+        .collect();
+    for dir in [logs_dir(), proton_logs_dir()] {
+        files.extend(
+            fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "log")),
+        );
+    }
+    files
+}
+
+/// The last `count` lines of a file
+pub fn last_lines(path: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(lines[lines.len().saturating_sub(count)..].iter().map(|l| l.to_string()).collect())
+}
+
+/// File name prefix shared by all logs of one game
+fn log_prefix(app_id: Option<u32>) -> String {
+    match app_id {
+        Some(id) => format!("{}-", id),
+        None => "unknown-".to_string(),
+    }
+}
+
+/// Path for a new game output log, named by App ID and local time
+pub fn new_game_log_path(app_id: Option<u32>) -> PathBuf {
+    logs_dir().join(format!("{}{}.log", log_prefix(app_id), timestamp()))
+}
+
+/// Log file hook output is appended to
+///
+/// Named so that it never matches a game's output log prefix, so rotation leaves it alone.
+pub fn hook_log_path(app_id: Option<u32>) -> PathBuf {
+    let name = app_id.map_or("unknown".to_string(), |id| id.to_string());
+    logs_dir().join(format!("hooks-{}.log", name))
+}
+
+/// Existing logs for a game, oldest first
+pub fn game_logs(app_id: Option<u32>) -> Vec<PathBuf> {
+    let prefix = log_prefix(app_id);
+    let mut logs: Vec<PathBuf> = fs::read_dir(logs_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.starts_with(&prefix) && name.ends_with(".log")
+                })
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    // Names end in a sortable timestamp, so lexical order is chronological
+    logs.sort();
+    logs
+}
+
+/// Remove a game's oldest logs so that, with one more log about to be written,
+/// at most `keep` remain and their total size stays within `max_total` bytes
+pub fn rotate_game_logs(app_id: Option<u32>, keep: usize, max_total: Option<u64>) {
+    let mut logs: Vec<(PathBuf, u64)> = game_logs(app_id)
+        .into_iter()
+        .map(|p| {
+            let size = fs::metadata(&p).map(|m| m.len()).unwrap_or(0);
+            (p, size)
+        })
+        .collect();
+
+    for (path, _) in select_for_removal(&mut logs, keep, max_total) {
+        debug!("Removing old game log {}", path.display());
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Pick the oldest logs to remove, leaving room for one new log
+fn select_for_removal(
+    logs: &mut Vec<(PathBuf, u64)>,
+    keep: usize,
+    max_total: Option<u64>,
+) -> Vec<(PathBuf, u64)> {
+    let mut removed = Vec::new();
+    let mut total: u64 = logs.iter().map(|(_, size)| size).sum();
+
+    while !logs.is_empty() {
+        let too_many = logs.len() >= keep.max(1);
+        let too_big = max_total.is_some_and(|max| total > max);
+        if !too_many && !too_big {
+            break;
+        }
+        let oldest = logs.remove(0);
+        total -= oldest.1;
+        removed.push(oldest);
+    }
+
+    removed
+}
+
+/// Local time formatted for file names (YYYYmmdd-HHMMSS)
+pub(crate) fn timestamp() -> String {
+    // SAFETY: time() and localtime_r() only write to the tm struct we own
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(sizes: &[u64]) -> Vec<(PathBuf, u64)> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (PathBuf::from(format!("{}.log", i)), *s))
+            .collect()
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let stamp = timestamp();
+        assert_eq!(stamp.len(), 15);
+        assert_eq!(stamp.as_bytes()[8], b'-');
+        assert!(stamp.chars().filter(|c| *c != '-').all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn test_log_target() {
+        assert_eq!("runner".parse(), Ok(LogTarget::File(LogFile::Runner)));
+        assert_eq!("shim".parse(), Ok(LogTarget::File(LogFile::Shim)));
+        assert_eq!("440".parse(), Ok(LogTarget::Game(440)));
+        assert!("nope".parse::<LogTarget>().is_err());
+    }
+
+    #[test]
+    fn test_rotation_by_count() {
+        let mut existing = logs(&[1, 1, 1, 1]);
+        let removed = select_for_removal(&mut existing, 3, None);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].0, PathBuf::from("0.log"));
+        assert_eq!(existing.len(), 2);
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let mut existing = logs(&[50, 40, 30]);
+        let removed = select_for_removal(&mut existing, 10, Some(75));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(existing.iter().map(|(_, s)| s).sum::<u64>(), 70);
+    }
+}
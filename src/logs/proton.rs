@@ -0,0 +1,136 @@
+use super::logs_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Directory PROTON_LOG_DIR points to when `proton.log` is enabled
+pub fn proton_logs_dir() -> PathBuf {
+    logs_dir().join("proton")
+}
+
+/// The most recently written steam-<app_id>.log
+///
+/// Looks in our Proton log directory and in the home directory, where Proton
+/// writes logs when PROTON_LOG is set without PROTON_LOG_DIR.
+pub fn latest_proton_log(app_id: u32) -> Option<PathBuf> {
+    let name = format!("steam-{}.log", app_id);
+    let mut dirs = vec![proton_logs_dir()];
+    dirs.extend(dirs::home_dir());
+    dirs.into_iter()
+        .map(|dir| dir.join(&name))
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _): &(SystemTime, PathBuf)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// A kind of problem spotted in a Proton log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtonIssue {
+    pub message: String,
+    /// What usually fixes it, if anything
+    pub hint: Option<String>,
+    /// How many log lines reported it
+    pub count: usize,
+}
+
+/// Summarize common problems in a Proton log: missing DLLs, crashes, Vulkan errors
+///
+/// Issues are listed in the order they first appear.
+pub fn summarize_proton_log(text: &str) -> Vec<ProtonIssue> {
+    let mut issues: Vec<ProtonIssue> = Vec::new();
+    for line in text.lines() {
+        let Some((message, hint)) = classify(line) else {
+            continue;
+        };
+        match issues.iter_mut().find(|issue| issue.message == message) {
+            Some(issue) => issue.count += 1,
+            None => issues.push(ProtonIssue { message, hint, count: 1 }),
+        }
+    }
+    issues
+}
+
+fn classify(line: &str) -> Option<(String, Option<String>)> {
+    if let Some(dll) = missing_dll(line) {
+        let hint = dll_hint(&dll).map(|verb| format!("winetricks = [\"{}\"]", verb));
+        return Some((format!("Missing DLL: {}", dll), hint));
+    }
+    if line.contains("Unhandled exception") || line.contains("Unhandled page fault") {
+        return Some(("The game crashed (unhandled exception)".to_string(), None));
+    }
+    if line.contains("VK_ERROR_INCOMPATIBLE_DRIVER") || line.contains("Failed to create Vulkan instance") {
+        return Some((
+            "No usable Vulkan driver".to_string(),
+            Some("install the Vulkan driver (and its 32-bit version) for your GPU".to_string()),
+        ));
+    }
+    if line.contains("mscoree") && line.contains("not found") {
+        return Some((".NET runtime not installed".to_string(), Some("winetricks = [\"dotnet48\"]".to_string())));
+    }
+    None
+}
+
+/// DLL named in a Wine "Library X (which is needed by Y) not found" error
+fn missing_dll(line: &str) -> Option<String> {
+    if !line.contains("err:module") || !line.contains("not found") {
+        return None;
+    }
+    let rest = line.split("Library ").nth(1)?;
+    let dll = rest.split_whitespace().next()?;
+    Some(dll.trim_matches('"').to_lowercase())
+}
+
+/// Winetricks verb providing a commonly missing DLL
+fn dll_hint(dll: &str) -> Option<&'static str> {
+    let dll = dll.trim_end_matches(".dll");
+    if dll.starts_with("msvcp") || dll.starts_with("vcruntime") || dll.starts_with("msvcr") || dll.starts_with("vcomp") {
+        return Some(match dll {
+            "msvcp100" | "msvcr100" => "vcrun2010",
+            "msvcp110" | "msvcr110" => "vcrun2012",
+            "msvcp120" | "msvcr120" => "vcrun2013",
+            _ => "vcrun2022",
+        });
+    }
+    if dll.starts_with("d3dx9") {
+        return Some("d3dx9");
+    }
+    if dll.starts_with("d3dcompiler") {
+        return Some("d3dcompiler_47");
+    }
+    if dll.starts_with("xaudio") || dll.starts_with("x3daudio") || dll.starts_with("xapofx") {
+        return Some("xact");
+    }
+    if dll.starts_with("physxloader") {
+        return Some("physx");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_proton_log() {
+        let log = r#"Proton: /games/Game/game.exe
+0114:err:module:import_dll Library MSVCP140.dll (which is needed by L"C:\\game\\engine.dll") not found
+0114:err:module:import_dll Library MSVCP140.dll (which is needed by L"C:\\game\\game.exe") not found
+0114:err:module:import_dll Library d3dx9_43.dll (which is needed by L"C:\\game\\render.dll") not found
+0120:err:module:import_dll Library foo.dll (which is needed by L"C:\\game\\game.exe") not found
+wine: Unhandled exception 0xc0000005 in thread 114 at address 0000000140001000
+"#;
+        let issues = summarize_proton_log(log);
+        assert_eq!(issues.len(), 4);
+        assert_eq!(issues[0].message, "Missing DLL: msvcp140.dll");
+        assert_eq!(issues[0].count, 2);
+        assert_eq!(issues[0].hint.as_deref(), Some("winetricks = [\"vcrun2022\"]"));
+        assert_eq!(issues[1].hint.as_deref(), Some("winetricks = [\"d3dx9\"]"));
+        assert_eq!(issues[2].message, "Missing DLL: foo.dll");
+        assert_eq!(issues[2].hint, None);
+        assert_eq!(issues[3].message, "The game crashed (unhandled exception)");
+        assert!(summarize_proton_log("fixme:heap:RtlSetHeapInformation\n").is_empty());
+    }
+}
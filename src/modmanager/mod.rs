@@ -0,0 +1,130 @@
+use crate::config::{ModManagerConfig, ModManagerKind};
+use crate::error::AppError;
+use tracing::{debug, warn};
+
+/// Convert a Linux path into the Wine path Proton exposes it under (Z: drive)
+fn to_wine_path(path: &str) -> String {
+    if path.starts_with('/') {
+        format!("Z:{}", path.replace('/', "\\"))
+    } else {
+        path.to_string()
+    }
+}
+
+/// Extra arguments configured for the manager
+fn extra_args(manager: &ModManagerConfig) -> Result<Vec<String>, AppError> {
+    match &manager.args {
+        Some(args) => shlex::split(args)
+            .ok_or_else(|| AppError::ModManager(format!("Could not parse args: {}", args))),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// MO2 profile selection arguments
+fn profile_args(manager: &ModManagerConfig) -> Vec<String> {
+    match &manager.profile {
+        Some(profile) => vec!["-p".to_string(), profile.clone()],
+        None => Vec::new(),
+    }
+}
+
+/// Command that opens the mod manager itself
+pub fn session_command(manager: &ModManagerConfig) -> Result<Vec<String>, AppError> {
+    let mut command = vec![manager.path.clone()];
+    if manager.kind == ModManagerKind::Mo2 {
+        command.extend(profile_args(manager));
+    }
+    command.extend(extra_args(manager)?);
+    debug!("Mod manager session command: {:?}", command);
+    Ok(command)
+}
+
+/// Command that starts the game through the mod manager so its VFS is active
+///
+/// MO2 starts the configured executable entry via a `moshortcut://` link, or the
+/// game binary itself when no entry is configured. Managers without a VFS don't
+/// need routing, so the game command is returned unchanged for them.
+pub fn routed_command(
+    manager: &ModManagerConfig,
+    game_command: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    if manager.kind != ModManagerKind::Mo2 {
+        warn!("route_launch only applies to MO2, launching the game directly");
+        return Ok(game_command);
+    }
+
+    let mut command = vec![manager.path.clone()];
+    command.extend(profile_args(manager));
+    command.extend(extra_args(manager)?);
+
+    match &manager.executable {
+        Some(executable) => {
+            let instance = manager.instance.as_deref().unwrap_or("");
+            command.push(format!("moshortcut://{}:{}", instance, executable));
+        }
+        None => {
+            let (game, game_args) = game_command
+                .split_first()
+                .ok_or(AppError::NoCommand)?;
+            command.push(to_wine_path(game));
+            command.extend(game_args.iter().cloned());
+        }
+    }
+
+    debug!("Routing launch through MO2: {:?}", command);
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mo2() -> ModManagerConfig {
+        ModManagerConfig {
+            kind: ModManagerKind::Mo2,
+            path: "/games/MO2/ModOrganizer.exe".to_string(),
+            args: None,
+            instance: None,
+            profile: Some("Survival".to_string()),
+            executable: Some("SKSE".to_string()),
+            route_launch: true,
+        }
+    }
+
+    #[test]
+    fn test_routed_command_moshortcut() {
+        let command = routed_command(&mo2(), vec!["/games/Skyrim/SkyrimSE.exe".to_string()]).unwrap();
+        assert_eq!(
+            command,
+            vec!["/games/MO2/ModOrganizer.exe", "-p", "Survival", "moshortcut://:SKSE"]
+        );
+    }
+
+    #[test]
+    fn test_routed_command_game_binary() {
+        let mut manager = mo2();
+        manager.executable = None;
+        manager.profile = None;
+        let command = routed_command(
+            &manager,
+            vec!["/games/Skyrim/SkyrimSE.exe".to_string(), "-windowed".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            vec![
+                "/games/MO2/ModOrganizer.exe",
+                "Z:\\games\\Skyrim\\SkyrimSE.exe",
+                "-windowed"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_routed_command_vortex_unchanged() {
+        let mut manager = mo2();
+        manager.kind = ModManagerKind::Vortex;
+        let game = vec!["/games/Skyrim/SkyrimSE.exe".to_string()];
+        assert_eq!(routed_command(&manager, game.clone()).unwrap(), game);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::config::{configured_notifications, Notifications};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// How important a notification is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    /// Routine events (game launched, launch options applied)
+    Normal,
+    /// Something went wrong (game crashed, launch failed)
+    Critical,
+}
+
+impl Urgency {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+
+    /// Whether this urgency is shown at the given verbosity
+    fn shown_at(self, level: Notifications) -> bool {
+        match level {
+            Notifications::Off => false,
+            Notifications::Errors => self == Self::Critical,
+            Notifications::All => true,
+        }
+    }
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join(program).exists()))
+        .unwrap_or(false)
+}
+
+/// Show a desktop notification, if `notifications` in the global config allows it
+///
+/// Uses notify-send, falling back to calling the notification service with gdbus.
+/// Failures are only logged; notifications never affect what the command does.
+pub fn notify(urgency: Urgency, summary: &str, body: &str) {
+    if !urgency.shown_at(configured_notifications()) {
+        return;
+    }
+
+    let mut command = if in_path("notify-send") {
+        let mut command = Command::new("notify-send");
+        command
+            .args(["--app-name=steam-command-runner", "--urgency", urgency.as_str()])
+            .args([summary, body]);
+        command
+    } else if in_path("gdbus") {
+        // gdbus parses GVariant text; JSON string literals are valid GVariant strings
+        let quote = |s: &str| serde_json::Value::from(s).to_string();
+        let hints = format!("{{'urgency': <byte {}>}}", if urgency == Urgency::Critical { 2 } else { 1 });
+        let mut command = Command::new("gdbus");
+        command
+            .args(["call", "--session", "--dest", "org.freedesktop.Notifications"])
+            .args(["--object-path", "/org/freedesktop/Notifications"])
+            .args(["--method", "org.freedesktop.Notifications.Notify"])
+            .args(["steam-command-runner", "0", "''"])
+            .args([quote(summary), quote(body)])
+            .args(["[]", &hints, "-1"]);
+        command
+    } else {
+        debug!("Neither notify-send nor gdbus found; not showing '{}'", summary);
+        return;
+    };
+
+    match command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+        Ok(status) if status.success() => debug!("Sent notification '{}'", summary),
+        Ok(status) => debug!("Sending notification '{}' failed ({})", summary, status),
+        Err(e) => debug!("Sending notification '{}' failed: {}", summary, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shown_at() {
+        assert!(!Urgency::Critical.shown_at(Notifications::Off));
+        assert!(Urgency::Critical.shown_at(Notifications::Errors));
+        assert!(!Urgency::Normal.shown_at(Notifications::Errors));
+        assert!(Urgency::Normal.shown_at(Notifications::All));
+    }
+}
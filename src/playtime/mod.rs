@@ -0,0 +1,185 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// One play session, stored as a line of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub app_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Launch time, in seconds since the Unix epoch
+    pub start: u64,
+    /// Exit time, in seconds since the Unix epoch
+    pub end: u64,
+    /// Exit code as reported to Steam (128 + signal number if killed)
+    pub exit_code: u8,
+    /// Proton build the game ran with (None for native games)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proton: Option<String>,
+}
+
+impl Session {
+    pub fn duration_secs(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// Playtime totals for one game
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStats {
+    pub app_id: Option<u32>,
+    pub name: Option<String>,
+    pub sessions: usize,
+    pub total_secs: u64,
+    pub last_played: u64,
+}
+
+/// File play sessions are appended to ($XDG_DATA_HOME/steam-command-runner/playtime.jsonl)
+pub fn playtime_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(".local/share"))
+        .join("steam-command-runner")
+        .join("playtime.jsonl")
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Append a finished session to the playtime store
+pub fn record_session(session: &Session) -> Result<(), AppError> {
+    let path = playtime_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(session).map_err(|e| AppError::Playtime(e.to_string()))?;
+
+    // A single write of a whole line, so concurrent games don't interleave records
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    debug!("Recorded play session in {}", path.display());
+    Ok(())
+}
+
+/// All recorded sessions, oldest first (unreadable lines are skipped)
+pub fn load_sessions() -> Vec<Session> {
+    fs::read_to_string(playtime_path())
+        .map(|content| parse_sessions(&content))
+        .unwrap_or_default()
+}
+
+fn parse_sessions(content: &str) -> Vec<Session> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                warn!("Skipping invalid playtime record: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Total playtime per game, most played first
+pub fn summarize(sessions: &[Session]) -> Vec<GameStats> {
+    let mut stats: Vec<GameStats> = Vec::new();
+    for session in sessions {
+        match stats.iter_mut().find(|s| s.app_id == session.app_id) {
+            Some(game) => {
+                game.sessions += 1;
+                game.total_secs += session.duration_secs();
+                game.last_played = game.last_played.max(session.end);
+                if session.name.is_some() {
+                    game.name = session.name.clone();
+                }
+            }
+            None => stats.push(GameStats {
+                app_id: session.app_id,
+                name: session.name.clone(),
+                sessions: 1,
+                total_secs: session.duration_secs(),
+                last_played: session.end,
+            }),
+        }
+    }
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total_secs));
+    stats
+}
+
+/// Format a duration as e.g. "12h 05m" or "3m 20s"
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, seconds)
+    }
+}
+
+/// Format a Unix time as local "YYYY-mm-dd HH:MM"
+pub fn format_time(secs: u64) -> String {
+    // SAFETY: localtime_r() only writes to the tm struct we own
+    let tm = unsafe {
+        let time = secs as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(app_id: u32, start: u64, end: u64) -> Session {
+        Session {
+            app_id: Some(app_id),
+            name: None,
+            start,
+            end,
+            exit_code: 0,
+            proton: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_summarize() {
+        let content = format!(
+            "{}\nnot json\n{}\n{}\n",
+            serde_json::to_string(&session(440, 0, 600)).unwrap(),
+            serde_json::to_string(&session(570, 1000, 8200)).unwrap(),
+            serde_json::to_string(&session(440, 9000, 9600)).unwrap(),
+        );
+        let sessions = parse_sessions(&content);
+        assert_eq!(sessions.len(), 3);
+
+        let stats = summarize(&sessions);
+        assert_eq!(stats[0].app_id, Some(570));
+        assert_eq!(stats[1].app_id, Some(440));
+        assert_eq!(stats[1].sessions, 2);
+        assert_eq!(stats[1].total_secs, 1200);
+        assert_eq!(stats[1].last_played, 9600);
+
+        assert_eq!(format_duration(200), "3m 20s");
+        assert_eq!(format_duration(7200 + 300), "2h 05m");
+    }
+}
@@ -0,0 +1,132 @@
+use super::prefix::dir_size;
+use crate::error::AppError;
+use crate::steam::get_library_folders;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Shader and pipeline caches belonging to one app
+#[derive(Debug, Clone)]
+pub struct ShaderCache {
+    pub app_id: u32,
+    /// shadercache/<app_id> directories and DXVK/VKD3D state cache files
+    pub paths: Vec<PathBuf>,
+    /// Total size in bytes
+    pub size: u64,
+}
+
+/// Whether a file name is a DXVK or VKD3D-Proton pipeline state cache
+fn is_state_cache(name: &str) -> bool {
+    name.ends_with(".dxvk-cache") || name.starts_with("vkd3d-proton.cache")
+}
+
+/// State cache files Proton leaves in a compatdata directory or its pfx
+fn state_cache_files(compat_data: &Path) -> Vec<PathBuf> {
+    [compat_data.to_path_buf(), compat_data.join("pfx")]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_state_cache))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Numbered subdirectories of a directory, as (App ID, path)
+fn app_dirs(dir: &Path) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let app_id = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let path = entry.path();
+            path.is_dir().then_some((app_id, path))
+        })
+        .collect()
+}
+
+/// Size of a file or directory, without following symlinks
+fn path_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => dir_size(path),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Find the shader caches of every app across all Steam libraries, by App ID
+pub fn list_shader_caches() -> Result<Vec<ShaderCache>, AppError> {
+    let mut caches: BTreeMap<u32, Vec<PathBuf>> = BTreeMap::new();
+
+    for steamapps in get_library_folders()? {
+        for (app_id, path) in app_dirs(&steamapps.join("shadercache")) {
+            caches.entry(app_id).or_default().push(path);
+        }
+        for (app_id, compat_data) in app_dirs(&steamapps.join("compatdata")) {
+            let files = state_cache_files(&compat_data);
+            if !files.is_empty() {
+                caches.entry(app_id).or_default().extend(files);
+            }
+        }
+    }
+
+    Ok(caches
+        .into_iter()
+        .map(|(app_id, paths)| {
+            let size = paths.iter().map(|p| path_size(p)).sum();
+            ShaderCache {
+                app_id,
+                paths,
+                size,
+            }
+        })
+        .collect())
+}
+
+/// Find the shader caches of one app
+pub fn find_shader_cache(app_id: u32) -> Result<Option<ShaderCache>, AppError> {
+    Ok(list_shader_caches()?
+        .into_iter()
+        .find(|cache| cache.app_id == app_id))
+}
+
+/// Delete an app's shader caches; Steam, DXVK and VKD3D rebuild them as the game runs
+pub fn clear_shader_cache(cache: &ShaderCache) -> Result<(), AppError> {
+    for path in &cache.paths {
+        info!("Removing {}", path.display());
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_cache_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("pfx/drive_c")).unwrap();
+        fs::write(dir.path().join("pfx/Game.dxvk-cache"), b"dxvk").unwrap();
+        fs::write(dir.path().join("vkd3d-proton.cache.write"), b"vkd3d").unwrap();
+        fs::write(dir.path().join("pfx/user.reg"), b"").unwrap();
+
+        let mut files = state_cache_files(dir.path());
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("pfx/Game.dxvk-cache"),
+                dir.path().join("vkd3d-proton.cache.write"),
+            ]
+        );
+        assert_eq!(files.iter().map(|f| path_size(f)).sum::<u64>(), 9);
+    }
+}
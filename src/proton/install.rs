@@ -0,0 +1,222 @@
+use crate::error::AppError;
+use crate::steam::get_steam_root;
+use serde::Deserialize;
+use sha2::{Digest, Sha512};
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+const GE_RELEASES_URL: &str =
+    "https://api.github.com/repos/GloriousEggroll/proton-ge-custom/releases";
+
+/// A GE-Proton release as returned by the GitHub API
+#[derive(Debug, Deserialize)]
+pub struct GeRelease {
+    pub tag_name: String,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl GeRelease {
+    fn asset(&self, suffix: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|a| a.name.ends_with(suffix))
+    }
+}
+
+/// Directory Steam loads custom compatibility tools from
+pub fn compat_tools_dir() -> Result<PathBuf, AppError> {
+    let steam_root = get_steam_root().ok_or_else(|| AppError::SteamNotFound(Vec::new()))?;
+    Ok(steam_root.join("compatibilitytools.d"))
+}
+
+/// Normalize a requested version ("9-20", "GE-Proton9-20", "latest") to a release tag
+pub fn normalize_ge_version(version: &str) -> Option<String> {
+    let version = version.trim();
+    if version.is_empty() || version.eq_ignore_ascii_case("latest") {
+        None
+    } else if version.starts_with("GE-Proton") {
+        Some(version.to_string())
+    } else {
+        Some(format!("GE-Proton{}", version))
+    }
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, AppError> {
+    Ok(reqwest::blocking::Client::builder()
+        .user_agent("steam-command-runner/0.2.0")
+        .timeout(None)
+        .build()?)
+}
+
+/// Fetch release metadata for a tag, or the latest release
+pub fn fetch_ge_release(tag: Option<&str>) -> Result<GeRelease, AppError> {
+    let url = match tag {
+        Some(tag) => format!("{}/tags/{}", GE_RELEASES_URL, tag),
+        None => format!("{}/latest", GE_RELEASES_URL),
+    };
+    debug!("Fetching: {}", url);
+
+    let response = http_client()?.get(&url).send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AppError::ProtonNotFound(
+            tag.unwrap_or("latest GE-Proton").to_string(),
+        ));
+    }
+    Ok(response.error_for_status()?.json()?)
+}
+
+/// Whether a release is already present in compatibilitytools.d
+pub fn is_installed(tag: &str) -> Result<bool, AppError> {
+    Ok(compat_tools_dir()?.join(tag).join("proton").exists())
+}
+
+/// Download, verify and extract a GE-Proton release into compatibilitytools.d
+///
+/// Returns the path of the installed tool.
+pub fn install_ge_release(release: &GeRelease) -> Result<PathBuf, AppError> {
+    let tag = &release.tag_name;
+    let tarball = release
+        .asset(".tar.gz")
+        .ok_or_else(|| AppError::ProtonInstall(format!("{} has no .tar.gz asset", tag)))?;
+    let checksum = release
+        .asset(".sha512sum")
+        .ok_or_else(|| AppError::ProtonInstall(format!("{} has no .sha512sum asset", tag)))?;
+
+    let tools_dir = compat_tools_dir()?;
+    fs::create_dir_all(&tools_dir)?;
+
+    let client = http_client()?;
+    let checksum_text = client
+        .get(&checksum.browser_download_url)
+        .send()?
+        .error_for_status()?
+        .text()?;
+    let expected = parse_sha512sum(&checksum_text)
+        .ok_or_else(|| AppError::ProtonInstall(format!("could not read checksum for {}", tag)))?;
+
+    // Download next to the destination so the final rename stays on one filesystem
+    let download_path = tools_dir.join(format!(".{}.part", tarball.name));
+    info!("Downloading {}", tarball.browser_download_url);
+    let actual = download(&client, &tarball.browser_download_url, &download_path)?;
+
+    if !actual.eq_ignore_ascii_case(&expected) {
+        let _ = fs::remove_file(&download_path);
+        return Err(AppError::ProtonInstall(format!(
+            "checksum mismatch for {} (expected {}, got {})",
+            tarball.name, expected, actual
+        )));
+    }
+    debug!("Checksum verified for {}", tarball.name);
+
+    let result = extract(&download_path, &tools_dir, tag);
+    let _ = fs::remove_file(&download_path);
+    result
+}
+
+/// Stream a URL to a file, returning the hex SHA-512 of the contents
+fn download(client: &reqwest::blocking::Client, url: &str, dest: &Path) -> Result<String, AppError> {
+    let mut response = client.get(url).send()?.error_for_status()?;
+    let total = response.content_length();
+    let mut file = File::create(dest)?;
+    let mut hasher = Sha512::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut written: u64 = 0;
+    let mut last_report = 0;
+
+    loop {
+        let n = response.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        file.write_all(&buf[..n])?;
+        written += n as u64;
+
+        let mib = written >> 20;
+        if mib >= last_report + 50 {
+            last_report = mib;
+            match total {
+                Some(total) => info!("Downloaded {} / {} MiB", mib, total >> 20),
+                None => info!("Downloaded {} MiB", mib),
+            }
+        }
+    }
+    file.sync_all()?;
+
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Extract the tarball into a staging directory and move the tool into place
+fn extract(tarball: &Path, tools_dir: &Path, tag: &str) -> Result<PathBuf, AppError> {
+    let staging = tools_dir.join(format!(".{}.extracting", tag));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    info!("Extracting {}", tag);
+    let decoder = flate2::read::GzDecoder::new(BufReader::new(File::open(tarball)?));
+    tar::Archive::new(decoder).unpack(&staging)?;
+
+    let extracted = staging.join(tag);
+    if !extracted.join("proton").exists() {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(AppError::ProtonInstall(format!(
+            "archive for {} does not contain {}/proton",
+            tag, tag
+        )));
+    }
+
+    let dest = tools_dir.join(tag);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)?;
+    }
+    fs::rename(&extracted, &dest)?;
+    fs::remove_dir_all(&staging)?;
+
+    info!("Installed {} to {}", tag, dest.display());
+    Ok(dest)
+}
+
+/// Parse the hash out of a `sha512sum` line ("<hash>  <file>")
+fn parse_sha512sum(content: &str) -> Option<String> {
+    let hash = content.split_whitespace().next()?;
+    (hash.len() == 128 && hash.chars().all(|c| c.is_ascii_hexdigit())).then(|| hash.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_ge_version() {
+        assert_eq!(normalize_ge_version("latest"), None);
+        assert_eq!(normalize_ge_version(""), None);
+        assert_eq!(normalize_ge_version("9-20").as_deref(), Some("GE-Proton9-20"));
+        assert_eq!(normalize_ge_version("GE-Proton10-4").as_deref(), Some("GE-Proton10-4"));
+    }
+
+    #[test]
+    fn test_parse_sha512sum() {
+        let hash = "a".repeat(128);
+        let line = format!("{}  GE-Proton9-20.tar.gz\n", hash);
+        assert_eq!(parse_sha512sum(&line), Some(hash));
+        assert_eq!(parse_sha512sum("not-a-hash  file"), None);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0xab, 0x0f]), "00ab0f");
+    }
+}
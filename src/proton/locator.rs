@@ -1,11 +1,13 @@
 use crate::error::AppError;
+use crate::steam::steam_root_candidates;
 use std::path::PathBuf;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Locate a Proton installation
 ///
 /// Search order:
-/// 1. If a specific version is requested, search for it by name
+/// 1. If a specific version is requested, search for it by exact name, then as a
+///    requirement ("GE >=9", "latest-ge", see `VersionSpec`), then by substring
 /// 2. Search in Steam's compatibilitytools.d (custom Proton)
 /// 3. Search in Steam's common directory (official Proton)
 /// 4. Use STEAM_COMPAT_TOOL_PATH if set
@@ -17,13 +19,27 @@ pub fn locate_proton(requested_version: Option<&str>) -> Result<PathBuf, AppErro
     if let Some(version) = requested_version {
         info!("Looking for Proton version: {}", version);
 
+        // Try exact match first
         for base_path in &search_paths {
-            // Try exact match first
             let exact_path = base_path.join(version);
             if is_valid_proton(&exact_path) {
                 return Ok(exact_path);
             }
+        }
+
+        // Then a requirement like "GE >=9" or "latest-ge"
+        if let Some(spec) = VersionSpec::parse(version) {
+            let versions = list_proton_versions();
+            let names: Vec<&str> = versions.iter().map(|(name, _)| name.as_str()).collect();
+            if let Some(picked) = spec.pick(&names) {
+                info!("Resolved Proton '{}' to {}", version, picked);
+                if let Some((_, path)) = versions.iter().find(|(name, _)| name == picked) {
+                    return Ok(path.clone());
+                }
+            }
+        }
 
+        for base_path in &search_paths {
             // Try case-insensitive search
             if let Ok(entries) = std::fs::read_dir(base_path) {
                 for entry in entries.flatten() {
@@ -75,14 +91,35 @@ pub fn locate_proton(requested_version: Option<&str>) -> Result<PathBuf, AppErro
     Err(AppError::ProtonNotFound("any".to_string()))
 }
 
+/// Locate the first installed Proton of a fallback chain, warning when it isn't the first
+///
+/// "any" accepts whatever Proton is installed; an empty chain means "any".
+pub fn locate_proton_chain(chain: &[&str]) -> Result<PathBuf, AppError> {
+    let Some(first) = chain.first() else {
+        return locate_proton(None);
+    };
+    for (i, version) in chain.iter().enumerate() {
+        let requested = (!version.eq_ignore_ascii_case("any")).then_some(*version);
+        match locate_proton(requested) {
+            Ok(path) => {
+                if i > 0 {
+                    warn!("Proton '{}' not found, falling back to '{}'", first, version);
+                }
+                return Ok(path);
+            }
+            Err(e) => debug!("{}", e),
+        }
+    }
+    Err(AppError::ProtonNotFound(chain.join("', '")))
+}
+
 /// Get list of paths to search for Proton
 fn get_search_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
-    // ~/.steam/root/compatibilitytools.d (custom Proton like GE)
-    if let Some(home) = dirs::home_dir() {
-        paths.push(home.join(".steam/root/compatibilitytools.d"));
-        paths.push(home.join(".local/share/Steam/compatibilitytools.d"));
+    // <steam root>/compatibilitytools.d (custom Proton like GE)
+    for root in steam_roots() {
+        paths.push(root.join("compatibilitytools.d"));
     }
 
     // Steam library paths - check common locations
@@ -94,15 +131,33 @@ fn get_search_paths() -> Vec<PathBuf> {
     paths
 }
 
+/// Existing Steam installations (native and Flatpak), deduplicated through symlinks
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let mut seen = Vec::new();
+    for candidate in steam_root_candidates() {
+        if let Ok(real) = std::fs::canonicalize(&candidate) {
+            if !seen.contains(&real) {
+                seen.push(real);
+                roots.push(candidate);
+            }
+        }
+    }
+    roots
+}
+
+/// Directories searched for custom compatibility tools
+pub fn compat_tool_search_dirs() -> Vec<PathBuf> {
+    steam_roots()
+        .into_iter()
+        .map(|root| root.join("compatibilitytools.d"))
+        .collect()
+}
+
 /// Get Steam library paths from libraryfolders.vdf
 fn get_steam_library_paths() -> Vec<PathBuf> {
-    let mut paths = Vec::new();
-
     // Default Steam locations
-    if let Some(home) = dirs::home_dir() {
-        paths.push(home.join(".steam/steam"));
-        paths.push(home.join(".local/share/Steam"));
-    }
+    let mut paths = steam_roots();
 
     // Try to read libraryfolders.vdf for additional library paths
     for base in &paths.clone() {
@@ -217,3 +272,191 @@ fn compare_parts(a: &str, b: &str) -> std::cmp::Ordering {
         _ => a.to_lowercase().cmp(&b.to_lowercase()),
     }
 }
+
+/// Family of a Proton build, told apart by its directory name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProtonFamily {
+    /// GloriousEggroll's builds ("GE-Proton9-20", or "Proton-6.21-GE-2" before GE-Proton7)
+    Ge,
+    /// Valve's builds ("Proton 9.0", "Proton - Experimental")
+    Official,
+}
+
+impl ProtonFamily {
+    /// Family named in a requirement ("ge", or "proton"/"official"/"valve")
+    fn from_keyword(word: &str) -> Option<Self> {
+        match word {
+            "ge" => Some(ProtonFamily::Ge),
+            "proton" | "official" | "valve" => Some(ProtonFamily::Official),
+            _ => None,
+        }
+    }
+
+    /// Family of an installed build, from its directory name
+    fn of(name: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        if lower.starts_with("ge-proton") || (lower.starts_with("proton-") && lower.contains("-ge")) {
+            Some(ProtonFamily::Ge)
+        } else if lower.starts_with("proton ") {
+            Some(ProtonFamily::Official)
+        } else {
+            None
+        }
+    }
+}
+
+/// Numbers in a build name ("GE-Proton9-20" -> [9, 20], "Proton 8.0" -> [8, 0])
+fn version_numbers(name: &str) -> Vec<u64> {
+    split_version_parts(name)
+        .iter()
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// A Proton version requirement from the config, resolved against installed builds
+///
+/// Either `latest`/`latest-ge`/`latest-proton`, or an optional family followed by a
+/// comparison: "GE >=9", "proton 8", ">=9.0". A version matches on as many numbers
+/// as the requirement gives, so "GE 9" is any GE-Proton9 build and "GE >9" starts
+/// at GE-Proton10.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VersionSpec {
+    family: Option<ProtonFamily>,
+    requirement: Option<(std::cmp::Ordering, bool, Vec<u64>)>,
+}
+
+impl VersionSpec {
+    /// Parse a requirement, or None for a plain build name
+    fn parse(spec: &str) -> Option<Self> {
+        let lower = spec.trim().to_lowercase();
+        if let Some(rest) = lower.strip_prefix("latest") {
+            let family = match rest {
+                "" => None,
+                _ => Some(ProtonFamily::from_keyword(rest.strip_prefix('-')?)?),
+            };
+            return Some(VersionSpec {
+                family,
+                requirement: None,
+            });
+        }
+
+        let (family, rest) = match lower.split_once(' ') {
+            Some((word, rest)) => match ProtonFamily::from_keyword(word) {
+                Some(family) => (Some(family), rest.trim()),
+                None => (None, lower.as_str()),
+            },
+            None => (None, lower.as_str()),
+        };
+
+        // (ordering the version must have against the requirement, whether equal is allowed)
+        use std::cmp::Ordering::{Equal, Greater, Less};
+        let (ordering, or_equal, version) = if let Some(v) = rest.strip_prefix(">=") {
+            (Greater, true, v)
+        } else if let Some(v) = rest.strip_prefix("<=") {
+            (Less, true, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (Greater, false, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (Less, false, v)
+        } else if let Some(v) = rest.strip_prefix('=') {
+            (Equal, true, v)
+        } else if family.is_some() {
+            (Equal, true, rest)
+        } else {
+            // No family and no operator: a build name like "GE-Proton9-20"
+            return None;
+        };
+
+        let version = version.trim();
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '-') {
+            return None;
+        }
+        Some(VersionSpec {
+            family,
+            requirement: Some((ordering, or_equal, version_numbers(version))),
+        })
+    }
+
+    /// Whether a build name satisfies the requirement
+    fn matches(&self, name: &str) -> bool {
+        if self.family.is_some_and(|family| ProtonFamily::of(name) != Some(family)) {
+            return false;
+        }
+        let numbers = version_numbers(name);
+        if numbers.is_empty() {
+            // "Proton - Experimental" and friends have no version to compare
+            return self.requirement.is_none() && self.family.is_some();
+        }
+        let Some((ordering, or_equal, wanted)) = &self.requirement else {
+            return true;
+        };
+        let len = wanted.len().min(numbers.len());
+        let actual = numbers[..len].cmp(&wanted[..len]);
+        actual == *ordering || (*or_equal && actual == std::cmp::Ordering::Equal)
+    }
+
+    /// Newest matching build among the given names
+    fn pick<'a>(&self, names: &[&'a str]) -> Option<&'a str> {
+        names
+            .iter()
+            .copied()
+            .filter(|name| self.matches(name))
+            .max_by(|a, b| {
+                version_numbers(a)
+                    .cmp(&version_numbers(b))
+                    .then_with(|| compare_version_names(a, b))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTALLED: [&str; 6] = [
+        "GE-Proton8-32",
+        "GE-Proton9-2",
+        "GE-Proton9-20",
+        "GE-Proton10-1",
+        "Proton 9.0",
+        "Proton - Experimental",
+    ];
+
+    fn pick(spec: &str) -> Option<&'static str> {
+        VersionSpec::parse(spec).unwrap().pick(&INSTALLED)
+    }
+
+    #[test]
+    fn test_parse_version_spec() {
+        assert_eq!(VersionSpec::parse("GE-Proton9-20"), None);
+        assert_eq!(VersionSpec::parse("Proton 9.0 (Beta)"), None);
+        assert_eq!(VersionSpec::parse("latest-foo"), None);
+        assert_eq!(
+            VersionSpec::parse("latest-ge"),
+            Some(VersionSpec {
+                family: Some(ProtonFamily::Ge),
+                requirement: None
+            })
+        );
+        assert_eq!(
+            VersionSpec::parse("GE >=9"),
+            Some(VersionSpec {
+                family: Some(ProtonFamily::Ge),
+                requirement: Some((std::cmp::Ordering::Greater, true, vec![9]))
+            })
+        );
+    }
+
+    #[test]
+    fn test_pick_version() {
+        assert_eq!(pick("latest-ge"), Some("GE-Proton10-1"));
+        assert_eq!(pick("GE 9"), Some("GE-Proton9-20"));
+        assert_eq!(pick("GE >=9"), Some("GE-Proton10-1"));
+        assert_eq!(pick("GE <9"), Some("GE-Proton8-32"));
+        assert_eq!(pick("GE <=9-10"), Some("GE-Proton9-2"));
+        assert_eq!(pick("proton >=9"), Some("Proton 9.0"));
+        assert_eq!(pick("latest-proton"), Some("Proton 9.0"));
+        assert_eq!(pick("latest"), Some("GE-Proton10-1"));
+        assert_eq!(pick("GE >10"), None);
+    }
+}
@@ -1,3 +1,17 @@
+pub mod cache;
+pub mod install;
 mod locator;
+pub mod prefix;
+pub mod winetricks;
 
-pub use locator::{list_proton_versions, locate_proton};
+pub use cache::{clear_shader_cache, find_shader_cache, list_shader_caches, ShaderCache};
+pub use install::{
+    compat_tools_dir, fetch_ge_release, install_ge_release, is_installed, normalize_ge_version,
+    GeRelease,
+};
+pub use locator::{compat_tool_search_dirs, list_proton_versions, locate_proton, locate_proton_chain};
+pub use prefix::{
+    backup_dir, backup_prefix, compat_environment, dir_size, find_compat_data_path, list_archives,
+    list_backups, list_prefixes, orphaned_prefixes, reset_prefix, resolve_compat_data_path, restore_prefix,
+    PrefixInfo,
+};
@@ -0,0 +1,360 @@
+use crate::config::expand_home;
+use crate::error::AppError;
+use crate::logs::timestamp;
+use crate::steam::{find_user_ids, get_library_folders, get_shortcuts_path, get_steam_root, read_shortcuts, Shortcut};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Find the compatdata directory (Proton prefix root) for an App ID
+///
+/// Prefers an existing compatdata directory in any library. Otherwise returns the
+/// location Steam would use: the library holding the game's appmanifest, or the
+/// main library if the game isn't installed.
+pub fn find_compat_data_path(app_id: u32) -> Result<PathBuf, AppError> {
+    let libraries = get_library_folders()?;
+
+    for steamapps in &libraries {
+        let candidate = steamapps.join("compatdata").join(app_id.to_string());
+        if candidate.is_dir() {
+            debug!("Found compatdata for {}: {}", app_id, candidate.display());
+            return Ok(candidate);
+        }
+    }
+
+    let manifest = format!("appmanifest_{}.acf", app_id);
+    let library = libraries
+        .iter()
+        .find(|steamapps| steamapps.join(&manifest).exists())
+        .or_else(|| libraries.first())
+        .ok_or_else(|| AppError::SteamNotFound(Vec::new()))?;
+
+    let path = library.join("compatdata").join(app_id.to_string());
+    debug!("No compatdata yet for {}, using: {}", app_id, path.display());
+    Ok(path)
+}
+
+/// compatdata directory Proton should use for a launch
+///
+/// Steam's STEAM_COMPAT_DATA_PATH wins, then `compat_data_path` from the game
+/// config ("~/" expands to home), then the App ID's compatdata in its Steam
+/// library. Outside Steam the prefix is one of our own, named after the App ID
+/// or, without one, a hash of the game executable's path. None when there is
+/// nothing to name it after.
+pub fn resolve_compat_data_path(configured: Option<&str>, app_id: Option<u32>, exe: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("STEAM_COMPAT_DATA_PATH") {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(path) = configured {
+        return Some(expand_home(path));
+    }
+    if let Some(path) = app_id.and_then(|id| find_compat_data_path(id).ok()) {
+        return Some(path);
+    }
+    let name = own_prefix_name(app_id, exe)?;
+    Some(
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from(".local/share"))
+            .join("steam-command-runner")
+            .join("prefixes")
+            .join(name),
+    )
+}
+
+/// Name of a prefix we provision ourselves: the App ID, else a hash of the executable
+fn own_prefix_name(app_id: Option<u32>, exe: Option<&str>) -> Option<String> {
+    if let Some(app_id) = app_id {
+        return Some(app_id.to_string());
+    }
+    let exe = exe?;
+    let path = fs::canonicalize(exe).unwrap_or_else(|_| PathBuf::from(exe));
+    Some(format!("{:08x}", crc32fast::hash(path.as_os_str().as_encoded_bytes())))
+}
+
+/// Environment Proton needs to run something in an app's prefix outside of Steam
+pub fn compat_environment(app_id: u32) -> Result<Vec<(String, String)>, AppError> {
+    let compat_data = find_compat_data_path(app_id)?;
+    let steam_root = get_steam_root().ok_or_else(|| AppError::SteamNotFound(Vec::new()))?;
+
+    Ok(vec![
+        (
+            "STEAM_COMPAT_DATA_PATH".to_string(),
+            compat_data.to_string_lossy().to_string(),
+        ),
+        (
+            "STEAM_COMPAT_CLIENT_INSTALL_PATH".to_string(),
+            steam_root.to_string_lossy().to_string(),
+        ),
+        ("SteamAppId".to_string(), app_id.to_string()),
+        ("SteamGameId".to_string(), app_id.to_string()),
+    ])
+}
+
+/// An existing Proton prefix
+#[derive(Debug, Clone)]
+pub struct PrefixInfo {
+    pub app_id: u32,
+    /// compatdata directory for the app
+    pub path: PathBuf,
+}
+
+/// List every compatdata prefix across all Steam libraries
+pub fn list_prefixes() -> Result<Vec<PrefixInfo>, AppError> {
+    let mut prefixes = Vec::new();
+
+    for steamapps in get_library_folders()? {
+        let Ok(entries) = fs::read_dir(steamapps.join("compatdata")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let app_id = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok());
+            if let (Some(app_id), true) = (app_id, path.is_dir()) {
+                prefixes.push(PrefixInfo { app_id, path });
+            }
+        }
+    }
+
+    prefixes.sort_by_key(|p| p.app_id);
+    Ok(prefixes)
+}
+
+/// Prefixes of apps with no appmanifest in any library and no non-Steam shortcut
+///
+/// These are left behind when Steam uninstalls a game but keeps its compatdata.
+/// A library or shortcuts.vdf that can't be read is an error rather than "not
+/// installed", since the answer decides which prefixes get deleted.
+pub fn orphaned_prefixes() -> Result<Vec<PrefixInfo>, AppError> {
+    let libraries = get_library_folders()?;
+    let mut shortcuts = HashSet::new();
+    for user_id in find_user_ids()? {
+        shortcuts.extend(read_shortcuts(get_shortcuts_path(user_id)?)?.iter().map(Shortcut::app_id));
+    }
+    Ok(list_prefixes()?
+        .into_iter()
+        .filter(|prefix| is_orphaned(prefix.app_id, &libraries, &shortcuts))
+        .collect())
+}
+
+/// Whether no library has an appmanifest for an app and no shortcut uses its ID
+///
+/// Only the manifest's presence counts, so a half-written one still keeps the prefix.
+/// compatdata/0 is shared by Steam itself and never an orphan.
+fn is_orphaned(app_id: u32, libraries: &[PathBuf], shortcuts: &HashSet<u32>) -> bool {
+    let manifest = format!("appmanifest_{}.acf", app_id);
+    app_id != 0
+        && !shortcuts.contains(&app_id)
+        && !libraries.iter().any(|steamapps| steamapps.join(&manifest).exists())
+}
+
+/// Total size in bytes of the files under a directory
+///
+/// Symlinks are counted but not followed, since a prefix's dosdevices links
+/// point at / and the home directory.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Find the existing compatdata directory for an app, failing if there is none
+fn existing_prefix(app_id: u32) -> Result<PathBuf, AppError> {
+    let path = find_compat_data_path(app_id)?;
+    if !path.is_dir() {
+        return Err(AppError::Prefix(format!(
+            "no prefix exists for app {} (expected {})",
+            app_id,
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+/// Directory prefix backups for an app are stored in
+pub fn backup_dir(app_id: u32) -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(".local/share"))
+        .join("steam-command-runner")
+        .join("prefix-backups")
+        .join(app_id.to_string())
+}
+
+/// Backups for an app, oldest first
+pub fn list_backups(app_id: u32) -> Vec<PathBuf> {
+    list_archives(&backup_dir(app_id))
+}
+
+/// The `.tar.zst` archives in a backup directory, oldest first
+pub fn list_archives(dir: &Path) -> Vec<PathBuf> {
+    let mut archives: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.to_string_lossy().ends_with(".tar.zst"))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Names are timestamps, so lexical order is chronological
+    archives.sort();
+    archives
+}
+
+/// Archive an app's prefix to a tar.zst file, returning the archive path
+///
+/// Symlinks (such as the drive letters in dosdevices) are stored as links, not followed.
+pub fn backup_prefix(app_id: u32, output: Option<PathBuf>) -> Result<PathBuf, AppError> {
+    let prefix = existing_prefix(app_id)?;
+    let output = match output {
+        Some(path) => path,
+        None => backup_dir(app_id).join(format!("{}.tar.zst", timestamp())),
+    };
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    info!("Backing up {} to {}", prefix.display(), output.display());
+    let partial = output.with_extension("zst.part");
+    if let Err(e) = write_archive(&prefix, &partial) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &output)?;
+
+    Ok(output)
+}
+
+/// Write a directory's contents to a tar.zst archive
+fn write_archive(dir: &Path, archive: &Path) -> Result<(), AppError> {
+    let encoder = zstd::Encoder::new(File::create(archive)?, 0)?.auto_finish();
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", dir)?;
+    builder.into_inner()?;
+    Ok(())
+}
+
+/// Extract a tar.zst archive into a directory
+fn unpack_archive(archive: &Path, dir: &Path) -> Result<(), AppError> {
+    let decoder = zstd::Decoder::new(File::open(archive)?)?;
+    tar::Archive::new(decoder).unpack(dir)?;
+    Ok(())
+}
+
+/// Replace an app's prefix with the contents of a backup
+///
+/// The archive is extracted next to the prefix first, so a failed restore leaves
+/// the current prefix untouched.
+pub fn restore_prefix(app_id: u32, archive: &Path) -> Result<PathBuf, AppError> {
+    if !archive.is_file() {
+        return Err(AppError::Prefix(format!(
+            "backup not found: {}",
+            archive.display()
+        )));
+    }
+
+    let prefix = find_compat_data_path(app_id)?;
+    let parent = prefix
+        .parent()
+        .ok_or_else(|| AppError::Prefix(format!("invalid prefix path {}", prefix.display())))?;
+    fs::create_dir_all(parent)?;
+
+    let staging = parent.join(format!(".{}.restoring", app_id));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    info!("Restoring {} from {}", prefix.display(), archive.display());
+    if let Err(e) = unpack_archive(archive, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    if prefix.exists() {
+        fs::remove_dir_all(&prefix)?;
+    }
+    fs::rename(&staging, &prefix)?;
+
+    Ok(prefix)
+}
+
+/// Delete an app's prefix so Proton recreates it on the next launch
+pub fn reset_prefix(app_id: u32) -> Result<PathBuf, AppError> {
+    let prefix = existing_prefix(app_id)?;
+    info!("Removing prefix {}", prefix.display());
+    fs::remove_dir_all(&prefix)?;
+    Ok(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_prefix_name() {
+        assert_eq!(own_prefix_name(Some(440), Some("/games/a.exe")).as_deref(), Some("440"));
+        let a = own_prefix_name(None, Some("/nonexistent/games/a.exe")).unwrap();
+        let b = own_prefix_name(None, Some("/nonexistent/games/b.exe")).unwrap();
+        assert_eq!(a.len(), 8);
+        assert_ne!(a, b);
+        assert_eq!(own_prefix_name(None, Some("/nonexistent/games/a.exe")), Some(a));
+        assert_eq!(own_prefix_name(None, None), None);
+    }
+
+    #[test]
+    fn test_is_orphaned() {
+        let dir = tempfile::tempdir().unwrap();
+        // Empty, as a manifest being written by Steam would be
+        fs::write(dir.path().join("appmanifest_440.acf"), "").unwrap();
+        let libraries = vec![dir.path().to_path_buf()];
+        let shortcuts = HashSet::from([3000000001]);
+
+        assert!(!is_orphaned(440, &libraries, &shortcuts));
+        assert!(!is_orphaned(3000000001, &libraries, &shortcuts));
+        assert!(!is_orphaned(0, &libraries, &shortcuts));
+        assert!(is_orphaned(570, &libraries, &shortcuts));
+    }
+
+    #[test]
+    fn test_dir_size_skips_symlinked_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("pfx/drive_c")).unwrap();
+        fs::write(dir.path().join("pfx/drive_c/save.dat"), b"progress").unwrap();
+        fs::create_dir_all(dir.path().join("pfx/dosdevices")).unwrap();
+        std::os::unix::fs::symlink("/", dir.path().join("pfx/dosdevices/z:")).unwrap();
+
+        // The 8-byte file plus the 1-byte link itself, not the filesystem behind it
+        assert_eq!(dir_size(dir.path()), 9);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_archive_round_trip_keeps_symlinks() {
+        let src = tempfile::tempdir().unwrap();
+        fs::create_dir_all(src.path().join("pfx/drive_c")).unwrap();
+        fs::write(src.path().join("pfx/drive_c/save.dat"), b"progress").unwrap();
+        fs::create_dir_all(src.path().join("pfx/dosdevices")).unwrap();
+        std::os::unix::fs::symlink("/", src.path().join("pfx/dosdevices/z:")).unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("backup.tar.zst");
+        write_archive(src.path(), &archive).unwrap();
+
+        let dest = out.path().join("restored");
+        unpack_archive(&archive, &dest).unwrap();
+        assert_eq!(fs::read(dest.join("pfx/drive_c/save.dat")).unwrap(), b"progress");
+        let link = dest.join("pfx/dosdevices/z:");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("/"));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::AppError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// State file in the app's compatdata directory listing verbs we have already installed
+const STATE_FILE: &str = "steam-command-runner-winetricks";
+
+fn state_path(compat_data: &Path) -> PathBuf {
+    compat_data.join(STATE_FILE)
+}
+
+/// Verbs recorded as installed in an app's prefix
+pub fn installed_verbs(compat_data: &Path) -> Vec<String> {
+    fs::read_to_string(state_path(compat_data))
+        .map(|content| parse_state(&content))
+        .unwrap_or_default()
+}
+
+fn parse_state(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Configured verbs that are not installed yet, in configuration order without duplicates
+pub fn pending_verbs(verbs: &[String], installed: &[String]) -> Vec<String> {
+    let mut pending: Vec<String> = Vec::new();
+    for verb in verbs {
+        let verb = verb.trim();
+        if !verb.is_empty()
+            && !installed.iter().any(|v| v == verb)
+            && !pending.iter().any(|v| v == verb)
+        {
+            pending.push(verb.to_string());
+        }
+    }
+    pending
+}
+
+fn record_verbs(compat_data: &Path, verbs: &[String]) -> Result<(), AppError> {
+    let mut all = installed_verbs(compat_data);
+    all.extend(pending_verbs(verbs, &all));
+    let mut content = String::from("# Winetricks verbs installed by steam-command-runner\n");
+    for verb in &all {
+        content.push_str(verb);
+        content.push('\n');
+    }
+    fs::write(state_path(compat_data), content)?;
+    Ok(())
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join(program).exists()))
+        .unwrap_or(false)
+}
+
+/// Wine binary shipped with a Proton build (newer builds use files/, older ones dist/)
+fn proton_wine(proton_path: &Path) -> Option<PathBuf> {
+    ["files", "dist"]
+        .iter()
+        .map(|dir| proton_path.join(dir).join("bin").join("wine"))
+        .find(|wine| wine.exists())
+}
+
+/// Build the command that installs `verbs` into the prefix
+///
+/// winetricks is preferred, driven by the same Proton's wine the game will run with;
+/// protontricks is the fallback, which works out the prefix and Proton from the app ID.
+fn winetricks_command(
+    app_id: Option<u32>,
+    compat_data: &Path,
+    proton_path: &Path,
+    verbs: &[String],
+) -> Result<Command, AppError> {
+    if in_path("winetricks") {
+        let mut command = Command::new("winetricks");
+        command
+            .args(["-q", "--unattended"])
+            .args(verbs)
+            .env("WINEPREFIX", compat_data.join("pfx"));
+        if let Some(wine) = proton_wine(proton_path) {
+            command
+                .env("WINE", &wine)
+                .env("WINESERVER", wine.with_file_name("wineserver"));
+        }
+        return Ok(command);
+    }
+
+    match app_id {
+        Some(app_id) if in_path("protontricks") => {
+            let mut command = Command::new("protontricks");
+            command.arg(app_id.to_string()).arg("-q").args(verbs);
+            Ok(command)
+        }
+        _ => Err(AppError::Winetricks(
+            "neither winetricks nor protontricks was found in PATH".to_string(),
+        )),
+    }
+}
+
+/// Install any configured winetricks verbs the prefix doesn't have yet
+///
+/// Successfully installed verbs are recorded in the compatdata directory, so each
+/// verb only runs once per prefix. Proton creates the prefix on first launch; until
+/// then there is nothing to install into and the verbs are left for the next launch.
+pub fn apply_winetricks(
+    app_id: Option<u32>,
+    compat_data: &Path,
+    proton_path: &Path,
+    verbs: &[String],
+) -> Result<(), AppError> {
+    let pending = pending_verbs(verbs, &installed_verbs(compat_data));
+    if pending.is_empty() {
+        debug!("All winetricks verbs already installed");
+        return Ok(());
+    }
+
+    if !compat_data.join("pfx").is_dir() {
+        warn!(
+            "Prefix {} does not exist yet; winetricks verbs ({}) will be installed on the next launch",
+            compat_data.display(),
+            pending.join(" ")
+        );
+        return Ok(());
+    }
+
+    info!("Installing winetricks verbs: {}", pending.join(" "));
+    let status = winetricks_command(app_id, compat_data, proton_path, &pending)?
+        .status()
+        .map_err(|e| AppError::Winetricks(e.to_string()))?;
+    if !status.success() {
+        return Err(AppError::Winetricks(format!(
+            "installing {} failed ({})",
+            pending.join(" "),
+            status
+        )));
+    }
+
+    record_verbs(compat_data, &pending)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verbs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pending_verbs() {
+        let state = parse_state("# header\nvcrun2019\n\n");
+        assert_eq!(state, verbs(&["vcrun2019"]));
+        assert_eq!(
+            pending_verbs(&verbs(&["vcrun2019", "dotnet48", "dotnet48", " "]), &state),
+            verbs(&["dotnet48"])
+        );
+        assert!(pending_verbs(&verbs(&["vcrun2019"]), &state).is_empty());
+    }
+}
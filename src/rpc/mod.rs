@@ -0,0 +1,202 @@
+//! JSON-RPC 2.0 server on a unix socket, used by `serve`
+//!
+//! Requests and responses are one JSON object per line, so a client (such as
+//! a Decky plugin backend) can keep one connection open for many calls.
+//! Requests without an `id` are notifications and get no response. Calls are
+//! handled one at a time, since most of them read and write config files.
+
+use crate::control::{private_dir, sockets_dir};
+use crate::error::AppError;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tracing::{debug, warn};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// Any error from the operation itself, with its message
+pub const SERVER_ERROR: i64 = -32000;
+
+/// An error to send back to the client
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(message: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, message.to_string())
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        Self::new(SERVER_ERROR, e.to_string())
+    }
+}
+
+/// Default socket path: next to the sessions' control sockets
+pub fn default_socket_path() -> PathBuf {
+    sockets_dir().join("rpc.sock")
+}
+
+/// Create the socket, replacing one left behind by a server that died
+///
+/// The socket's directory must belong to us, as for the control sockets, since
+/// any client that can connect can change launch options.
+pub fn bind(path: &Path) -> Result<UnixListener, AppError> {
+    if UnixStream::connect(path).is_ok() {
+        return Err(AppError::Rpc(format!("already serving on {}", path.display())));
+    }
+    if let Some(parent) = path.parent() {
+        private_dir(parent).map_err(|e| AppError::Rpc(e.to_string()))?;
+    }
+    let _ = fs::remove_file(path);
+    UnixListener::bind(path)
+        .map_err(|e| AppError::Rpc(format!("could not listen on {}: {}", path.display(), e)))
+}
+
+/// Answer calls on every connection until the process is stopped
+///
+/// `handler` gets the method name and its params (`null` when none were given).
+pub fn serve<F>(listener: UnixListener, handler: F)
+where
+    F: Fn(&str, Value) -> Result<Value, RpcError> + Send + 'static,
+{
+    let handler = Arc::new(Mutex::new(handler));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept RPC connection: {}", e);
+                continue;
+            }
+        };
+        let handler = Arc::clone(&handler);
+        thread::spawn(move || {
+            let result = answer(stream, |method, params| {
+                let handler = handler.lock().unwrap_or_else(|e| e.into_inner());
+                handler(method, params)
+            });
+            if let Err(e) = result {
+                debug!("RPC connection closed: {}", e);
+            }
+        });
+    }
+}
+
+/// Answer each line of one connection until the client hangs up
+fn answer<F>(stream: UnixStream, handler: F) -> Result<(), AppError>
+where
+    F: Fn(&str, Value) -> Result<Value, RpcError>,
+{
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&line, &handler) {
+            writeln!(writer, "{}", response)?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one request line, or None for a notification
+fn handle_line<F>(line: &str, handler: &F) -> Option<Value>
+where
+    F: Fn(&str, Value) -> Result<Value, RpcError>,
+{
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string())))),
+    };
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+    let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str)) else {
+        let error = RpcError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request object");
+        return Some(response(id.unwrap_or(Value::Null), Err(error)));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    debug!("RPC call {}", method);
+    let result = handler(method, params);
+    if let Err(e) = &result {
+        debug!("RPC call {} failed: {}", method, e.message);
+    }
+    id.map(|id| response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(stream: &mut UnixStream, reader: &mut BufReader<UnixStream>, request: &str) -> Value {
+        writeln!(stream, "{}", request).unwrap();
+        let mut reply = String::new();
+        reader.read_line(&mut reply).unwrap();
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    #[test]
+    fn test_serve() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rpc.sock");
+        let listener = bind(&path).unwrap();
+        thread::spawn(move || {
+            serve(listener, |method, params| match method {
+                "echo" => Ok(params),
+                "fail" => Err(RpcError::from(AppError::Rpc("broken".to_string()))),
+                _ => Err(RpcError::new(METHOD_NOT_FOUND, method)),
+            })
+        });
+        assert!(bind(&path).is_err());
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let reply = call(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":1,"method":"echo","params":[1,2]}"#);
+        assert_eq!(reply, json!({ "jsonrpc": "2.0", "id": 1, "result": [1, 2] }));
+
+        // A notification gets no reply, so the next line answered is the next call
+        writeln!(stream, r#"{{"jsonrpc":"2.0","method":"echo"}}"#).unwrap();
+        let reply = call(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":"a","method":"fail"}"#);
+        assert_eq!(reply["id"], "a");
+        assert_eq!(reply["error"]["code"], SERVER_ERROR);
+        assert_eq!(reply["error"]["message"], "RPC server error: broken");
+
+        let reply = call(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":2,"method":"nope"}"#);
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+
+        let reply = call(&mut stream, &mut reader, r#"{"id":3,"method":"echo"}"#);
+        assert_eq!(reply["id"], 3);
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+
+        let reply = call(&mut stream, &mut reader, "not json");
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::config::MergedConfig;
+use crate::steam::{game_install_dir, get_library_folders};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Anti-cheat systems Proton has a Steam-provided runtime for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiCheat {
+    EasyAntiCheat,
+    BattlEye,
+}
+
+impl AntiCheat {
+    const ALL: [AntiCheat; 2] = [AntiCheat::EasyAntiCheat, AntiCheat::BattlEye];
+
+    /// Lowercase prefix of the files and directories the anti-cheat ships in a game
+    fn marker(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "easyanticheat",
+            AntiCheat::BattlEye => "battleye",
+        }
+    }
+
+    /// Environment variable pointing Proton at the runtime
+    fn env_var(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "PROTON_EAC_RUNTIME",
+            AntiCheat::BattlEye => "PROTON_BATTLEYE_RUNTIME",
+        }
+    }
+
+    /// Directory of the runtime under steamapps/common
+    fn dir_name(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "Proton EasyAntiCheat Runtime",
+            AntiCheat::BattlEye => "Proton BattlEye Runtime",
+        }
+    }
+
+    /// App ID of the runtime, for installing it through Steam
+    fn app_id(self) -> u32 {
+        match self {
+            AntiCheat::EasyAntiCheat => 1826330,
+            AntiCheat::BattlEye => 1161040,
+        }
+    }
+}
+
+/// Anti-cheat systems a game ships, from files in the top three levels of its install directory
+///
+/// EasyAntiCheat and BattlEye keep their client in an `EasyAntiCheat`/`BattlEye`
+/// directory (or `EasyAntiCheat_x64.dll`-style files) beside the game's executable,
+/// which Unreal games keep in Binaries/Win64.
+pub fn detect_anticheat(game_dir: &Path) -> Vec<AntiCheat> {
+    let mut names = Vec::new();
+    collect_names(game_dir, 3, &mut names);
+    AntiCheat::ALL
+        .into_iter()
+        .filter(|ac| names.iter().any(|name| name.starts_with(ac.marker())))
+        .collect()
+}
+
+/// Lowercased entry names under a directory, down to `depth` levels
+fn collect_names(dir: &Path, depth: usize, names: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        names.push(entry.file_name().to_string_lossy().to_lowercase());
+        if depth > 1 && entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_names(&entry.path(), depth - 1, names);
+        }
+    }
+}
+
+/// Installed runtime directory, searching each library's steamapps directory
+fn find_runtime(libraries: &[PathBuf], anticheat: AntiCheat) -> Option<PathBuf> {
+    libraries
+        .iter()
+        .map(|steamapps| steamapps.join("common").join(anticheat.dir_name()))
+        .find(|path| path.is_dir())
+}
+
+/// PROTON_EAC_RUNTIME / PROTON_BATTLEYE_RUNTIME for the anti-cheat a game ships
+///
+/// Variables already set by Steam or in `[env]` are left alone. A missing runtime
+/// is only warned about: the game may still run offline.
+pub fn anticheat_env(config: &MergedConfig) -> Vec<(String, String)> {
+    let Some(game_dir) = config.app_id.and_then(game_install_dir) else {
+        return Vec::new();
+    };
+    let detected = detect_anticheat(&game_dir);
+    if detected.is_empty() {
+        return Vec::new();
+    }
+
+    let libraries = get_library_folders().unwrap_or_default();
+    let mut env = Vec::new();
+    for anticheat in detected {
+        let var = anticheat.env_var();
+        if std::env::var_os(var).is_some() || config.env.contains_key(var) {
+            debug!("{} already set, not changing it", var);
+            continue;
+        }
+        match find_runtime(&libraries, anticheat) {
+            Some(runtime) => {
+                info!("Game uses {:?}, setting {}={}", anticheat, var, runtime.display());
+                env.push((var.to_string(), runtime.to_string_lossy().to_string()));
+            }
+            None => warn!(
+                "Game uses {:?} but the {} isn't installed; online play will likely fail. \
+                 Install it with: steam steam://install/{}",
+                anticheat,
+                anticheat.dir_name(),
+                anticheat.app_id()
+            ),
+        }
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_anticheat() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Binaries/Win64/EasyAntiCheat")).unwrap();
+        assert_eq!(detect_anticheat(dir.path()), vec![AntiCheat::EasyAntiCheat]);
+
+        fs::create_dir_all(dir.path().join("BattlEye")).unwrap();
+        assert_eq!(
+            detect_anticheat(dir.path()),
+            vec![AntiCheat::EasyAntiCheat, AntiCheat::BattlEye]
+        );
+
+        let plain = tempfile::tempdir().unwrap();
+        fs::create_dir_all(plain.path().join("bin")).unwrap();
+        assert!(detect_anticheat(plain.path()).is_empty());
+    }
+
+    #[test]
+    fn test_find_runtime() {
+        let library = tempfile::tempdir().unwrap();
+        let libraries = vec![library.path().to_path_buf()];
+        assert_eq!(find_runtime(&libraries, AntiCheat::BattlEye), None);
+
+        let runtime = library.path().join("common/Proton BattlEye Runtime");
+        fs::create_dir_all(&runtime).unwrap();
+        assert_eq!(find_runtime(&libraries, AntiCheat::BattlEye), Some(runtime));
+    }
+}
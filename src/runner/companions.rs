@@ -0,0 +1,110 @@
+use crate::config::CompanionConfig;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How long companions get to exit after SIGTERM before they are killed
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Companion programs started for a game session
+pub struct Companions {
+    children: Vec<(String, Child)>,
+}
+
+impl Companions {
+    /// Start all configured companions in order
+    ///
+    /// A companion that fails to start is logged and skipped; it never blocks the game.
+    pub fn start(configs: &[CompanionConfig]) -> Self {
+        let mut children = Vec::new();
+
+        for companion in configs {
+            match spawn_companion(companion) {
+                Ok(child) => {
+                    info!("Started companion '{}' (pid {})", companion.command, child.id());
+                    children.push((companion.command.clone(), child));
+                }
+                Err(e) => warn!("Failed to start companion '{}': {}", companion.command, e),
+            }
+
+            if companion.delay_secs > 0 {
+                debug!("Waiting {}s before continuing", companion.delay_secs);
+                thread::sleep(Duration::from_secs(companion.delay_secs));
+            }
+        }
+
+        Self { children }
+    }
+
+    /// Stop all companions in reverse start order
+    ///
+    /// Each companion runs in its own process group, so helpers it spawned are
+    /// stopped too. SIGTERM is sent first, then SIGKILL after a grace period.
+    pub fn stop(&mut self) {
+        for (command, child) in self.children.iter_mut().rev() {
+            if let Ok(Some(_)) = child.try_wait() {
+                debug!("Companion '{}' already exited", command);
+                continue;
+            }
+
+            info!("Stopping companion '{}' (pid {})", command, child.id());
+            signal_group(child.id(), libc::SIGTERM);
+
+            let deadline = Instant::now() + STOP_GRACE_PERIOD;
+            loop {
+                match child.try_wait() {
+                    Ok(Some(_)) => break,
+                    Ok(None) if Instant::now() < deadline => {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                    _ => {
+                        warn!("Companion '{}' did not exit, killing it", command);
+                        signal_group(child.id(), libc::SIGKILL);
+                        let _ = child.wait();
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.children.clear();
+    }
+}
+
+impl Drop for Companions {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Spawn a single companion in its own process group
+fn spawn_companion(companion: &CompanionConfig) -> std::io::Result<Child> {
+    let args = shlex::split(&companion.command)
+        .filter(|args| !args.is_empty())
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "could not parse command")
+        })?;
+
+    let mut command = Command::new(&args[0]);
+    command
+        .args(&args[1..])
+        .envs(&companion.env)
+        .stdin(Stdio::null())
+        .process_group(0);
+
+    if let Some(ref dir) = companion.working_dir {
+        command.current_dir(dir);
+    }
+
+    command.spawn()
+}
+
+/// Send a signal to a whole process group led by `pid`
+fn signal_group(pid: u32, signal: libc::c_int) {
+    // SAFETY: kill() has no memory-safety preconditions; a negative pid targets the group
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
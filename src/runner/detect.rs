@@ -0,0 +1,305 @@
+use crate::config::ExecutionMode;
+use crate::steam::AppInfoCache;
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// How much of a shell script to scan for references to Windows executables
+const SCRIPT_SCAN_LIMIT: u64 = 64 * 1024;
+
+/// What kind of executable a file is, based on its contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryKind {
+    /// Linux ELF binary (32 or 64 bit)
+    Elf { bits: u8 },
+    /// Windows PE executable
+    Pe,
+    /// Interpreted script (`#!` line); `launches_windows` if it passes a .exe on for us to run
+    Script { launches_windows: bool },
+    /// Anything we can't identify
+    Unknown,
+}
+
+/// Identify an executable from its leading bytes
+fn classify_bytes(bytes: &[u8]) -> BinaryKind {
+    if bytes.len() >= 5 && bytes.starts_with(b"\x7fELF") {
+        let bits = match bytes[4] {
+            1 => 32,
+            _ => 64,
+        };
+        return BinaryKind::Elf { bits };
+    }
+
+    if bytes.starts_with(b"MZ") {
+        // e_lfanew at 0x3c points to the "PE\0\0" signature; fall back to treating
+        // any MZ executable as Windows if the header lies outside what we read
+        let is_pe = bytes
+            .get(0x3c..0x40)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .and_then(|offset| bytes.get(offset..offset + 4))
+            .map(|sig| sig == b"PE\0\0")
+            .unwrap_or(true);
+        if is_pe {
+            return BinaryKind::Pe;
+        }
+    }
+
+    if bytes.starts_with(b"#!") {
+        let text = String::from_utf8_lossy(bytes).to_lowercase();
+        return BinaryKind::Script { launches_windows: script_passes_exe(&text) };
+    }
+
+    BinaryKind::Unknown
+}
+
+/// Whether a script's commands pass a Windows executable on to be run
+///
+/// Only an explicit `.exe` argument counts, not one in a comment. A script that
+/// starts Wine or Proton itself already runs natively and is left to do so.
+fn script_passes_exe(text: &str) -> bool {
+    let words: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(|word| word.trim_matches(|c| matches!(c, '"' | '\'' | ';' | '&' | '(' | ')')))
+        .collect();
+    let runs_wine = words.iter().any(|word| {
+        let name = word.rsplit('/').next().unwrap_or(word);
+        name == "wine" || name == "wine64" || name == "proton" || name == "umu-run"
+    });
+    !runs_wine && words.iter().any(|word| word.ends_with(".exe"))
+}
+
+/// Read the start of a file and identify what kind of executable it is
+pub fn classify_file(path: &Path) -> BinaryKind {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return BinaryKind::Unknown,
+    };
+
+    let mut bytes = Vec::new();
+    if file.take(SCRIPT_SCAN_LIMIT).read_to_end(&mut bytes).is_err() {
+        return BinaryKind::Unknown;
+    }
+
+    classify_bytes(&bytes)
+}
+
+/// Find the single obvious executable in a directory (one .exe, or one executable file)
+pub fn find_single_executable(dir: &Path) -> Option<PathBuf> {
+    let files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+
+    let exes: Vec<&PathBuf> = files.iter().filter(|p| has_windows_extension(p)).collect();
+    if exes.len() == 1 {
+        return Some(exes[0].clone());
+    }
+
+    let executables: Vec<&PathBuf> = files
+        .iter()
+        .filter(|p| {
+            fs::metadata(p)
+                .map(|m| m.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+        .collect();
+    if executables.len() == 1 {
+        return Some(executables[0].clone());
+    }
+
+    None
+}
+
+/// Check for a Windows executable file extension
+fn has_windows_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            ext == "exe" || ext == "msi" || ext == "bat"
+        })
+        .unwrap_or(false)
+}
+
+/// Detect execution mode by inspecting the file, falling back to its extension
+pub fn detect_execution_mode(path: &str) -> ExecutionMode {
+    detect_from_file(path).unwrap_or_else(|| {
+        debug!("Could not identify executable, using Native mode");
+        ExecutionMode::Native
+    })
+}
+
+/// Detect execution mode for a Steam app
+///
+/// The file itself is checked first. When it can't be identified, the platforms
+/// Steam's app cache lists for the app decide: Windows-only games need Proton.
+pub fn detect_execution_mode_for_app(path: &str, app_id: Option<u32>) -> ExecutionMode {
+    if let Some(mode) = detect_from_file(path) {
+        return mode;
+    }
+
+    if let Some(app_id) = app_id {
+        match AppInfoCache::load().and_then(|cache| cache.get(app_id)) {
+            Ok(Some(app)) if app.is_windows_only() => {
+                debug!("App {} only supports Windows, using Proton mode", app_id);
+                return ExecutionMode::Proton;
+            }
+            Ok(Some(app)) => {
+                debug!("App {} platforms: {:?}", app_id, app.oslist);
+            }
+            Ok(None) => debug!("App {} not in Steam's app cache", app_id),
+            Err(e) => debug!("Could not read Steam's app cache: {}", e),
+        }
+    }
+
+    debug!("Could not identify executable, using Native mode");
+    ExecutionMode::Native
+}
+
+/// Work out the execution mode from the file's contents or extension, if possible
+fn detect_from_file(path: &str) -> Option<ExecutionMode> {
+    let mut target = PathBuf::from(path);
+
+    if target.is_dir() {
+        match find_single_executable(&target) {
+            Some(exe) => {
+                debug!("Directory contains single executable: {}", exe.display());
+                target = exe;
+            }
+            None => {
+                debug!("Could not pick an executable in directory");
+                return None;
+            }
+        }
+    }
+
+    match classify_file(&target) {
+        BinaryKind::Elf { bits } => {
+            debug!("Detected {}-bit ELF executable, using Native mode", bits);
+            Some(ExecutionMode::Native)
+        }
+        BinaryKind::Pe => {
+            debug!("Detected Windows PE executable, using Proton mode");
+            Some(ExecutionMode::Proton)
+        }
+        BinaryKind::Script { launches_windows: true } => {
+            debug!("Detected script launching a Windows executable, using Proton mode");
+            Some(ExecutionMode::Proton)
+        }
+        BinaryKind::Script { launches_windows: false } => {
+            debug!("Detected native script, using Native mode");
+            Some(ExecutionMode::Native)
+        }
+        BinaryKind::Unknown if has_windows_extension(&target) => {
+            debug!("Detected Windows executable by extension, using Proton mode");
+            Some(ExecutionMode::Proton)
+        }
+        BinaryKind::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe_header() -> Vec<u8> {
+        let mut bytes = vec![0u8; 0x84];
+        bytes[0] = b'M';
+        bytes[1] = b'Z';
+        bytes[0x3c] = 0x80;
+        bytes[0x80..0x84].copy_from_slice(b"PE\0\0");
+        bytes
+    }
+
+    #[test]
+    fn test_classify_elf() {
+        assert_eq!(
+            classify_bytes(b"\x7fELF\x02\x01\x01"),
+            BinaryKind::Elf { bits: 64 }
+        );
+        assert_eq!(
+            classify_bytes(b"\x7fELF\x01\x01\x01"),
+            BinaryKind::Elf { bits: 32 }
+        );
+    }
+
+    #[test]
+    fn test_classify_pe() {
+        assert_eq!(classify_bytes(&pe_header()), BinaryKind::Pe);
+    }
+
+    #[test]
+    fn test_classify_scripts() {
+        assert_eq!(
+            classify_bytes(b"#!/bin/sh\ncd \"$(dirname \"$0\")\"\nexec ./Game.x86_64 \"$@\"\n"),
+            BinaryKind::Script { launches_windows: false }
+        );
+        assert_eq!(
+            classify_bytes(b"#!/bin/bash\nexec \"$1\" Launcher.EXE --skip\n"),
+            BinaryKind::Script { launches_windows: true }
+        );
+        // Scripts that run Wine themselves, or only mention an .exe, are native
+        assert_eq!(
+            classify_bytes(b"#!/bin/sh\nexec /usr/bin/wine \"Game.exe\"\n"),
+            BinaryKind::Script { launches_windows: false }
+        );
+        assert_eq!(
+            classify_bytes(b"#!/bin/sh\n# Linux port of Game.exe, no wine needed\nexec ./Game.x86_64\n"),
+            BinaryKind::Script { launches_windows: false }
+        );
+    }
+
+    #[test]
+    fn test_detect_execution_mode_from_contents() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A Windows executable without its extension
+        let pe = dir.path().join("game");
+        fs::write(&pe, pe_header()).unwrap();
+        assert_eq!(detect_execution_mode(pe.to_str().unwrap()), ExecutionMode::Proton);
+
+        // A shell wrapper named like a native launcher
+        let script = dir.path().join("start.sh");
+        fs::write(&script, "#!/bin/sh\nexec \"$@\" game.exe\n").unwrap();
+        assert_eq!(
+            detect_execution_mode(script.to_str().unwrap()),
+            ExecutionMode::Proton
+        );
+
+        // A launcher that brings its own Wine
+        let wine_script = dir.path().join("play.sh");
+        fs::write(&wine_script, "#!/bin/sh\nWINEPREFIX=\"$PWD/pfx\" wine game.exe\n").unwrap();
+        assert_eq!(
+            detect_execution_mode(wine_script.to_str().unwrap()),
+            ExecutionMode::Native
+        );
+    }
+
+    #[test]
+    fn test_detect_execution_mode_extension_fallback() {
+        assert_eq!(
+            detect_execution_mode("/nonexistent/setup.msi"),
+            ExecutionMode::Proton
+        );
+        assert_eq!(
+            detect_execution_mode("/nonexistent/game.x86_64"),
+            ExecutionMode::Native
+        );
+    }
+
+    #[test]
+    fn test_detect_execution_mode_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("readme.txt"), "hello").unwrap();
+        fs::write(dir.path().join("Game.exe"), pe_header()).unwrap();
+        assert_eq!(
+            detect_execution_mode(dir.path().to_str().unwrap()),
+            ExecutionMode::Proton
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::config::MergedConfig;
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// IPC opcodes
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+/// How long to wait for Discord to answer a request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Encode an IPC frame: opcode and payload length (little-endian u32s), then JSON
+fn encode_frame(opcode: u32, payload: &Value) -> Vec<u8> {
+    let body = payload.to_string();
+    let mut frame = Vec::with_capacity(8 + body.len());
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(body.as_bytes());
+    frame
+}
+
+/// Candidate IPC sockets: native, Flatpak and Snap installs, discord-ipc-0 to 9 each
+fn socket_paths() -> Vec<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    let dirs = [
+        runtime_dir.clone(),
+        runtime_dir.join("app/com.discordapp.Discord"),
+        runtime_dir.join("snap.discord"),
+    ];
+    dirs.iter()
+        .flat_map(|dir| (0..10).map(move |i| dir.join(format!("discord-ipc-{}", i))))
+        .collect()
+}
+
+/// Rich Presence for the running game, shown until the session ends
+///
+/// Discord drops the activity when the connection closes, so the presence lasts
+/// exactly as long as this value.
+pub struct DiscordPresence {
+    stream: Option<UnixStream>,
+}
+
+impl DiscordPresence {
+    /// Connect to Discord and publish the game, if `[discord]` is enabled
+    ///
+    /// Discord not running (or rejecting us) is logged and never blocks the game.
+    pub fn start(config: &MergedConfig) -> Self {
+        if !config.discord.is_enabled() {
+            return Self { stream: None };
+        }
+        let Some(client_id) = config.discord.client_id.as_deref() else {
+            warn!("[discord] is enabled but client_id is not set");
+            return Self { stream: None };
+        };
+
+        match connect(client_id).and_then(|mut stream| {
+            request(&mut stream, set_activity(Some(activity(config))))?;
+            Ok(stream)
+        }) {
+            Ok(stream) => {
+                info!("Published Discord Rich Presence");
+                Self { stream: Some(stream) }
+            }
+            Err(e) => {
+                debug!("Discord Rich Presence unavailable: {}", e);
+                Self { stream: None }
+            }
+        }
+    }
+
+    /// Clear the presence and disconnect
+    pub fn clear(&mut self) {
+        if let Some(mut stream) = self.stream.take() {
+            if let Err(e) = request(&mut stream, set_activity(None)) {
+                debug!("Failed to clear Discord Rich Presence: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for DiscordPresence {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Connect to the first Discord IPC socket that answers the handshake
+fn connect(client_id: &str) -> io::Result<UnixStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no Discord IPC socket found");
+    for path in socket_paths() {
+        let Ok(mut stream) = UnixStream::connect(&path) else {
+            continue;
+        };
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        let handshake = json!({ "v": 1, "client_id": client_id });
+        match stream
+            .write_all(&encode_frame(OP_HANDSHAKE, &handshake))
+            .and_then(|_| read_frame(&mut stream))
+        {
+            Ok((OP_FRAME, _)) => {
+                debug!("Connected to Discord at {}", path.display());
+                return Ok(stream);
+            }
+            Ok((_, reply)) => {
+                last_error = io::Error::other(format!("handshake rejected: {}", reply));
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Send a command frame and wait for Discord's reply
+fn request(stream: &mut UnixStream, payload: Value) -> io::Result<()> {
+    stream.write_all(&encode_frame(OP_FRAME, &payload))?;
+    let (_, reply) = read_frame(stream)?;
+    if reply["evt"] == "ERROR" {
+        return Err(io::Error::other(reply["data"]["message"].to_string()));
+    }
+    Ok(())
+}
+
+fn read_frame(stream: &mut UnixStream) -> io::Result<(u32, Value)> {
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header)?;
+    let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    let value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    Ok((opcode, value))
+}
+
+fn set_activity(activity: Option<Value>) -> Value {
+    json!({
+        "cmd": "SET_ACTIVITY",
+        "args": { "pid": std::process::id(), "activity": activity },
+        "nonce": format!("{}-{}", std::process::id(), crate::playtime::now()),
+    })
+}
+
+/// The activity shown for the game: its name and elapsed time
+fn activity(config: &MergedConfig) -> Value {
+    let details = match (&config.name, config.app_id) {
+        (Some(name), _) => name.clone(),
+        (None, Some(app_id)) => format!("App {}", app_id),
+        (None, None) => "Playing a game".to_string(),
+    };
+    let mut activity = json!({
+        "details": details,
+        "timestamps": { "start": crate::playtime::now() },
+    });
+    if let Some(state) = &config.discord.state {
+        activity["state"] = json!(state);
+    }
+    if let Some(image) = &config.discord.large_image {
+        activity["assets"] = json!({ "large_image": image, "large_text": details });
+    }
+    activity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let frame = encode_frame(OP_HANDSHAKE, &json!({ "v": 1 }));
+        assert_eq!(&frame[..4], &[0, 0, 0, 0]);
+        assert_eq!(&frame[4..8], &[7, 0, 0, 0]);
+        assert_eq!(&frame[8..], br#"{"v":1}"#);
+    }
+}
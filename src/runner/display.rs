@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Resolution and refresh rate of a connected display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    /// Rounded to whole Hz; None when only the resolution is known
+    pub refresh: Option<u32>,
+}
+
+/// Mode of the display games will show up on
+///
+/// Asks X RandR (XWayland on Wayland desktops) for the primary output's current
+/// mode, and falls back to the preferred mode of the first connected DRM
+/// connector, which has no refresh rate.
+pub fn current_display_mode() -> Option<DisplayMode> {
+    let mode = xrandr_mode().or_else(|| drm_mode(Path::new("/sys/class/drm")));
+    debug!("Display mode: {:?}", mode);
+    mode
+}
+
+fn xrandr_mode() -> Option<DisplayMode> {
+    std::env::var_os("DISPLAY")?;
+    let output = Command::new("xrandr")
+        .arg("--current")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_xrandr(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Current mode of the primary output, or of the first output that has one
+///
+/// Outputs are lines like "DP-1 connected primary 2560x1440+0+0 ...", followed
+/// by indented modes; the current one carries a `*` after its rate.
+fn parse_xrandr(text: &str) -> Option<DisplayMode> {
+    let mut primary = None;
+    let mut first = None;
+    let mut in_primary = false;
+
+    for line in text.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            in_primary = line.contains(" connected primary");
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some((width, height)) = words.next().and_then(parse_size) else {
+            continue;
+        };
+        let Some(rate) = words.find(|w| w.contains('*')) else {
+            continue;
+        };
+        let refresh = rate
+            .trim_end_matches(['*', '+'])
+            .parse::<f64>()
+            .ok()
+            .map(|hz| hz.round() as u32);
+        let mode = DisplayMode { width, height, refresh };
+        if in_primary && primary.is_none() {
+            primary = Some(mode);
+        }
+        first = first.or(Some(mode));
+    }
+    primary.or(first)
+}
+
+/// Preferred mode of the first connected connector in /sys/class/drm
+fn drm_mode(drm: &Path) -> Option<DisplayMode> {
+    let mut connectors: Vec<_> = fs::read_dir(drm)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("status").exists())
+        .collect();
+    connectors.sort();
+
+    connectors.iter().find_map(|connector| {
+        let status = fs::read_to_string(connector.join("status")).ok()?;
+        if status.trim() != "connected" {
+            return None;
+        }
+        // The kernel lists the preferred mode first
+        let modes = fs::read_to_string(connector.join("modes")).ok()?;
+        let (width, height) = parse_size(modes.lines().next()?)?;
+        Some(DisplayMode {
+            width,
+            height,
+            refresh: None,
+        })
+    })
+}
+
+/// Parse "2560x1440", ignoring a trailing "i" for interlaced modes
+fn parse_size(text: &str) -> Option<(u32, u32)> {
+    let (width, height) = text.split_once('x')?;
+    let height = height.trim_end_matches('i');
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xrandr() {
+        let text = "Screen 0: minimum 16 x 16, current 6400 x 2160, maximum 32767 x 32767
+HDMI-A-1 connected 3840x2160+2560+0 (normal left inverted right x axis y axis) 600mm x 340mm
+   3840x2160     60.00*+  30.00
+   1920x1080     60.00
+DP-1 connected primary 2560x1440+0+0 (normal left inverted right x axis y axis) 600mm x 340mm
+   2560x1440     59.95 +  143.97*
+   1920x1080     60.00
+DP-2 disconnected (normal left inverted right x axis y axis)
+";
+        assert_eq!(
+            parse_xrandr(text),
+            Some(DisplayMode {
+                width: 2560,
+                height: 1440,
+                refresh: Some(144)
+            })
+        );
+
+        let no_primary = text.replace(" primary", "");
+        assert_eq!(
+            parse_xrandr(&no_primary),
+            Some(DisplayMode {
+                width: 3840,
+                height: 2160,
+                refresh: Some(60)
+            })
+        );
+        assert_eq!(parse_xrandr("Screen 0: minimum 16 x 16\n"), None);
+    }
+
+    #[test]
+    fn test_drm_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let connector = |name: &str, status: &str, modes: &str| {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("status"), status).unwrap();
+            fs::write(path.join("modes"), modes).unwrap();
+        };
+        connector("card0-DP-1", "disconnected\n", "");
+        connector("card0-HDMI-A-1", "connected\n", "3840x2160\n1920x1080\n");
+        fs::create_dir_all(dir.path().join("card0")).unwrap();
+
+        assert_eq!(
+            drm_mode(dir.path()),
+            Some(DisplayMode {
+                width: 3840,
+                height: 2160,
+                refresh: None
+            })
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::config::MergedConfig;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Object path and interface of our D-Bus signals
+const OBJECT_PATH: &str = "/io/github/steamcommandrunner";
+const INTERFACE: &str = "io.github.steamcommandrunner";
+
+/// A D-Bus signal argument
+enum Arg<'a> {
+    U32(u32),
+    I32(i32),
+    Str(&'a str),
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join(program).exists()))
+        .unwrap_or(false)
+}
+
+/// Build the command that emits a signal on the session bus
+///
+/// busctl (part of systemd) is preferred, with dbus-send as the fallback.
+fn emit_command(member: &str, args: &[Arg]) -> Option<Command> {
+    if in_path("busctl") {
+        let signature: String = args
+            .iter()
+            .map(|arg| match arg {
+                Arg::U32(_) => 'u',
+                Arg::I32(_) => 'i',
+                Arg::Str(_) => 's',
+            })
+            .collect();
+        let mut command = Command::new("busctl");
+        command
+            .args(["--user", "emit", OBJECT_PATH, INTERFACE, member, &signature])
+            .args(args.iter().map(|arg| match arg {
+                Arg::U32(v) => v.to_string(),
+                Arg::I32(v) => v.to_string(),
+                Arg::Str(s) => s.to_string(),
+            }));
+        return Some(command);
+    }
+
+    if in_path("dbus-send") {
+        let mut command = Command::new("dbus-send");
+        command
+            .args(["--session", "--type=signal", OBJECT_PATH])
+            .arg(format!("{}.{}", INTERFACE, member))
+            .args(args.iter().map(|arg| match arg {
+                Arg::U32(v) => format!("uint32:{}", v),
+                Arg::I32(v) => format!("int32:{}", v),
+                Arg::Str(s) => format!("string:{}", s),
+            }));
+        return Some(command);
+    }
+    None
+}
+
+fn emit(config: &MergedConfig, member: &str, args: &[Arg]) {
+    if !config.dbus_signals {
+        return;
+    }
+    let Some(mut command) = emit_command(member, args) else {
+        debug!("Neither busctl nor dbus-send found; not emitting {}", member);
+        return;
+    };
+    match command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+        Ok(status) if status.success() => debug!("Emitted D-Bus signal {}", member),
+        Ok(status) => debug!("Emitting D-Bus signal {} failed ({})", member, status),
+        Err(e) => debug!("Emitting D-Bus signal {} failed: {}", member, e),
+    }
+}
+
+/// Emit GameStarted(app_id, name, pid); app_id is 0 when unknown
+pub fn game_started(config: &MergedConfig, pid: u32) {
+    let name = config.name.as_deref().unwrap_or("");
+    emit(
+        config,
+        "GameStarted",
+        &[Arg::U32(config.app_id.unwrap_or(0)), Arg::Str(name), Arg::I32(pid as i32)],
+    );
+}
+
+/// Emit GameStopped(app_id, name, exit_code)
+pub fn game_stopped(config: &MergedConfig, exit_code: u8) {
+    let name = config.name.as_deref().unwrap_or("");
+    emit(
+        config,
+        "GameStopped",
+        &[Arg::U32(config.app_id.unwrap_or(0)), Arg::Str(name), Arg::I32(exit_code as i32)],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_command() {
+        let Some(command) = emit_command("GameStarted", &[Arg::U32(440), Arg::Str("TF2"), Arg::I32(7)]) else {
+            return;
+        };
+        let args: Vec<String> = command.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        match command.get_program().to_str() {
+            Some("busctl") => assert_eq!(
+                args,
+                ["--user", "emit", OBJECT_PATH, INTERFACE, "GameStarted", "usi", "440", "TF2", "7"]
+            ),
+            _ => assert_eq!(args[3..], ["uint32:440", "string:TF2", "int32:7"]),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use super::supervisor::needs_supervision;
+use crate::config::{ExecutionMode, MergedConfig};
+use crate::logs::logs_dir;
+use crate::playtime::playtime_path;
+use crate::saves::saves_backup_dir;
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::process::Command;
+
+/// Quote a word for display in a copy-pasteable shell command line
+fn quote(word: &OsStr) -> String {
+    let word = word.to_string_lossy();
+    shlex::try_quote(&word)
+        .map(|q| q.into_owned())
+        .unwrap_or_else(|_| format!("{:?}", word))
+}
+
+/// Describe everything a launch would do, without doing it
+///
+/// Used by `run --dry-run` and `explain`. The command line and environment come
+/// from the fully prepared process, so they match what would be exec'd exactly.
+pub fn describe_launch(config: &MergedConfig, mode: ExecutionMode, process: &Command) -> String {
+    let mut out = String::new();
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    let app_id = config.app_id.map_or("(none)".to_string(), |id| id.to_string());
+    let _ = writeln!(out, "App ID:    {}", app_id);
+    if let Some(name) = &config.name {
+        let _ = writeln!(out, "Name:      {}", name);
+    }
+    if let Some(profile) = &config.profile {
+        let _ = writeln!(out, "Profile:   {}", profile);
+    }
+    let _ = writeln!(out, "Mode:      {:?}", mode);
+    if config.is_steam_deck {
+        let _ = writeln!(out, "Device:    Steam Deck");
+    }
+    let _ = writeln!(
+        out,
+        "Gamescope: {} (inside gamescope session: {})",
+        yes_no(config.gamescope_enabled),
+        yes_no(config.is_gamescope_session)
+    );
+
+    let command: Vec<String> = std::iter::once(process.get_program())
+        .chain(process.get_args())
+        .map(quote)
+        .collect();
+    let _ = writeln!(out, "\nCommand:\n  {}", command.join(" "));
+
+    let mut envs: Vec<(String, Option<String>)> = process
+        .get_envs()
+        .map(|(k, v)| {
+            (
+                k.to_string_lossy().to_string(),
+                v.map(|v| v.to_string_lossy().to_string()),
+            )
+        })
+        .collect();
+    envs.sort();
+    let _ = writeln!(out, "\nEnvironment (on top of the inherited environment):");
+    if envs.is_empty() {
+        let _ = writeln!(out, "  (none)");
+    }
+    for (key, value) in envs {
+        match value {
+            Some(value) => {
+                let _ = writeln!(out, "  {}={}", key, quote(OsStr::new(&value)));
+            }
+            None => {
+                let _ = writeln!(out, "  unset {}", key);
+            }
+        }
+    }
+    if let Some(dir) = process.get_current_dir() {
+        let _ = writeln!(out, "\nWorking directory:\n  {}", dir.display());
+    }
+
+    let _ = writeln!(out, "\nLaunch:");
+    if matches!(mode, ExecutionMode::Proton | ExecutionMode::Umu) && !config.winetricks.is_empty() {
+        let _ = writeln!(
+            out,
+            "  winetricks verbs (installed once per prefix): {}",
+            config.winetricks.join(" ")
+        );
+    }
+    if mode == ExecutionMode::Wine {
+        let _ = writeln!(
+            out,
+            "  Wine prefix: {}",
+            config.wine.prefix_path(config.app_id).display()
+        );
+    }
+    if mode == ExecutionMode::Umu {
+        let _ = writeln!(out, "  game runs through umu-run (protonfixes applied)");
+    }
+    for hook in &config.pre_launch_hooks {
+        let _ = writeln!(out, "  pre_launch hook: {}", hook.command);
+    }
+    for companion in &config.companions {
+        let _ = writeln!(out, "  companion: {}", companion.command);
+    }
+    let supervised = needs_supervision(config);
+    let _ = writeln!(
+        out,
+        "  {}",
+        if supervised {
+            "game runs supervised (steam-command-runner waits for it to exit)"
+        } else {
+            "steam-command-runner exec()s into the game"
+        }
+    );
+    if config.gamescope_fallback && config.gamescope_enabled && !config.is_gamescope_session {
+        let _ = writeln!(out, "  relaunched without gamescope if it fails to start (runs supervised)");
+    }
+    for hook in &config.post_exit_hooks {
+        let note = if supervised { "" } else { " (not run: requires supervise = true)" };
+        let _ = writeln!(out, "  post_exit hook: {}{}", hook.command, note);
+    }
+    for hook in &config.on_crash_hooks {
+        let note = if supervised { "" } else { " (not run: requires supervise = true)" };
+        let _ = writeln!(out, "  on_crash hook: {}{}", hook.command, note);
+    }
+    if config.restart_on_crash.enabled {
+        let _ = writeln!(
+            out,
+            "  restarted after a crash (up to {} times, {}s backoff)",
+            config.restart_on_crash.max_retries, config.restart_on_crash.backoff_secs
+        );
+    }
+    if config.track_playtime {
+        let _ = writeln!(out, "  play session recorded in {}", playtime_path().display());
+    }
+    if let Some(app_id) = config.app_id.filter(|_| config.saves.as_ref().is_some_and(|s| s.backup_on_exit)) {
+        let _ = writeln!(out, "  saves backed up to {} when the game exits", saves_backup_dir(app_id).display());
+    }
+    if let Some(saves) = config.saves.as_ref() {
+        let with = match (&saves.sync_command, &saves.sync_target) {
+            (Some(command), _) => Some(command),
+            (None, Some(target)) => Some(target),
+            (None, None) => None,
+        };
+        if let Some(with) = with {
+            let _ = writeln!(out, "  saves synced with {} (pulled before launch, pushed after exit)", with);
+        }
+    }
+    if config.dbus_signals {
+        let _ = writeln!(out, "  GameStarted/GameStopped D-Bus signals emitted");
+    }
+    if config.inhibit_sleep {
+        let _ = writeln!(out, "  idle and sleep inhibited (systemd-inhibit) while the game runs");
+    }
+    if let Some(profile) = &config.power_profile {
+        let _ = writeln!(out, "  power profile '{}' (previous profile restored on exit)", profile);
+    }
+    if config.discord.is_enabled() {
+        let _ = writeln!(out, "  shown in Discord Rich Presence while running");
+    }
+    if let Some(runtime) = config.steam_runtime.dir_name().filter(|_| mode == ExecutionMode::Native) {
+        let _ = writeln!(out, "  game runs in the {} container", runtime);
+    }
+    if config.sandbox.is_enabled() {
+        let _ = writeln!(
+            out,
+            "  game runs in a bubblewrap sandbox (network: {})",
+            yes_no(config.sandbox.network.unwrap_or(true))
+        );
+    }
+    if let Some(max) = &config.memory_max {
+        let _ = writeln!(out, "  memory limit: {}", max);
+    }
+    if let Some(quota) = &config.limits.cpu_quota {
+        let _ = writeln!(out, "  CPU quota: {}", quota);
+    }
+    if let Some(weight) = config.limits.io_weight {
+        let _ = writeln!(out, "  I/O weight: {}", weight);
+    }
+    if config.limits.scope == Some(true) {
+        let _ = writeln!(out, "  leftover processes are stopped with the game's systemd scope");
+    }
+    if config.mangohud.is_enabled() {
+        let _ = writeln!(out, "  MangoHud enabled (config generated from [mangohud])");
+    }
+    if let Some(gpu) = &config.gpu {
+        let _ = writeln!(out, "  GPU: {:?}", gpu);
+    }
+    if let Some(cpus) = &config.cpu_affinity {
+        let _ = writeln!(out, "  pinned to CPUs: {}", cpus);
+    }
+    if let Some(nice) = config.nice {
+        let _ = writeln!(out, "  nice: {}", nice);
+    }
+    if let Some(ionice) = &config.ionice {
+        let _ = writeln!(out, "  ionice: {}", ionice);
+    }
+    if let Some(score) = config.oom_score_adj {
+        let _ = writeln!(out, "  oom_score_adj: {}", score);
+    }
+    if config.logging.capture_game_output {
+        let _ = writeln!(out, "  game output captured to {}", logs_dir().display());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GlobalConfig, HostFacts};
+
+    #[test]
+    fn test_describe_launch() {
+        let config = MergedConfig::merge(
+            GlobalConfig::default(),
+            None,
+            false,
+            Some(440),
+            &HostFacts::detect(),
+            None,
+        );
+        let mut process = Command::new("/games/tf 2/hl2.sh");
+        process.args(["-game", "tf"]).env("DXVK_HUD", "fps").env_remove("LD_PRELOAD");
+
+        let text = describe_launch(&config, ExecutionMode::Native, &process);
+        assert!(text.contains("App ID:    440"));
+        assert!(text.contains("Mode:      Native"));
+        assert!(text.contains("  '/games/tf 2/hl2.sh' -game tf\n"));
+        assert!(text.contains("  DXVK_HUD=fps\n"));
+        assert!(text.contains("  unset LD_PRELOAD\n"));
+        assert!(text.contains("exec()s into the game"));
+    }
+}
@@ -0,0 +1,213 @@
+use super::display::{current_display_mode, DisplayMode};
+use super::gamescope_flags::{filter_args, installed_flags};
+use crate::config::{GamescopeOptions, HdrSetting, MergedConfig};
+use crate::error::AppError;
+use tracing::warn;
+
+/// Builder for gamescope command-line arguments
+#[derive(Debug, Default, Clone)]
+pub struct GamescopeArgs {
+    args: Vec<String>,
+}
+
+impl GamescopeArgs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Game width in pixels
+    pub fn width(self, width: u32) -> Self {
+        self.flag_value("-w", width)
+    }
+
+    /// Game height in pixels
+    pub fn height(self, height: u32) -> Self {
+        self.flag_value("-h", height)
+    }
+
+    /// Frame rate limit
+    pub fn refresh(self, refresh: u32) -> Self {
+        self.flag_value("-r", refresh)
+    }
+
+    /// Output (window or screen) width in pixels
+    pub fn output_width(self, width: u32) -> Self {
+        self.flag_value("-W", width)
+    }
+
+    /// Output (window or screen) height in pixels
+    pub fn output_height(self, height: u32) -> Self {
+        self.flag_value("-H", height)
+    }
+
+    /// Upscale with AMD FSR
+    pub fn fsr(self) -> Self {
+        self.flag_value("-F", "fsr")
+    }
+
+    /// Enable HDR output
+    pub fn hdr(self) -> Self {
+        self.flag("--hdr-enabled")
+    }
+
+    /// Start fullscreen
+    pub fn fullscreen(self) -> Self {
+        self.flag("-f")
+    }
+
+    /// Append raw arguments as-is
+    pub fn raw<I: IntoIterator<Item = String>>(mut self, args: I) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    /// Append a shell-quoted argument string
+    pub fn raw_str(self, args: &str) -> Result<Self, AppError> {
+        let parsed =
+            shlex::split(args).ok_or_else(|| AppError::GamescopeArgsParse(args.to_string()))?;
+        Ok(self.raw(parsed))
+    }
+
+    /// Apply every set field of the typed options
+    pub fn options(mut self, options: &GamescopeOptions) -> Result<Self, AppError> {
+        if let Some(width) = options.width {
+            self = self.width(width);
+        }
+        if let Some(height) = options.height {
+            self = self.height(height);
+        }
+        if let Some(refresh) = options.refresh {
+            self = self.refresh(refresh);
+        }
+        if options.fsr == Some(true) {
+            self = self.fsr();
+        }
+        if options.hdr == Some(HdrSetting::Enabled(true)) {
+            self = self.hdr();
+        }
+        if options.fullscreen == Some(true) {
+            self = self.fullscreen();
+        }
+        if let Some(extra) = &options.extra_args {
+            self = self.raw_str(extra)?;
+        }
+        Ok(self)
+    }
+
+    /// Size the output to a display mode, and use its refresh rate unless one is set
+    pub fn display_mode(mut self, mode: DisplayMode, options: &GamescopeOptions) -> Self {
+        self = self.output_width(mode.width).output_height(mode.height);
+        match (mode.refresh, options.refresh) {
+            (Some(refresh), None) => self.refresh(refresh),
+            _ => self,
+        }
+    }
+
+    pub fn build(self) -> Vec<String> {
+        self.args
+    }
+
+    fn flag(mut self, flag: &str) -> Self {
+        self.args.push(flag.to_string());
+        self
+    }
+
+    fn flag_value(mut self, flag: &str, value: impl ToString) -> Self {
+        self.args.push(flag.to_string());
+        self.args.push(value.to_string());
+        self
+    }
+}
+
+/// Gamescope arguments for a config: typed options first, then the raw `args` string
+///
+/// Returns None when neither is configured, meaning gamescope shouldn't wrap the game.
+/// Arguments the installed gamescope doesn't accept are translated or left out.
+pub fn gamescope_args_for(config: &MergedConfig) -> Result<Option<Vec<String>>, AppError> {
+    if config.gamescope.is_empty() && config.gamescope_args.is_none() {
+        return Ok(None);
+    }
+
+    let mut builder = GamescopeArgs::new().options(&config.gamescope)?;
+    if config.gamescope.auto_resolution == Some(true) {
+        match current_display_mode() {
+            Some(mode) => builder = builder.display_mode(mode, &config.gamescope),
+            None => warn!("auto_resolution is on, but no connected display was found"),
+        }
+    }
+    if let Some(raw) = &config.gamescope_args {
+        builder = builder.raw_str(raw)?;
+    }
+    // gamescope draws MangoHud itself; a mangohud wrapper inside it would not show
+    if config.mangohud.is_enabled() {
+        builder = builder.flag("--mangoapp");
+    }
+    let args = match installed_flags() {
+        Some(flags) => filter_args(builder.build(), flags),
+        None => builder.build(),
+    };
+    Ok(Some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_serializes_options_in_order() {
+        let options = GamescopeOptions {
+            width: Some(1920),
+            height: Some(1080),
+            refresh: Some(60),
+            fsr: Some(true),
+            hdr: Some(HdrSetting::Enabled(false)),
+            fullscreen: Some(true),
+            auto_resolution: None,
+            extra_args: Some("--adaptive-sync --cursor '/tmp/my cursor.png'".to_string()),
+        };
+        let args = GamescopeArgs::new().options(&options).unwrap().build();
+        assert_eq!(
+            args,
+            vec![
+                "-w",
+                "1920",
+                "-h",
+                "1080",
+                "-r",
+                "60",
+                "-F",
+                "fsr",
+                "-f",
+                "--adaptive-sync",
+                "--cursor",
+                "/tmp/my cursor.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_display_mode() {
+        let mode = DisplayMode {
+            width: 3840,
+            height: 2160,
+            refresh: Some(120),
+        };
+        let args = |options: &GamescopeOptions| GamescopeArgs::new().display_mode(mode, options).build();
+        assert_eq!(args(&GamescopeOptions::default()), vec!["-W", "3840", "-H", "2160", "-r", "120"]);
+
+        let capped = GamescopeOptions {
+            refresh: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(args(&capped), vec!["-W", "3840", "-H", "2160"]);
+    }
+
+    #[test]
+    fn test_builder_rejects_unbalanced_quotes() {
+        let options = GamescopeOptions {
+            extra_args: Some("--cursor 'oops".to_string()),
+            ..Default::default()
+        };
+        assert!(GamescopeArgs::new().options(&options).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::shim::gamescope::find_real_gamescope;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, warn};
+
+/// Flags the installed gamescope accepts, read from its `--help` output
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamescopeFlags {
+    /// First line of `gamescope --version`
+    pub version: Option<String>,
+    pub flags: HashSet<String>,
+}
+
+impl GamescopeFlags {
+    pub fn supports(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+/// What the flag cache was built from, so a gamescope update invalidates it
+#[derive(Debug, Serialize, Deserialize)]
+struct FlagCache {
+    path: PathBuf,
+    modified: u64,
+    #[serde(flatten)]
+    flags: GamescopeFlags,
+}
+
+/// File the installed gamescope's flags are cached in
+fn cache_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from(".cache"))
+        .join("steam-command-runner")
+        .join("gamescope-flags.json")
+}
+
+/// Flags of the real gamescope binary, or None when it can't be found or asked
+///
+/// Detected once per process, and cached on disk until the binary changes.
+pub fn installed_flags() -> Option<&'static GamescopeFlags> {
+    static FLAGS: OnceLock<Option<GamescopeFlags>> = OnceLock::new();
+    FLAGS
+        .get_or_init(|| {
+            let binary = find_real_gamescope()?;
+            let flags = cached_flags(&binary);
+            if let Some(flags) = &flags {
+                debug!(
+                    "gamescope {} supports {} flags",
+                    flags.version.as_deref().unwrap_or("(unknown version)"),
+                    flags.flags.len()
+                );
+            }
+            flags
+        })
+        .as_ref()
+}
+
+fn cached_flags(binary: &Path) -> Option<GamescopeFlags> {
+    let modified = fs::metadata(binary)
+        .and_then(|m| m.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+
+    let cached = fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<FlagCache>(&content).ok())
+        .filter(|cache| cache.path == binary && cache.modified == modified);
+    if let Some(cache) = cached {
+        return Some(cache.flags);
+    }
+
+    let flags = query_flags(binary)?;
+    let cache = FlagCache {
+        path: binary.to_path_buf(),
+        modified,
+        flags: flags.clone(),
+    };
+    let path = cache_path();
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(&cache).unwrap_or_default()));
+    if let Err(e) = written {
+        debug!("Could not cache gamescope flags: {}", e);
+    }
+    Some(flags)
+}
+
+/// Ask gamescope for its flags and version
+fn query_flags(binary: &Path) -> Option<GamescopeFlags> {
+    let run = |arg: &str| {
+        let output = Command::new(binary)
+            .arg(arg)
+            .stdin(Stdio::null())
+            .output()
+            .ok()?;
+        // gamescope prints its usage to stderr
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        Some(text)
+    };
+
+    let flags = parse_help(&run("--help")?);
+    if flags.is_empty() {
+        debug!("No flags found in {} --help", binary.display());
+        return None;
+    }
+    let version = run("--version").and_then(|text| text.lines().next().map(|l| l.trim().to_string()));
+    Some(GamescopeFlags { version, flags })
+}
+
+/// Collect the option names from gamescope's usage text ("  -W, --output-width  ...")
+fn parse_help(text: &str) -> HashSet<String> {
+    text.lines()
+        .filter(|line| line.trim_start().starts_with('-'))
+        .flat_map(|line| {
+            line.split_whitespace()
+                .take_while(|word| word.starts_with('-'))
+                .map(|word| word.trim_end_matches(',').split('=').next().unwrap_or("").to_string())
+                .filter(|flag| is_flag(flag))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn is_flag(arg: &str) -> bool {
+    arg.len() > 1 && arg != "--" && arg.starts_with('-') && !arg[1..].starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Older spellings of flags: `-F fsr`/`-F nis` were `-U`/`-Y` before `--filter` existed
+fn translate(flag: &str, value: Option<&str>, flags: &GamescopeFlags) -> Option<Vec<String>> {
+    let old = match (flag, value) {
+        ("-F" | "--filter", Some("fsr")) => "-U",
+        ("-F" | "--filter", Some("nis")) => "-Y",
+        _ => return None,
+    };
+    flags.supports(old).then(|| vec![old.to_string()])
+}
+
+/// Drop or translate arguments the installed gamescope doesn't accept
+///
+/// An unsupported flag takes the following argument with it when that isn't a
+/// flag itself, so `-F fsr` doesn't leave a stray `fsr` behind.
+pub fn filter_args(args: Vec<String>, flags: &GamescopeFlags) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            result.push(arg);
+            result.extend(args);
+            break;
+        }
+        let name = arg.split('=').next().unwrap_or(&arg);
+        if !is_flag(name) || flags.supports(name) {
+            result.push(arg);
+            continue;
+        }
+
+        let value = match arg.split_once('=') {
+            Some((_, value)) => Some(value.to_string()),
+            None => args.next_if(|next| !is_flag(next) && next != "--"),
+        };
+        let shown = match &value {
+            Some(value) => format!("{} {}", name, value),
+            None => name.to_string(),
+        };
+        match translate(name, value.as_deref(), flags) {
+            Some(replacement) => {
+                warn!("The installed gamescope doesn't support '{}'; using '{}'", shown, replacement.join(" "));
+                result.extend(replacement);
+            }
+            None => warn!("The installed gamescope doesn't support '{}'; leaving it out", shown),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "usage: gamescope [options...] -- [app...]
+
+Options:
+  --help                         show help message
+  -W, --output-width             output width
+  -H, --output-height            output height
+  -w, --nested-width             game width
+  -r, --nested-refresh           game refresh rate (frames per second)
+  -U, --fsr-upscaling            use AMD FidelityFX Super Resolution 1.0 for upscaling
+  -f, --fullscreen               make the window fullscreen
+
+Keyboard shortcuts:
+  Super + F                      toggle fullscreen
+";
+
+    #[test]
+    fn test_parse_help() {
+        let flags = parse_help(HELP);
+        for flag in ["--help", "-W", "--output-width", "-r", "--nested-refresh", "-U", "-f"] {
+            assert!(flags.contains(flag), "{}", flag);
+        }
+        assert!(!flags.contains("-F"));
+        assert!(!flags.contains("Super"));
+    }
+
+    #[test]
+    fn test_filter_args() {
+        let flags = GamescopeFlags {
+            version: None,
+            flags: parse_help(HELP),
+        };
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            filter_args(
+                args(&["-w", "1920", "-F", "fsr", "--hdr-enabled", "--mangoapp", "-f", "--", "game", "--hdr-enabled"]),
+                &flags
+            ),
+            args(&["-w", "1920", "-U", "-f", "--", "game", "--hdr-enabled"])
+        );
+        assert_eq!(filter_args(args(&["--filter=nis", "-r", "60"]), &flags), args(&["-r", "60"]));
+    }
+}
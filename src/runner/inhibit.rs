@@ -0,0 +1,74 @@
+use crate::config::MergedConfig;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use tracing::{debug, info, warn};
+
+/// What the lock blocks: the idle action (screen blanking, auto-suspend) and sleep
+const INHIBIT_WHAT: &str = "idle:sleep";
+
+/// A systemd inhibitor lock held for the duration of a game session
+///
+/// `systemd-inhibit` holds the lock for as long as the command it runs; we run
+/// `sleep infinity` and stop it when the session ends.
+pub struct Inhibitor {
+    child: Option<Child>,
+}
+
+impl Inhibitor {
+    /// Take the lock if `inhibit_sleep` is enabled
+    ///
+    /// Failure to take the lock is logged and never blocks the game.
+    pub fn acquire(config: &MergedConfig) -> Self {
+        if !config.inhibit_sleep {
+            return Self { child: None };
+        }
+
+        let why = format!("Playing {}", config.display_name());
+        let child = Command::new("systemd-inhibit")
+            .arg(format!("--what={}", INHIBIT_WHAT))
+            .arg("--who=steam-command-runner")
+            .arg(format!("--why={}", why))
+            .arg("--mode=block")
+            .args(["sleep", "infinity"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .process_group(0)
+            .spawn();
+
+        match child {
+            Ok(child) => {
+                info!("Holding {} inhibitor lock (pid {})", INHIBIT_WHAT, child.id());
+                Self { child: Some(child) }
+            }
+            Err(e) => {
+                warn!("Failed to take an inhibitor lock with systemd-inhibit: {}", e);
+                Self { child: None }
+            }
+        }
+    }
+
+    /// Release the lock
+    pub fn release(&mut self) {
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        if let Ok(Some(status)) = child.try_wait() {
+            warn!("systemd-inhibit exited early: {}", status);
+            return;
+        }
+
+        // SAFETY: kill() has no memory-safety preconditions; a negative pid targets the
+        // group, so the sleep exits along with systemd-inhibit
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM);
+        }
+        let _ = child.wait();
+        debug!("Released inhibitor lock");
+    }
+}
+
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
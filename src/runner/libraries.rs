@@ -0,0 +1,180 @@
+use crate::config::MergedConfig;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Join path-list entries with ':' skipping empty entries and duplicates
+/// (the first occurrence wins, so earlier entries keep their priority)
+fn join_unique<'a>(entries: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut seen = Vec::new();
+    for entry in entries {
+        let entry = entry.trim();
+        if !entry.is_empty() && !seen.contains(&entry) {
+            seen.push(entry);
+        }
+    }
+
+    if seen.is_empty() {
+        None
+    } else {
+        Some(seen.join(":"))
+    }
+}
+
+/// Build an LD_LIBRARY_PATH value with configured directories in front of `base`
+///
+/// `base` is the value inherited from Steam (or the pressure-vessel runtime), which is
+/// kept intact after our entries so the runtime's own libraries are still found.
+/// Returns None if there is nothing to prepend, leaving the inherited value untouched.
+pub fn build_ld_library_path(prepend: &[String], base: Option<&str>) -> Option<String> {
+    if prepend.is_empty() {
+        return None;
+    }
+
+    let inherited = base.unwrap_or("").split(':');
+    let value = join_unique(prepend.iter().map(String::as_str).chain(inherited))?;
+    debug!("LD_LIBRARY_PATH with prepended entries: {}", value);
+    Some(value)
+}
+
+/// Append configured libraries to an LD_PRELOAD value without duplicating entries
+///
+/// LD_PRELOAD accepts both ':' and ' ' as separators, so both are accepted in `base`.
+pub fn append_ld_preload(base: Option<String>, extra: &[String]) -> Option<String> {
+    if extra.is_empty() {
+        return base;
+    }
+
+    let base = base.unwrap_or_default();
+    let existing = base.split([':', ' ']);
+    let value = join_unique(existing.chain(extra.iter().map(String::as_str)));
+    debug!("LD_PRELOAD with extra libraries: {:?}", value);
+    value
+}
+
+/// LD_LIBRARY_PATH for a launch: configured directories ahead of the inherited value
+///
+/// A value from the config's `env` table is treated as the inherited value.
+pub fn ld_library_path_for(config: &MergedConfig) -> Option<String> {
+    let base = config
+        .env
+        .get("LD_LIBRARY_PATH")
+        .cloned()
+        .or_else(|| std::env::var("LD_LIBRARY_PATH").ok());
+    build_ld_library_path(&config.ld_library_path_prepend, base.as_deref())
+}
+
+/// LD_PRELOAD for a launch: `base` with the configured extra libraries appended
+pub fn ld_preload_for(config: &MergedConfig, base: Option<String>) -> Option<String> {
+    append_ld_preload(base, &config.ld_preload_extra)
+}
+
+/// Build a WINEDLLOVERRIDES value with configured overrides merged into `base`
+///
+/// Entries are `dll=mode` separated by ';'. Inherited entries for DLLs we also
+/// override are dropped; the rest keep their place ahead of ours, which are sorted.
+/// Returns None if there is nothing to add, leaving the inherited value untouched.
+pub fn build_dll_overrides(overrides: &HashMap<String, String>, base: Option<&str>) -> Option<String> {
+    if overrides.is_empty() {
+        return None;
+    }
+
+    // An inherited entry can name several DLLs ("d3d11,dxgi=n,b"); keep the ones we don't set
+    let mut entries: Vec<String> = base
+        .unwrap_or("")
+        .split(';')
+        .filter_map(|entry| {
+            let (dlls, mode) = entry.split_once('=').unwrap_or((entry, ""));
+            let kept: Vec<&str> = dlls
+                .split(',')
+                .map(str::trim)
+                .filter(|dll| !dll.is_empty() && !overrides.contains_key(*dll))
+                .collect();
+            (!kept.is_empty()).then(|| format!("{}={}", kept.join(","), mode.trim()))
+        })
+        .collect();
+
+    let mut ours: Vec<(&String, &String)> = overrides.iter().collect();
+    ours.sort();
+    entries.extend(ours.into_iter().map(|(dll, mode)| format!("{}={}", dll, mode)));
+    let value = entries.join(";");
+    debug!("WINEDLLOVERRIDES with configured overrides: {}", value);
+    Some(value)
+}
+
+/// WINEDLLOVERRIDES for a launch: `dll_overrides` merged into the inherited value
+///
+/// A value from the config's `env` table is treated as the inherited value.
+pub fn dll_overrides_for(config: &MergedConfig) -> Option<String> {
+    let base = config
+        .env
+        .get("WINEDLLOVERRIDES")
+        .cloned()
+        .or_else(|| std::env::var("WINEDLLOVERRIDES").ok());
+    build_dll_overrides(&config.dll_overrides, base.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_ld_library_path_prepends() {
+        let prepend = vec!["/opt/ssl/lib".to_string()];
+        assert_eq!(
+            build_ld_library_path(&prepend, Some("/runtime/lib:/usr/lib")),
+            Some("/opt/ssl/lib:/runtime/lib:/usr/lib".to_string())
+        );
+        assert_eq!(
+            build_ld_library_path(&prepend, None),
+            Some("/opt/ssl/lib".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_ld_library_path_dedupes() {
+        let prepend = vec!["/usr/lib".to_string()];
+        assert_eq!(
+            build_ld_library_path(&prepend, Some("/runtime/lib:/usr/lib")),
+            Some("/usr/lib:/runtime/lib".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_ld_library_path_nothing_to_prepend() {
+        assert_eq!(build_ld_library_path(&[], Some("/runtime/lib")), None);
+    }
+
+    #[test]
+    fn test_append_ld_preload() {
+        let extra = vec!["/usr/lib/libfoo.so".to_string()];
+        assert_eq!(
+            append_ld_preload(Some("/overlay64.so /overlay32.so".to_string()), &extra),
+            Some("/overlay64.so:/overlay32.so:/usr/lib/libfoo.so".to_string())
+        );
+        assert_eq!(
+            append_ld_preload(None, &extra),
+            Some("/usr/lib/libfoo.so".to_string())
+        );
+        assert_eq!(
+            append_ld_preload(Some("/a.so".to_string()), &[]),
+            Some("/a.so".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_dll_overrides() {
+        let overrides = HashMap::from([
+            ("winhttp".to_string(), "n,b".to_string()),
+            ("dxgi".to_string(), "n,b".to_string()),
+        ]);
+        assert_eq!(
+            build_dll_overrides(&overrides, None),
+            Some("dxgi=n,b;winhttp=n,b".to_string())
+        );
+        assert_eq!(
+            build_dll_overrides(&overrides, Some("d3d11,dxgi=b; xinput1_3=n")),
+            Some("d3d11=b;xinput1_3=n;dxgi=n,b;winhttp=n,b".to_string())
+        );
+        assert_eq!(build_dll_overrides(&HashMap::new(), Some("dxgi=n")), None);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::config::MergedConfig;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::{debug, info, warn};
+
+/// Parse a size like "8G", "512M", "1.5GiB" or "1073741824" into bytes (1024-based units)
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let unit = unit.trim().to_uppercase();
+    let unit = unit.trim_end_matches("IB").trim_end_matches('B');
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Parse a CPU list like "0-7,12,14-15" into CPU numbers
+pub fn parse_cpu_list(value: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                if start > end {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    (!cpus.is_empty()).then_some(cpus)
+}
+
+/// Parse an I/O priority like "idle", "best-effort:7" or "realtime" into an ioprio value
+pub fn parse_ionice(value: &str) -> Option<i32> {
+    const IOPRIO_CLASS_SHIFT: i32 = 13;
+    let (class, level) = match value.trim().split_once(':') {
+        Some((class, level)) => (class, Some(level.trim().parse::<i32>().ok()?)),
+        None => (value.trim(), None),
+    };
+    let class = match class.to_lowercase().as_str() {
+        "realtime" | "rt" => 1,
+        "best-effort" | "be" => 2,
+        "idle" => 3,
+        _ => return None,
+    };
+    let level = level.unwrap_or(4);
+    if !(0..=7).contains(&level) {
+        return None;
+    }
+    Some((class << IOPRIO_CLASS_SHIFT) | level)
+}
+
+fn cpu_set_for(config: &MergedConfig) -> Option<libc::cpu_set_t> {
+    let value = config.cpu_affinity.as_deref()?;
+    let Some(cpus) = parse_cpu_list(value) else {
+        warn!("Invalid cpu_affinity '{}', ignoring", value);
+        return None;
+    };
+    // SAFETY: cpu_set_t is a plain bitmask, for which all zeroes is a valid (empty) value
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            warn!("CPU {} in cpu_affinity is out of range, ignoring it", cpu);
+            continue;
+        }
+        // SAFETY: cpu is below CPU_SETSIZE, so the bit is within the set
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    debug!("Pinning game to CPUs {}", value);
+    Some(set)
+}
+
+/// Check whether systemd can place the game in its own user scope
+pub fn systemd_run_available() -> bool {
+    let in_path = std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join("systemd-run").exists()))
+        .unwrap_or(false);
+    in_path && Path::new("/run/systemd/system").exists()
+}
+
+/// Build the systemd-run prefix that starts the game in a transient scope with limits
+///
+/// `systemd-run --scope` execs the command itself, so the game keeps our PID.
+pub fn systemd_scope_prefix(unit: &str, properties: &[String]) -> Vec<String> {
+    let mut prefix = vec![
+        "systemd-run".to_string(),
+        "--user".to_string(),
+        "--scope".to_string(),
+        "--quiet".to_string(),
+        "--collect".to_string(),
+        format!("--unit={}", unit),
+    ];
+    for property in properties {
+        prefix.push("-p".to_string());
+        prefix.push(property.clone());
+    }
+    prefix.push("--".to_string());
+    prefix
+}
+
+/// cgroup properties for the configured limits
+fn scope_properties(config: &MergedConfig) -> Vec<String> {
+    let mut properties = Vec::new();
+    if let Some(max) = &config.memory_max {
+        properties.push(format!("MemoryMax={}", max));
+    }
+    if let Some(quota) = &config.limits.cpu_quota {
+        properties.push(format!("CPUQuota={}", quota));
+    }
+    if let Some(weight) = config.limits.io_weight {
+        properties.push(format!("IOWeight={}", weight.clamp(1, 10000)));
+    }
+    properties
+}
+
+/// Name of the scope unit the game runs in, if it runs in one
+///
+/// Derived from our PID so it can be stopped after a supervised game exits.
+pub fn scope_unit(config: &MergedConfig) -> Option<String> {
+    let wanted = config.limits.scope.unwrap_or(false) || !scope_properties(config).is_empty();
+    if !wanted || !systemd_run_available() {
+        return None;
+    }
+    let game = config.app_id.map_or("game".to_string(), |id| id.to_string());
+    Some(format!("steam-command-runner-{}-{}", game, std::process::id()))
+}
+
+/// Wrap the command in a systemd scope when limits (or a scope) are configured
+///
+/// Returns the command unchanged if nothing is configured or systemd isn't
+/// available; `apply_process_limits` then falls back to an rlimit for memory.
+pub fn wrap_with_scope(config: &MergedConfig, command: Vec<String>) -> Vec<String> {
+    let properties = scope_properties(config);
+    let Some(unit) = scope_unit(config) else {
+        if config.limits.cpu_quota.is_some() || config.limits.io_weight.is_some() {
+            warn!("cpu_quota and io_weight need systemd-run, ignoring them");
+        }
+        return command;
+    };
+
+    if properties.is_empty() {
+        info!("Starting game in systemd scope {}", unit);
+    } else {
+        info!("Starting game in systemd scope {} with {}", unit, properties.join(" "));
+    }
+    let mut wrapped = systemd_scope_prefix(&unit, &properties);
+    wrapped.extend(command);
+    wrapped
+}
+
+/// Stop the game's scope, killing anything it left running
+pub fn stop_scope(unit: &str) {
+    debug!("Stopping systemd scope {}", unit);
+    let result = Command::new("systemctl")
+        .args(["--user", "stop", &format!("{}.scope", unit)])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match result {
+        Ok(status) if status.success() => info!("Stopped leftover game processes ({})", unit),
+        Ok(status) => debug!("systemctl stop {} exited with {}", unit, status),
+        Err(e) => warn!("Failed to stop scope {}: {}", unit, e),
+    }
+}
+
+/// Apply the OOM score adjustment and (without systemd) the memory rlimit to the process
+pub fn apply_process_limits(config: &MergedConfig, process: &mut Command) {
+    let oom_score_adj = config.oom_score_adj.map(|value| {
+        let value = value.clamp(-1000, 1000);
+        let current = fs::read_to_string("/proc/self/oom_score_adj")
+            .ok()
+            .and_then(|s| s.trim().parse::<i32>().ok())
+            .unwrap_or(0);
+        if value < current {
+            warn!(
+                "oom_score_adj {} is below the current {} and needs CAP_SYS_RESOURCE; it may be ignored",
+                value, current
+            );
+        }
+        debug!("Setting oom_score_adj={}", value);
+        value.to_string().into_bytes()
+    });
+
+    let rlimit_data = match &config.memory_max {
+        Some(max) if !systemd_run_available() => match parse_size(max) {
+            Some(bytes) => {
+                info!("systemd not available, limiting data segment to {} bytes", bytes);
+                Some(bytes)
+            }
+            None => {
+                warn!("memory_max '{}' needs systemd (or a plain size like 8G), ignoring", max);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let cpu_set = cpu_set_for(config);
+
+    let nice = config.nice.map(|nice| {
+        let nice = nice.clamp(-20, 19);
+        if nice < 0 {
+            warn!("nice {} needs CAP_SYS_NICE (or a raised RLIMIT_NICE); it may be ignored", nice);
+        }
+        debug!("Setting nice={}", nice);
+        nice
+    });
+
+    let ioprio = config.ionice.as_deref().and_then(|value| match parse_ionice(value) {
+        Some(prio) => {
+            debug!("Setting ionice {} (ioprio {})", value, prio);
+            Some(prio)
+        }
+        None => {
+            warn!("Invalid ionice '{}', ignoring", value);
+            None
+        }
+    });
+
+    if oom_score_adj.is_none()
+        && rlimit_data.is_none()
+        && cpu_set.is_none()
+        && nice.is_none()
+        && ioprio.is_none()
+    {
+        return;
+    }
+
+    // SAFETY: the closure only makes async-signal-safe libc calls on data prepared
+    // above, so it is sound to run between fork and exec
+    unsafe {
+        process.pre_exec(move || {
+            if let Some(ref value) = oom_score_adj {
+                let fd = libc::open(c"/proc/self/oom_score_adj".as_ptr(), libc::O_WRONLY);
+                if fd >= 0 {
+                    libc::write(fd, value.as_ptr().cast(), value.len());
+                    libc::close(fd);
+                }
+            }
+            if let Some(bytes) = rlimit_data {
+                let limit = libc::rlimit {
+                    rlim_cur: bytes as libc::rlim_t,
+                    rlim_max: bytes as libc::rlim_t,
+                };
+                libc::setrlimit(libc::RLIMIT_DATA, &limit);
+            }
+            // Affinity and priorities are inherited, so they also cover Wine's processes
+            if let Some(ref set) = cpu_set {
+                libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set);
+            }
+            if let Some(nice) = nice {
+                libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+            }
+            if let Some(prio) = ioprio {
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, prio);
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("8G"), Some(8 << 30));
+        assert_eq!(parse_size("512M"), Some(512 << 20));
+        assert_eq!(parse_size("1.5GiB"), Some(3 << 29));
+        assert_eq!(parse_size("64k"), Some(64 << 10));
+        assert_eq!(parse_size("80%"), None);
+        assert_eq!(parse_size("lots"), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(parse_cpu_list("0-1, 4,6-7"), Some(vec![0, 1, 4, 6, 7]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("p-cores"), None);
+        assert_eq!(parse_cpu_list(""), None);
+    }
+
+    #[test]
+    fn test_parse_ionice() {
+        assert_eq!(parse_ionice("idle"), Some(3 << 13 | 4));
+        assert_eq!(parse_ionice("best-effort:0"), Some(2 << 13));
+        assert_eq!(parse_ionice("realtime:7"), Some(1 << 13 | 7));
+        assert_eq!(parse_ionice("best-effort:9"), None);
+        assert_eq!(parse_ionice("fast"), None);
+    }
+
+    #[test]
+    fn test_systemd_scope_prefix() {
+        let prefix = systemd_scope_prefix("scr-440", &["MemoryMax=8G".to_string()]);
+        assert_eq!(
+            prefix,
+            vec![
+                "systemd-run",
+                "--user",
+                "--scope",
+                "--quiet",
+                "--collect",
+                "--unit=scr-440",
+                "-p",
+                "MemoryMax=8G",
+                "--"
+            ]
+        );
+    }
+}
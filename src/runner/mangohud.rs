@@ -0,0 +1,158 @@
+use crate::config::{MangoHudConfig, MergedConfig};
+use crate::error::AppError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Directory holding the generated MangoHud config files
+fn mangohud_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(".local/share"))
+        .join("steam-command-runner")
+        .join("mangohud")
+}
+
+/// Render MangoHud settings in MangoHud's config file format
+///
+/// Boolean options are written as a bare key when on and `key=0` when off, so
+/// MangoHud's own defaults apply to anything left unset.
+pub fn mangohud_config_contents(mangohud: &MangoHudConfig) -> String {
+    let mut out = String::from("# Generated by steam-command-runner; edit [mangohud] in its config instead\n");
+
+    if let Some(limit) = mangohud.fps_limit {
+        out.push_str(&format!("fps_limit={}\n", limit));
+    }
+    if let Some(position) = &mangohud.position {
+        out.push_str(&format!("position={}\n", position));
+    }
+    if let Some(size) = mangohud.font_size {
+        out.push_str(&format!("font_size={}\n", size));
+    }
+    if let Some(key) = &mangohud.toggle_hud {
+        out.push_str(&format!("toggle_hud={}\n", key));
+    }
+    if mangohud.hidden == Some(true) {
+        out.push_str("no_display\n");
+    }
+
+    let metrics = [
+        ("fps", mangohud.fps),
+        ("frametime", mangohud.frametime),
+        ("cpu_stats", mangohud.cpu_stats),
+        ("cpu_temp", mangohud.cpu_temp),
+        ("gpu_stats", mangohud.gpu_stats),
+        ("gpu_temp", mangohud.gpu_temp),
+        ("ram", mangohud.ram),
+        ("vram", mangohud.vram),
+        ("battery", mangohud.battery),
+        ("wine", mangohud.wine),
+        ("gamemode", mangohud.gamemode),
+    ];
+    for (key, value) in metrics {
+        match value {
+            Some(true) => out.push_str(&format!("{}\n", key)),
+            Some(false) => out.push_str(&format!("{}=0\n", key)),
+            None => {}
+        }
+    }
+
+    for line in &mangohud.extra {
+        out.push_str(line.trim());
+        out.push('\n');
+    }
+    out
+}
+
+/// Path of a game's generated MangoHud config file
+fn mangohud_config_path(config: &MergedConfig) -> PathBuf {
+    let name = config.app_id.map_or("default".to_string(), |id| id.to_string());
+    mangohud_dir().join(format!("{}.conf", name))
+}
+
+/// Write the game's MangoHud config file, returning its path
+pub fn write_mangohud_config(config: &MergedConfig) -> Result<PathBuf, AppError> {
+    let path = mangohud_config_path(config);
+    let contents = mangohud_config_contents(&config.mangohud);
+
+    // Leave the file alone when nothing changed, so MangoHud's file watcher stays quiet
+    if fs::read_to_string(&path).ok().as_deref() != Some(contents.as_str()) {
+        fs::create_dir_all(mangohud_dir())?;
+        fs::write(&path, contents)?;
+        debug!("Wrote MangoHud config: {}", path.display());
+    }
+    Ok(path)
+}
+
+/// Whether the command line already runs MangoHud
+fn already_wrapped(config: &MergedConfig) -> bool {
+    config
+        .effective_pre_command()
+        .is_some_and(|cmd| cmd.split_whitespace().any(|w| w == "mangohud" || w.ends_with("/mangohud")))
+}
+
+fn mangohud_available() -> bool {
+    std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join("mangohud").exists()))
+        .unwrap_or(false)
+}
+
+/// Prepare MangoHud for a launch
+///
+/// Returns the MANGOHUD_CONFIGFILE value and the wrapper to put in front of the
+/// command (empty when gamescope draws the HUD via --mangoapp, or when the
+/// pre-command already runs mangohud).
+pub fn prepare_mangohud(
+    config: &MergedConfig,
+    using_gamescope: bool,
+) -> Option<(PathBuf, Vec<String>)> {
+    if !config.mangohud.is_enabled() {
+        return None;
+    }
+
+    let path = if config.dry_run {
+        mangohud_config_path(config)
+    } else {
+        match write_mangohud_config(config) {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("Failed to write MangoHud config: {}", e);
+                return None;
+            }
+        }
+    };
+
+    let wrapper = if using_gamescope || already_wrapped(config) {
+        Vec::new()
+    } else if mangohud_available() || config.dry_run {
+        info!("Running the game with MangoHud");
+        vec!["mangohud".to_string()]
+    } else {
+        warn!("MangoHud is enabled but mangohud was not found in PATH");
+        Vec::new()
+    };
+    Some((path, wrapper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mangohud_config_contents() {
+        let mangohud = MangoHudConfig {
+            enabled: Some(true),
+            fps_limit: Some(60),
+            position: Some("top-right".to_string()),
+            fps: Some(true),
+            cpu_stats: Some(false),
+            extra: vec!["background_alpha=0.4".to_string()],
+            ..MangoHudConfig::default()
+        };
+        let contents = mangohud_config_contents(&mangohud);
+        let lines: Vec<&str> = contents.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec!["fps_limit=60", "position=top-right", "fps", "cpu_stats=0", "background_alpha=0.4"]
+        );
+    }
+}
@@ -1,25 +1,105 @@
+mod anticheat;
+mod companions;
+mod detect;
+mod discord;
+mod display;
+mod events;
+mod explain;
+mod gamescope;
+mod gamescope_flags;
+mod inhibit;
+mod libraries;
+mod limits;
+mod mangohud;
 mod native;
+mod output;
+mod power;
+mod preflight;
 mod proton;
+mod sandbox;
+mod steam_runtime;
+mod supervisor;
+mod wine;
 
-use crate::config::{ExecutionMode, MergedConfig};
+use crate::config::{expand_home, ExecutionMode, MergedConfig, RESOLVED_CONFIG_ENV};
 use crate::error::AppError;
-use std::process::ExitCode;
-use tracing::{debug, info};
+use crate::hooks::{self, HookContext};
+use crate::notify::{notify, Urgency};
+use crate::saves::{sync_for_launch, SyncDirection};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::time::Duration;
+use tracing::{info, warn};
 
+pub use detect::{
+    classify_file, detect_execution_mode, detect_execution_mode_for_app, find_single_executable,
+    BinaryKind,
+};
+pub use display::{current_display_mode, DisplayMode};
+pub use explain::describe_launch;
+pub use gamescope::{gamescope_args_for, GamescopeArgs};
+pub use gamescope_flags::{installed_flags, GamescopeFlags};
+pub use libraries::{ld_library_path_for, ld_preload_for};
+pub use limits::parse_size;
+pub use mangohud::write_mangohud_config;
 pub use native::NativeRunner;
 pub use proton::ProtonRunner;
+pub use wine::WineRunner;
+
+/// How long gamescope must keep running before a launch counts as started
+const GAMESCOPE_STARTUP_WINDOW: Duration = Duration::from_secs(3);
 
 /// Execute a game with the given configuration
+///
+/// With `gamescope.fallback`, a launch whose gamescope fails to start is retried
+/// without gamescope.
 pub fn execute_game(config: &MergedConfig, command: Vec<String>) -> Result<ExitCode, AppError> {
+    if config.passthrough {
+        return run_unmodified(config, command);
+    }
+    let prepared = prepare_game(config, command.clone())?;
+    let without_gamescope = || {
+        let mut fallback = config.clone();
+        fallback.gamescope_enabled = false;
+        let prepared = prepare_game(&fallback, command.clone())?;
+        Ok((fallback, prepared))
+    };
+    launch(config, prepared, Some(&without_gamescope))
+}
+
+/// Exec Steam's command exactly as given, for games with `passthrough = true`
+fn run_unmodified(config: &MergedConfig, command: Vec<String>) -> Result<ExitCode, AppError> {
+    let (program, args) = command.split_first().ok_or(AppError::NoCommand)?;
+    if config.dry_run {
+        let command = shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+        println!("Passthrough: the command runs unmodified\n\nCommand:\n  {}", command);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    info!("Passthrough enabled for {}; running the command unmodified", config.display_name());
+    let err = Command::new(program).args(args).exec();
+    Err(AppError::ExecutionFailed(format!("exec failed: {}", err)))
+}
+
+fn prepare_game(config: &MergedConfig, mut command: Vec<String>) -> Result<PreparedLaunch, AppError> {
     if command.is_empty() {
         return Err(AppError::NoCommand);
     }
 
+    // A directory is launched via the single obvious executable inside it
+    if Path::new(&command[0]).is_dir() {
+        if let Some(exe) = find_single_executable(Path::new(&command[0])) {
+            info!("Launching {} from directory {}", exe.display(), command[0]);
+            command[0] = exe.to_string_lossy().to_string();
+        }
+    }
+
     let game_path = &command[0];
 
     // Determine execution mode
     let mode = match config.mode {
-        ExecutionMode::Auto => detect_execution_mode(game_path),
+        ExecutionMode::Auto => detect_execution_mode_for_app(game_path, config.app_id),
         m => m,
     };
 
@@ -28,23 +108,230 @@ pub fn execute_game(config: &MergedConfig, command: Vec<String>) -> Result<ExitC
     match mode {
         ExecutionMode::Native | ExecutionMode::Auto => {
             let runner = NativeRunner::new(config);
-            runner.run(command)
+            runner.prepare(command)
         }
         ExecutionMode::Proton => {
             let runner = ProtonRunner::new(config)?;
-            runner.run(command)
+            runner.prepare(command)
+        }
+        ExecutionMode::Umu => {
+            let runner = ProtonRunner::umu(config)?;
+            runner.prepare(command)
+        }
+        ExecutionMode::Wine => {
+            let runner = WineRunner::new(config);
+            runner.prepare(command)
         }
     }
 }
 
-/// Detect execution mode based on file extension
-fn detect_execution_mode(path: &str) -> ExecutionMode {
-    let path_lower = path.to_lowercase();
-    if path_lower.ends_with(".exe") || path_lower.ends_with(".msi") || path_lower.ends_with(".bat") {
-        debug!("Detected Windows executable, using Proton mode");
-        ExecutionMode::Proton
-    } else {
-        debug!("Detected native executable, using Native mode");
-        ExecutionMode::Native
+/// Directory to start the game in, from `working_dir`
+///
+/// Relative paths are taken from the directory of the game executable, so
+/// `working_dir = "bin"` works wherever the game is installed.
+fn working_dir_for(config: &MergedConfig, game_path: &str) -> Option<PathBuf> {
+    let dir = expand_home(config.working_dir.as_deref()?);
+    if dir.is_absolute() {
+        return Some(dir);
+    }
+    let base = Path::new(game_path).parent().filter(|p| !p.as_os_str().is_empty());
+    Some(base.map_or(dir.clone(), |base| base.join(&dir)))
+}
+
+/// Combine the game command with `launch_args`
+///
+/// Without placeholders the arguments are appended to the command. Like Steam's
+/// launch options, an argument of `%command%` instead marks where the game
+/// command goes, so arguments can come before it or wrap it. `%args%` stands for
+/// the game's arguments alone and `%exe%` (also inside an argument) for its
+/// executable, so a mod loader can replace the executable entirely.
+fn with_launch_args(launch_args: &[String], command: Vec<String>) -> Vec<String> {
+    let positional = launch_args
+        .iter()
+        .any(|arg| arg == "%command%" || arg == "%args%" || arg.contains("%exe%"));
+    if !positional {
+        let mut command = command;
+        command.extend(launch_args.iter().cloned());
+        return command;
+    }
+
+    let (exe, args) = command.split_first().map_or(("", &[][..]), |(exe, args)| (exe.as_str(), args));
+    let mut result = Vec::new();
+    for arg in launch_args {
+        match arg.as_str() {
+            "%command%" => result.extend(command.iter().cloned()),
+            "%args%" => result.extend(args.iter().cloned()),
+            _ => result.push(arg.replace("%exe%", exe)),
+        }
+    }
+    result
+}
+
+/// Wrap the final command line in outer wrappers that must start before anything else
+///
+/// The systemd scope goes outermost so the sandbox itself is inside the cgroup.
+fn wrap_command(config: &MergedConfig, command: Vec<String>) -> Result<Vec<String>, AppError> {
+    let command = sandbox::wrap_with_sandbox(config, command)?;
+    Ok(limits::wrap_with_scope(config, command))
+}
+
+/// A game process built by one of the runners, not yet started
+struct PreparedLaunch {
+    mode: ExecutionMode,
+    proton: Option<PathBuf>,
+    using_gamescope: bool,
+    process: Command,
+}
+
+/// Rebuilds the game process with gamescope disabled, along with the config it was built from
+type Fallback<'a> = &'a dyn Fn() -> Result<(MergedConfig, PreparedLaunch), AppError>;
+
+/// Hand the fully prepared game process over
+///
+/// By default we exec() so the game replaces this process, which keeps Steam Input
+/// and the overlay attached to the process Steam launched. When something has to
+/// happen after the game exits (e.g. companion programs), or when gamescope has to
+/// be watched for a failed start, the game runs supervised. In dry-run mode the
+/// launch is only described.
+fn launch(config: &MergedConfig, prepared: PreparedLaunch, fallback: Option<Fallback>) -> Result<ExitCode, AppError> {
+    launch_with(config, prepared, fallback, start)
+}
+
+/// `launch`, with the step that starts the game process passed in
+///
+/// Only that step is repeated when falling back from gamescope; the pre_launch
+/// hooks, save sync and launch notification happen once per launch.
+fn launch_with(
+    config: &MergedConfig,
+    prepared: PreparedLaunch,
+    fallback: Option<Fallback>,
+    start: fn(&MergedConfig, Command, HookContext, Option<Duration>) -> Result<ExitCode, AppError>,
+) -> Result<ExitCode, AppError> {
+    let PreparedLaunch { mode, proton, using_gamescope, process } = prepared;
+    if config.dry_run {
+        print!("{}", explain::describe_launch(config, mode, &process));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let context = HookContext::for_launch(config, proton.as_deref());
+    // A pre_launch hook with continue_on_error = false aborts the launch
+    hooks::run_all("pre_launch", &config.pre_launch_hooks, &context)?;
+    sync_for_launch(config, SyncDirection::Pull);
+    notify(
+        Urgency::Normal,
+        &format!("Launching {}", config.display_name()),
+        &format!("{:?} mode", mode),
+    );
+
+    let startup_check = (using_gamescope && config.gamescope_fallback).then_some(GAMESCOPE_STARTUP_WINDOW);
+    match (start(config, process, context.clone(), startup_check), fallback) {
+        (Err(AppError::GamescopeFailed(reason)), Some(without_gamescope)) => {
+            warn!("Gamescope failed to start ({}), launching without it", reason);
+            notify(
+                Urgency::Critical,
+                &format!("Launching {} without gamescope", config.display_name()),
+                &reason,
+            );
+            let (config, retry) = without_gamescope()?;
+            start(&config, retry.process, context, None)
+        }
+        (result, _) => result,
+    }
+}
+
+/// Start the game process, supervised if anything has to watch it
+fn start(
+    config: &MergedConfig,
+    mut process: Command,
+    context: HookContext,
+    startup_check: Option<Duration>,
+) -> Result<ExitCode, AppError> {
+    limits::apply_process_limits(config, &mut process);
+    output::capture_game_output(config, &mut process);
+    // The config handed over by the gamescope shim is for us, not the game
+    process.env_remove(RESOLVED_CONFIG_ENV);
+
+    if startup_check.is_some() || supervisor::needs_supervision(config) {
+        return supervisor::supervise(config, process, context, startup_check);
+    }
+
+    info!("Exec'ing into game (replacing this process)");
+    let err = process.exec();
+
+    // If exec returns, it failed
+    Err(AppError::ExecutionFailed(format!("exec failed: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GameConfig, GlobalConfig, HostFacts};
+
+    #[test]
+    fn test_with_launch_args() {
+        let command = || vec!["game.exe".to_string(), "-windowed".to_string()];
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            with_launch_args(&args(&["-skipintro"]), command()),
+            args(&["game.exe", "-windowed", "-skipintro"])
+        );
+        assert_eq!(
+            with_launch_args(&args(&["loader.exe", "--", "%command%", "-x"]), command()),
+            args(&["loader.exe", "--", "game.exe", "-windowed", "-x"])
+        );
+        assert_eq!(
+            with_launch_args(&args(&["ModOrganizer.exe", "moshortcut://:%exe%", "%args%"]), command()),
+            args(&["ModOrganizer.exe", "moshortcut://:game.exe", "-windowed"])
+        );
+    }
+
+    #[test]
+    fn test_gamescope_fallback_sets_up_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let mut config = MergedConfig::merge(GlobalConfig::default(), None, false, None, &HostFacts::detect(), None);
+        config.gamescope_fallback = true;
+        config.pre_launch_hooks = vec![toml::from_str(&format!(
+            r#"command = "sh -c 'echo run >> {}'""#,
+            runs.display()
+        ))
+        .unwrap()];
+
+        let prepared = |using_gamescope| PreparedLaunch {
+            mode: ExecutionMode::Native,
+            proton: None,
+            using_gamescope,
+            process: Command::new("true"),
+        };
+        let without_gamescope = || Ok((config.clone(), prepared(false)));
+        // Gamescope fails its startup check; the retry without it starts
+        fn start(_: &MergedConfig, _: Command, _: HookContext, startup_check: Option<Duration>) -> Result<ExitCode, AppError> {
+            match startup_check {
+                Some(_) => Err(AppError::GamescopeFailed("exited with status 1".to_string())),
+                None => Ok(ExitCode::from(3)),
+            }
+        }
+
+        let result = launch_with(&config, prepared(true), Some(&without_gamescope), start);
+        assert_eq!(result.unwrap(), ExitCode::from(3));
+        assert_eq!(std::fs::read_to_string(&runs).unwrap(), "run\n");
+    }
+
+    #[test]
+    fn test_working_dir_for() {
+        let game: GameConfig = toml::from_str(r#"working_dir = "bin""#).unwrap();
+        let config = MergedConfig::merge(GlobalConfig::default(), Some(game), false, None, &HostFacts::detect(), None);
+        assert_eq!(
+            working_dir_for(&config, "/games/quake/quake.sh"),
+            Some(PathBuf::from("/games/quake/bin"))
+        );
+
+        let game: GameConfig = toml::from_str(r#"working_dir = "/srv/mods""#).unwrap();
+        let config = MergedConfig::merge(GlobalConfig::default(), Some(game), false, None, &HostFacts::detect(), None);
+        assert_eq!(
+            working_dir_for(&config, "/games/quake/quake.sh"),
+            Some(PathBuf::from("/srv/mods"))
+        );
     }
 }
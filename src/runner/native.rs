@@ -1,15 +1,21 @@
-use crate::config::MergedConfig;
+use super::gamescope::gamescope_args_for;
+use super::libraries::{ld_library_path_for, ld_preload_for};
+use super::mangohud::prepare_mangohud;
+use super::{launch, with_launch_args, working_dir_for, wrap_command, PreparedLaunch};
+use super::preflight::check_native_libraries;
+use super::steam_runtime::wrap_with_steam_runtime;
+use crate::config::{ExecutionMode, MergedConfig};
 use crate::error::AppError;
-use std::os::unix::process::CommandExt;
-use std::path::PathBuf;
+use crate::gpu::gpu_env_for;
+use crate::steam::get_steam_root;
+use std::path::Path;
 use std::process::{Command, ExitCode};
 use tracing::{debug, info};
 
 /// Get the Steam overlay library paths for LD_PRELOAD
 fn get_steam_overlay_paths() -> Option<String> {
-    // Try to find Steam installation path
-    let home = std::env::var("HOME").ok()?;
-    let steam_path = PathBuf::from(&home).join(".local/share/Steam");
+    // Overlay libraries live in the Steam installation (native or Flatpak)
+    let steam_path = get_steam_root()?;
 
     let overlay_64 = steam_path.join("ubuntu12_64/gameoverlayrenderer.so");
     let overlay_32 = steam_path.join("ubuntu12_32/gameoverlayrenderer.so");
@@ -55,7 +61,32 @@ impl<'a> NativeRunner<'a> {
         Self { config }
     }
 
-    pub fn run(&self, mut command: Vec<String>) -> Result<ExitCode, AppError> {
+    pub fn run(&self, command: Vec<String>) -> Result<ExitCode, AppError> {
+        launch(self.config, self.prepare(command)?, None)
+    }
+
+    /// Build the game process without starting it
+    pub(super) fn prepare(&self, mut command: Vec<String>) -> Result<PreparedLaunch, AppError> {
+        // Warn about missing shared libraries before exec fails cryptically
+        if self.config.library_preflight {
+            if let Some(game_path) = command.first() {
+                let ld_path = ld_library_path_for(self.config)
+                    .or_else(|| std::env::var("LD_LIBRARY_PATH").ok());
+                check_native_libraries(Path::new(game_path), ld_path.as_deref());
+            }
+        }
+
+        let working_dir = command.first().and_then(|game| working_dir_for(self.config, game));
+
+        // Add launch args (positioned by %command% if they use it)
+        if !self.config.launch_args.is_empty() {
+            debug!("Adding launch args: {:?}", self.config.launch_args);
+            command = with_launch_args(&self.config.launch_args, command);
+        }
+
+        // Run the game inside the Steam Linux Runtime container; wrappers stay outside it
+        command = wrap_with_steam_runtime(self.config, command)?;
+
         // Track if we're adding gamescope
         let mut using_gamescope = false;
 
@@ -63,9 +94,7 @@ impl<'a> NativeRunner<'a> {
         if self.config.gamescope_enabled {
             if self.config.is_gamescope_session {
                 debug!("Already in gamescope session, skipping gamescope wrapper");
-            } else if let Some(ref gs_args) = self.config.gamescope_args {
-                let gs_args_parsed = shlex::split(gs_args)
-                    .ok_or_else(|| AppError::GamescopeArgsParse(gs_args.to_string()))?;
+            } else if let Some(gs_args_parsed) = gamescope_args_for(self.config)? {
 
                 debug!("Wrapping with gamescope: {:?}", gs_args_parsed);
 
@@ -81,17 +110,29 @@ impl<'a> NativeRunner<'a> {
                 gs_command.push("ENABLE_GAMESCOPE_WSI=1".to_string());
 
                 // Also pass LD_PRELOAD for legacy overlay support
-                if let Some(ld_preload) = build_ld_preload_with_overlay() {
+                if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                     debug!("Also adding LD_PRELOAD: {}", ld_preload);
                     gs_command.push(format!("LD_PRELOAD={}", ld_preload));
                 }
 
+                // gamescope runs with elevated capabilities, so the loader drops
+                // LD_LIBRARY_PATH before the game sees it - pass it explicitly
+                if let Some(ld_path) = ld_library_path_for(self.config) {
+                    gs_command.push(format!("LD_LIBRARY_PATH={}", ld_path));
+                }
+
                 gs_command.extend(command);
                 command = gs_command;
                 using_gamescope = true;
             }
         }
 
+        // Run the game under MangoHud (gamescope uses --mangoapp instead)
+        let mangohud = prepare_mangohud(self.config, using_gamescope);
+        if let Some((_, wrapper)) = &mangohud {
+            command.splice(0..0, wrapper.iter().cloned());
+        }
+
         // Add pre-command if configured
         if let Some(pre_cmd) = self.config.effective_pre_command() {
             let pre_args = shlex::split(pre_cmd)
@@ -105,11 +146,7 @@ impl<'a> NativeRunner<'a> {
             }
         }
 
-        // Add launch args
-        if !self.config.launch_args.is_empty() {
-            debug!("Adding launch args: {:?}", self.config.launch_args);
-            command.extend(self.config.launch_args.clone());
-        }
+        let command = wrap_command(self.config, command)?;
 
         // Extract command and args
         let (cmd, args) = command.split_first()
@@ -121,18 +158,37 @@ impl<'a> NativeRunner<'a> {
         let mut process = Command::new(cmd);
         process.args(args);
 
+        if let Some(dir) = &working_dir {
+            debug!("Working directory: {}", dir.display());
+            process.current_dir(dir);
+        }
+
+        if let Some((config_file, _)) = &mangohud {
+            process.env("MANGOHUD_CONFIGFILE", config_file);
+        }
+
+        // Steer the game onto the configured GPU; explicit env entries below win
+        if let Some(selector) = &self.config.gpu {
+            process.envs(gpu_env_for(selector));
+        }
+
         // Set environment variables
         for (key, value) in &self.config.env {
             debug!("Setting env: {}={}", key, value);
             process.env(key, value);
         }
 
+        if let Some(ld_path) = ld_library_path_for(self.config) {
+            debug!("Setting LD_LIBRARY_PATH: {}", ld_path);
+            process.env("LD_LIBRARY_PATH", ld_path);
+        }
+
         // Set Steam overlay environment variables on the process itself
         // This is critical: gamescope needs to inherit these so the overlay is loaded
         // into gamescope, not just the game.
         if using_gamescope {
             // Set LD_PRELOAD on the process so gamescope loads the overlay
-            if let Some(ld_preload) = build_ld_preload_with_overlay() {
+            if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                 debug!("Setting LD_PRELOAD on gamescope process: {}", ld_preload);
                 process.env("LD_PRELOAD", &ld_preload);
             }
@@ -154,7 +210,7 @@ impl<'a> NativeRunner<'a> {
         } else if self.config.is_gamescope_session {
             // We're inside gamescope (either native session or launched by our wrapper)
             // We still need to set LD_PRELOAD so gameoverlayrenderer.so connects to LIBEI_SOCKET
-            if let Some(ld_preload) = build_ld_preload_with_overlay() {
+            if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                 debug!("In gamescope session, setting LD_PRELOAD: {}", ld_preload);
                 process.env("LD_PRELOAD", &ld_preload);
             }
@@ -163,14 +219,13 @@ impl<'a> NativeRunner<'a> {
             debug!("Setting ENABLE_VK_LAYER_VALVE_steam_overlay_1=1 for gamescope session");
             process.env("ENABLE_VK_LAYER_VALVE_steam_overlay_1", "1");
             process.env("ENABLE_GAMESCOPE_WSI", "1");
+        } else if !self.config.ld_preload_extra.is_empty() {
+            if let Some(ld_preload) = ld_preload_for(self.config, std::env::var("LD_PRELOAD").ok()) {
+                debug!("Setting LD_PRELOAD: {}", ld_preload);
+                process.env("LD_PRELOAD", &ld_preload);
+            }
         }
 
-        // Use exec to replace this process entirely
-        // This is important for Steam Input to work properly
-        info!("Exec'ing into game (replacing this process)");
-        let err = process.exec();
-
-        // If exec returns, it failed
-        Err(AppError::ExecutionFailed(format!("exec failed: {}", err)))
+        Ok(PreparedLaunch { mode: ExecutionMode::Native, proton: None, using_gamescope, process })
     }
 }
@@ -0,0 +1,53 @@
+use super::limits::parse_size;
+use crate::config::MergedConfig;
+use crate::logs::{new_game_log_path, rotate_game_logs};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+
+/// Redirect the game's stdout/stderr to a fresh per-game log file when enabled
+///
+/// The file is attached directly to the game's file descriptors, so capturing
+/// works whether we exec into the game or supervise it.
+pub fn capture_game_output(config: &MergedConfig, process: &mut Command) {
+    let logging = &config.logging;
+    if !logging.capture_game_output {
+        return;
+    }
+
+    let max_total = parse_size(&logging.max_log_size);
+    if max_total.is_none() {
+        warn!("Invalid max_log_size '{}', not limiting log size", logging.max_log_size);
+    }
+    rotate_game_logs(config.app_id, logging.keep_logs, max_total);
+
+    let path = new_game_log_path(config.app_id);
+    let file = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+    let mut file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("Could not open game log {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let _ = writeln!(
+        file,
+        "# {:?} {:?}",
+        process.get_program(),
+        process.get_args().collect::<Vec<_>>()
+    );
+
+    match file.try_clone() {
+        Ok(stderr) => {
+            process.stdout(Stdio::from(file));
+            process.stderr(Stdio::from(stderr));
+            info!("Capturing game output to {}", path.display());
+        }
+        Err(e) => warn!("Could not capture game output: {}", e),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::config::MergedConfig;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// Daemon used to switch power profiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    /// power-profiles-daemon, via powerprofilesctl
+    PowerProfilesDaemon,
+    /// tuned, via tuned-adm
+    Tuned,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        let in_path = |program: &str| {
+            std::env::var("PATH")
+                .map(|path| path.split(':').any(|dir| Path::new(dir).join(program).exists()))
+                .unwrap_or(false)
+        };
+        if in_path("powerprofilesctl") {
+            Some(Self::PowerProfilesDaemon)
+        } else if in_path("tuned-adm") {
+            Some(Self::Tuned)
+        } else {
+            None
+        }
+    }
+
+    fn current(self) -> Option<String> {
+        let output = match self {
+            Self::PowerProfilesDaemon => Command::new("powerprofilesctl").arg("get").output(),
+            Self::Tuned => Command::new("tuned-adm").arg("active").output(),
+        };
+        let output = output.ok().filter(|o| o.status.success())?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match self {
+            Self::PowerProfilesDaemon => Some(stdout.trim().to_string()).filter(|s| !s.is_empty()),
+            Self::Tuned => parse_tuned_active(&stdout),
+        }
+    }
+
+    fn set(self, profile: &str) -> bool {
+        let status = match self {
+            Self::PowerProfilesDaemon => Command::new("powerprofilesctl").args(["set", profile]).status(),
+            Self::Tuned => Command::new("tuned-adm").args(["profile", profile]).status(),
+        };
+        match status {
+            Ok(status) if status.success() => true,
+            Ok(status) => {
+                warn!("Failed to switch power profile to '{}' ({})", profile, status);
+                false
+            }
+            Err(e) => {
+                warn!("Failed to switch power profile to '{}': {}", profile, e);
+                false
+            }
+        }
+    }
+}
+
+/// Extract the profile name from `tuned-adm active` output
+fn parse_tuned_active(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("Current active profile:"))
+        .map(|profile| profile.trim().to_string())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// A power profile switched for a game session, restored when the session ends
+pub struct PowerProfile {
+    backend: Backend,
+    previous: Option<String>,
+}
+
+impl PowerProfile {
+    /// Switch to the configured `power_profile`, remembering the current one
+    ///
+    /// Returns None when no profile is configured or it could not be switched.
+    pub fn switch(config: &MergedConfig) -> Option<Self> {
+        let profile = config.power_profile.as_deref()?;
+        let Some(backend) = Backend::detect() else {
+            warn!("power_profile is set but neither powerprofilesctl nor tuned-adm was found");
+            return None;
+        };
+
+        let previous = backend.current();
+        if previous.as_deref() == Some(profile) {
+            debug!("Power profile is already '{}'", profile);
+            return None;
+        }
+        if !backend.set(profile) {
+            return None;
+        }
+        info!("Switched power profile to '{}'", profile);
+        Some(Self { backend, previous })
+    }
+
+    /// Restore the profile that was active before the switch
+    pub fn restore(&mut self) {
+        let Some(previous) = self.previous.take() else {
+            return;
+        };
+        if self.backend.set(&previous) {
+            info!("Restored power profile '{}'", previous);
+        }
+    }
+}
+
+impl Drop for PowerProfile {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tuned_active() {
+        let output = "Current active profile: throughput-performance\n";
+        assert_eq!(parse_tuned_active(output), Some("throughput-performance".to_string()));
+        assert_eq!(parse_tuned_active("No current active profile.\n"), None);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, warn};
+
+/// Linux distribution families we know package names for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Distro {
+    Arch,
+    Debian,
+    Fedora,
+    OpenSuse,
+    NixOS,
+    Unknown,
+}
+
+impl Distro {
+    /// Detect the distribution family from /etc/os-release (ID and ID_LIKE)
+    fn detect() -> Self {
+        fs::read_to_string("/etc/os-release")
+            .map(|content| Self::from_os_release(&content))
+            .unwrap_or(Distro::Unknown)
+    }
+
+    fn from_os_release(content: &str) -> Self {
+        let mut ids = Vec::new();
+        for line in content.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                if key == "ID" || key == "ID_LIKE" {
+                    let value = value.trim_matches('"').to_lowercase();
+                    ids.extend(value.split_whitespace().map(str::to_string));
+                }
+            }
+        }
+
+        for id in &ids {
+            match id.as_str() {
+                "arch" | "steamos" | "manjaro" | "endeavouros" | "cachyos" => return Distro::Arch,
+                "debian" | "ubuntu" | "pop" | "linuxmint" => return Distro::Debian,
+                "fedora" | "rhel" | "nobara" | "bazzite" => return Distro::Fedora,
+                "opensuse" | "suse" | "opensuse-tumbleweed" => return Distro::OpenSuse,
+                "nixos" => return Distro::NixOS,
+                _ => {}
+            }
+        }
+
+        Distro::Unknown
+    }
+
+    /// Command that finds which package ships a given file
+    fn file_search_hint(&self, library: &str) -> Option<String> {
+        match self {
+            Distro::Arch => Some(format!("pacman -F {}", library)),
+            Distro::Debian => Some(format!("apt-file search {}", library)),
+            Distro::Fedora => Some(format!("dnf provides '*/{}'", library)),
+            Distro::OpenSuse => Some(format!("zypper search --provides {}", library)),
+            Distro::NixOS => Some(format!("nix-locate {}", library)),
+            Distro::Unknown => None,
+        }
+    }
+}
+
+/// Known packages for libraries that old native ports commonly miss
+/// Columns: library, Arch, Debian/Ubuntu, Fedora, openSUSE
+const KNOWN_PACKAGES: &[(&str, &str, &str, &str, &str)] = &[
+    ("libssl.so.1.0.0", "openssl-1.0", "libssl1.0.0", "compat-openssl10", "libopenssl1_0_0"),
+    ("libcrypto.so.1.0.0", "openssl-1.0", "libssl1.0.0", "compat-openssl10", "libopenssl1_0_0"),
+    ("libssl.so.1.1", "openssl-1.1", "libssl1.1", "openssl1.1", "libopenssl1_1"),
+    ("libcrypto.so.1.1", "openssl-1.1", "libssl1.1", "openssl1.1", "libopenssl1_1"),
+    ("libSDL2-2.0.so.0", "sdl2", "libsdl2-2.0-0", "SDL2", "libSDL2-2_0-0"),
+    ("libSDL-1.2.so.0", "sdl12-compat", "libsdl1.2debian", "sdl12-compat", "libSDL-1_2-0"),
+    ("libGLU.so.1", "glu", "libglu1-mesa", "mesa-libGLU", "libGLU1"),
+    ("libopenal.so.1", "openal", "libopenal1", "openal-soft", "libopenal1"),
+    ("libpng12.so.0", "libpng12", "libpng12-0", "libpng12", "libpng12-0"),
+    ("libcurl-gnutls.so.4", "libcurl-gnutls", "libcurl3-gnutls", "libcurl", "libcurl4"),
+    ("libvulkan.so.1", "vulkan-icd-loader", "libvulkan1", "vulkan-loader", "libvulkan1"),
+    ("libgconf-2.so.4", "gconf", "libgconf-2-4", "GConf2", "gconf2"),
+];
+
+/// Look up the package providing a library on the given distribution
+fn package_hint(library: &str, distro: Distro) -> Option<&'static str> {
+    let (_, arch, debian, fedora, suse) = KNOWN_PACKAGES.iter().find(|entry| entry.0 == library)?;
+    match distro {
+        Distro::Arch => Some(arch),
+        Distro::Debian => Some(debian),
+        Distro::Fedora => Some(fedora),
+        Distro::OpenSuse => Some(suse),
+        Distro::NixOS | Distro::Unknown => None,
+    }
+}
+
+/// Extract the names of unresolved libraries from `ldd` output
+fn parse_missing_libraries(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, target) = line.trim().split_once("=>")?;
+            if target.trim() == "not found" {
+                Some(name.trim().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Check that all shared libraries needed by a native binary resolve
+///
+/// Returns the missing library names. Scripts, missing files and anything
+/// `ldd` can't inspect are treated as having nothing missing.
+pub fn find_missing_libraries(binary: &Path, ld_library_path: Option<&str>) -> Vec<String> {
+    if !binary.is_file() {
+        debug!("Skipping library preflight, not a file: {}", binary.display());
+        return Vec::new();
+    }
+
+    let mut command = Command::new("ldd");
+    command.arg(binary);
+    if let Some(path) = ld_library_path {
+        command.env("LD_LIBRARY_PATH", path);
+    }
+
+    match command.output() {
+        Ok(output) => parse_missing_libraries(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            debug!("Could not run ldd for library preflight: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Run the preflight check and warn about missing libraries with package hints
+pub fn check_native_libraries(binary: &Path, ld_library_path: Option<&str>) {
+    let missing = find_missing_libraries(binary, ld_library_path);
+    if missing.is_empty() {
+        debug!("All shared libraries resolved for {}", binary.display());
+        return;
+    }
+
+    let distro = Distro::detect();
+    warn!(
+        "{} is missing {} shared librar{}; the game will probably fail to start:",
+        binary.display(),
+        missing.len(),
+        if missing.len() == 1 { "y" } else { "ies" }
+    );
+    for library in &missing {
+        match (package_hint(library, distro), distro.file_search_hint(library)) {
+            (Some(package), _) => warn!("  {} (try installing: {})", library, package),
+            (None, Some(search)) => warn!("  {} (find the package with: {})", library, search),
+            (None, None) => warn!("  {}", library),
+        }
+    }
+    warn!("Alternatively add a directory containing them to ld_library_path_prepend");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_missing_libraries() {
+        let output = "\tlinux-vdso.so.1 (0x00007ffd)\n\
+            \tlibssl.so.1.0.0 => not found\n\
+            \tlibc.so.6 => /usr/lib/libc.so.6 (0x00007f)\n\
+            \tlibcrypto.so.1.0.0 => not found\n";
+        assert_eq!(
+            parse_missing_libraries(output),
+            vec!["libssl.so.1.0.0", "libcrypto.so.1.0.0"]
+        );
+        assert!(parse_missing_libraries("\tnot a dynamic executable\n").is_empty());
+    }
+
+    #[test]
+    fn test_distro_from_os_release() {
+        assert_eq!(Distro::from_os_release("ID=arch\n"), Distro::Arch);
+        assert_eq!(
+            Distro::from_os_release("ID=steamos\nID_LIKE=arch\n"),
+            Distro::Arch
+        );
+        assert_eq!(
+            Distro::from_os_release("ID=pop\nID_LIKE=\"ubuntu debian\"\n"),
+            Distro::Debian
+        );
+        assert_eq!(Distro::from_os_release("ID=\"fedora\"\n"), Distro::Fedora);
+        assert_eq!(Distro::from_os_release("ID=gentoo\n"), Distro::Unknown);
+    }
+
+    #[test]
+    fn test_package_hint() {
+        assert_eq!(package_hint("libssl.so.1.1", Distro::Arch), Some("openssl-1.1"));
+        assert_eq!(package_hint("libssl.so.1.1", Distro::Debian), Some("libssl1.1"));
+        assert_eq!(package_hint("libssl.so.1.1", Distro::Unknown), None);
+        assert_eq!(package_hint("libunknown.so", Distro::Arch), None);
+    }
+}
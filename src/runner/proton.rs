@@ -1,36 +1,29 @@
-use crate::config::MergedConfig;
+use super::gamescope::gamescope_args_for;
+use super::{launch, with_launch_args, working_dir_for, wrap_command, PreparedLaunch};
+use super::anticheat::anticheat_env;
+use super::libraries::{dll_overrides_for, ld_library_path_for, ld_preload_for};
+use super::mangohud::prepare_mangohud;
+use crate::config::{ExecutionMode, LogLevel, MergedConfig};
 use crate::error::AppError;
-use crate::proton::locate_proton;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::os::unix::process::CommandExt;
-use std::path::PathBuf;
+use crate::gpu::gpu_env_for;
+use crate::logs::{proton_logs_dir, write_log, LogFile};
+use crate::modmanager::routed_command;
+use crate::proton::winetricks::apply_winetricks;
+use crate::proton::{locate_proton_chain, resolve_compat_data_path};
+use crate::steam::get_steam_root;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitCode};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-/// Write a message to the debug log file
+/// Write a debug message to the runner log
 fn log_to_file(message: &str) {
-    if let Ok(home) = std::env::var("HOME") {
-        let log_path = PathBuf::from(&home).join(".steam-command-runner.log");
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            let _ = writeln!(file, "[{}] {}", timestamp, message);
-        }
-    }
+    write_log(LogFile::Runner, LogLevel::Debug, message);
 }
 
 /// Get the Steam overlay library paths for LD_PRELOAD
 fn get_steam_overlay_paths() -> Option<String> {
-    // Try to find Steam installation path
-    let home = std::env::var("HOME").ok()?;
-    let steam_path = PathBuf::from(&home).join(".local/share/Steam");
+    // Overlay libraries live in the Steam installation (native or Flatpak)
+    let steam_path = get_steam_root()?;
 
     let overlay_64 = steam_path.join("ubuntu12_64/gameoverlayrenderer.so");
     let overlay_32 = steam_path.join("ubuntu12_32/gameoverlayrenderer.so");
@@ -126,24 +119,74 @@ fn log_steam_env_vars() {
     info!("=== End Steam Environment Variables ===");
 }
 
+/// umu-run from PATH, if umu-launcher is installed
+fn find_umu_run() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join("umu-run"))
+        .find(|umu| umu.is_file())
+}
+
 /// Runner for games using Proton/Wine
 pub struct ProtonRunner<'a> {
     config: &'a MergedConfig,
     proton_path: PathBuf,
+    /// umu-run to launch through instead of calling Proton directly
+    umu_run: Option<PathBuf>,
 }
 
 impl<'a> ProtonRunner<'a> {
     pub fn new(config: &'a MergedConfig) -> Result<Self, AppError> {
-        let proton_path = locate_proton(config.proton.as_deref())?;
+        let proton_path = locate_proton_chain(&config.proton_chain())?;
         info!("Using Proton at: {}", proton_path.display());
 
         Ok(Self {
             config,
             proton_path,
+            umu_run: None,
         })
     }
 
+    /// Runner that hands the game to umu-run, so protonfixes are applied
+    ///
+    /// Falls back to running Proton directly when umu-launcher isn't installed.
+    pub fn umu(config: &'a MergedConfig) -> Result<Self, AppError> {
+        let mut runner = Self::new(config)?;
+        runner.umu_run = find_umu_run();
+        match &runner.umu_run {
+            Some(umu) => info!("Using umu-run at: {}", umu.display()),
+            None => warn!("umu-run not found in PATH; running Proton directly"),
+        }
+        Ok(runner)
+    }
+
+    /// Install configured winetricks verbs into the prefix before the game starts
+    ///
+    /// Failures are logged rather than aborting the launch; the verbs are retried next time.
+    fn apply_winetricks(&self, compat_data: Option<&Path>) {
+        let Some(compat_data) = compat_data else {
+            warn!("Cannot locate the game's prefix; skipping winetricks");
+            return;
+        };
+
+        log_to_file(&format!("Applying winetricks verbs: {:?}", self.config.winetricks));
+        if let Err(e) = apply_winetricks(
+            self.config.app_id,
+            compat_data,
+            &self.proton_path,
+            &self.config.winetricks,
+        ) {
+            warn!("{}", e);
+            write_log(LogFile::Runner, LogLevel::Warn, &format!("Winetricks failed: {}", e));
+        }
+    }
+
     pub fn run(&self, command: Vec<String>) -> Result<ExitCode, AppError> {
+        launch(self.config, self.prepare(command)?, None)
+    }
+
+    /// Build the game process without starting it
+    pub(super) fn prepare(&self, command: Vec<String>) -> Result<PreparedLaunch, AppError> {
         log_to_file("========================================");
         log_to_file("ProtonRunner::run() starting");
         info!("ProtonRunner starting");
@@ -154,6 +197,27 @@ impl<'a> ProtonRunner<'a> {
         info!("{}", config_msg);
         log_to_file(&config_msg);
 
+        // Outside Steam there is no STEAM_COMPAT_DATA_PATH, so provision a prefix ourselves
+        let compat_data = resolve_compat_data_path(
+            self.config.compat_data_path.as_deref(),
+            self.config.app_id,
+            command.first().map(String::as_str),
+        );
+        match &compat_data {
+            Some(path) if !self.config.dry_run && !path.exists() => {
+                info!("Creating compatdata directory {}", path.display());
+                std::fs::create_dir_all(path)?;
+            }
+            Some(_) => {}
+            None => warn!("No App ID or game path to name a prefix after; Proton will likely fail"),
+        }
+
+        if !self.config.winetricks.is_empty() && !self.config.dry_run {
+            self.apply_winetricks(compat_data.as_deref());
+        }
+
+        let working_dir = command.first().and_then(|game| working_dir_for(self.config, game));
+
         // Build the Proton command
         let mut full_command = Vec::new();
 
@@ -171,9 +235,7 @@ impl<'a> ProtonRunner<'a> {
         if self.config.gamescope_enabled {
             if self.config.is_gamescope_session {
                 debug!("Already in gamescope session, skipping gamescope wrapper");
-            } else if let Some(ref gs_args) = self.config.gamescope_args {
-                let gs_args_parsed = shlex::split(gs_args)
-                    .ok_or_else(|| AppError::GamescopeArgsParse(gs_args.to_string()))?;
+            } else if let Some(gs_args_parsed) = gamescope_args_for(self.config)? {
 
                 debug!("Wrapping with gamescope: {:?}", gs_args_parsed);
                 log_to_file(&format!("Wrapping with gamescope: {:?}", gs_args_parsed));
@@ -194,29 +256,53 @@ impl<'a> ProtonRunner<'a> {
                 full_command.push("ENABLE_GAMESCOPE_WSI=1".to_string());
 
                 // Also pass LD_PRELOAD for legacy overlay support
-                if let Some(ld_preload) = build_ld_preload_with_overlay() {
+                if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                     log_to_file(&format!("Also adding LD_PRELOAD: {}", ld_preload));
                     full_command.push(format!("LD_PRELOAD={}", ld_preload));
                 }
 
+                // LD_LIBRARY_PATH is dropped when gamescope starts (it has elevated
+                // capabilities), so it must also go through the inner env wrapper
+                if let Some(ld_path) = ld_library_path_for(self.config) {
+                    log_to_file(&format!("Also adding LD_LIBRARY_PATH: {}", ld_path));
+                    full_command.push(format!("LD_LIBRARY_PATH={}", ld_path));
+                }
+
                 using_gamescope = true;
             }
         }
 
 
 
-        // Add Proton executable
-        let proton_exe = self.proton_path.join("proton");
-        full_command.push(proton_exe.to_string_lossy().to_string());
+        // Run Proton under MangoHud (gamescope uses --mangoapp instead)
+        let mangohud = prepare_mangohud(self.config, using_gamescope);
+        if let Some((_, wrapper)) = &mangohud {
+            full_command.extend(wrapper.iter().cloned());
+        }
+
+        match &self.umu_run {
+            // umu-run finds Proton through PROTONPATH and picks the verb itself
+            Some(umu) => full_command.push(umu.to_string_lossy().to_string()),
+            None => {
+                // Add Proton executable
+                let proton_exe = self.proton_path.join("proton");
+                full_command.push(proton_exe.to_string_lossy().to_string());
+
+                // Add verb (waitforexitandrun is the standard)
+                full_command.push("waitforexitandrun".to_string());
+            }
+        }
 
-        // Add verb (waitforexitandrun is the standard)
-        full_command.push("waitforexitandrun".to_string());
+        // Route the launch through the mod manager's VFS if configured
+        let command = match &self.config.modmanager {
+            Some(manager) if manager.route_launch => routed_command(manager, command)?,
+            _ => command,
+        };
 
-        // Add game command and args
-        full_command.extend(command);
+        // Add game command and args, with launch args (positioned by %command% if they use it)
+        full_command.extend(with_launch_args(&self.config.launch_args, command));
 
-        // Add launch args
-        full_command.extend(self.config.launch_args.clone());
+        let full_command = wrap_command(self.config, full_command)?;
 
         // Extract command and args
         let (cmd, args) = full_command.split_first()
@@ -228,23 +314,90 @@ impl<'a> ProtonRunner<'a> {
         let mut process = Command::new(cmd);
         process.args(args);
 
-        // Set required Proton environment variables
-        if let Ok(compat_data) = std::env::var("STEAM_COMPAT_DATA_PATH") {
-            process.env("STEAM_COMPAT_DATA_PATH", &compat_data);
-            debug!("STEAM_COMPAT_DATA_PATH={}", compat_data);
+        if let Some(dir) = &working_dir {
+            log_to_file(&format!("Working directory: {}", dir.display()));
+            process.current_dir(dir);
+        }
+
+        // Set required Proton environment variables, filling in what Steam would set
+        if let Some(compat_data) = &compat_data {
+            process.env("STEAM_COMPAT_DATA_PATH", compat_data);
+            debug!("STEAM_COMPAT_DATA_PATH={}", compat_data.display());
+        }
+
+        let client_path = std::env::var_os("STEAM_COMPAT_CLIENT_INSTALL_PATH")
+            .map(PathBuf::from)
+            .or_else(get_steam_root);
+        if let Some(client_path) = client_path {
+            debug!("STEAM_COMPAT_CLIENT_INSTALL_PATH={}", client_path.display());
+            process.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", client_path);
+        }
+
+        // umu-run needs the Proton build and prefix spelled out; GAMEID selects the
+        // protonfixes (umu-<Steam App ID>), and an explicit env entry below overrides it
+        if self.umu_run.is_some() {
+            process.env("PROTONPATH", &self.proton_path);
+            if let Some(app_id) = self.config.app_id {
+                process.env("GAMEID", format!("umu-{}", app_id));
+            }
+            if let Some(compat_data) = &compat_data {
+                process.env("WINEPREFIX", compat_data.join("pfx"));
+            }
+        }
+
+        if let Some((config_file, _)) = &mangohud {
+            process.env("MANGOHUD_CONFIGFILE", config_file);
+        }
+
+        // Steer the game onto the configured GPU
+        if let Some(selector) = &self.config.gpu {
+            process.envs(gpu_env_for(selector));
+        }
+
+        // Typed Proton tunables; explicit env entries below take precedence
+        for (key, value) in self.config.proton_options.env_vars() {
+            debug!("Proton option: {}={}", key, value);
+            process.env(key, value);
         }
 
-        if let Ok(client_path) = std::env::var("STEAM_COMPAT_CLIENT_INSTALL_PATH") {
-            process.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", &client_path);
-            debug!("STEAM_COMPAT_CLIENT_INSTALL_PATH={}", client_path);
+        // Keep Proton logs with ours, where `logs proton` finds them
+        if self.config.proton_options.log == Some(true) {
+            let dir = proton_logs_dir();
+            if !self.config.dry_run {
+                if let Err(e) = std::fs::create_dir_all(&dir) {
+                    warn!("Could not create {}: {}", dir.display(), e);
+                }
+            }
+            process.env("PROTON_LOG_DIR", dir);
         }
 
+        // HDR for DXVK and the Vulkan HDR WSI layer
+        if self.config.hdr_enabled() {
+            process.env("DXVK_HDR", "1");
+            process.env("ENABLE_HDR_WSI", "1");
+        }
+
+        // Point Proton at the EasyAntiCheat/BattlEye runtime the game needs
+        process.envs(anticheat_env(self.config));
+
         // Set user-configured environment variables
         for (key, value) in &self.config.env {
             debug!("Setting env: {}={}", key, value);
             process.env(key, value);
         }
 
+        // Configured DLL overrides, merged into any WINEDLLOVERRIDES from Steam or [env]
+        if let Some(overrides) = dll_overrides_for(self.config) {
+            log_to_file(&format!("Setting WINEDLLOVERRIDES: {}", overrides));
+            process.env("WINEDLLOVERRIDES", overrides);
+        }
+
+        // Prepend configured library directories, keeping Steam's runtime path after them
+        if let Some(ld_path) = ld_library_path_for(self.config) {
+            log_to_file(&format!("Setting LD_LIBRARY_PATH: {}", ld_path));
+            process.env("LD_LIBRARY_PATH", ld_path);
+        }
+
         // Set Steam overlay environment variables on the process itself
         // This is critical: gamescope needs to inherit these so the overlay is loaded
         // into gamescope, not just the game. Steam does this when it sees gamescope
@@ -254,7 +407,7 @@ impl<'a> ProtonRunner<'a> {
 
         if using_gamescope {
             // Set LD_PRELOAD on the process so gamescope loads the overlay
-            if let Some(ld_preload) = build_ld_preload_with_overlay() {
+            if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                 log_to_file(&format!("Setting LD_PRELOAD on gamescope process: {}", ld_preload));
                 info!("Setting LD_PRELOAD on gamescope process: {}", ld_preload);
                 process.env("LD_PRELOAD", &ld_preload);
@@ -277,7 +430,7 @@ impl<'a> ProtonRunner<'a> {
         } else if self.config.is_gamescope_session {
             // We're inside gamescope (either native session or launched by our wrapper)
             // We still need to set LD_PRELOAD so gameoverlayrenderer.so connects to LIBEI_SOCKET
-            if let Some(ld_preload) = build_ld_preload_with_overlay() {
+            if let Some(ld_preload) = ld_preload_for(self.config, build_ld_preload_with_overlay()) {
                 log_to_file(&format!("In gamescope session, setting LD_PRELOAD: {}", ld_preload));
                 info!("In gamescope session, setting LD_PRELOAD: {}", ld_preload);
                 process.env("LD_PRELOAD", &ld_preload);
@@ -287,22 +440,25 @@ impl<'a> ProtonRunner<'a> {
             log_to_file("Setting ENABLE_VK_LAYER_VALVE_steam_overlay_1=1 for gamescope session");
             process.env("ENABLE_VK_LAYER_VALVE_steam_overlay_1", "1");
             process.env("ENABLE_GAMESCOPE_WSI", "1");
+        } else if !self.config.ld_preload_extra.is_empty() {
+            if let Some(ld_preload) = ld_preload_for(self.config, std::env::var("LD_PRELOAD").ok()) {
+                log_to_file(&format!("Setting LD_PRELOAD: {}", ld_preload));
+                process.env("LD_PRELOAD", &ld_preload);
+            }
         }
 
-        // Use exec to replace this process entirely
+        // Use exec to replace this process entirely (unless the launch is supervised)
         // This is important for Steam Input to work properly - Steam Input
         // attaches to the process it launches, and using exec ensures the
         // game IS that process rather than a child of it.
         log_to_file("=== Final command to exec ===");
         log_to_file(&format!("Command: {} {:?}", cmd, args));
-        log_to_file("=== About to exec (this process will be replaced) ===");
+        log_to_file("=== About to launch ===");
         info!("=== Final command to exec ===");
         info!("Command: {} {:?}", cmd, args);
-        info!("=== About to exec (this process will be replaced) ===");
-
-        let err = process.exec();
+        info!("=== About to launch ===");
 
-        // If exec returns, it failed
-        Err(AppError::ExecutionFailed(format!("exec failed: {}", err)))
+        let mode = if self.umu_run.is_some() { ExecutionMode::Umu } else { ExecutionMode::Proton };
+        Ok(PreparedLaunch { mode, proton: Some(self.proton_path.clone()), using_gamescope, process })
     }
 }
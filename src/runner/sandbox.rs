@@ -0,0 +1,231 @@
+use crate::config::{expand_home, MergedConfig, SandboxConfig};
+use crate::error::AppError;
+use crate::steam::{get_library_folders, get_steam_root};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// System directories mounted read-only into every sandbox (missing ones are skipped)
+const SYSTEM_DIRS: &[&str] = &[
+    "/usr",
+    "/etc",
+    "/opt",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/nix",
+    "/run/current-system",
+    "/run/opengl-driver",
+    "/run/opengl-driver-32",
+    "/sys",
+];
+
+/// Devices games need: GPU, controllers and sound
+const DEVICES: &[&str] = &["/dev/dri", "/dev/input", "/dev/snd", "/dev/shm", "/dev/uinput"];
+
+/// Host paths a sandbox is built from
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPaths {
+    /// The user's home directory (replaced inside the sandbox)
+    pub home: PathBuf,
+    /// Directory mounted as the home directory, or None for an empty tmpfs
+    pub private_home: Option<PathBuf>,
+    /// $XDG_RUNTIME_DIR, for the display and sound sockets
+    pub runtime_dir: Option<PathBuf>,
+    /// $WAYLAND_DISPLAY socket name
+    pub wayland_display: Option<String>,
+    /// Steam installation and libraries (game files, runtimes, Proton), read-only
+    pub steam_dirs: Vec<PathBuf>,
+    /// The game's own directories (install dir, prefix, working dir), read-write
+    pub game_dirs: Vec<PathBuf>,
+}
+
+impl SandboxPaths {
+    /// Gather the paths for the current launch from Steam's environment
+    fn detect(config: &MergedConfig) -> Self {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let env_path = |var: &str| std::env::var_os(var).map(PathBuf::from);
+
+        let private_home = config.sandbox.private_home.unwrap_or(true).then(|| {
+            let name = config.app_id.map_or("default".to_string(), |id| id.to_string());
+            dirs::data_dir()
+                .unwrap_or_else(|| home.join(".local/share"))
+                .join("steam-command-runner")
+                .join("sandbox")
+                .join(name)
+        });
+
+        let mut steam_dirs: Vec<PathBuf> = get_steam_root().into_iter().collect();
+        steam_dirs.push(home.join(".steam"));
+        steam_dirs.extend(get_library_folders().unwrap_or_default());
+        steam_dirs.extend(env_path("STEAM_COMPAT_CLIENT_INSTALL_PATH"));
+        if let Ok(tools) = std::env::var("STEAM_COMPAT_TOOL_PATHS") {
+            steam_dirs.extend(tools.split(':').filter(|p| !p.is_empty()).map(PathBuf::from));
+        }
+
+        let mut game_dirs: Vec<PathBuf> = Vec::new();
+        game_dirs.extend(std::env::current_dir().ok());
+        game_dirs.extend(env_path("STEAM_COMPAT_INSTALL_PATH"));
+        game_dirs.extend(env_path("STEAM_COMPAT_DATA_PATH"));
+        game_dirs.extend(env_path("STEAM_COMPAT_SHADER_PATH"));
+
+        Self {
+            home,
+            private_home,
+            runtime_dir: env_path("XDG_RUNTIME_DIR"),
+            wayland_display: std::env::var("WAYLAND_DISPLAY").ok(),
+            steam_dirs,
+            game_dirs,
+        }
+    }
+}
+
+fn push_bind(args: &mut Vec<String>, flag: &str, path: &Path) {
+    let path = path.to_string_lossy().to_string();
+    args.push(flag.to_string());
+    args.push(path.clone());
+    args.push(path);
+}
+
+/// Build the bwrap command line (up to and including "--") for a sandbox
+///
+/// The host filesystem is not visible except for system directories, Steam and
+/// the game's own directories; home is replaced by a private or empty directory.
+pub fn bwrap_args(sandbox: &SandboxConfig, paths: &SandboxPaths) -> Result<Vec<String>, AppError> {
+    let mut args = vec![
+        "bwrap".to_string(),
+        "--unshare-all".to_string(),
+        "--die-with-parent".to_string(),
+    ];
+    if sandbox.network.unwrap_or(true) {
+        args.push("--share-net".to_string());
+    }
+
+    for dir in SYSTEM_DIRS {
+        push_bind(&mut args, "--ro-bind-try", Path::new(dir));
+    }
+    args.extend(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].map(String::from));
+    for device in DEVICES {
+        push_bind(&mut args, "--dev-bind-try", Path::new(device));
+    }
+    push_bind(&mut args, "--ro-bind-try", Path::new("/tmp/.X11-unix"));
+
+    // Home goes first so the binds below can be placed inside it
+    let home = paths.home.to_string_lossy().to_string();
+    match &paths.private_home {
+        Some(private) => {
+            args.extend(["--bind".to_string(), private.to_string_lossy().to_string(), home]);
+        }
+        None => args.extend(["--tmpfs".to_string(), home]),
+    }
+
+    if let Some(runtime) = &paths.runtime_dir {
+        args.extend(["--tmpfs".to_string(), runtime.to_string_lossy().to_string()]);
+        if let Some(display) = &paths.wayland_display {
+            push_bind(&mut args, "--ro-bind-try", &runtime.join(display));
+        }
+        if sandbox.share_pulse.unwrap_or(true) {
+            push_bind(&mut args, "--ro-bind-try", &runtime.join("pulse"));
+            push_bind(&mut args, "--ro-bind-try", &runtime.join("pipewire-0"));
+        }
+    }
+
+    for dir in &paths.steam_dirs {
+        push_bind(&mut args, "--ro-bind-try", dir);
+    }
+    for path in &sandbox.read_only_paths {
+        push_bind(&mut args, "--ro-bind-try", &expand_home(path));
+    }
+    // Writable binds come last so they win over read-only parents (e.g. compatdata)
+    for dir in &paths.game_dirs {
+        push_bind(&mut args, "--bind-try", dir);
+    }
+    for path in &sandbox.allow_paths {
+        push_bind(&mut args, "--bind-try", &expand_home(path));
+    }
+
+    if let Some(extra) = &sandbox.extra_args {
+        let extra =
+            shlex::split(extra).ok_or_else(|| AppError::Sandbox(format!("invalid extra_args: {}", extra)))?;
+        args.extend(extra);
+    }
+
+    args.push("--".to_string());
+    Ok(args)
+}
+
+fn bwrap_available() -> bool {
+    std::env::var("PATH")
+        .map(|path| path.split(':').any(|dir| Path::new(dir).join("bwrap").exists()))
+        .unwrap_or(false)
+}
+
+/// Wrap the command in a bwrap sandbox when one is enabled
+///
+/// Unlike other optional wrappers this refuses to launch when bwrap is missing,
+/// rather than silently running the game unsandboxed.
+pub fn wrap_with_sandbox(config: &MergedConfig, command: Vec<String>) -> Result<Vec<String>, AppError> {
+    if !config.sandbox.is_enabled() {
+        return Ok(command);
+    }
+    if !bwrap_available() && !config.dry_run {
+        return Err(AppError::Sandbox(
+            "sandbox is enabled but bwrap was not found in PATH".to_string(),
+        ));
+    }
+
+    let paths = SandboxPaths::detect(config);
+    if let Some(private) = &paths.private_home {
+        if !config.dry_run {
+            std::fs::create_dir_all(private)?;
+        }
+    }
+    debug!("Sandbox paths: {:?}", paths);
+
+    info!("Running game in a bubblewrap sandbox");
+    let mut wrapped = bwrap_args(&config.sandbox, &paths)?;
+    wrapped.extend(command);
+    Ok(wrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(args: &[String], window: &[&str]) -> Option<usize> {
+        args.windows(window.len()).position(|w| w == window)
+    }
+
+    #[test]
+    fn test_bwrap_args() {
+        let sandbox = SandboxConfig {
+            enabled: Some(true),
+            network: Some(false),
+            allow_paths: vec!["~/Mods".to_string()],
+            ..SandboxConfig::default()
+        };
+        let paths = SandboxPaths {
+            home: PathBuf::from("/home/user"),
+            private_home: Some(PathBuf::from("/home/user/.local/share/scr/sandbox/440")),
+            runtime_dir: Some(PathBuf::from("/run/user/1000")),
+            wayland_display: Some("wayland-0".to_string()),
+            steam_dirs: vec![PathBuf::from("/home/user/.local/share/Steam")],
+            game_dirs: vec![PathBuf::from("/home/user/.local/share/Steam/steamapps/common/TF2")],
+        };
+
+        let args = bwrap_args(&sandbox, &paths).unwrap();
+        assert_eq!(args.first().map(String::as_str), Some("bwrap"));
+        assert_eq!(args.last().map(String::as_str), Some("--"));
+        assert!(!args.contains(&"--share-net".to_string()));
+
+        let home = position(&args, &["--bind", "/home/user/.local/share/scr/sandbox/440", "/home/user"]).unwrap();
+        let steam = position(&args, &["--ro-bind-try", "/home/user/.local/share/Steam"]).unwrap();
+        let game = position(&args, &["--bind-try", "/home/user/.local/share/Steam/steamapps/common/TF2"]).unwrap();
+        assert!(home < steam && steam < game);
+        let mods = dirs::home_dir().unwrap().join("Mods").to_string_lossy().to_string();
+        assert!(position(&args, &["--bind-try", &mods, &mods]).is_some());
+        assert!(position(&args, &["--ro-bind-try", "/run/user/1000/wayland-0"]).is_some());
+        assert!(position(&args, &["--ro-bind-try", "/run/user/1000/pulse"]).is_some());
+    }
+}
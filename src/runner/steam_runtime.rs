@@ -0,0 +1,94 @@
+use crate::config::MergedConfig;
+use crate::error::AppError;
+use crate::steam::get_library_folders;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Script Steam starts container runtimes through
+const ENTRY_POINT: &str = "_v2-entry-point";
+
+/// Wrap a native game command in the configured Steam Linux Runtime container
+///
+/// Mirrors what Steam does when a runtime is picked as the compatibility tool:
+/// `<runtime>/_v2-entry-point --verb=waitforexitandrun -- <command>`. A command
+/// Steam already started through a runtime is left alone.
+pub fn wrap_with_steam_runtime(config: &MergedConfig, command: Vec<String>) -> Result<Vec<String>, AppError> {
+    let Some(dir_name) = config.steam_runtime.dir_name() else {
+        return Ok(command);
+    };
+    if command.iter().any(|arg| is_entry_point(arg)) {
+        debug!("Command already runs in a Steam Linux Runtime, not wrapping it again");
+        return Ok(command);
+    }
+
+    let libraries = get_library_folders().unwrap_or_default();
+    let entry_point = find_entry_point(&libraries, dir_name).ok_or_else(|| {
+        AppError::SteamRuntimeNotFound(dir_name.to_string(), config.steam_runtime.app_id().unwrap_or(0))
+    })?;
+    info!("Running in {}", dir_name);
+    Ok(runtime_command(&entry_point, command))
+}
+
+/// Entry point of an installed runtime, searching each library's steamapps directory
+fn find_entry_point(libraries: &[PathBuf], dir_name: &str) -> Option<PathBuf> {
+    libraries
+        .iter()
+        .map(|steamapps| steamapps.join("common").join(dir_name).join(ENTRY_POINT))
+        .find(|path| path.is_file())
+}
+
+fn is_entry_point(arg: &str) -> bool {
+    Path::new(arg).file_name().is_some_and(|name| name == ENTRY_POINT)
+}
+
+fn runtime_command(entry_point: &Path, command: Vec<String>) -> Vec<String> {
+    let mut result = vec![
+        entry_point.to_string_lossy().to_string(),
+        "--verb=waitforexitandrun".to_string(),
+        "--".to_string(),
+    ];
+    result.extend(command);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_find_entry_point() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("lib1/steamapps");
+        let second = dir.path().join("lib2/steamapps");
+        let runtime = second.join("common/SteamLinuxRuntime_sniper");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&runtime).unwrap();
+        fs::write(runtime.join(ENTRY_POINT), "").unwrap();
+
+        let libraries = vec![first, second];
+        assert_eq!(
+            find_entry_point(&libraries, "SteamLinuxRuntime_sniper"),
+            Some(runtime.join(ENTRY_POINT))
+        );
+        assert_eq!(find_entry_point(&libraries, "SteamLinuxRuntime_soldier"), None);
+    }
+
+    #[test]
+    fn test_runtime_command() {
+        let entry_point = Path::new("/steam/steamapps/common/SteamLinuxRuntime_sniper/_v2-entry-point");
+        let command = runtime_command(entry_point, vec!["/games/game.sh".to_string(), "-fullscreen".to_string()]);
+        assert_eq!(
+            command,
+            [
+                "/steam/steamapps/common/SteamLinuxRuntime_sniper/_v2-entry-point",
+                "--verb=waitforexitandrun",
+                "--",
+                "/games/game.sh",
+                "-fullscreen"
+            ]
+        );
+        assert!(is_entry_point(&command[0]));
+        assert!(!is_entry_point("/games/game.sh"));
+    }
+}
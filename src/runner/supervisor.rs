@@ -0,0 +1,323 @@
+use super::companions::Companions;
+use super::discord::DiscordPresence;
+use super::events;
+use super::inhibit::Inhibitor;
+use super::limits::{scope_unit, stop_scope};
+use super::power::PowerProfile;
+use crate::config::MergedConfig;
+use crate::control::{command_line, ControlServer, SessionStatus};
+use crate::error::AppError;
+use crate::hooks::{self, HookContext};
+use crate::notify::{notify, Urgency};
+use crate::playtime::{self, Session};
+use crate::saves::{backup_saves, sync_enabled, sync_for_launch, SyncDirection};
+use crate::steam::game_name;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitCode, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Signals passed on to the game while we supervise it
+const FORWARDED_SIGNALS: [libc::c_int; 5] = [
+    libc::SIGTERM,
+    libc::SIGINT,
+    libc::SIGHUP,
+    libc::SIGQUIT,
+    libc::SIGUSR1,
+];
+
+/// PID of the supervised game, read by the signal handler (0 when none)
+///
+/// The game leads its own process group, so this is also the group's ID.
+static CHILD_PID: AtomicI32 = AtomicI32::new(0);
+
+/// Set once a termination signal arrives, so a stopped game isn't treated as a crash
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// Whether this launch needs us to stay alive while the game runs
+///
+/// Normally the runner exec()s into the game so it disappears from the process
+/// tree. Anything that must happen after the game exits requires supervision.
+pub fn needs_supervision(config: &MergedConfig) -> bool {
+    if config.supervise
+        || !config.companions.is_empty()
+        || config.limits.scope == Some(true)
+        || config.restart_on_crash.enabled
+        || config.track_playtime
+        || config.dbus_signals
+        || config.inhibit_sleep
+        || config.power_profile.is_some()
+        || config.discord.is_enabled()
+        || config.saves.as_ref().is_some_and(|saves| saves.backup_on_exit)
+        || sync_enabled(config)
+    {
+        return true;
+    }
+    if !config.post_exit_hooks.is_empty() {
+        warn!("post_exit hook is configured but only runs with supervise = true");
+    }
+    if !config.on_crash_hooks.is_empty() {
+        warn!("on_crash hook is configured but only runs with supervise = true");
+    }
+    false
+}
+
+/// Run the game as a child process and clean up after it exits
+///
+/// Termination signals we receive are forwarded to the game's whole process
+/// group, so Proton, Wine and everything else the game started stop with it.
+/// The game's exit status becomes our own once post-exit work is done. With a
+/// `startup_check`, a first run that fails within that window is reported as
+/// `GamescopeFailed` (after cleanup, without running post_exit hooks).
+pub fn supervise(
+    config: &MergedConfig,
+    mut process: Command,
+    mut context: HookContext,
+    mut startup_check: Option<Duration>,
+) -> Result<ExitCode, AppError> {
+    let mut power_profile = PowerProfile::switch(config);
+    let mut inhibitor = Inhibitor::acquire(config);
+    let mut companions = Companions::start(&config.companions);
+    let mut presence = DiscordPresence::start(config);
+    let scope_unit = scope_unit(config);
+
+    install_signal_forwarding();
+    let _control = ControlServer::start(
+        SessionStatus {
+            app_id: config.app_id,
+            name: config.name.clone().or_else(|| config.app_id.and_then(game_name)),
+            pid: std::process::id(),
+            game_pid: None,
+            started: playtime::now(),
+            proton: context.proton.clone(),
+            profile: config.profile.clone(),
+            command: command_line(&process),
+        },
+        &CHILD_PID,
+    );
+    let restart = &config.restart_on_crash;
+    let mut restarts = 0;
+    let result = loop {
+        let start = playtime::now();
+        let status = match run_once(config, &mut process, startup_check.take()) {
+            Ok(status) => status,
+            Err(e) => break Err(e),
+        };
+        if config.track_playtime {
+            record_playtime(config, start, status, context.proton.clone());
+        }
+        context.exit_code = Some(status_code(status));
+        context.signal = status.signal();
+        if status.success() || STOPPING.load(Ordering::SeqCst) {
+            break Ok(status);
+        }
+
+        warn!("Game crashed: {}", status);
+        notify(
+            Urgency::Critical,
+            &format!("{} crashed", config.display_name()),
+            &status.to_string(),
+        );
+        let _ = hooks::run_all("on_crash", &config.on_crash_hooks, &context);
+        if !restart.enabled || restarts >= restart.max_retries {
+            break Ok(status);
+        }
+
+        let delay = backoff(restart.backoff_secs, restarts);
+        restarts += 1;
+        info!(
+            "Restarting game in {}s (restart {} of {})",
+            delay.as_secs(),
+            restarts,
+            restart.max_retries
+        );
+        thread::sleep(delay);
+        if STOPPING.load(Ordering::SeqCst) {
+            break Ok(status);
+        }
+    };
+
+    presence.clear();
+    companions.stop();
+    inhibitor.release();
+    if let Some(power_profile) = &mut power_profile {
+        power_profile.restore();
+    }
+    if config.limits.scope == Some(true) {
+        if let Some(unit) = &scope_unit {
+            stop_scope(unit);
+        }
+    }
+
+    let status = result?;
+    if config.saves.as_ref().is_some_and(|saves| saves.backup_on_exit) {
+        match backup_saves(config) {
+            Ok(backup) => info!("Backed up {} save files to {}", backup.files, backup.path.display()),
+            Err(e) => warn!("Failed to back up saves: {}", e),
+        }
+    }
+    sync_for_launch(config, SyncDirection::Push);
+    let _ = hooks::run_all("post_exit", &config.post_exit_hooks, &context);
+
+    Ok(exit_code(status))
+}
+
+/// Start the game and wait for it to exit
+///
+/// With a `startup_check`, failing to start or exiting with an error within that
+/// window is a `GamescopeFailed` error rather than a finished run.
+fn run_once(
+    config: &MergedConfig,
+    process: &mut Command,
+    startup_check: Option<Duration>,
+) -> Result<ExitStatus, AppError> {
+    info!("Starting game under supervision");
+    // Its own process group lets a stop reach every process the game starts
+    process.process_group(0);
+    let mut child = process.spawn().map_err(|e| match startup_check {
+        Some(_) => AppError::GamescopeFailed(format!("spawn failed: {}", e)),
+        None => AppError::ExecutionFailed(format!("spawn failed: {}", e)),
+    })?;
+
+    CHILD_PID.store(child.id() as i32, Ordering::SeqCst);
+    if let Some(window) = startup_check {
+        if let Some(status) = exit_within(&mut child, window)? {
+            if !status.success() && !STOPPING.load(Ordering::SeqCst) {
+                CHILD_PID.store(0, Ordering::SeqCst);
+                return Err(AppError::GamescopeFailed(format!("exited during startup: {}", status)));
+            }
+        }
+    }
+    events::game_started(config, child.id());
+    let status = child.wait();
+    CHILD_PID.store(0, Ordering::SeqCst);
+    let status = status?;
+    info!("Game exited: {}", status);
+    events::game_stopped(config, status_code(status));
+    Ok(status)
+}
+
+/// Wait up to `window` for a child to exit, returning its status if it did
+fn exit_within(child: &mut Child, window: Duration) -> Result<Option<ExitStatus>, AppError> {
+    let deadline = Instant::now() + window;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(child.try_wait()?)
+}
+
+/// Add a finished run of the game to the playtime store
+fn record_playtime(config: &MergedConfig, start: u64, status: ExitStatus, proton: Option<String>) {
+    let session = Session {
+        app_id: config.app_id,
+        name: config.name.clone(),
+        start,
+        end: playtime::now(),
+        exit_code: status_code(status),
+        proton,
+    };
+    if let Err(e) = playtime::record_session(&session) {
+        warn!("Failed to record playtime: {}", e);
+    }
+}
+
+/// Delay before a restart: the base delay, doubled for each earlier restart
+fn backoff(base_secs: u64, restarts: u32) -> Duration {
+    Duration::from_secs(base_secs.saturating_mul(1 << restarts.min(16)))
+}
+
+/// Forward termination signals to the supervised game instead of dying first
+fn install_signal_forwarding() {
+    for signal in FORWARDED_SIGNALS {
+        // SAFETY: the handler only uses atomics and calls kill(), all async-signal-safe
+        unsafe {
+            libc::signal(signal, forward_signal as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        }
+    }
+    debug!("Forwarding signals {:?} to the game", FORWARDED_SIGNALS);
+}
+
+extern "C" fn forward_signal(signal: libc::c_int) {
+    if signal != libc::SIGUSR1 {
+        STOPPING.store(true, Ordering::SeqCst);
+    }
+    let pid = CHILD_PID.load(Ordering::SeqCst);
+    if pid > 0 {
+        // SAFETY: kill() is async-signal-safe
+        unsafe {
+            libc::kill(signal_target(pid, signal), signal);
+        }
+    }
+}
+
+/// Where to send a forwarded signal: termination signals go to the game's
+/// process group, SIGUSR1 only to the game itself
+///
+/// SIGUSR1 would kill helpers like wineserver that don't handle it.
+fn signal_target(pid: libc::pid_t, signal: libc::c_int) -> libc::pid_t {
+    if signal == libc::SIGUSR1 {
+        pid
+    } else {
+        -pid
+    }
+}
+
+/// Convert a child's exit status into our own exit code
+///
+/// A game killed by a signal is reported the way a shell would: 128 + signal number.
+fn exit_code(status: ExitStatus) -> ExitCode {
+    ExitCode::from(status_code(status))
+}
+
+fn status_code(status: ExitStatus) -> u8 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code as u8,
+        (None, Some(signal)) => (128 + signal) as u8,
+        (None, None) => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(5, 0), Duration::from_secs(5));
+        assert_eq!(backoff(5, 2), Duration::from_secs(20));
+        assert_eq!(backoff(0, 3), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_exit_within() {
+        let mut child = Command::new("false").spawn().unwrap();
+        let status = exit_within(&mut child, Duration::from_secs(5)).unwrap();
+        assert!(status.is_some_and(|s| !s.success()));
+
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        assert!(exit_within(&mut child, Duration::from_millis(100)).unwrap().is_none());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_signal_target() {
+        assert_eq!(signal_target(4242, libc::SIGTERM), -4242);
+        assert_eq!(signal_target(4242, libc::SIGINT), -4242);
+        assert_eq!(signal_target(4242, libc::SIGUSR1), 4242);
+    }
+
+    #[test]
+    fn test_status_code() {
+        // Raw wait statuses: exit code in the high byte, signal in the low bits
+        assert_eq!(status_code(ExitStatus::from_raw(0)), 0);
+        assert_eq!(status_code(ExitStatus::from_raw(3 << 8)), 3);
+        assert_eq!(status_code(ExitStatus::from_raw(libc::SIGKILL)), 137);
+        assert_eq!(status_code(ExitStatus::from_raw(libc::SIGTERM)), 143);
+    }
+}
@@ -0,0 +1,131 @@
+use super::gamescope::gamescope_args_for;
+use super::libraries::{dll_overrides_for, ld_library_path_for, ld_preload_for};
+use super::mangohud::prepare_mangohud;
+use super::{launch, with_launch_args, working_dir_for, wrap_command, PreparedLaunch};
+use crate::config::{ExecutionMode, MergedConfig};
+use crate::error::AppError;
+use crate::gpu::gpu_env_for;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use tracing::{debug, info};
+
+/// Runner for Windows games using a plain Wine install
+///
+/// Needs nothing from Steam: the prefix comes from `[wine]` (or a per-game
+/// default), so `run game.exe` works for games Steam has never seen.
+pub struct WineRunner<'a> {
+    config: &'a MergedConfig,
+}
+
+impl<'a> WineRunner<'a> {
+    pub fn new(config: &'a MergedConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn run(&self, command: Vec<String>) -> Result<ExitCode, AppError> {
+        launch(self.config, self.prepare(command)?, None)
+    }
+
+    /// Build the game process without starting it
+    pub(super) fn prepare(&self, command: Vec<String>) -> Result<PreparedLaunch, AppError> {
+        let wine = self.config.wine.binary_path();
+        let prefix = self.config.wine.prefix_path(self.config.app_id);
+        info!("Using Wine '{}' with prefix {}", wine, prefix.display());
+
+        // Wine creates the prefix itself, but not its parent directories
+        if !self.config.dry_run {
+            if let Some(parent) = prefix.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        // Windows games expect to start in their own directory
+        let working_dir = command.first().and_then(|game| {
+            working_dir_for(self.config, game).or_else(|| exe_dir(game))
+        });
+
+        let mut full_command = Vec::new();
+
+        // Add pre-command if configured
+        if let Some(pre_cmd) = self.config.effective_pre_command() {
+            let pre_args = shlex::split(pre_cmd)
+                .ok_or_else(|| AppError::PreCommandParse(pre_cmd.to_string()))?;
+            full_command.extend(pre_args);
+        }
+
+        // Add gamescope wrapper if enabled and not already in a gamescope session
+        let mut using_gamescope = false;
+        if self.config.gamescope_enabled && !self.config.is_gamescope_session {
+            if let Some(gs_args) = gamescope_args_for(self.config)? {
+                debug!("Wrapping with gamescope: {:?}", gs_args);
+                full_command.push("gamescope".to_string());
+                full_command.extend(gs_args);
+                full_command.push("--".to_string());
+
+                // LD_LIBRARY_PATH is dropped when gamescope starts, so pass it through env
+                if let Some(ld_path) = ld_library_path_for(self.config) {
+                    full_command.push("env".to_string());
+                    full_command.push(format!("LD_LIBRARY_PATH={}", ld_path));
+                }
+                using_gamescope = true;
+            }
+        }
+
+        // Run Wine under MangoHud (gamescope uses --mangoapp instead)
+        let mangohud = prepare_mangohud(self.config, using_gamescope);
+        if let Some((_, wrapper)) = &mangohud {
+            full_command.extend(wrapper.iter().cloned());
+        }
+
+        full_command.push(wine);
+        full_command.extend(with_launch_args(&self.config.launch_args, command));
+
+        let full_command = wrap_command(self.config, full_command)?;
+        let (cmd, args) = full_command.split_first().ok_or(AppError::NoCommand)?;
+        info!("Executing via Wine: {} {:?}", cmd, args);
+
+        let mut process = Command::new(cmd);
+        process.args(args);
+        if let Some(dir) = &working_dir {
+            debug!("Working directory: {}", dir.display());
+            process.current_dir(dir);
+        }
+
+        process.env("WINEPREFIX", &prefix);
+        if let Some((config_file, _)) = &mangohud {
+            process.env("MANGOHUD_CONFIGFILE", config_file);
+        }
+        if let Some(selector) = &self.config.gpu {
+            process.envs(gpu_env_for(selector));
+        }
+        if self.config.hdr_enabled() {
+            process.env("DXVK_HDR", "1");
+            process.env("ENABLE_HDR_WSI", "1");
+        }
+        for (key, value) in &self.config.env {
+            debug!("Setting env: {}={}", key, value);
+            process.env(key, value);
+        }
+        if let Some(overrides) = dll_overrides_for(self.config) {
+            process.env("WINEDLLOVERRIDES", overrides);
+        }
+        if let Some(ld_path) = ld_library_path_for(self.config) {
+            process.env("LD_LIBRARY_PATH", ld_path);
+        }
+        if !self.config.ld_preload_extra.is_empty() {
+            if let Some(ld_preload) = ld_preload_for(self.config, std::env::var("LD_PRELOAD").ok()) {
+                process.env("LD_PRELOAD", ld_preload);
+            }
+        }
+
+        Ok(PreparedLaunch { mode: ExecutionMode::Wine, proton: None, using_gamescope, process })
+    }
+}
+
+/// Directory containing the game executable, when the path has one
+fn exe_dir(game: &str) -> Option<PathBuf> {
+    Path::new(game)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+}
@@ -0,0 +1,398 @@
+//! Backing up, restoring and syncing a game's save files
+
+mod sync;
+
+pub use sync::{sync_enabled, sync_for_launch, sync_saves, SyncDirection, SyncReport};
+
+use crate::config::{expand_home, glob_match, ExecutionMode, MergedConfig, SavesConfig};
+use crate::error::AppError;
+use crate::logs::timestamp;
+use crate::proton::{list_archives, resolve_compat_data_path};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Archive entry recording the save paths; files are stored under `<index>/` of their path
+const MANIFEST: &str = "paths.json";
+
+/// A save backup that was just written
+#[derive(Debug)]
+pub struct SaveBackup {
+    pub path: PathBuf,
+    /// Number of files in the backup
+    pub files: usize,
+}
+
+/// Directory save backups for an app are stored in
+pub fn saves_backup_dir(app_id: u32) -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(".local/share"))
+        .join("steam-command-runner")
+        .join("save-backups")
+        .join(app_id.to_string())
+}
+
+/// Save backups for an app, oldest first
+pub fn list_save_backups(app_id: u32) -> Vec<PathBuf> {
+    list_archives(&saves_backup_dir(app_id))
+}
+
+/// The game's `[saves]` table and App ID, failing when either is missing
+fn saves_config(config: &MergedConfig) -> Result<(u32, &SavesConfig), AppError> {
+    let app_id = config
+        .app_id
+        .ok_or_else(|| AppError::Saves("save backups need the game's App ID".to_string()))?;
+    let saves = config.saves.as_ref().ok_or_else(|| {
+        AppError::Saves(format!(
+            "no [saves] configured for app {} (see 'config edit --app-id {}')",
+            app_id, app_id
+        ))
+    })?;
+    Ok((app_id, saves))
+}
+
+/// The game's save paths with "~/" and `%prefix_path%` expanded
+pub fn save_paths(config: &MergedConfig) -> Result<Vec<PathBuf>, AppError> {
+    let (_, saves) = saves_config(config)?;
+    let prefix = prefix_path(config);
+    saves
+        .paths
+        .iter()
+        .map(|path| {
+            let path = match (&prefix, path.contains("%prefix_path%")) {
+                (Some(prefix), true) => path.replace("%prefix_path%", &prefix.to_string_lossy()),
+                (None, true) => {
+                    return Err(AppError::Saves(format!("cannot locate the game's prefix for {}", path)))
+                }
+                (_, false) => path.clone(),
+            };
+            let path = expand_home(&path);
+            if !path.is_absolute() {
+                return Err(AppError::Saves(format!(
+                    "save path {} must be absolute, start with ~/ or use %prefix_path%",
+                    path.display()
+                )));
+            }
+            Ok(path)
+        })
+        .collect()
+}
+
+/// The game's Wine prefix: the `[wine]` prefix in Wine mode, else Proton's `pfx`
+fn prefix_path(config: &MergedConfig) -> Option<PathBuf> {
+    match config.mode {
+        ExecutionMode::Wine => Some(config.wine.prefix_path(config.app_id)),
+        _ => resolve_compat_data_path(config.compat_data_path.as_deref(), config.app_id, None)
+            .map(|compat_data| compat_data.join("pfx")),
+    }
+}
+
+/// Back up the game's saves, then delete backups beyond its `keep` setting
+pub fn backup_saves(config: &MergedConfig) -> Result<SaveBackup, AppError> {
+    let (app_id, saves) = saves_config(config)?;
+    let backup = create_backup(config)?;
+    prune_backups(app_id, saves.keep);
+    Ok(backup)
+}
+
+/// Write a new backup of the game's saves, named by date
+fn create_backup(config: &MergedConfig) -> Result<SaveBackup, AppError> {
+    let (app_id, saves) = saves_config(config)?;
+    let paths = save_paths(config)?;
+    let dir = saves_backup_dir(app_id);
+    fs::create_dir_all(&dir)?;
+    let output = unused_name(&dir, &timestamp());
+
+    info!("Backing up saves for app {} to {}", app_id, output.display());
+    let partial = output.with_extension("zst.part");
+    let files = match write_archive(&paths, saves, &partial) {
+        Ok(0) => {
+            let _ = fs::remove_file(&partial);
+            return Err(AppError::Saves(format!(
+                "no save files found in {}",
+                paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+            )));
+        }
+        Ok(files) => files,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial, &output)?;
+
+    Ok(SaveBackup { path: output, files })
+}
+
+/// `<stamp>.tar.zst` in `dir`, or `<stamp>_2.tar.zst` and so on when a backup was
+/// already taken that second (`_` sorts after `.`, keeping the order chronological)
+fn unused_name(dir: &Path, stamp: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.tar.zst", stamp));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.tar.zst", stamp, n));
+        n += 1;
+    }
+    path
+}
+
+/// Delete the oldest backups so at most `keep` remain
+fn prune_backups(app_id: u32, keep: usize) {
+    let backups = list_save_backups(app_id);
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in &backups[..excess] {
+        debug!("Removing old save backup {}", old.display());
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// Restore the files in a save backup to the game's save paths
+///
+/// Each save path in the backup is restored to the game's save path at the same
+/// position in its config, not to where it was backed up from, so a backup made
+/// on another machine or before the prefix moved lands where the game looks now.
+/// The current saves are backed up first, so a restore can be undone. Files in
+/// the backup replace the current ones; other files are left alone. Returns the
+/// backup of the current saves, if there were any, and the number of files restored.
+pub fn restore_saves(config: &MergedConfig, archive: &Path) -> Result<(Option<SaveBackup>, usize), AppError> {
+    let (app_id, saves) = saves_config(config)?;
+    if !archive.is_file() {
+        return Err(AppError::Saves(format!("backup not found: {}", archive.display())));
+    }
+    let paths = save_paths(config)?;
+    let backed_up = read_manifest(archive)?;
+    if backed_up.len() != paths.len() {
+        return Err(AppError::Saves(format!(
+            "{} holds {} save paths but app {} has {} configured; not restoring to the wrong places",
+            archive.display(),
+            backed_up.len(),
+            app_id,
+            paths.len()
+        )));
+    }
+
+    let current = match create_backup(config) {
+        Ok(backup) => Some(backup),
+        Err(AppError::Saves(e)) => {
+            debug!("Not backing up the current saves: {}", e);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    info!("Restoring saves for app {} from {}", app_id, archive.display());
+    let restored = unpack_archive(archive, &paths)?;
+    prune_backups(app_id, saves.keep);
+    Ok((current, restored))
+}
+
+/// Whether a file, given relative to its save directory, is backed up
+fn wanted(relative: &str, saves: &SavesConfig) -> bool {
+    (saves.include.is_empty() || saves.include.iter().any(|p| glob_match(p, relative)))
+        && !saves.exclude.iter().any(|p| glob_match(p, relative))
+}
+
+/// Files under a save directory, with their paths relative to it
+///
+/// Symlinks to files are followed; symlinked directories are not.
+fn files_under(dir: &Path, relative: &str, out: &mut Vec<(PathBuf, String)>) -> Result<(), AppError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = if relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative, name)
+        };
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            files_under(&path, &relative, out)?;
+        } else if path.is_file() {
+            out.push((path, relative));
+        }
+    }
+    Ok(())
+}
+
+/// Every file to back up, with its name in the archive (`<index>/<relative path>`,
+/// or just `<index>` for a save path that is a file)
+fn save_files(paths: &[PathBuf], saves: &SavesConfig) -> Result<Vec<(PathBuf, String)>, AppError> {
+    let mut out = Vec::new();
+    for (index, path) in paths.iter().enumerate() {
+        if path.is_file() {
+            out.push((path.clone(), index.to_string()));
+            continue;
+        }
+        if !path.is_dir() {
+            warn!("Save path {} does not exist, skipping", path.display());
+            continue;
+        }
+        let mut files = Vec::new();
+        files_under(path, "", &mut files)?;
+        out.extend(
+            files
+                .into_iter()
+                .filter(|(_, relative)| wanted(relative, saves))
+                .map(|(file, relative)| (file, format!("{}/{}", index, relative))),
+        );
+    }
+    Ok(out)
+}
+
+/// Write the save paths' files to a tar.zst archive, returning the number of files
+fn write_archive(paths: &[PathBuf], saves: &SavesConfig, archive: &Path) -> Result<usize, AppError> {
+    let files = save_files(paths, saves)?;
+    let encoder = zstd::Encoder::new(File::create(archive)?, 0)?.auto_finish();
+    let mut builder = tar::Builder::new(encoder);
+
+    let manifest = serde_json::to_vec_pretty(paths)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST, manifest.as_slice())?;
+
+    for (file, name) in &files {
+        debug!("Adding {}", file.display());
+        builder.append_path_with_name(file, name)?;
+    }
+    builder.into_inner()?;
+    Ok(files.len())
+}
+
+/// The save paths a backup was made from, which its manifest (always the first entry) records
+fn read_manifest(archive: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let decoder = zstd::Decoder::new(File::open(archive)?)?;
+    let mut tar = tar::Archive::new(decoder);
+    let not_a_backup = || AppError::Saves(format!("{} is not a save backup", archive.display()));
+    let mut entry = tar.entries()?.next().ok_or_else(not_a_backup)??;
+    if entry.path()?.as_ref() != Path::new(MANIFEST) {
+        return Err(not_a_backup());
+    }
+    let mut manifest = String::new();
+    entry.read_to_string(&mut manifest)?;
+    Ok(serde_json::from_str(&manifest)?)
+}
+
+/// Extract a save backup's files to `paths`, returning the number of files
+///
+/// Entry `<index>/...` goes under `paths[index]`. Only regular files are
+/// extracted, so a link in the archive can't redirect a later entry.
+fn unpack_archive(archive: &Path, paths: &[PathBuf]) -> Result<usize, AppError> {
+    let decoder = zstd::Decoder::new(File::open(archive)?)?;
+    let mut tar = tar::Archive::new(decoder);
+    let mut restored = 0;
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        if name == Path::new(MANIFEST) {
+            continue;
+        }
+        let target = target_path(paths, &name).filter(|_| entry.header().entry_type().is_file());
+        let Some(target) = target else {
+            warn!("Skipping unexpected entry {} in {}", name.display(), archive.display());
+            continue;
+        };
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        debug!("Restoring {}", target.display());
+        entry.unpack(&target)?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// Where an archive entry (`<index>/<relative path>`) is restored to
+fn target_path(paths: &[PathBuf], name: &Path) -> Option<PathBuf> {
+    let mut components = name.components();
+    let index: usize = match components.next()? {
+        Component::Normal(index) => index.to_str()?.parse().ok()?,
+        _ => return None,
+    };
+    let root = paths.get(index)?;
+    let rest = components.as_path();
+    if rest.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    Some(if rest.as_os_str().is_empty() {
+        root.clone()
+    } else {
+        root.join(rest)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saves(include: &[&str], exclude: &[&str]) -> SavesConfig {
+        SavesConfig {
+            paths: Vec::new(),
+            include: include.iter().map(|p| p.to_string()).collect(),
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            backup_on_exit: false,
+            keep: 10,
+            sync_target: None,
+            sync_command: None,
+        }
+    }
+
+    #[test]
+    fn test_wanted() {
+        let all = saves(&[], &["*.log", "cache/*"]);
+        assert!(wanted("slot1.sav", &all));
+        assert!(wanted("profiles/slot1.sav", &all));
+        assert!(!wanted("logs/game.log", &all));
+        assert!(!wanted("cache/shaders.bin", &all));
+
+        let only = saves(&["*.sav"], &[]);
+        assert!(wanted("profiles/slot1.sav", &only));
+        assert!(!wanted("settings.ini", &only));
+    }
+
+    #[test]
+    fn test_target_path() {
+        let paths = [PathBuf::from("/saves/game"), PathBuf::from("/saves/settings.ini")];
+        assert_eq!(
+            target_path(&paths, Path::new("0/profiles/slot1.sav")),
+            Some(PathBuf::from("/saves/game/profiles/slot1.sav"))
+        );
+        assert_eq!(target_path(&paths, Path::new("1")), Some(PathBuf::from("/saves/settings.ini")));
+        assert_eq!(target_path(&paths, Path::new("2/slot1.sav")), None);
+        assert_eq!(target_path(&paths, Path::new("0/../../etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let game = dir.path().join("game");
+        fs::create_dir_all(game.join("profiles")).unwrap();
+        fs::write(game.join("profiles/slot1.sav"), b"level 3").unwrap();
+        fs::write(game.join("debug.log"), b"noise").unwrap();
+        let settings = dir.path().join("settings.ini");
+        fs::write(&settings, b"vsync=1").unwrap();
+
+        let archive = dir.path().join("backup.tar.zst");
+        let paths = [game.clone(), settings.clone(), dir.path().join("missing")];
+        let count = write_archive(&paths, &saves(&[], &["*.log"]), &archive).unwrap();
+        assert_eq!(count, 2);
+
+        assert_eq!(read_manifest(&archive).unwrap(), paths);
+
+        fs::write(game.join("profiles/slot1.sav"), b"corrupted").unwrap();
+        fs::remove_file(&settings).unwrap();
+        assert_eq!(unpack_archive(&archive, &paths).unwrap(), 2);
+        assert_eq!(fs::read(game.join("profiles/slot1.sav")).unwrap(), b"level 3");
+        assert_eq!(fs::read(&settings).unwrap(), b"vsync=1");
+        assert_eq!(fs::read(game.join("debug.log")).unwrap(), b"noise");
+
+        // Restored to where the game keeps its saves now, not where they were
+        let moved = dir.path().join("moved");
+        let moved_paths = [moved.clone(), moved.join("settings.ini"), dir.path().join("missing")];
+        assert_eq!(unpack_archive(&archive, &moved_paths).unwrap(), 2);
+        assert_eq!(fs::read(moved.join("profiles/slot1.sav")).unwrap(), b"level 3");
+        assert_eq!(fs::read(moved.join("settings.ini")).unwrap(), b"vsync=1");
+    }
+}
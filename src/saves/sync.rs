@@ -0,0 +1,371 @@
+use super::{create_backup, save_files, save_paths, saves_backup_dir, saves_config};
+use crate::config::{MergedConfig, SavesConfig};
+use crate::error::AppError;
+use crate::hooks::HookContext;
+use crate::notify::{notify, Urgency};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// How long a sync may run before it is stopped, so an unreachable target can't hold up a launch
+const SYNC_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Which way saves are synced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyncDirection {
+    /// Fetch the saves from the sync target
+    Pull,
+    /// Send the saves to the sync target
+    Push,
+}
+
+impl SyncDirection {
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncDirection::Pull => "pull",
+            SyncDirection::Push => "push",
+        }
+    }
+}
+
+/// What a sync did besides moving files
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Files changed here since the last sync that a pull replaced with the target's copy
+    pub conflicts: Vec<PathBuf>,
+    /// Backup of the saves taken before a pull that could overwrite local changes
+    pub backup: Option<PathBuf>,
+}
+
+/// A file's modification time and size when the saves were last synced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    modified_ns: u64,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified_ns: modified.as_nanos() as u64,
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Whether the game's saves are synced around launches
+pub fn sync_enabled(config: &MergedConfig) -> bool {
+    config
+        .saves
+        .as_ref()
+        .is_some_and(|saves| saves.sync_target.is_some() || saves.sync_command.is_some())
+}
+
+/// Sync the game's saves with its `sync_target` or `sync_command`
+///
+/// Before a pull, files changed here since the last sync (every file, before the
+/// first one) are backed up; any of them the pull replaces are reported as
+/// conflicts. Changes are found by comparing each file with its state after the
+/// last sync, so clocks that differ between machines don't matter.
+pub fn sync_saves(config: &MergedConfig, direction: SyncDirection) -> Result<SyncReport, AppError> {
+    let (app_id, saves) = saves_config(config)?;
+    let paths = save_paths(config)?;
+    let mut report = SyncReport::default();
+
+    let unsynced = match direction {
+        SyncDirection::Pull => unsynced_changes(app_id, &paths, saves)?,
+        SyncDirection::Push => Vec::new(),
+    };
+    if !unsynced.is_empty() {
+        debug!("{} save files changed since the last sync", unsynced.len());
+        match create_backup(config) {
+            Ok(backup) => report.backup = Some(backup.path),
+            Err(AppError::Saves(e)) => debug!("Not backing up the saves before pulling: {}", e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    info!("Syncing saves for app {} ({})", app_id, direction.label());
+    match (&saves.sync_command, &saves.sync_target) {
+        (Some(command), _) => run(sync_command(command, direction, config, &paths)?)?,
+        (None, Some(target)) => {
+            check_unique_names(&paths)?;
+            for path in &paths {
+                if direction == SyncDirection::Push && !path.exists() {
+                    debug!("Save path {} does not exist, not pushing it", path.display());
+                    continue;
+                }
+                run(rsync_command(target, path, saves, direction)?)?;
+            }
+        }
+        (None, None) => {
+            return Err(AppError::Saves(format!(
+                "no sync_target or sync_command configured for app {}",
+                app_id
+            )))
+        }
+    }
+
+    report.conflicts = unsynced
+        .into_iter()
+        .filter(|(path, checksum)| file_checksum(path) != Some(*checksum))
+        .map(|(path, _)| path)
+        .collect();
+    record_sync(app_id, &paths, saves)?;
+    Ok(report)
+}
+
+/// Pull before launch or push after exit, warning about failures and conflicts
+/// without stopping the game
+pub fn sync_for_launch(config: &MergedConfig, direction: SyncDirection) {
+    if !sync_enabled(config) {
+        return;
+    }
+    let report = match sync_saves(config, direction) {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("Failed to {} saves: {}", direction.label(), e);
+            notify(
+                Urgency::Critical,
+                &format!("Could not sync {} saves", config.display_name()),
+                &e.to_string(),
+            );
+            return;
+        }
+    };
+    if report.conflicts.is_empty() {
+        return;
+    }
+
+    let kept = match &report.backup {
+        Some(backup) => format!("the local copies are in {}", backup.display()),
+        None => "the local copies were not backed up".to_string(),
+    };
+    warn!(
+        "Save conflict: {} files changed both here and on the sync target since the last sync; \
+         the newer copies from the target replaced them ({})",
+        report.conflicts.len(),
+        kept
+    );
+    for path in &report.conflicts {
+        warn!("  {}", path.display());
+    }
+    notify(
+        Urgency::Critical,
+        &format!("Save conflict for {}", config.display_name()),
+        &format!("Newer saves from the sync target replaced local changes; {}", kept),
+    );
+}
+
+/// Files changed since the last sync, with checksums of their contents
+fn unsynced_changes(app_id: u32, paths: &[PathBuf], saves: &SavesConfig) -> Result<Vec<(PathBuf, u32)>, AppError> {
+    let synced = load_sync_state(app_id);
+    Ok(save_files(paths, saves)?
+        .into_iter()
+        .map(|(file, _)| file)
+        .filter(|file| {
+            let key = file.to_string_lossy();
+            synced.get(key.as_ref()) != FileStamp::of(file).as_ref()
+        })
+        .filter_map(|file| file_checksum(&file).map(|checksum| (file, checksum)))
+        .collect())
+}
+
+fn file_checksum(path: &Path) -> Option<u32> {
+    fs::read(path).ok().map(|content| crc32fast::hash(&content))
+}
+
+/// File recording the state of each save file after the last sync
+fn sync_state_path(app_id: u32) -> PathBuf {
+    saves_backup_dir(app_id).join("sync-state.json")
+}
+
+fn load_sync_state(app_id: u32) -> HashMap<String, FileStamp> {
+    fs::read_to_string(sync_state_path(app_id))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn record_sync(app_id: u32, paths: &[PathBuf], saves: &SavesConfig) -> Result<(), AppError> {
+    let state: HashMap<String, FileStamp> = save_files(paths, saves)?
+        .into_iter()
+        .filter_map(|(file, _)| Some((file.to_string_lossy().into_owned(), FileStamp::of(&file)?)))
+        .collect();
+    fs::create_dir_all(saves_backup_dir(app_id))?;
+    fs::write(sync_state_path(app_id), serde_json::to_string_pretty(&state)?)?;
+    Ok(())
+}
+
+/// rsync keeps each save path under its own name in the target, so names must differ
+fn check_unique_names(paths: &[PathBuf]) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for path in paths {
+        if let Some(name) = path.file_name() {
+            if !seen.insert(name) {
+                return Err(AppError::Saves(format!(
+                    "more than one save path is named '{}'; sync_target needs distinct names",
+                    name.to_string_lossy()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// rsync invocation syncing one save path with `<target>/<its name>`
+///
+/// `--update` skips files that are newer on the receiving side, so the newest copy
+/// of each file wins. The source has no trailing slash and the destination is the
+/// parent directory, which works for files and directories alike.
+fn rsync_command(target: &str, path: &Path, saves: &SavesConfig, direction: SyncDirection) -> Result<Command, AppError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::Saves(format!("cannot sync {}", path.display())))?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| AppError::Saves(format!("cannot sync {}", path.display())))?;
+    let target = target.trim_end_matches('/');
+
+    let mut command = Command::new("rsync");
+    command.args(["--archive", "--update", "--timeout=30"]);
+    if !path.is_file() {
+        for pattern in &saves.exclude {
+            command.arg(format!("--exclude={}", pattern));
+        }
+        if !saves.include.is_empty() {
+            command.args(["--prune-empty-dirs", "--include=*/"]);
+            for pattern in &saves.include {
+                command.arg(format!("--include={}", pattern));
+            }
+            command.arg("--exclude=*");
+        }
+    }
+    match direction {
+        SyncDirection::Pull => {
+            fs::create_dir_all(parent)?;
+            command
+                .arg(format!("{}/{}", target, name.to_string_lossy()))
+                .arg(format!("{}/", parent.display()));
+        }
+        SyncDirection::Push => {
+            command
+                .arg("--mkpath")
+                .arg(path)
+                .arg(format!("{}/", target));
+        }
+    }
+    Ok(command)
+}
+
+/// The configured sync command, with `%direction%`, `%appid%` and `%name%` expanded
+/// and the save paths in SCR_SAVE_PATHS (colon-separated)
+fn sync_command(
+    command: &str,
+    direction: SyncDirection,
+    config: &MergedConfig,
+    paths: &[PathBuf],
+) -> Result<Command, AppError> {
+    let context = HookContext {
+        app_id: config.app_id,
+        name: config.name.clone(),
+        ..Default::default()
+    };
+    let args: Vec<String> = shlex::split(command)
+        .filter(|args| !args.is_empty())
+        .ok_or_else(|| AppError::Saves(format!("could not parse sync_command: {}", command)))?
+        .iter()
+        .map(|arg| context.expand(&arg.replace("%direction%", direction.label())))
+        .collect();
+    let save_paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+
+    let mut process = Command::new(&args[0]);
+    process
+        .args(&args[1..])
+        .envs(context.env_vars())
+        .env("SCR_SYNC_DIRECTION", direction.label())
+        .env("SCR_SAVE_PATHS", save_paths.join(":"));
+    Ok(process)
+}
+
+/// Run a sync command to completion, stopping it after SYNC_TIMEOUT
+fn run(mut command: Command) -> Result<(), AppError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    debug!("Running {:?}", command);
+    let mut child = command
+        .spawn()
+        .map_err(|e| AppError::Saves(format!("could not run {}: {}", program, e)))?;
+
+    let deadline = Instant::now() + SYNC_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if status.success() {
+                return Ok(());
+            }
+            return Err(AppError::Saves(format!("{} failed: {}", program, status)));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(AppError::Saves(format!(
+                "{} did not finish within {}s",
+                program,
+                SYNC_TIMEOUT.as_secs()
+            )));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsync_command() {
+        let saves = SavesConfig {
+            paths: Vec::new(),
+            include: Vec::new(),
+            exclude: vec!["*.log".to_string()],
+            backup_on_exit: false,
+            keep: 10,
+            sync_target: None,
+            sync_command: None,
+        };
+        let path = Path::new("/nonexistent/saves/Hades II");
+        let args = |command: Command| -> Vec<String> {
+            command.get_args().map(|a| a.to_string_lossy().into_owned()).collect()
+        };
+
+        let push = rsync_command("deck:saves/", path, &saves, SyncDirection::Push).unwrap();
+        assert_eq!(
+            args(push),
+            [
+                "--archive",
+                "--update",
+                "--timeout=30",
+                "--exclude=*.log",
+                "--mkpath",
+                "/nonexistent/saves/Hades II",
+                "deck:saves/"
+            ]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("saves/Hades II");
+        let pull = rsync_command("deck:saves", &path, &saves, SyncDirection::Pull).unwrap();
+        let pull = args(pull);
+        assert_eq!(pull[pull.len() - 2..], ["deck:saves/Hades II".to_string(), format!("{}/saves/", dir.path().display())]);
+        assert!(dir.path().join("saves").is_dir());
+
+        let twice = [PathBuf::from("/a/Saves"), PathBuf::from("/b/Saves")];
+        assert!(check_unique_names(&twice).is_err());
+    }
+}
@@ -1,10 +1,14 @@
-use crate::config::MergedConfig;
-use std::env;
-use std::fs;
-use std::os::unix::fs::MetadataExt;
+use super::{configure_logging, find_real_binary, get_app_id, log_to_file, run_guarded};
+use crate::config::{MergedConfig, RESOLVED_CONFIG_ENV};
+use crate::runner::{
+    gamescope_args_for, ld_library_path_for, ld_preload_for, write_mangohud_config,
+};
+use crate::steam::get_steam_root;
+use std::convert::Infallible;
+use std::ffi::OsStr;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::io::Write;
 
 /// Check if the current binary was invoked as "gamescope"
 pub fn is_invoked_as_gamescope() -> bool {
@@ -42,74 +46,68 @@ fn parse_gamescope_args(args: Vec<String>) -> (Vec<String>, Vec<String>) {
     (gamescope_args, command)
 }
 
-/// Get the Steam App ID from environment
-fn get_app_id() -> Option<u32> {
-    env::var("SteamAppId")
-        .ok()
-        .and_then(|s| s.parse().ok())
-}
-
 /// Find the real gamescope binary, excluding ourselves
-fn find_real_gamescope() -> Option<PathBuf> {
-    // Get our own inode to exclude from search
-    let self_path = std::env::current_exe().ok()?;
-    let self_inode = fs::metadata(&self_path).ok()?.ino();
-
-    // Search PATH for gamescope
-    let path_env = std::env::var("PATH").ok()?;
-
-    for dir in path_env.split(':') {
-        let candidate = Path::new(dir).join("gamescope");
-
-        if !candidate.exists() {
-            continue;
-        }
-
-        // Check if it's a different file (by inode) to skip our symlink
-        if let Ok(metadata) = fs::metadata(&candidate) {
-            // Follow symlinks to get the real file
-            if let Ok(canonical) = fs::canonicalize(&candidate) {
-                if let Ok(canonical_meta) = fs::metadata(&canonical) {
-                    if canonical_meta.ino() != self_inode {
-                        return Some(candidate);
-                    }
-                }
-            } else if metadata.ino() != self_inode {
-                return Some(candidate);
-            }
-        }
-    }
-
-    None
+pub(crate) fn find_real_gamescope() -> Option<PathBuf> {
+    find_real_binary("gamescope")
 }
 
-/// Handle execution when invoked as the gamescope shim
-/// Load the full merged configuration
-fn load_config() -> Option<MergedConfig> {
+/// Load the full merged configuration, and the parsed files to hand to a nested `run`
+///
+/// The gamescope arguments alone depend on every layer (drop-ins, group, game,
+/// profiles, `[[when]]` blocks, Steam Deck defaults), so there is no smaller
+/// part to load; the detection nothing here asks for is skipped by the load itself.
+fn load_config() -> Result<(MergedConfig, Option<String>), String> {
     let app_id = get_app_id();
-    MergedConfig::load(app_id, None).ok()
+    MergedConfig::load_for_handoff(app_id).map_err(|e| format!("could not load config: {}", e))
+}
+
+/// Whether the game command starts the program `name` (e.g. `steam-command-runner run -- ...`)
+fn runs_program(command: &[String], name: &OsStr) -> bool {
+    command.iter().any(|arg| Path::new(arg).file_name() == Some(name))
 }
 
 /// Handle execution when invoked as the gamescope shim
+///
+/// Any failure falls back to running the real gamescope with the original arguments.
 pub fn handle_gamescope_shim() -> ExitCode {
-    // Load config first to check logging preference
-    let config = load_config();
-    let debug_enabled = config.as_ref().map(|c| c.shim_debug).unwrap_or(false);
+    run_guarded("gamescope", gamescope_shim)
+}
 
-    log_to_file("Shim started", debug_enabled);
+/// Build the configured gamescope command and exec() it; only returns on failure
+fn gamescope_shim() -> Result<Infallible, String> {
+    // Load config first to pick up the log settings
+    let (config, handoff) = load_config()?;
+    configure_logging(&config);
+
+    log_to_file("Shim started");
     let args: Vec<String> = std::env::args().collect();
-    log_to_file(&format!("Args: {:?}", args), debug_enabled);
+    log_to_file(&format!("Args: {:?}", args));
     let (cli_gamescope_args, command) = parse_gamescope_args(args);
 
+    // Passthrough drops gamescope along with everything else we would add
+    if config.passthrough {
+        let err = match command.split_first() {
+            Some((program, args)) => {
+                log_to_file(&format!("Passthrough enabled; running {:?} without gamescope", command));
+                std::process::Command::new(program).args(args).exec()
+            }
+            None => {
+                let real_gamescope = find_real_gamescope().ok_or("real gamescope binary not found in PATH")?;
+                log_to_file("Passthrough enabled; running gamescope with the original arguments");
+                std::process::Command::new(real_gamescope).args(std::env::args_os().skip(1)).exec()
+            }
+        };
+        return Err(format!("passthrough exec failed: {}", err));
+    }
+
     // Get gamescope args from config
-    let config_gamescope_args = if let Some(c) = &config {
-        if c.gamescope_enabled {
-             match &c.gamescope_args {
-                Some(args_str) => shlex::split(args_str).unwrap_or_default(),
-                None => Vec::new(),
+    let config_gamescope_args = if config.gamescope_enabled {
+        match gamescope_args_for(&config) {
+            Ok(args) => args.unwrap_or_default(),
+            Err(e) => {
+                log_to_file(&format!("Ignoring config gamescope args: {}", e));
+                Vec::new()
             }
-        } else {
-            Vec::new()
         }
     } else {
         Vec::new()
@@ -119,33 +117,29 @@ pub fn handle_gamescope_shim() -> ExitCode {
     all_gamescope_args.extend(cli_gamescope_args);
 
     // Find the real gamescope binary
-    let real_gamescope = match find_real_gamescope() {
-        Some(path) => {
-            log_to_file(&format!("Found real gamescope at: {:?}", path), debug_enabled);
-            path
-        },
-        None => {
-            log_to_file("Error: Real gamescope binary not found in PATH", debug_enabled);
-            eprintln!("Error: Real gamescope binary not found in PATH");
-            eprintln!("Make sure gamescope is installed and the steam-command-runner symlink");
-            eprintln!("is not shadowing the real gamescope binary.");
-            return ExitCode::FAILURE;
-        }
-    };
+    let real_gamescope = find_real_gamescope().ok_or("real gamescope binary not found in PATH")?;
+    log_to_file(&format!("Found real gamescope at: {:?}", real_gamescope));
 
     // Use exec to replace the current process
     // This preserves all environment variables set by Steam (including LIBEI_SOCKET, LD_PRELOAD)
-    use std::os::unix::process::CommandExt;
-
     let mut cmd = std::process::Command::new(&real_gamescope);
     cmd.args(&all_gamescope_args);
-    log_to_file(&format!("Executing: {:?} args: {:?}", real_gamescope, all_gamescope_args), debug_enabled);
+    log_to_file(&format!("Executing: {:?} args: {:?}", real_gamescope, all_gamescope_args));
 
     // Apply environment variables from config
-    if let Some(c) = &config {
-        for (key, value) in &c.env {
-            log_to_file(&format!("Setting env: {}={}", key, value), debug_enabled);
-            cmd.env(key, value);
+    for (key, value) in &config.env {
+        log_to_file(&format!("Setting env: {}={}", key, value));
+        cmd.env(key, value);
+    }
+
+    // gamescope's --mangoapp reads the generated MangoHud config
+    if config.mangohud.is_enabled() {
+        match write_mangohud_config(&config) {
+            Ok(path) => {
+                log_to_file(&format!("Setting MANGOHUD_CONFIGFILE: {}", path.display()));
+                cmd.env("MANGOHUD_CONFIGFILE", path);
+            }
+            Err(e) => log_to_file(&format!("Failed to write MangoHud config: {}", e)),
         }
     }
 
@@ -154,10 +148,10 @@ pub fn handle_gamescope_shim() -> ExitCode {
     // Instead, we must inject it into the INNER command using 'env'.
 
     // Set Gamescope Overlay variables (These are likely safe from stripping or gamescope might use them)
-    log_to_file("Setting ENABLE_VK_LAYER_VALVE_steam_overlay_1=1", debug_enabled);
+    log_to_file("Setting ENABLE_VK_LAYER_VALVE_steam_overlay_1=1");
     cmd.env("ENABLE_VK_LAYER_VALVE_steam_overlay_1", "1");
     
-    log_to_file("Setting ENABLE_GAMESCOPE_WSI=1", debug_enabled);
+    log_to_file("Setting ENABLE_GAMESCOPE_WSI=1");
     cmd.env("ENABLE_GAMESCOPE_WSI", "1");
 
     // Copy STEAM_GAMESCOPE_* env vars
@@ -168,51 +162,62 @@ pub fn handle_gamescope_shim() -> ExitCode {
     cmd.env("STEAM_GAMESCOPE_HAS_TEARING_SUPPORT", "1");
 
     if !command.is_empty() {
+        // A nested `run` uses the config files read here rather than loading them again
+        let ours = std::env::current_exe().ok().and_then(|exe| exe.file_name().map(ToOwned::to_owned));
+        if let (Some(handoff), Some(ours)) = (handoff, ours) {
+            if runs_program(&command, &ours) {
+                log_to_file(&format!("Handing the config to the nested {}", ours.to_string_lossy()));
+                cmd.env(RESOLVED_CONFIG_ENV, handoff);
+            }
+        }
+
         cmd.arg("--");
         
-        // Inject Steam Overlay via env wrapper in inner command
-        if let Some(ld_preload) = build_ld_preload_with_overlay(debug_enabled) {
-            log_to_file(&format!("Injecting LD_PRELOAD via inner 'env' wrapper: {}", ld_preload), debug_enabled);
+        // Inject Steam Overlay and configured libraries via env wrapper in inner command
+        let mut inner_env = Vec::new();
+        let ld_preload = ld_preload_for(&config, build_ld_preload_with_overlay());
+        if let Some(ld_path) = ld_library_path_for(&config) {
+            log_to_file(&format!("Injecting LD_LIBRARY_PATH via inner 'env' wrapper: {}", ld_path));
+            inner_env.push(format!("LD_LIBRARY_PATH={}", ld_path));
+        }
+        if let Some(ld_preload) = ld_preload {
+            log_to_file(&format!("Injecting LD_PRELOAD via inner 'env' wrapper: {}", ld_preload));
+            inner_env.insert(0, format!("LD_PRELOAD={}", ld_preload));
+        }
+        if !inner_env.is_empty() {
             cmd.arg("env");
-            cmd.arg(format!("LD_PRELOAD={}", ld_preload));
+            cmd.args(&inner_env);
         }
 
         // Inject pre_command (e.g., mangohud) into inner command
         // This ensures it runs AFTER gamescope has started, avoiding capability stripping
-        if let Some(c) = &config {
-            if let Some(pre_cmd) = c.effective_pre_command() {
-                log_to_file(&format!("Injecting pre_command: {}", pre_cmd), debug_enabled);
-                if let Some(pre_args) = shlex::split(pre_cmd) {
-                    cmd.args(pre_args);
-                }
+        if let Some(pre_cmd) = config.effective_pre_command() {
+            log_to_file(&format!("Injecting pre_command: {}", pre_cmd));
+            if let Some(pre_args) = shlex::split(pre_cmd) {
+                cmd.args(pre_args);
             }
         }
 
         cmd.args(&command);
 
         // Append explicit game_args from config (e.g. --skip-intro)
-        if let Some(c) = &config {
-            if let Some(args_str) = &c.game_args {
-                log_to_file(&format!("Appending game_args: {}", args_str), debug_enabled);
-                if let Some(extra_args) = shlex::split(args_str) {
-                    cmd.args(extra_args);
-                }
+        if let Some(args_str) = &config.game_args {
+            log_to_file(&format!("Appending game_args: {}", args_str));
+            if let Some(extra_args) = shlex::split(args_str) {
+                cmd.args(extra_args);
             }
         }
     }
 
     // exec() replaces the current process - this never returns on success
     let err = cmd.exec();
-    log_to_file(&format!("Error: Failed to exec gamescope: {}", err), debug_enabled);
-    eprintln!("Error: Failed to exec gamescope: {}", err);
-    ExitCode::FAILURE
+    Err(format!("failed to exec gamescope: {}", err))
 }
 
 /// Get the Steam overlay library paths for LD_PRELOAD
-fn get_steam_overlay_paths(debug: bool) -> Option<String> {
-    // Try to find Steam installation path
-    let home = std::env::var("HOME").ok()?;
-    let steam_path = PathBuf::from(&home).join(".local/share/Steam");
+fn get_steam_overlay_paths() -> Option<String> {
+    // Overlay libraries live in the Steam installation (native or Flatpak)
+    let steam_path = get_steam_root()?;
 
     let overlay_64 = steam_path.join("ubuntu12_64/gameoverlayrenderer.so");
     let overlay_32 = steam_path.join("ubuntu12_32/gameoverlayrenderer.so");
@@ -223,52 +228,36 @@ fn get_steam_overlay_paths(debug: bool) -> Option<String> {
             paths.push(':');
             paths.push_str(&overlay_32.to_string_lossy());
         }
-        log_to_file(&format!("Found Steam overlay paths: {}", paths), debug);
+        log_to_file(&format!("Found Steam overlay paths: {}", paths));
         Some(paths)
     } else {
-        log_to_file("Steam overlay 64-bit library not found!", debug);
+        log_to_file("Steam overlay 64-bit library not found!");
         None
     }
 }
 
 /// Build LD_PRELOAD value with Steam overlay added
-fn build_ld_preload_with_overlay(debug: bool) -> Option<String> {
-    let overlay_paths = get_steam_overlay_paths(debug)?;
+fn build_ld_preload_with_overlay() -> Option<String> {
+    let overlay_paths = get_steam_overlay_paths()?;
 
     // Check existing LD_PRELOAD
     let existing_preload = std::env::var("LD_PRELOAD").ok();
     
     if let Some(existing) = existing_preload {
         if existing.contains("gameoverlayrenderer.so") {
-            log_to_file("LD_PRELOAD already contains gameoverlayrenderer.so, mimicking it", debug);
+            log_to_file("LD_PRELOAD already contains gameoverlayrenderer.so, mimicking it");
             Some(existing)
         } else {
             let new_preload = format!("{}:{}", overlay_paths, existing);
-            log_to_file(&format!("Prepending overlay to existing LD_PRELOAD"), debug);
+            log_to_file(&format!("Prepending overlay to existing LD_PRELOAD"));
             Some(new_preload)
         }
     } else {
-        log_to_file("Setting new LD_PRELOAD with overlay", debug);
+        log_to_file("Setting new LD_PRELOAD with overlay");
         Some(overlay_paths)
     }
 }
 
-fn log_to_file(message: &str, enabled: bool) {
-    if !enabled {
-        return;
-    }
-    if let Ok(home) = std::env::var("HOME") {
-        let log_path = PathBuf::from(&home).join(".steam-command-runner-shim.log");
-        if let Ok(mut file) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            let _ = writeln!(file, "{}", message);
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,4 +304,14 @@ mod tests {
         assert!(gs_args.is_empty());
         assert!(cmd.is_empty());
     }
+
+    #[test]
+    fn test_runs_program() {
+        let command = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let ours = OsStr::new("steam-command-runner");
+        assert!(runs_program(&command(&["/usr/bin/steam-command-runner", "run", "--", "game"]), ours));
+        assert!(runs_program(&command(&["mangohud", "steam-command-runner", "run", "--", "game"]), ours));
+        assert!(!runs_program(&command(&["/games/steam-command-runner-notes.txt"]), ours));
+        assert!(!runs_program(&command(&["/path/to/game", "arg1"]), ours));
+    }
 }
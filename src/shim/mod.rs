@@ -1,3 +1,132 @@
 pub mod gamescope;
+pub mod path;
+pub mod wrapper;
 
 pub use gamescope::{handle_gamescope_shim, is_invoked_as_gamescope};
+pub use path::{shim_path_order, ShimPathOrder, ShimPathReport};
+pub use wrapper::{handle_wrapper_shim, invoked_wrapper, WRAPPERS};
+
+use crate::config::{LogLevel, MergedConfig};
+use crate::logs::{configure, write_log, LogFile};
+use std::any::Any;
+use std::convert::Infallible;
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::panic::{self, UnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+/// Get the Steam App ID from environment
+fn get_app_id() -> Option<u32> {
+    env::var("SteamAppId")
+        .ok()
+        .and_then(|s| s.parse().ok())
+}
+
+/// Find the real binary called `name` in PATH, excluding ourselves
+fn find_real_binary(name: &str) -> Option<PathBuf> {
+    // Get our own inode to exclude from search
+    let self_path = std::env::current_exe().ok()?;
+    let self_inode = fs::metadata(&self_path).ok()?.ino();
+
+    // Search PATH for the binary
+    let path_env = std::env::var("PATH").ok()?;
+
+    for dir in path_env.split(':') {
+        let candidate = Path::new(dir).join(name);
+
+        if !candidate.exists() {
+            continue;
+        }
+
+        // Check if it's a different file (by inode) to skip our symlink
+        if let Ok(metadata) = fs::metadata(&candidate) {
+            // Follow symlinks to get the real file
+            if let Ok(canonical) = fs::canonicalize(&candidate) {
+                if let Ok(canonical_meta) = fs::metadata(&canonical) {
+                    if canonical_meta.ino() != self_inode {
+                        return Some(candidate);
+                    }
+                }
+            } else if metadata.ino() != self_inode {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+/// Run a shim, falling back to the real program with the original arguments
+/// when it fails or panics, so a shim problem never stops a game from launching
+fn run_guarded<F>(name: &str, shim: F) -> ExitCode
+where
+    F: FnOnce() -> Result<Infallible, String> + UnwindSafe,
+{
+    let error = match panic::catch_unwind(shim) {
+        Ok(Err(e)) => e,
+        Ok(Ok(never)) => match never {},
+        Err(panic) => format!("panicked: {}", panic_message(panic.as_ref())),
+    };
+    passthrough(name, &error)
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// exec() the real program with our arguments untouched
+fn passthrough(name: &str, error: &str) -> ExitCode {
+    log_error(&format!("{} shim: {}; running {} with the original arguments", name, error, name));
+
+    let Some(real) = find_real_binary(name) else {
+        log_error(&format!("{} shim: real {} binary not found in PATH", name, name));
+        eprintln!("Error: Real {} binary not found in PATH", name);
+        eprintln!("Make sure {} is installed and the steam-command-runner symlink", name);
+        eprintln!("is not shadowing the real {} binary.", name);
+        return ExitCode::FAILURE;
+    };
+
+    // exec() replaces the current process - this never returns on success
+    let err = Command::new(&real).args(env::args_os().skip(1)).exec();
+    log_error(&format!("{} shim: failed to exec {}: {}", name, real.display(), err));
+    eprintln!("Error: Failed to exec {}: {}", name, err);
+    ExitCode::FAILURE
+}
+
+/// Record a shim error in the shim log
+fn log_error(message: &str) {
+    write_log(LogFile::Shim, LogLevel::Error, message);
+}
+
+/// Apply the config's log settings; `shim_debug` turns on debug messages
+fn configure_logging(config: &MergedConfig) {
+    let at_least = if config.shim_debug { LogLevel::Debug } else { LogLevel::Off };
+    configure(&config.logging, config.app_id, at_least);
+}
+
+/// Write a debug message to the shim log
+fn log_to_file(message: &str) {
+    write_log(LogFile::Shim, LogLevel::Debug, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let message = |f: fn()| panic::catch_unwind(f).map_err(|p| panic_message(p.as_ref()).to_string());
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        assert_eq!(message(|| panic!("static")), Err("static".to_string()));
+        assert_eq!(message(|| panic!("formatted {}", 1)), Err("formatted 1".to_string()));
+        panic::set_hook(previous);
+    }
+}
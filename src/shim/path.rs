@@ -0,0 +1,205 @@
+use crate::steam::find_steam_pid;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Where a shim comes in a PATH search for its name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShimPathOrder {
+    /// The shim is found first
+    First,
+    /// These programs are found before the shim (or instead of it, if it isn't in PATH)
+    Shadowed(Vec<PathBuf>),
+    /// The shim's directory isn't in PATH, and nothing else has the name either
+    Missing,
+}
+
+/// Work out what running the shim's name without a path finds in `path`
+pub fn shim_path_order(shim: &Path, path: &OsStr) -> ShimPathOrder {
+    let Some(name) = shim.file_name() else {
+        return ShimPathOrder::Missing;
+    };
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let shim_dir = shim.parent().map(canonical);
+    let shim_target = canonical(shim);
+
+    let mut before = Vec::new();
+    for dir in env::split_paths(path).filter(|dir| !dir.as_os_str().is_empty()) {
+        if Some(canonical(&dir)) == shim_dir {
+            return if before.is_empty() {
+                ShimPathOrder::First
+            } else {
+                ShimPathOrder::Shadowed(before)
+            };
+        }
+        let candidate = dir.join(name);
+        if candidate.is_file() && canonical(&candidate) != shim_target && !before.contains(&candidate) {
+            before.push(candidate);
+        }
+    }
+
+    if before.is_empty() {
+        ShimPathOrder::Missing
+    } else {
+        ShimPathOrder::Shadowed(before)
+    }
+}
+
+/// How a shim fares in each PATH it may be run from
+pub struct ShimPathReport {
+    /// Sources where the shim is found first
+    pub ok: Vec<&'static str>,
+    /// Sources where it isn't, with what is found instead
+    pub problems: Vec<(&'static str, ShimPathOrder)>,
+}
+
+impl ShimPathReport {
+    /// Check a shim against every PATH source
+    pub fn check(shim: &Path) -> Self {
+        let mut report = ShimPathReport {
+            ok: Vec::new(),
+            problems: Vec::new(),
+        };
+        for (source, path) in path_sources() {
+            match shim_path_order(shim, &path) {
+                ShimPathOrder::First => report.ok.push(source),
+                order => report.problems.push((source, order)),
+            }
+        }
+        report
+    }
+
+    /// Whether another program would run instead of the shim somewhere
+    pub fn shadowed(&self) -> bool {
+        self.problems
+            .iter()
+            .any(|(_, order)| matches!(order, ShimPathOrder::Shadowed(_)))
+    }
+
+    /// One line per problem, plus a hint when only some sources include the shim
+    pub fn describe(&self, shim: &Path) -> Vec<String> {
+        let dir = shim.parent().unwrap_or(Path::new("/")).display();
+        let mut lines: Vec<String> = self
+            .problems
+            .iter()
+            .map(|(source, order)| match order {
+                ShimPathOrder::Shadowed(found) => {
+                    let found: Vec<_> = found.iter().map(|p| p.display().to_string()).collect();
+                    format!("{} PATH runs {} instead of {}", source, found.join(", "), shim.display())
+                }
+                _ => format!("{} PATH doesn't include {}", source, dir),
+            })
+            .collect();
+
+        // The classic breakage: right in a terminal, wrong where Steam gets started from
+        let shells_ok = self.ok.iter().any(|source| source.ends_with("shell"));
+        let desktop_wrong = self
+            .problems
+            .iter()
+            .any(|(source, _)| !source.ends_with("shell"));
+        if shells_ok && desktop_wrong {
+            lines.push(format!(
+                "{} comes first for {} only; shell profiles don't apply to desktop-launched \
+                 programs like Steam, so also set PATH in ~/.config/environment.d/*.conf",
+                dir,
+                self.ok.join(" and ")
+            ));
+        }
+        lines
+    }
+}
+
+/// The PATHs games may be started with, labelled by where they come from
+///
+/// Steam inherits PATH from whatever started it: usually the desktop session,
+/// which doesn't read shell profiles, so a directory added in ~/.bashrc or
+/// ~/.profile can be missing there. Sources that can't be read are left out.
+pub fn path_sources() -> Vec<(&'static str, OsString)> {
+    let mut sources = Vec::new();
+    if let Some(path) = env::var_os("PATH") {
+        sources.push(("this shell", path));
+    }
+    if let Some(path) = login_shell_path() {
+        sources.push(("login shell", path));
+    }
+    if let Some(path) = session_path() {
+        sources.push(("desktop session", path));
+    }
+    if let Some(path) = find_steam_pid().and_then(process_path) {
+        sources.push(("running Steam", path));
+    }
+    sources
+}
+
+/// PATH as set up by the user's login shell and its profile
+fn login_shell_path() -> Option<OsString> {
+    let shell = env::var_os("SHELL").unwrap_or_else(|| "/bin/sh".into());
+    let output = Command::new(shell)
+        .args(["-l", "-c", "printf %s \"$PATH\""])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let path = output.stdout.rsplit(|&b| b == b'\n').next()?;
+    (output.status.success() && !path.is_empty()).then(|| OsStr::from_bytes(path).to_os_string())
+}
+
+/// PATH of the systemd user session, which desktop-launched programs inherit
+fn session_path() -> Option<OsString> {
+    let output = Command::new("systemctl")
+        .args(["--user", "show-environment"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    output
+        .stdout
+        .split(|&b| b == b'\n')
+        .find_map(|line| line.strip_prefix(b"PATH="))
+        .map(|path| OsStr::from_bytes(path).to_os_string())
+}
+
+/// PATH from a running process's environment
+fn process_path(pid: u32) -> Option<OsString> {
+    let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    environ
+        .split(|&b| b == 0)
+        .find_map(|var| var.strip_prefix(b"PATH="))
+        .map(|path| OsStr::from_bytes(path).to_os_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_shim_path_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let (local, usr) = (dir.path().join("local"), dir.path().join("usr"));
+        fs::create_dir_all(&local).unwrap();
+        fs::create_dir_all(&usr).unwrap();
+        fs::write(dir.path().join("runner"), b"").unwrap();
+        symlink(dir.path().join("runner"), local.join("gamescope")).unwrap();
+        fs::write(usr.join("gamescope"), b"").unwrap();
+
+        let shim = local.join("gamescope");
+        let path = |dirs: &[&Path]| env::join_paths(dirs).unwrap();
+        assert_eq!(shim_path_order(&shim, &path(&[&local, &usr])), ShimPathOrder::First);
+        assert_eq!(
+            shim_path_order(&shim, &path(&[&usr, &local])),
+            ShimPathOrder::Shadowed(vec![usr.join("gamescope")])
+        );
+        assert_eq!(
+            shim_path_order(&shim, &path(&[&usr])),
+            ShimPathOrder::Shadowed(vec![usr.join("gamescope")])
+        );
+        assert_eq!(shim_path_order(&shim, &path(&[dir.path()])), ShimPathOrder::Missing);
+    }
+}
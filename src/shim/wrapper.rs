@@ -0,0 +1,87 @@
+use super::{configure_logging, find_real_binary, get_app_id, log_to_file, run_guarded};
+use crate::config::MergedConfig;
+use crate::runner::write_mangohud_config;
+use std::convert::Infallible;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, ExitCode};
+
+/// Wrappers the binary can stand in for besides gamescope
+pub const WRAPPERS: &[&str] = &["mangohud", "gamemoderun"];
+
+/// The wrapper the current binary was invoked as, if any
+pub fn invoked_wrapper() -> Option<&'static str> {
+    std::env::args().next().and_then(|arg0| wrapper_name(&arg0))
+}
+
+fn wrapper_name(arg0: &str) -> Option<&'static str> {
+    let name = Path::new(arg0).file_name()?.to_str()?;
+    WRAPPERS.iter().copied().find(|wrapper| *wrapper == name)
+}
+
+/// Handle execution when invoked as a wrapper shim (`mangohud`, `gamemoderun`)
+///
+/// Adds the game's environment and the `[wrappers.<name>]` args and env, then
+/// exec()s the real wrapper found later in PATH with Steam's arguments after ours.
+/// Any failure falls back to running the real wrapper with the original arguments.
+pub fn handle_wrapper_shim(name: &'static str) -> ExitCode {
+    run_guarded(name, move || wrapper_shim(name))
+}
+
+/// Build the configured wrapper command and exec() it; only returns on failure
+fn wrapper_shim(name: &str) -> Result<Infallible, String> {
+    let config = MergedConfig::load(get_app_id(), None).map_err(|e| format!("could not load config: {}", e))?;
+    configure_logging(&config);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    log_to_file(&format!("{} shim started, args: {:?}", name, args));
+
+    let real = find_real_binary(name).ok_or_else(|| format!("real {} binary not found in PATH", name))?;
+    if config.passthrough {
+        log_to_file("Passthrough enabled; running the real wrapper with the original arguments");
+        return Err(format!("failed to exec {}: {}", name, Command::new(&real).args(&args).exec()));
+    }
+    let mut cmd = Command::new(&real);
+    cmd.envs(&config.env);
+
+    // Point MangoHud at the config generated from [mangohud]
+    if name == "mangohud" && config.mangohud.is_enabled() {
+        match write_mangohud_config(&config) {
+            Ok(path) => {
+                cmd.env("MANGOHUD_CONFIGFILE", path);
+            }
+            Err(e) => log_to_file(&format!("Failed to write MangoHud config: {}", e)),
+        }
+    }
+
+    if let Some(wrapper) = config.wrappers.get(name) {
+        cmd.envs(&wrapper.env);
+        if let Some(extra) = &wrapper.args {
+            match shlex::split(extra) {
+                Some(extra) => {
+                    cmd.args(extra);
+                }
+                None => log_to_file(&format!("Ignoring unparseable {} args: {}", name, extra)),
+            }
+        }
+    }
+    cmd.args(&args);
+    log_to_file(&format!("Executing: {:?} args: {:?}", real, cmd.get_args().collect::<Vec<_>>()));
+
+    // exec() replaces the current process - this never returns on success
+    let err = cmd.exec();
+    Err(format!("failed to exec {}: {}", name, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapper_name() {
+        assert_eq!(wrapper_name("/home/user/.local/bin/mangohud"), Some("mangohud"));
+        assert_eq!(wrapper_name("gamemoderun"), Some("gamemoderun"));
+        assert_eq!(wrapper_name("gamescope"), None);
+        assert_eq!(wrapper_name("steam-command-runner"), None);
+    }
+}
@@ -0,0 +1,446 @@
+use super::binary_vdf::{BinaryValue, Reader};
+use super::userdata::get_steam_root;
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+// appinfo.vdf format versions (magic numbers)
+const MAGIC_V27: u32 = 0x0756_4427;
+const MAGIC_V28: u32 = 0x0756_4428;
+const MAGIC_V29: u32 = 0x0756_4429;
+
+/// App whose appinfo carries Valve's per-game default compatibility tools
+const STEAM_PLAY_MANIFESTS_APP_ID: u32 = 891390;
+
+/// Get the path to Steam's app metadata cache
+pub fn get_appinfo_path() -> Result<PathBuf, AppError> {
+    let steam_root = get_steam_root().ok_or_else(|| AppError::SteamNotFound(Vec::new()))?;
+    Ok(steam_root.join("appcache").join("appinfo.vdf"))
+}
+
+/// A launch entry from an app's config section
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchEntry {
+    pub executable: String,
+    pub arguments: Option<String>,
+    pub description: Option<String>,
+    /// Operating systems this entry applies to (empty means all)
+    pub oslist: Vec<String>,
+}
+
+/// Metadata Steam caches for an app
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppInfo {
+    pub app_id: u32,
+    pub name: Option<String>,
+    /// App type as Steam reports it (e.g. "Game", "Tool", "Application")
+    pub app_type: Option<String>,
+    /// Operating systems the app supports (e.g. "windows", "linux")
+    pub oslist: Vec<String>,
+    pub launch: Vec<LaunchEntry>,
+}
+
+impl AppInfo {
+    fn from_value(app_id: u32, root: &BinaryValue) -> Self {
+        let info = root.get("appinfo").unwrap_or(root);
+        let string = |path: &[&str]| {
+            info.get_path(path)
+                .and_then(BinaryValue::as_str)
+                .map(str::to_string)
+        };
+
+        let launch = info
+            .get_path(&["config", "launch"])
+            .map(|launch| {
+                launch
+                    .entries()
+                    .iter()
+                    .filter_map(|(_, entry)| {
+                        let get = |key: &str| {
+                            entry.get(key).and_then(BinaryValue::as_str).map(str::to_string)
+                        };
+                        Some(LaunchEntry {
+                            executable: get("executable")?,
+                            arguments: get("arguments").filter(|a| !a.is_empty()),
+                            description: get("description"),
+                            oslist: split_oslist(
+                                entry.get_path(&["config", "oslist"]).and_then(BinaryValue::as_str),
+                            ),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            app_id,
+            name: string(&["common", "name"]),
+            app_type: string(&["common", "type"]),
+            oslist: split_oslist(info.get_path(&["common", "oslist"]).and_then(BinaryValue::as_str)),
+            launch,
+        }
+    }
+
+    /// Whether the app lists the given OS (apps with no oslist are assumed to support all)
+    pub fn supports_os(&self, os: &str) -> bool {
+        self.oslist.is_empty() || self.oslist.iter().any(|o| o.eq_ignore_ascii_case(os))
+    }
+
+    /// Whether the app only ships Windows builds and needs Proton on Linux
+    pub fn is_windows_only(&self) -> bool {
+        !self.oslist.is_empty() && self.supports_os("windows") && !self.supports_os("linux")
+    }
+
+    /// Launch entries usable on the given OS
+    pub fn launch_entries_for(&self, os: &str) -> impl Iterator<Item = &LaunchEntry> {
+        let os = os.to_string();
+        self.launch
+            .iter()
+            .filter(move |e| e.oslist.is_empty() || e.oslist.iter().any(|o| o.eq_ignore_ascii_case(&os)))
+    }
+}
+
+fn split_oslist(oslist: Option<&str>) -> Vec<String> {
+    oslist
+        .map(|s| {
+            s.split(',')
+                .map(|o| o.trim().to_lowercase())
+                .filter(|o| !o.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Steam's appcache/appinfo.vdf, indexed by app ID
+///
+/// The file holds metadata for every app the client has seen and is commonly
+/// tens of megabytes, so entries are only decoded when looked up.
+pub struct AppInfoCache {
+    data: Vec<u8>,
+    keys: Vec<String>,
+    /// Byte range of each app's key/value section
+    offsets: HashMap<u32, (usize, usize)>,
+}
+
+impl AppInfoCache {
+    /// Load appinfo.vdf from the Steam installation
+    pub fn load() -> Result<Self, AppError> {
+        Self::read(get_appinfo_path()?)
+    }
+
+    /// Load an appinfo.vdf file
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, AppError> {
+        let data = fs::read(path.as_ref())?;
+        debug!("Read {} ({} bytes)", path.as_ref().display(), data.len());
+        Self::parse(data)
+    }
+
+    /// Index the apps in an appinfo.vdf buffer
+    pub fn parse(data: Vec<u8>) -> Result<Self, AppError> {
+        let (keys, offsets) = index(&data).map_err(appinfo_error)?;
+        debug!("Indexed {} apps in appinfo.vdf", offsets.len());
+        Ok(Self {
+            data,
+            keys,
+            offsets,
+        })
+    }
+
+    pub fn app_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.offsets.keys().copied()
+    }
+
+    /// Decode the raw key/values for an app
+    fn value(&self, app_id: u32) -> Result<Option<BinaryValue>, AppError> {
+        let Some(&(start, end)) = self.offsets.get(&app_id) else {
+            return Ok(None);
+        };
+        let section = &self.data[start..end];
+        let mut reader = if self.keys.is_empty() {
+            Reader::new(section)
+        } else {
+            Reader::with_key_table(section, &self.keys)
+        };
+        let entries = reader.map().map_err(|e| match e {
+            AppError::VdfParse(msg) => AppError::AppInfoParse(format!("app {}: {}", app_id, msg)),
+            other => other,
+        })?;
+        Ok(Some(BinaryValue::Map(entries)))
+    }
+
+    /// Look up an app's metadata
+    pub fn get(&self, app_id: u32) -> Result<Option<AppInfo>, AppError> {
+        Ok(self
+            .value(app_id)?
+            .map(|value| AppInfo::from_value(app_id, &value)))
+    }
+
+    /// Find games and applications whose name contains the query (case-insensitive)
+    pub fn search(&self, query: &str, limit: usize) -> Vec<AppInfo> {
+        let query = query.to_lowercase();
+        let mut results: Vec<AppInfo> = self
+            .app_ids()
+            .filter_map(|id| match self.get(id) {
+                Ok(info) => info,
+                Err(e) => {
+                    debug!("{}", e);
+                    None
+                }
+            })
+            .filter(|info| {
+                let playable = info
+                    .app_type
+                    .as_deref()
+                    .map(|t| t.eq_ignore_ascii_case("game") || t.eq_ignore_ascii_case("application"))
+                    .unwrap_or(false);
+                playable
+                    && info
+                        .name
+                        .as_deref()
+                        .map(|n| n.to_lowercase().contains(&query))
+                        .unwrap_or(false)
+            })
+            .collect();
+
+        results.sort_by_key(|info| info.app_id);
+        results.truncate(limit);
+        results
+    }
+
+    /// The compatibility tool Valve selects by default for an app, if any
+    pub fn default_compat_tool(&self, app_id: u32) -> Option<String> {
+        let manifests = self.value(STEAM_PLAY_MANIFESTS_APP_ID).ok()??;
+        let mappings = manifests.get_path(&["appinfo", "extended", "app_mappings"])?;
+        let id = app_id.to_string();
+        mappings
+            .entries()
+            .iter()
+            .map(|(_, mapping)| mapping)
+            .find(|mapping| mapping.get("appid").and_then(BinaryValue::as_str) == Some(&id))
+            .and_then(|mapping| mapping.get("tool"))
+            .and_then(BinaryValue::as_str)
+            .map(str::to_string)
+    }
+}
+
+/// Key string table and the byte range of each app's section
+type AppInfoIndex = (Vec<String>, HashMap<u32, (usize, usize)>);
+
+/// Walk the entry headers, returning the key table and each app's section range
+fn index(data: &[u8]) -> Result<AppInfoIndex, AppError> {
+    let mut reader = Reader::new(data);
+    let magic = reader.u32()?;
+    let _universe = reader.u32()?;
+
+    // Fixed header bytes after the size field: state, last updated, token, sha1,
+    // change number, and from v28 the sha1 of the binary section
+    let header_len = match magic {
+        MAGIC_V27 => 4 + 4 + 8 + 20 + 4,
+        MAGIC_V28 | MAGIC_V29 => 4 + 4 + 8 + 20 + 4 + 20,
+        other => {
+            return Err(AppError::VdfParse(format!(
+                "unsupported version (magic 0x{:08x})",
+                other
+            )))
+        }
+    };
+
+    let keys = if magic == MAGIC_V29 {
+        let table_offset = reader.u64()? as usize;
+        let entries_start = reader.pos();
+        reader.seek(table_offset);
+        let count = reader.u32()?;
+        let keys = (0..count)
+            .map(|_| reader.cstring())
+            .collect::<Result<Vec<_>, _>>()?;
+        reader.seek(entries_start);
+        keys
+    } else {
+        Vec::new()
+    };
+
+    let mut offsets = HashMap::new();
+    loop {
+        let app_id = reader.u32()?;
+        if app_id == 0 {
+            break;
+        }
+        let size = reader.u32()? as usize;
+        let start = reader.pos() + header_len;
+        let end = reader.pos() + size;
+        if size < header_len || end > data.len() {
+            return Err(AppError::VdfParse(format!(
+                "entry for app {} is truncated",
+                app_id
+            )));
+        }
+        offsets.insert(app_id, (start, end));
+        reader.seek(end);
+    }
+
+    Ok((keys, offsets))
+}
+
+fn appinfo_error(e: AppError) -> AppError {
+    match e {
+        AppError::VdfParse(msg) => AppError::AppInfoParse(msg),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::binary_vdf::{write_map, TYPE_MAP, TYPE_MAP_END, TYPE_STRING};
+    use super::*;
+
+    fn string(s: &str) -> BinaryValue {
+        BinaryValue::String(s.to_string())
+    }
+
+    fn map(entries: Vec<(&str, BinaryValue)>) -> BinaryValue {
+        BinaryValue::Map(entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    fn app(name: &str, oslist: &str) -> BinaryValue {
+        map(vec![(
+            "appinfo",
+            map(vec![
+                (
+                    "common",
+                    map(vec![("name", string(name)), ("type", string("Game")), ("oslist", string(oslist))]),
+                ),
+                (
+                    "config",
+                    map(vec![(
+                        "launch",
+                        map(vec![
+                            (
+                                "0",
+                                map(vec![
+                                    ("executable", string("game.exe")),
+                                    ("config", map(vec![("oslist", string("windows"))])),
+                                ]),
+                            ),
+                            (
+                                "1",
+                                map(vec![
+                                    ("executable", string("game.sh")),
+                                    ("arguments", string("-vulkan")),
+                                    ("config", map(vec![("oslist", string("linux"))])),
+                                ]),
+                            ),
+                        ]),
+                    )]),
+                ),
+            ]),
+        )])
+    }
+
+    /// Build a v28 appinfo.vdf (string keys)
+    fn appinfo_v28(apps: &[(u32, BinaryValue)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&MAGIC_V28.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for (app_id, value) in apps {
+            let mut section = Vec::new();
+            write_map(&mut section, value.entries());
+            data.extend_from_slice(&app_id.to_le_bytes());
+            data.extend_from_slice(&(60 + section.len() as u32).to_le_bytes());
+            data.extend_from_slice(&[0u8; 60]);
+            data.extend_from_slice(&section);
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_v28() {
+        let cache = AppInfoCache::parse(appinfo_v28(&[
+            (10, app("Counter-Strike", "windows,macos,linux")),
+            (20, app("Windows Only Game", "windows")),
+        ]))
+        .unwrap();
+
+        let cs = cache.get(10).unwrap().unwrap();
+        assert_eq!(cs.name.as_deref(), Some("Counter-Strike"));
+        assert_eq!(cs.oslist, vec!["windows", "macos", "linux"]);
+        assert!(!cs.is_windows_only());
+        let linux: Vec<_> = cs.launch_entries_for("linux").collect();
+        assert_eq!(linux.len(), 1);
+        assert_eq!(linux[0].executable, "game.sh");
+        assert_eq!(linux[0].arguments.as_deref(), Some("-vulkan"));
+
+        assert!(cache.get(20).unwrap().unwrap().is_windows_only());
+        assert!(cache.get(30).unwrap().is_none());
+
+        let found = cache.search("only", 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].app_id, 20);
+    }
+
+    #[test]
+    fn test_parse_v29_key_table() {
+        // Keys are u32 indices into a string table at the end of the file
+        let keys = ["appinfo", "common", "name"];
+        let mut section = vec![TYPE_MAP];
+        section.extend_from_slice(&0u32.to_le_bytes());
+        section.push(TYPE_MAP);
+        section.extend_from_slice(&1u32.to_le_bytes());
+        section.push(TYPE_STRING);
+        section.extend_from_slice(&2u32.to_le_bytes());
+        section.extend_from_slice(b"Portal\0");
+        section.extend_from_slice(&[TYPE_MAP_END, TYPE_MAP_END, TYPE_MAP_END]);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&MAGIC_V29.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        let table_offset = 16 + 4 + 4 + 60 + section.len() + 4;
+        data.extend_from_slice(&(table_offset as u64).to_le_bytes());
+        data.extend_from_slice(&400u32.to_le_bytes());
+        data.extend_from_slice(&(60 + section.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0u8; 60]);
+        data.extend_from_slice(&section);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(data.len(), table_offset);
+        data.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for key in keys {
+            data.extend_from_slice(key.as_bytes());
+            data.push(0);
+        }
+
+        let cache = AppInfoCache::parse(data).unwrap();
+        assert_eq!(cache.get(400).unwrap().unwrap().name.as_deref(), Some("Portal"));
+    }
+
+    #[test]
+    fn test_default_compat_tool() {
+        let manifests = map(vec![(
+            "appinfo",
+            map(vec![(
+                "extended",
+                map(vec![(
+                    "app_mappings",
+                    map(vec![(
+                        "0",
+                        map(vec![("appid", string("20")), ("tool", string("proton_experimental"))]),
+                    )]),
+                )]),
+            )]),
+        )]);
+        let cache = AppInfoCache::parse(appinfo_v28(&[
+            (20, app("Game", "windows")),
+            (STEAM_PLAY_MANIFESTS_APP_ID, manifests),
+        ]))
+        .unwrap();
+
+        assert_eq!(cache.default_compat_tool(20).as_deref(), Some("proton_experimental"));
+        assert_eq!(cache.default_compat_tool(10), None);
+    }
+
+    #[test]
+    fn test_unknown_version_is_an_error() {
+        assert!(AppInfoCache::parse(vec![0u8; 16]).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::error::AppError;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use tracing::info;
+
+/// Exclusive advisory lock on a directory, released when dropped
+///
+/// Locking the directory rather than the file means the lock survives the file
+/// being replaced by a rename, and leaves no lock files next to Steam's.
+pub struct DirLock {
+    _dir: File,
+}
+
+/// Take an exclusive lock on a directory, waiting for other holders to finish
+pub fn lock_dir(dir: &Path) -> Result<DirLock, AppError> {
+    let file = File::open(dir)?;
+    let fd = file.as_raw_fd();
+    // SAFETY: flock() only operates on the descriptor we own
+    if unsafe { libc::flock(fd, libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EWOULDBLOCK) {
+            return Err(err.into());
+        }
+        info!("Waiting for another steam-command-runner to finish writing in {}", dir.display());
+        // SAFETY: as above
+        if unsafe { libc::flock(fd, libc::LOCK_EX) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+    Ok(DirLock { _dir: file })
+}
+
+/// Replace a file's contents so that it is never seen half-written
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk,
+/// then renamed over the original, keeping the original's permissions.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), AppError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let temp = dir.join(format!(".{}.tmp-{}", name, std::process::id()));
+
+    let result = (|| {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        if let Ok(meta) = fs::metadata(path) {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        // Make the rename itself durable
+        File::open(dir)?.sync_all()
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("localconfig.vdf");
+        fs::write(&path, "old").unwrap();
+
+        let _lock = lock_dir(dir.path()).unwrap();
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // Only the file itself is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
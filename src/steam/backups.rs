@@ -0,0 +1,141 @@
+use crate::error::AppError;
+use crate::logs::timestamp;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Directory holding backups of Steam config files
+fn backups_root() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(".local/share"))
+        .join("steam-command-runner")
+        .join("steam-backups")
+}
+
+/// Directory holding backups of a Steam user's config files
+/// ($XDG_DATA_HOME/steam-command-runner/steam-backups/<user_id>)
+pub fn backups_dir(user_id: u64) -> PathBuf {
+    backups_root().join(user_id.to_string())
+}
+
+/// Directory holding backups of Steam's global config.vdf, which isn't tied to a user
+/// ($XDG_DATA_HOME/steam-command-runner/steam-backups/global)
+pub fn global_backups_dir() -> PathBuf {
+    backups_root().join("global")
+}
+
+/// Split a file name into stem and extension ("localconfig.vdf" -> ("localconfig", "vdf"))
+fn name_parts(file_name: &str) -> (&str, &str) {
+    file_name.rsplit_once('.').unwrap_or((file_name, ""))
+}
+
+/// Timestamp of a backup, given the name of the file it was taken from
+pub fn backup_timestamp(backup: &Path, file_name: &str) -> Option<String> {
+    let (stem, ext) = name_parts(file_name);
+    let name = backup.file_name()?.to_str()?;
+    let rest = name.strip_prefix(stem)?.strip_prefix('-')?;
+    let stamp = if ext.is_empty() {
+        rest
+    } else {
+        rest.strip_suffix(ext)?.strip_suffix('.')?
+    };
+    (!stamp.is_empty()).then(|| stamp.to_string())
+}
+
+/// Backups of one of a user's Steam config files (e.g. "localconfig.vdf"), oldest first
+pub fn list_backups(user_id: u64, file_name: &str) -> Vec<PathBuf> {
+    backups_in(&backups_dir(user_id), file_name)
+}
+
+/// Backups of a file in a backup directory, oldest first
+fn backups_in(dir: &Path, file_name: &str) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| backup_timestamp(p, file_name).is_some())
+                .collect()
+        })
+        .unwrap_or_default();
+    // Names end in a sortable timestamp, so lexical order is chronological
+    backups.sort();
+    backups
+}
+
+/// Find a backup by timestamp, or the most recent one
+pub fn find_backup(
+    user_id: u64,
+    file_name: &str,
+    timestamp: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    let backups = list_backups(user_id, file_name);
+    let found = match timestamp {
+        Some(stamp) => backups
+            .into_iter()
+            .find(|b| backup_timestamp(b, file_name).as_deref() == Some(stamp)),
+        None => backups.into_iter().next_back(),
+    };
+    found.ok_or_else(|| {
+        AppError::Backup(match timestamp {
+            Some(stamp) => format!("no backup of {} from {}", file_name, stamp),
+            None => format!("no backups of {} found", file_name),
+        })
+    })
+}
+
+/// Copy a Steam config file into the backup directory, keeping at most `keep` backups of it
+pub fn create_backup(user_id: u64, path: &Path, keep: usize) -> Result<PathBuf, AppError> {
+    backup_into(&backups_dir(user_id), path, keep)
+}
+
+/// Copy Steam's global config.vdf into the backup directory, keeping at most `keep` backups of it
+pub fn create_global_backup(path: &Path, keep: usize) -> Result<PathBuf, AppError> {
+    backup_into(&global_backups_dir(), path, keep)
+}
+
+/// Copy a file into `dir` under a timestamped name and prune its oldest backups
+fn backup_into(dir: &Path, path: &Path, keep: usize) -> Result<PathBuf, AppError> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::Backup(format!("invalid file name: {}", path.display())))?;
+    let (stem, ext) = name_parts(file_name);
+
+    fs::create_dir_all(dir)?;
+    let backup_name = if ext.is_empty() {
+        format!("{}-{}", stem, timestamp())
+    } else {
+        format!("{}-{}.{}", stem, timestamp(), ext)
+    };
+    let backup_path = dir.join(backup_name);
+
+    debug!("Creating backup: {}", backup_path.display());
+    fs::copy(path, &backup_path)?;
+    info!("Created backup: {}", backup_path.display());
+
+    let backups = backups_in(dir, file_name);
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in &backups[..excess] {
+        debug!("Removing old backup {}", old.display());
+        let _ = fs::remove_file(old);
+    }
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_timestamp() {
+        let path = Path::new("/backups/localconfig-20261016-120000.vdf");
+        assert_eq!(
+            backup_timestamp(path, "localconfig.vdf").as_deref(),
+            Some("20261016-120000")
+        );
+        assert_eq!(backup_timestamp(path, "shortcuts.vdf"), None);
+        assert_eq!(backup_timestamp(Path::new("/backups/localconfig.vdf"), "localconfig.vdf"), None);
+    }
+}
@@ -0,0 +1,220 @@
+//! Binary VDF (KeyValues) reading and writing
+//!
+//! Steam uses this format for shortcuts.vdf and for the per-app sections of
+//! appcache/appinfo.vdf. Maps keep their entries in file order so unknown keys
+//! survive a rewrite.
+
+use crate::error::AppError;
+
+// Binary VDF type markers
+pub(crate) const TYPE_MAP: u8 = 0x00;
+pub(crate) const TYPE_STRING: u8 = 0x01;
+pub(crate) const TYPE_INT32: u8 = 0x02;
+pub(crate) const TYPE_FLOAT32: u8 = 0x03;
+pub(crate) const TYPE_UINT64: u8 = 0x07;
+pub(crate) const TYPE_MAP_END: u8 = 0x08;
+
+/// A value in a binary VDF document
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryValue {
+    String(String),
+    /// A string that isn't valid UTF-8, kept byte for byte
+    Bytes(Vec<u8>),
+    Int32(u32),
+    Float32(f32),
+    UInt64(u64),
+    Map(Vec<(String, BinaryValue)>),
+}
+
+impl BinaryValue {
+    /// Look up a key in a map value (case-insensitive, like Steam)
+    pub fn get(&self, key: &str) -> Option<&BinaryValue> {
+        match self {
+            BinaryValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Follow a path of nested map keys
+    pub fn get_path(&self, path: &[&str]) -> Option<&BinaryValue> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    /// Entries of a map value (empty for anything else)
+    pub fn entries(&self) -> &[(String, BinaryValue)] {
+        match self {
+            BinaryValue::Map(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BinaryValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            BinaryValue::Int32(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// Reader over a binary VDF byte buffer
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Key string table; when set, keys are stored as u32 indices into it
+    keys: Option<&'a [String]>,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            keys: None,
+        }
+    }
+
+    /// A reader whose keys are indices into a string table (appinfo.vdf v29+)
+    pub(crate) fn with_key_table(data: &'a [u8], keys: &'a [String]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            keys: Some(keys),
+        }
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub(crate) fn byte(&mut self) -> Result<u8, AppError> {
+        let b = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| AppError::VdfParse("unexpected end of file".to_string()))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    pub(crate) fn bytes<const N: usize>(&mut self) -> Result<[u8; N], AppError> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| AppError::VdfParse("unexpected end of file".to_string()))?;
+        self.pos += N;
+        Ok(slice.try_into().expect("slice length checked"))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, AppError> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, AppError> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    /// Bytes up to the next NUL, which is consumed
+    fn raw_cstring(&mut self) -> Result<&'a [u8], AppError> {
+        let rest = self.data.get(self.pos..).unwrap_or_default();
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| AppError::VdfParse("unterminated string".to_string()))?;
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+
+    pub(crate) fn cstring(&mut self) -> Result<String, AppError> {
+        let offset = self.pos;
+        String::from_utf8(self.raw_cstring()?.to_vec())
+            .map_err(|_| AppError::VdfParse(format!("string at offset {} is not valid UTF-8", offset)))
+    }
+
+    /// A string value; one that isn't UTF-8 is kept as raw bytes so it is written back unchanged
+    fn string_value(&mut self) -> Result<BinaryValue, AppError> {
+        let raw = self.raw_cstring()?.to_vec();
+        Ok(String::from_utf8(raw).map_or_else(|e| BinaryValue::Bytes(e.into_bytes()), BinaryValue::String))
+    }
+
+    fn key(&mut self) -> Result<String, AppError> {
+        match self.keys {
+            None => self.cstring(),
+            Some(keys) => {
+                let index = self.u32()?;
+                keys.get(index as usize).cloned().ok_or_else(|| {
+                    AppError::VdfParse(format!("key index {} outside string table", index))
+                })
+            }
+        }
+    }
+
+    /// Read map entries up to and including the end marker
+    pub(crate) fn map(&mut self) -> Result<Vec<(String, BinaryValue)>, AppError> {
+        let mut entries = Vec::new();
+        loop {
+            let kind = self.byte()?;
+            if kind == TYPE_MAP_END {
+                return Ok(entries);
+            }
+            let key = self.key()?;
+            let value = match kind {
+                TYPE_MAP => BinaryValue::Map(self.map()?),
+                TYPE_STRING => self.string_value()?,
+                TYPE_INT32 => BinaryValue::Int32(self.u32()?),
+                TYPE_FLOAT32 => BinaryValue::Float32(f32::from_le_bytes(self.bytes()?)),
+                TYPE_UINT64 => BinaryValue::UInt64(self.u64()?),
+                other => {
+                    return Err(AppError::VdfParse(format!(
+                        "unknown type 0x{:02x} for key '{}' at offset {}",
+                        other, key, self.pos
+                    )))
+                }
+            };
+            entries.push((key, value));
+        }
+    }
+}
+
+/// Write map entries followed by the end marker
+pub(crate) fn write_map(out: &mut Vec<u8>, entries: &[(String, BinaryValue)]) {
+    for (key, value) in entries {
+        let kind = match value {
+            BinaryValue::Map(_) => TYPE_MAP,
+            BinaryValue::String(_) | BinaryValue::Bytes(_) => TYPE_STRING,
+            BinaryValue::Int32(_) => TYPE_INT32,
+            BinaryValue::Float32(_) => TYPE_FLOAT32,
+            BinaryValue::UInt64(_) => TYPE_UINT64,
+        };
+        out.push(kind);
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        match value {
+            BinaryValue::Map(inner) => write_map(out, inner),
+            BinaryValue::String(s) => {
+                out.extend_from_slice(s.as_bytes());
+                out.push(0);
+            }
+            BinaryValue::Bytes(bytes) => {
+                out.extend_from_slice(bytes);
+                out.push(0);
+            }
+            BinaryValue::Int32(v) => out.extend_from_slice(&v.to_le_bytes()),
+            BinaryValue::Float32(v) => out.extend_from_slice(&v.to_le_bytes()),
+            BinaryValue::UInt64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        }
+    }
+    out.push(TYPE_MAP_END);
+}
@@ -0,0 +1,151 @@
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::atomic::{lock_dir, write_atomic};
+use super::userdata::get_steam_root;
+use super::vdf::{self, VdfObject, VdfValue};
+
+/// Get the path to Steam's global config.vdf
+pub fn get_steam_config_path() -> Result<PathBuf, AppError> {
+    let steam_root = get_steam_root().ok_or_else(|| AppError::SteamNotFound(Vec::new()))?;
+    Ok(steam_root.join("config").join("config.vdf"))
+}
+
+/// Where Steam keeps CompatToolMapping in config.vdf
+const MAPPING_PATH: [&str; 4] = ["InstallConfigStore", "Software", "Valve", "Steam"];
+
+/// Set (or with `None`, remove) the compat tool Steam uses for an app
+///
+/// Edits the CompatToolMapping section of config.vdf's content, creating it if
+/// no compatibility tool has been chosen for any game yet.
+pub fn set_compat_tool_mapping(
+    content: &str,
+    app_id: u32,
+    tool: Option<&str>,
+) -> Result<String, AppError> {
+    let mut root = vdf::parse(content)?;
+    if root.find_object("CompatToolMapping").is_none() {
+        debug!("Creating CompatToolMapping in config.vdf");
+        MAPPING_PATH
+            .iter()
+            .fold(&mut root, |object, key| object.object_entry(key))
+            .object_entry("CompatToolMapping");
+    }
+    let section = root
+        .find_object_mut("CompatToolMapping")
+        .expect("CompatToolMapping was just created");
+
+    let key = app_id.to_string();
+    match tool {
+        Some(tool) => {
+            debug!("Setting CompatToolMapping for {} to {}", app_id, tool);
+            let mut entry = VdfObject::new();
+            entry.set_str("name", tool);
+            entry.set_str("config", "");
+            entry.set_str("priority", "250");
+            section.set(&key, VdfValue::Object(entry));
+        }
+        None => {
+            debug!("Removing CompatToolMapping for {}", app_id);
+            section.remove(&key);
+        }
+    }
+    Ok(vdf::to_string(&root))
+}
+
+/// Assign a compat tool to an app in Steam's config.vdf
+pub fn assign_compat_tool(app_id: u32, tool: Option<&str>) -> Result<(), AppError> {
+    assign_compat_tools(&[app_id], tool)
+}
+
+/// Assign (or with `None`, clear) the same compat tool for several apps at once
+///
+/// config.vdf is only written if every edit succeeds, and not at all if Steam
+/// changed it in the meantime.
+pub fn assign_compat_tools(app_ids: &[u32], tool: Option<&str>) -> Result<(), AppError> {
+    let path = get_steam_config_path()?;
+    let original = fs::read_to_string(&path)?;
+    let mut content = original.clone();
+    for &app_id in app_ids {
+        content = set_compat_tool_mapping(&content, app_id, tool)?;
+    }
+
+    let _lock = lock_dir(path.parent().unwrap_or(Path::new(".")))?;
+    if fs::read_to_string(&path)? != original {
+        return Err(AppError::ChangedOnDisk(path.display().to_string()));
+    }
+    debug!("Writing config.vdf ({} bytes)", content.len());
+    write_atomic(&path, content.as_bytes())
+}
+
+/// Read the compat tool chosen for each app from config.vdf content
+///
+/// App ID 0 holds the tool Steam Play uses by default and is included as-is.
+pub fn parse_compat_tool_mappings(content: &str) -> Result<HashMap<u32, String>, AppError> {
+    let root = vdf::parse(content)?;
+    let Some(section) = root.find_object("CompatToolMapping") else {
+        return Ok(HashMap::new());
+    };
+
+    Ok(section
+        .entries()
+        .filter_map(|(key, value)| {
+            let app_id = key.parse::<u32>().ok()?;
+            let tool = value.as_object()?.get_str("name")?;
+            (!tool.is_empty()).then(|| (app_id, tool.to_string()))
+        })
+        .collect())
+}
+
+/// Read the compat tool chosen for each app from Steam's config.vdf
+pub fn read_compat_tool_mappings() -> Result<HashMap<u32, String>, AppError> {
+    let path = get_steam_config_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    parse_compat_tool_mappings(&fs::read_to_string(&path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\"InstallConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"CompatToolMapping\"\n\t\t{\n\t\t\t\"0\"\n\t\t\t{\n\t\t\t\t\"name\"\t\t\"proton_9\"\n\t\t\t\t\"config\"\t\t\"\"\n\t\t\t\t\"priority\"\t\t\"75\"\n\t\t\t}\n\t\t}\n\t}\n}\n";
+
+    #[test]
+    fn test_add_replace_and_remove_mapping() {
+        let added = set_compat_tool_mapping(CONFIG, 2_147_483_650, Some("GE-Proton9-20")).unwrap();
+        assert!(added.contains("\t\t\t\"2147483650\"\n\t\t\t{\n\t\t\t\t\"name\"\t\t\"GE-Proton9-20\""));
+        assert!(added.contains("\"proton_9\""));
+
+        let replaced = set_compat_tool_mapping(&added, 2_147_483_650, Some("proton_experimental")).unwrap();
+        assert!(!replaced.contains("GE-Proton9-20"));
+        assert_eq!(replaced.matches("\"2147483650\"").count(), 1);
+
+        let removed = set_compat_tool_mapping(&replaced, 2_147_483_650, None).unwrap();
+        assert_eq!(removed, CONFIG);
+    }
+
+    #[test]
+    fn test_parse_compat_tool_mappings() {
+        let content = set_compat_tool_mapping(CONFIG, 440, Some("GE-Proton9-20")).unwrap();
+        let mappings = parse_compat_tool_mappings(&content).unwrap();
+        assert_eq!(mappings.get(&0).map(String::as_str), Some("proton_9"));
+        assert_eq!(mappings.get(&440).map(String::as_str), Some("GE-Proton9-20"));
+        assert!(parse_compat_tool_mappings("\"InstallConfigStore\"\n{\n}\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_section_is_created() {
+        let content = set_compat_tool_mapping("\"InstallConfigStore\"\n{\n}\n", 10, Some("proton_9")).unwrap();
+        let root = vdf::parse(&content).unwrap();
+        let section = root.get_path(&MAPPING_PATH).and_then(|steam| steam.get_object("CompatToolMapping"));
+        assert_eq!(
+            section.and_then(|s| s.get_object("10")).and_then(|entry| entry.get_str("name")),
+            Some("proton_9")
+        );
+    }
+}
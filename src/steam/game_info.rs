@@ -0,0 +1,92 @@
+use crate::config::{find_game_config_path, MergedConfig};
+use crate::error::AppError;
+use crate::proton::find_compat_data_path;
+use crate::steam::{
+    add_platform_info, default_user_id, find_installed_games, game_name, get_localconfig_path,
+    read_compat_tool_mappings, read_localconfig,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Everything known about one game, gathered from Steam's files and our config
+///
+/// Fields are None when the game isn't installed, has no prefix, or Steam
+/// doesn't record the value.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GameInfo {
+    pub app_id: u32,
+    /// From the appmanifest, or shortcuts.vdf for non-Steam games
+    pub name: Option<String>,
+    /// steamapps directory of the library the game is installed in
+    pub library: Option<PathBuf>,
+    pub install_path: Option<PathBuf>,
+    pub size_on_disk: Option<u64>,
+    /// Compatibility tool Steam runs the game with (the user's choice, or Valve's default)
+    pub compat_tool: Option<String>,
+    /// compatdata directory, when the game has a prefix
+    pub compat_data: Option<PathBuf>,
+    /// Per-game config file, when one exists
+    pub config_file: Option<PathBuf>,
+    /// Configured Proton version followed by its fallbacks
+    pub proton: Vec<String>,
+    /// Steam user whose launch options and last-played time are shown
+    pub user_id: Option<u64>,
+    pub launch_options: Option<String>,
+    /// Unix time the game was last started, from localconfig.vdf
+    pub last_played: Option<u64>,
+}
+
+/// Resolve everything about a game from its App ID
+///
+/// Launch options and last-played time belong to a Steam user: `user_id`, or the
+/// only (or most recently logged in) user. Missing pieces are left empty rather than
+/// failing, so the result is useful for uninstalled games and orphaned prefixes.
+pub fn game_info(app_id: u32, user_id: Option<u64>) -> Result<GameInfo, AppError> {
+    let mut info = GameInfo {
+        app_id,
+        name: game_name(app_id),
+        ..GameInfo::default()
+    };
+
+    let mut installed: Vec<_> = find_installed_games()?
+        .into_iter()
+        .filter(|game| game.app_id == app_id)
+        .collect();
+    add_platform_info(&mut installed);
+    if let Some(game) = installed.pop() {
+        info.install_path = Some(game.install_path());
+        info.library = Some(game.library);
+        info.size_on_disk = Some(game.size_on_disk);
+        info.compat_tool = game.compat_tool;
+    } else {
+        // Non-Steam games have no appmanifest, but can still have a tool mapped
+        info.compat_tool = read_compat_tool_mappings()
+            .unwrap_or_default()
+            .remove(&app_id);
+    }
+
+    info.compat_data = find_compat_data_path(app_id).ok().filter(|path| path.is_dir());
+    info.config_file = Some(find_game_config_path(app_id)).filter(|path| path.exists());
+    match MergedConfig::load(Some(app_id), None) {
+        Ok(config) => info.proton = config.proton_chain().into_iter().map(String::from).collect(),
+        Err(e) => debug!("Could not load config for {}: {}", app_id, e),
+    }
+
+    info.user_id = user_id.or_else(default_user_id);
+    if let Some(user_id) = info.user_id {
+        let localconfig = get_localconfig_path(user_id).and_then(read_localconfig);
+        match localconfig {
+            Ok(localconfig) => {
+                info.launch_options = localconfig.get_launch_options(app_id);
+                info.last_played = localconfig
+                    .get_app_value(app_id, "LastPlayed")
+                    .and_then(|value| value.parse().ok())
+                    .filter(|time| *time > 0);
+            }
+            Err(e) => debug!("Could not read localconfig.vdf of user {}: {}", user_id, e),
+        }
+    }
+
+    Ok(info)
+}
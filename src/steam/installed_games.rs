@@ -1,8 +1,14 @@
 use crate::error::AppError;
-use crate::steam::userdata::get_steam_root;
-use std::collections::HashSet;
+use crate::steam::appinfo::AppInfoCache;
+use crate::steam::compat_mapping::read_compat_tool_mappings;
+use crate::steam::shortcuts::read_shortcuts;
+use crate::steam::userdata::{find_user_ids, get_shortcuts_path, get_steam_root};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
 use tracing::debug;
 
 /// Information about an installed Steam game
@@ -11,6 +17,35 @@ pub struct InstalledGame {
     pub app_id: u32,
     pub name: String,
     pub install_dir: String,
+    /// The steamapps directory of the library the game is installed in
+    pub library: PathBuf,
+    /// Bytes on disk, as recorded by Steam in the appmanifest
+    pub size_on_disk: u64,
+    /// Platforms the game supports, from Steam's app cache (see `add_platform_info`)
+    pub oslist: Vec<String>,
+    /// Compatibility tool the game runs with: the user's choice, or Valve's default
+    pub compat_tool: Option<String>,
+}
+
+impl InstalledGame {
+    /// Directory the game is installed in (steamapps/common/<installdir>)
+    pub fn install_path(&self) -> PathBuf {
+        self.library.join("common").join(&self.install_dir)
+    }
+
+    /// Whether Steam runs this game through Proton
+    ///
+    /// True when a Proton compat tool is selected for it, or when it only ships
+    /// Windows builds (Steam Play's global default then applies).
+    pub fn runs_proton(&self) -> bool {
+        match &self.compat_tool {
+            Some(tool) => tool.to_lowercase().contains("proton"),
+            None => {
+                let supports = |os: &str| self.oslist.iter().any(|o| o == os);
+                supports("windows") && !supports("linux")
+            }
+        }
+    }
 }
 
 /// Parse a VDF key-value line like: "key"		"value"
@@ -45,8 +80,8 @@ fn parse_vdf_key_value(line: &str) -> Option<(&str, &str)> {
     }
 }
 
-/// Get all Steam library folders from libraryfolders.vdf
-fn get_library_folders() -> Result<Vec<PathBuf>, AppError> {
+/// Get all Steam library folders (their steamapps directories) from libraryfolders.vdf
+pub fn get_library_folders() -> Result<Vec<PathBuf>, AppError> {
     let steam_root = get_steam_root().ok_or_else(|| {
         AppError::SteamUserNotFound("Could not find Steam installation".to_string())
     })?;
@@ -97,12 +132,13 @@ fn get_library_folders() -> Result<Vec<PathBuf>, AppError> {
 }
 
 /// Parse an appmanifest_*.acf file to get game info
-fn parse_appmanifest(path: &PathBuf) -> Option<InstalledGame> {
+fn parse_appmanifest(path: &Path) -> Option<InstalledGame> {
     let content = fs::read_to_string(path).ok()?;
 
     let mut app_id: Option<u32> = None;
     let mut name: Option<String> = None;
     let mut install_dir: Option<String> = None;
+    let mut size_on_disk = 0;
 
     for line in content.lines() {
         let line = line.trim();
@@ -119,32 +155,138 @@ fn parse_appmanifest(path: &PathBuf) -> Option<InstalledGame> {
                     "appid" => app_id = value.parse().ok(),
                     "name" => name = Some(value),
                     "installdir" => install_dir = Some(value),
+                    "sizeondisk" => size_on_disk = value.parse().unwrap_or(0),
                     _ => {}
                 }
             }
         }
     }
 
-    match (app_id, name, install_dir) {
-        (Some(app_id), Some(name), Some(install_dir)) => Some(InstalledGame {
-            app_id,
-            name,
-            install_dir,
-        }),
-        (Some(app_id), Some(name), None) => Some(InstalledGame {
+    let library = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    match (app_id, name) {
+        (Some(app_id), Some(name)) => Some(InstalledGame {
             app_id,
             name,
-            install_dir: String::new(),
+            install_dir: install_dir.unwrap_or_default(),
+            library,
+            size_on_disk,
+            oslist: Vec::new(),
+            compat_tool: None,
         }),
         _ => None,
     }
 }
 
+/// Set by --refresh: rescan every appmanifest instead of trusting the scan cache
+static REFRESH_SCAN: AtomicBool = AtomicBool::new(false);
+
+/// Make `find_installed_games` re-read every appmanifest, rewriting the scan cache
+pub fn refresh_installed_games() {
+    REFRESH_SCAN.store(true, Ordering::Relaxed);
+}
+
+/// An appmanifest's contents as parsed at a given modification time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedManifest {
+    modified: u64,
+    app_id: u32,
+    name: String,
+    install_dir: String,
+    size_on_disk: u64,
+}
+
+/// Parsed appmanifests by path, so unchanged ones aren't read again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanCache {
+    manifests: HashMap<PathBuf, CachedManifest>,
+}
+
+/// File the installed-games scan is cached in
+fn scan_cache_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from(".cache"))
+        .join("steam-command-runner")
+        .join("installed-games.json")
+}
+
+fn load_scan_cache() -> ScanCache {
+    if REFRESH_SCAN.load(Ordering::Relaxed) {
+        return ScanCache::default();
+    }
+    fs::read_to_string(scan_cache_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_scan_cache(cache: &ScanCache) {
+    let path = scan_cache_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(cache).unwrap_or_default()));
+    if let Err(e) = result {
+        debug!("Could not write {}: {}", path.display(), e);
+    }
+}
+
+/// Modification time of a file in nanoseconds since the epoch
+fn modified_nanos(metadata: &fs::Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(modified.as_nanos() as u64)
+}
+
+/// Parse an appmanifest, reusing the cached result while its mtime is unchanged
+fn scan_appmanifest(
+    path: &Path,
+    modified: Option<u64>,
+    old: &ScanCache,
+    new: &mut ScanCache,
+) -> Option<InstalledGame> {
+    let library = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    if let (Some(modified), Some(cached)) = (modified, old.manifests.get(path)) {
+        if cached.modified == modified {
+            new.manifests.insert(path.to_path_buf(), cached.clone());
+            return Some(InstalledGame {
+                app_id: cached.app_id,
+                name: cached.name.clone(),
+                install_dir: cached.install_dir.clone(),
+                library,
+                size_on_disk: cached.size_on_disk,
+                oslist: Vec::new(),
+                compat_tool: None,
+            });
+        }
+    }
+
+    let game = parse_appmanifest(path)?;
+    if let Some(modified) = modified {
+        new.manifests.insert(
+            path.to_path_buf(),
+            CachedManifest {
+                modified,
+                app_id: game.app_id,
+                name: game.name.clone(),
+                install_dir: game.install_dir.clone(),
+                size_on_disk: game.size_on_disk,
+            },
+        );
+    }
+    Some(game)
+}
+
 /// Find all installed games across all Steam library folders
+///
+/// Parsed appmanifests are cached under $XDG_CACHE_HOME by path and mtime, so
+/// only new or changed ones are read; large network libraries would otherwise
+/// be re-read on every command. `refresh_installed_games` bypasses the cache.
 pub fn find_installed_games() -> Result<Vec<InstalledGame>, AppError> {
     let library_folders = get_library_folders()?;
     let mut games = Vec::new();
     let mut seen_ids: HashSet<u32> = HashSet::new();
+    let old_cache = load_scan_cache();
+    let mut new_cache = ScanCache::default();
 
     for steamapps in library_folders {
         debug!("Scanning library folder: {}", steamapps.display());
@@ -159,7 +301,8 @@ pub fn find_installed_games() -> Result<Vec<InstalledGame>, AppError> {
             let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
             if filename.starts_with("appmanifest_") && filename.ends_with(".acf") {
-                if let Some(game) = parse_appmanifest(&path) {
+                let modified = entry.metadata().ok().as_ref().and_then(modified_nanos);
+                if let Some(game) = scan_appmanifest(&path, modified, &old_cache, &mut new_cache) {
                     if !seen_ids.contains(&game.app_id) {
                         debug!("Found game: {} ({})", game.name, game.app_id);
                         seen_ids.insert(game.app_id);
@@ -170,17 +313,232 @@ pub fn find_installed_games() -> Result<Vec<InstalledGame>, AppError> {
         }
     }
 
+    let changed = new_cache.manifests.len() != old_cache.manifests.len()
+        || new_cache
+            .manifests
+            .iter()
+            .any(|(path, entry)| old_cache.manifests.get(path).map(|old| old.modified) != Some(entry.modified));
+    if changed {
+        save_scan_cache(&new_cache);
+    }
+
     // Sort by name
     games.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
 
     Ok(games)
 }
 
+/// App IDs and names of installed games, straight from the scan cache
+///
+/// Meant for shell completion, which runs on every Tab press: the libraries
+/// are only scanned when there is no cache yet, so games installed since the
+/// last command may be missing.
+pub fn cached_game_names() -> Vec<(u32, String)> {
+    let cache = load_scan_cache();
+    let mut games: Vec<(u32, String)> = if cache.manifests.is_empty() {
+        find_installed_games()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|game| (game.app_id, game.name))
+            .collect()
+    } else {
+        cache
+            .manifests
+            .into_values()
+            .map(|manifest| (manifest.app_id, manifest.name))
+            .collect()
+    };
+    games.sort_by_key(|(_, name)| name.to_lowercase());
+    games.dedup_by_key(|game| game.0);
+    games
+}
+
+/// Install directory of a Steam game, from its appmanifest
+pub fn game_install_dir(app_id: u32) -> Option<PathBuf> {
+    let manifest = format!("appmanifest_{}.acf", app_id);
+    get_library_folders().ok()?.iter().find_map(|steamapps| {
+        let game = parse_appmanifest(&steamapps.join(&manifest))?;
+        (!game.install_dir.is_empty()).then(|| game.install_path())
+    })
+}
+
+/// Name of an installed game or non-Steam shortcut, from its appmanifest or shortcuts.vdf
+pub fn game_name(app_id: u32) -> Option<String> {
+    let manifest = format!("appmanifest_{}.acf", app_id);
+    let installed = get_library_folders()
+        .unwrap_or_default()
+        .iter()
+        .find_map(|steamapps| parse_appmanifest(&steamapps.join(&manifest)));
+    if let Some(game) = installed {
+        return Some(game.name);
+    }
+
+    find_user_ids().ok()?.into_iter().find_map(|user_id| {
+        let shortcuts = read_shortcuts(get_shortcuts_path(user_id).ok()?).ok()?;
+        let shortcut = shortcuts.iter().find(|s| s.app_id() == app_id)?;
+        Some(shortcut.name().to_string())
+    })
+}
+
+/// Lowercase a name and reduce punctuation to single spaces ("Half-Life 2" -> "half life 2")
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How well a game name matches a search query (higher is better), or None
+///
+/// Exact and prefix matches rank first, then substrings, then names containing
+/// every query word, then names containing the query's characters in order
+/// (so "hl2" finds "Half-Life 2").
+pub fn name_match_score(query: &str, name: &str) -> Option<u32> {
+    let query = normalize_name(query);
+    let name = normalize_name(name);
+    if query.is_empty() {
+        return None;
+    }
+
+    if name == query {
+        return Some(100);
+    }
+    if name.starts_with(&query) {
+        return Some(90);
+    }
+    if name.contains(&query) {
+        return Some(80);
+    }
+    if query.split(' ').all(|word| name.contains(word)) {
+        return Some(60);
+    }
+
+    let mut chars = name.chars().filter(|c| *c != ' ');
+    let compact: String = query.chars().filter(|c| *c != ' ').collect();
+    if compact.len() >= 2 && compact.chars().all(|q| chars.any(|c| c == q)) {
+        return Some(30);
+    }
+    None
+}
+
+/// Installed games matching a search query, best matches first
+pub fn match_installed_games<'a>(query: &str, games: &'a [InstalledGame]) -> Vec<&'a InstalledGame> {
+    let mut matches: Vec<(u32, &InstalledGame)> = games
+        .iter()
+        .filter_map(|game| name_match_score(query, &game.name).map(|score| (score, game)))
+        .collect();
+    // Stable sort keeps the name order of equally good matches
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, game)| game).collect()
+}
+
+/// Fill in platform and compat tool details from Steam's app cache and config.vdf
+///
+/// Both sources are optional; games keep empty details when they can't be read.
+pub fn add_platform_info(games: &mut [InstalledGame]) {
+    let appinfo = AppInfoCache::load()
+        .map_err(|e| debug!("Steam app cache unavailable: {}", e))
+        .ok();
+    let mappings = read_compat_tool_mappings()
+        .map_err(|e| debug!("Could not read compat tool mappings: {}", e))
+        .unwrap_or_default();
+
+    for game in games {
+        if let Some(appinfo) = &appinfo {
+            if let Ok(Some(info)) = appinfo.get(game.app_id) {
+                game.oslist = info.oslist;
+            }
+        }
+        game.compat_tool = mappings.get(&game.app_id).cloned().or_else(|| {
+            appinfo
+                .as_ref()
+                .and_then(|a| a.default_compat_tool(game.app_id))
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_parse_appmanifest_content() {
-        // This is a simplified test - in reality we'd need a temp file
-        // Just testing that the function exists and doesn't panic
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("appmanifest_440.acf");
+        fs::write(
+            &path,
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"440\"\n\t\"name\"\t\t\"Team Fortress 2\"\n\t\"installdir\"\t\t\"Team Fortress 2\"\n\t\"SizeOnDisk\"\t\t\"26843545600\"\n}\n",
+        )
+        .unwrap();
+
+        let game = parse_appmanifest(&path).unwrap();
+        assert_eq!(game.app_id, 440);
+        assert_eq!(game.name, "Team Fortress 2");
+        assert_eq!(game.install_dir, "Team Fortress 2");
+        assert_eq!(game.library, dir.path());
+        assert_eq!(game.size_on_disk, 26_843_545_600);
+        assert_eq!(game.install_path(), dir.path().join("common/Team Fortress 2"));
+    }
+
+    #[test]
+    fn test_scan_cache_reuses_unchanged_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("appmanifest_440.acf");
+        fs::write(
+            &path,
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"440\"\n\t\"name\"\t\t\"Team Fortress 2\"\n}\n",
+        )
+        .unwrap();
+
+        let mut first = ScanCache::default();
+        let game = scan_appmanifest(&path, Some(1), &ScanCache::default(), &mut first).unwrap();
+        assert_eq!(game.name, "Team Fortress 2");
+        assert_eq!(first.manifests[&path].app_id, 440);
+
+        // Same mtime: the cached entry is trusted without reading the file
+        first.manifests.get_mut(&path).unwrap().name = "Cached".to_string();
+        let mut second = ScanCache::default();
+        let game = scan_appmanifest(&path, Some(1), &first, &mut second).unwrap();
+        assert_eq!(game.name, "Cached");
+        assert_eq!(game.library, dir.path());
+
+        // Changed mtime: parsed again
+        let game = scan_appmanifest(&path, Some(2), &first, &mut ScanCache::default()).unwrap();
+        assert_eq!(game.name, "Team Fortress 2");
+    }
+
+    #[test]
+    fn test_name_match_score() {
+        assert_eq!(name_match_score("half-life 2", "Half-Life 2"), Some(100));
+        assert_eq!(name_match_score("half", "Half-Life 2"), Some(90));
+        assert_eq!(name_match_score("life 2", "Half-Life 2"), Some(80));
+        assert_eq!(name_match_score("2 half", "Half-Life 2"), Some(60));
+        assert_eq!(name_match_score("hl2", "Half-Life 2"), Some(30));
+        assert_eq!(name_match_score("portal", "Half-Life 2"), None);
+        assert_eq!(name_match_score("", "Half-Life 2"), None);
+    }
+
+    #[test]
+    fn test_runs_proton() {
+        let mut game = InstalledGame {
+            app_id: 10,
+            name: "Game".to_string(),
+            install_dir: String::new(),
+            library: PathBuf::new(),
+            size_on_disk: 0,
+            oslist: vec!["windows".to_string()],
+            compat_tool: None,
+        };
+        assert!(game.runs_proton());
+
+        game.oslist.push("linux".to_string());
+        assert!(!game.runs_proton());
+
+        game.compat_tool = Some("GE-Proton9-20".to_string());
+        assert!(game.runs_proton());
+
+        game.compat_tool = Some("steamlinuxruntime_sniper".to_string());
+        assert!(!game.runs_proton());
     }
 }
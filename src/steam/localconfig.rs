@@ -1,339 +1,211 @@
+use super::atomic::{lock_dir, write_atomic};
+use super::vdf::{self, VdfObject};
 use crate::error::AppError;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use tracing::debug;
 
-/// Represents the localconfig with just the apps section we need
+/// Parsed localconfig.vdf
+///
+/// The whole document is kept, so writing it back preserves every other setting.
+#[derive(Clone)]
 pub struct LocalConfig {
-    /// The raw file content
-    content: String,
-    /// Parsed launch options by app ID
-    launch_options: HashMap<u32, String>,
+    root: VdfObject,
+    /// The text it was parsed from, to notice the file changing before we write
+    original: String,
+    /// Settings changed since parsing, as (App ID, key)
+    edits: Vec<(u32, String)>,
 }
 
 impl LocalConfig {
     /// Parse a localconfig.vdf file
-    fn parse(content: &str) -> Self {
-        let mut launch_options = HashMap::new();
-
-        // Find the apps section and parse launch options
-        // VDF format: "apps" { "12345" { "LaunchOptions" "options here" } }
-        let mut current_app_id: Option<u32> = None;
-        let mut in_apps_section = false;
-        let mut brace_depth = 0;
-        let mut apps_brace_depth = 0;
-
-        for line in content.lines() {
-            let trimmed = line.trim();
-
-            // Track brace depth
-            if trimmed == "{" {
-                brace_depth += 1;
-            } else if trimmed == "}" {
-                brace_depth -= 1;
-                if in_apps_section && brace_depth < apps_brace_depth {
-                    in_apps_section = false;
-                }
-                if in_apps_section && current_app_id.is_some() && brace_depth == apps_brace_depth {
-                    current_app_id = None;
-                }
-            }
-
-            // Check for apps section
-            if trimmed.starts_with("\"apps\"") {
-                in_apps_section = true;
-                apps_brace_depth = brace_depth + 1;
-                continue;
-            }
-
-            if !in_apps_section {
-                continue;
-            }
-
-            // Parse app ID entries (e.g., "1850570")
-            if current_app_id.is_none() {
-                if let Some(app_id) = parse_quoted_key(trimmed) {
-                    if let Ok(id) = app_id.parse::<u32>() {
-                        current_app_id = Some(id);
-                    }
-                }
-                continue;
-            }
-
-            // Look for LaunchOptions within an app
-            if let Some((key, value)) = parse_key_value(trimmed) {
-                if key.eq_ignore_ascii_case("LaunchOptions") {
-                    if let Some(app_id) = current_app_id {
-                        debug!("Found launch options for app {}: {}", app_id, value);
-                        launch_options.insert(app_id, value);
-                    }
-                }
-            }
-        }
-
-        LocalConfig {
-            content: content.to_string(),
-            launch_options,
-        }
+    fn parse(content: &str) -> Result<Self, AppError> {
+        Ok(LocalConfig {
+            root: vdf::parse(content)?,
+            original: content.to_string(),
+            edits: Vec::new(),
+        })
     }
 
-    /// Get launch options for a specific app
-    pub fn get_launch_options(&self, app_id: u32) -> Option<&String> {
-        self.launch_options.get(&app_id)
+    /// The per-app settings section (UserLocalConfigStore/Software/Valve/Steam/apps)
+    fn apps(&self) -> Option<&VdfObject> {
+        self.root
+            .get_path(&["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"])
+            .or_else(|| self.root.find_object("apps"))
     }
 
-    /// Set launch options for a specific app
-    pub fn set_launch_options(&mut self, app_id: u32, options: Option<&str>) {
-        match options {
-            Some(opts) => {
-                self.launch_options.insert(app_id, opts.to_string());
-            }
-            None => {
-                self.launch_options.remove(&app_id);
-            }
+    /// The per-app settings section, created if missing
+    fn apps_mut(&mut self) -> &mut VdfObject {
+        if self.apps().is_none() {
+            return self
+                .root
+                .object_entry("UserLocalConfigStore")
+                .object_entry("Software")
+                .object_entry("Valve")
+                .object_entry("Steam")
+                .object_entry("apps");
         }
-
-        // Regenerate content
-        self.regenerate_content(app_id, options);
-    }
-
-    /// Regenerate the content with the updated launch options
-    fn regenerate_content(&mut self, app_id: u32, options: Option<&str>) {
-        let app_id_str = app_id.to_string();
-        let mut new_lines = Vec::new();
-        let mut in_apps_section = false;
-        let mut in_target_app = false;
-        let mut brace_depth = 0;
-        let mut apps_brace_depth = 0;
-        let mut app_brace_depth = 0;
-        let mut added_launch_options = false;
-
-        let lines: Vec<&str> = self.content.lines().collect();
-        let mut i = 0;
-
-        while i < lines.len() {
-            let line = lines[i];
-            let trimmed = line.trim();
-
-            // Track brace depth
-            if trimmed == "{" {
-                brace_depth += 1;
-            } else if trimmed == "}" {
-                // Before closing the app section, add launch options if needed
-                if in_target_app
-                    && brace_depth == app_brace_depth
-                    && !added_launch_options
-                    && options.is_some()
-                {
-                    let indent = get_indent(line);
-                    new_lines.push(format!(
-                        "{}\t\"LaunchOptions\"\t\t\"{}\"",
-                        indent,
-                        escape_vdf_string(options.unwrap())
-                    ));
-                    added_launch_options = true;
-                }
-
-                brace_depth -= 1;
-                if in_apps_section && brace_depth < apps_brace_depth {
-                    in_apps_section = false;
-                }
-                if in_target_app && brace_depth < app_brace_depth {
-                    in_target_app = false;
-                }
-            }
-
-            // Check for apps section
-            if trimmed.starts_with("\"apps\"") {
-                in_apps_section = true;
-                apps_brace_depth = brace_depth + 1;
-            }
-
-            // Check for target app
-            if in_apps_section
-                && !in_target_app
-                && parse_quoted_key(trimmed) == Some(&app_id_str)
-            {
-                in_target_app = true;
-                app_brace_depth = brace_depth + 1;
-                added_launch_options = false;
-            }
-
-            // Handle LaunchOptions within target app
-            if in_target_app {
-                if let Some((key, _)) = parse_key_value(trimmed) {
-                    if key.eq_ignore_ascii_case("LaunchOptions") {
-                        if let Some(opts) = options {
-                            // Replace with new value
-                            let indent = get_line_indent(line);
-                            new_lines.push(format!(
-                                "{}\"LaunchOptions\"\t\t\"{}\"",
-                                indent,
-                                escape_vdf_string(opts)
-                            ));
-                            added_launch_options = true;
-                        }
-                        // If options is None, skip this line (remove it)
-                        i += 1;
-                        continue;
-                    }
-                }
-            }
-
-            new_lines.push(line.to_string());
-            i += 1;
-        }
-
-        // If we didn't find the app at all, we need to add it
-        if !in_target_app && options.is_some() {
-            // Find the apps section and add the new entry
-            self.content = add_app_entry(&new_lines.join("\n"), app_id, options.unwrap());
+        let path = ["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"];
+        if self.root.get_path(&path).is_some() {
+            self.root.get_path_mut(&path).expect("path exists")
         } else {
-            self.content = new_lines.join("\n");
+            self.root.find_object_mut("apps").expect("apps exists")
         }
     }
 
-    /// Get the raw content
-    pub fn content(&self) -> &str {
-        &self.content
+    /// Get a string setting for a specific app
+    pub fn get_app_value(&self, app_id: u32, key: &str) -> Option<&str> {
+        self.apps()?.get_object(&app_id.to_string())?.get_str(key)
     }
-}
 
-/// Add a new app entry with launch options
-fn add_app_entry(content: &str, app_id: u32, options: &str) -> String {
-    let mut new_lines = Vec::new();
-    let mut in_apps_section = false;
-    let mut brace_depth = 0;
-    let mut apps_brace_depth = 0;
-    let mut added = false;
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed == "{" {
-            brace_depth += 1;
-            new_lines.push(line.to_string());
-
-            // Add entry right after opening brace of apps section
-            if in_apps_section && brace_depth == apps_brace_depth && !added {
-                new_lines.push(format!("\t\t\t\t\t\"{}\"", app_id));
-                new_lines.push("\t\t\t\t\t{".to_string());
-                new_lines.push(format!(
-                    "\t\t\t\t\t\t\"LaunchOptions\"\t\t\"{}\"",
-                    escape_vdf_string(options)
-                ));
-                new_lines.push("\t\t\t\t\t}".to_string());
-                added = true;
+    /// Set (or with `None`, remove) a string setting for a specific app
+    pub fn set_app_value(&mut self, app_id: u32, key: &str, value: Option<&str>) {
+        self.edits.push((app_id, key.to_string()));
+        let app_key = app_id.to_string();
+        match value {
+            Some(value) => {
+                self.apps_mut().object_entry(&app_key).set_str(key, value);
             }
-            continue;
-        } else if trimmed == "}" {
-            brace_depth -= 1;
-            if in_apps_section && brace_depth < apps_brace_depth {
-                in_apps_section = false;
+            None => {
+                if let Some(app) = self.apps_mut().get_object_mut(&app_key) {
+                    app.remove(key);
+                }
             }
         }
-
-        if trimmed.starts_with("\"apps\"") {
-            in_apps_section = true;
-            apps_brace_depth = brace_depth + 1;
-        }
-
-        new_lines.push(line.to_string());
     }
 
-    new_lines.join("\n")
-}
-
-/// Parse a quoted key from a line (e.g., '"key"' returns "key")
-/// Only matches standalone keys (e.g., section names, app IDs), not key-value pairs
-fn parse_quoted_key(line: &str) -> Option<&str> {
-    let line = line.trim();
-    if !line.starts_with('"') {
-        return None;
-    }
-
-    let rest = &line[1..];
-    let end = rest.find('"')?;
-
-    // Make sure there's nothing significant after (just optional whitespace or opening brace)
-    // This excludes key-value pairs like "key" "value"
-    let after = rest[end + 1..].trim();
-    if after.is_empty() || after == "{" {
-        Some(&rest[..end])
-    } else {
-        None
+    /// Get launch options for a specific app
+    pub fn get_launch_options(&self, app_id: u32) -> Option<String> {
+        self.get_app_value(app_id, "LaunchOptions").map(str::to_string)
     }
-}
 
-/// Parse a key-value pair (e.g., '"key" "value"' returns ("key", "value"))
-fn parse_key_value(line: &str) -> Option<(&str, String)> {
-    let line = line.trim();
-    if !line.starts_with('"') {
-        return None;
+    /// Set launch options for a specific app
+    pub fn set_launch_options(&mut self, app_id: u32, options: Option<&str>) {
+        debug!("Setting launch options for app {}: {:?}", app_id, options);
+        self.set_app_value(app_id, "LaunchOptions", options);
     }
 
-    let rest = &line[1..];
-    let key_end = rest.find('"')?;
-    let key = &rest[..key_end];
-
-    let after_key = rest[key_end + 1..].trim();
-    if !after_key.starts_with('"') {
-        return None;
+    /// All app IDs with launch options set
+    pub fn apps_with_launch_options(&self) -> Vec<(u32, String)> {
+        self.apps()
+            .map(|apps| {
+                apps.entries()
+                    .filter_map(|(key, value)| {
+                        let id = key.parse::<u32>().ok()?;
+                        let options = value.as_object()?.get_str("LaunchOptions")?;
+                        Some((id, options.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    let value_rest = &after_key[1..];
-    // Handle escaped quotes in value
-    let mut value = String::new();
-    let mut chars = value_rest.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            if let Some(&next) = chars.peek() {
-                if next == '"' || next == '\\' {
-                    value.push(next);
-                    chars.next();
-                    continue;
-                }
+    /// Remove a setting for checking, along with its app if nothing else is left
+    fn strip_app_value(&mut self, app_id: u32, key: &str) {
+        let app_key = app_id.to_string();
+        let apps = self.apps_mut();
+        if let Some(app) = apps.get_object_mut(&app_key) {
+            app.remove(key);
+            if app.entries().next().is_none() {
+                apps.remove(&app_key);
             }
-            value.push(c);
-        } else if c == '"' {
-            break;
-        } else {
-            value.push(c);
         }
     }
 
-    Some((key, value))
-}
-
-/// Get the indentation of a line
-fn get_line_indent(line: &str) -> &str {
-    let trimmed_len = line.trim_start().len();
-    &line[..line.len() - trimmed_len]
+    /// Serialize the document back to VDF text
+    pub fn content(&self) -> String {
+        vdf::to_string(&self.root)
+    }
 }
 
-/// Get a reasonable indent based on a closing brace line
-fn get_indent(line: &str) -> &str {
-    get_line_indent(line)
+/// Launch options that differ between two versions of localconfig.vdf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchOptionsChange {
+    pub app_id: u32,
+    pub before: Option<String>,
+    pub after: Option<String>,
 }
 
-/// Escape a string for VDF format
-fn escape_vdf_string(s: &str) -> String {
-    s.replace('\\', "\\\\").replace('"', "\\\"")
+/// Compare the launch options of two configs, ordered by app ID
+pub fn diff_launch_options(before: &LocalConfig, after: &LocalConfig) -> Vec<LaunchOptionsChange> {
+    let before: HashMap<u32, String> = before.apps_with_launch_options().into_iter().collect();
+    let after: HashMap<u32, String> = after.apps_with_launch_options().into_iter().collect();
+
+    let mut app_ids: Vec<u32> = before.keys().chain(after.keys()).copied().collect();
+    app_ids.sort_unstable();
+    app_ids.dedup();
+
+    app_ids
+        .into_iter()
+        .filter(|id| before.get(id) != after.get(id))
+        .map(|app_id| LaunchOptionsChange {
+            app_id,
+            before: before.get(&app_id).cloned(),
+            after: after.get(&app_id).cloned(),
+        })
+        .collect()
 }
 
 /// Read and parse localconfig.vdf
 pub fn read_localconfig<P: AsRef<Path>>(path: P) -> Result<LocalConfig, AppError> {
     let content = fs::read_to_string(path.as_ref())?;
     debug!("Read localconfig.vdf ({} bytes)", content.len());
-    Ok(LocalConfig::parse(&content))
+    LocalConfig::parse(&content)
 }
 
 /// Write localconfig.vdf back to disk
+///
+/// The new file must parse, and the old one must not have changed since it was
+/// read (by Steam, or another invocation of ours). The file is replaced
+/// atomically while holding a lock on its directory.
 pub fn write_localconfig<P: AsRef<Path>>(path: P, config: &LocalConfig) -> Result<(), AppError> {
-    debug!("Writing localconfig.vdf ({} bytes)", config.content.len());
-    fs::write(path.as_ref(), &config.content)?;
-    Ok(())
+    let path = path.as_ref();
+    let content = config.content();
+    verify_rewrite(&config.original, &content, &config.edits)?;
+
+    let _lock = lock_dir(path.parent().unwrap_or(Path::new(".")))?;
+    if fs::read_to_string(path)? != config.original {
+        return Err(AppError::ChangedOnDisk(path.display().to_string()));
+    }
+
+    debug!("Writing localconfig.vdf ({} bytes)", content.len());
+    write_atomic(path, content.as_bytes())
+}
+
+/// Check that rewritten localconfig.vdf text still holds everything the original did
+///
+/// Apart from the settings in `edits`, the parsed documents must be identical:
+/// the same apps, and the same keys and values everywhere else.
+fn verify_rewrite(original: &str, rewritten: &str, edits: &[(u32, String)]) -> Result<(), AppError> {
+    let mut before = LocalConfig::parse(original)?;
+    let mut after = LocalConfig::parse(rewritten)
+        .map_err(|e| AppError::RewriteVerification(format!("the new file doesn't parse: {}", e)))?;
+    for (app_id, key) in edits {
+        before.strip_app_value(*app_id, key);
+        after.strip_app_value(*app_id, key);
+    }
+    if before.root == after.root {
+        return Ok(());
+    }
+
+    let app_ids = |config: &LocalConfig| -> Vec<String> {
+        config
+            .apps()
+            .map(|apps| apps.entries().map(|(key, _)| key.to_string()).collect())
+            .unwrap_or_default()
+    };
+    let (before_apps, after_apps) = (app_ids(&before), app_ids(&after));
+    let missing: Vec<_> = before_apps.iter().filter(|id| !after_apps.contains(id)).collect();
+    let added: Vec<_> = after_apps.iter().filter(|id| !before_apps.contains(id)).collect();
+    let problem = if !missing.is_empty() {
+        format!("apps {:?} would be lost", missing)
+    } else if !added.is_empty() {
+        format!("apps {:?} would appear", added)
+    } else {
+        "settings other than the ones being changed would be altered".to_string()
+    };
+    Err(AppError::RewriteVerification(problem))
 }
 
 /// Set launch options (convenience function)
@@ -343,7 +215,7 @@ pub fn set_launch_options(config: &mut LocalConfig, app_id: u32, options: Option
 
 /// Get launch options (convenience function)
 pub fn get_launch_options(config: &LocalConfig, app_id: u32) -> Option<String> {
-    config.get_launch_options(app_id).cloned()
+    config.get_launch_options(app_id)
 }
 
 /// Check if launch options look like they were set by steam-command-runner
@@ -367,6 +239,76 @@ pub fn is_our_launch_options(options: &str) -> bool {
     false
 }
 
+/// How new launch options combine with ones a game already has
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergeMode {
+    /// Overwrite existing launch options
+    #[default]
+    Replace,
+    /// Put our wrapper outside the existing command (`ours -- theirs %command%`)
+    Prepend,
+    /// Put our wrapper inside the existing command (`theirs ours -- %command%`)
+    Append,
+}
+
+/// Combine new launch options with a game's existing ones
+///
+/// `ours` is a `<wrapper> %command% [args]` string. Existing options without
+/// `%command%` are plain game arguments, as Steam treats them. Leading
+/// `VAR=value` assignments always stay first so the shell still applies them.
+/// Options that already contain our wrapper are returned unchanged.
+pub fn merge_launch_options(existing: Option<&str>, ours: &str, mode: MergeMode) -> String {
+    let existing = existing.map(str::trim).unwrap_or("");
+    if mode == MergeMode::Replace || existing.is_empty() {
+        return ours.to_string();
+    }
+
+    let (our_wrapper, our_args) = split_at_command(ours).unwrap_or((ours.trim(), ""));
+    if is_our_launch_options(existing) || (!our_wrapper.is_empty() && existing.contains(our_wrapper)) {
+        debug!("Launch options already wrap with '{}', leaving them alone", our_wrapper);
+        return existing.to_string();
+    }
+
+    let (their_prefix, their_args) = split_at_command(existing).unwrap_or(("", existing));
+    let (env, their_wrapper) = split_env_assignments(their_prefix);
+
+    let wrappers = match mode {
+        MergeMode::Prepend => [our_wrapper, their_wrapper.as_str()],
+        MergeMode::Append | MergeMode::Replace => [their_wrapper.as_str(), our_wrapper],
+    };
+
+    [env.as_str(), wrappers[0], wrappers[1], "%command%", our_args, their_args]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split options around `%command%`, returning the trimmed text before and after it
+fn split_at_command(options: &str) -> Option<(&str, &str)> {
+    let (before, after) = options.split_once("%command%")?;
+    Some((before.trim(), after.trim()))
+}
+
+/// Split leading `VAR=value` assignments from the rest of a command prefix
+fn split_env_assignments(prefix: &str) -> (String, String) {
+    let words: Vec<&str> = prefix.split_whitespace().collect();
+    let count = words.iter().take_while(|word| is_env_assignment(word)).count();
+    (words[..count].join(" "), words[count..].join(" "))
+}
+
+fn is_env_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
 /// Generate the default launch options string
 ///
 /// Returns the absolute path to the local gamescope shim:
@@ -403,26 +345,129 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_quoted_key() {
-        assert_eq!(parse_quoted_key("\"1850570\""), Some("1850570"));
-        assert_eq!(parse_quoted_key("\"apps\""), Some("apps"));
-        assert_eq!(parse_quoted_key("\"key\" \"value\""), None);
-        assert_eq!(parse_quoted_key("not quoted"), None);
+    fn test_merge_launch_options() {
+        let ours = "/home/user/.local/bin/gamescope -- %command%";
+        let merge = |existing, mode| merge_launch_options(existing, ours, mode);
+
+        assert_eq!(merge(None, MergeMode::Prepend), ours);
+        assert_eq!(merge(Some("mangohud %command%"), MergeMode::Replace), ours);
+        assert_eq!(
+            merge(Some("mangohud %command% -novid"), MergeMode::Prepend),
+            "/home/user/.local/bin/gamescope -- mangohud %command% -novid"
+        );
+        assert_eq!(
+            merge(Some("mangohud %command%"), MergeMode::Append),
+            "mangohud /home/user/.local/bin/gamescope -- %command%"
+        );
+        // Plain arguments are passed to the game
+        assert_eq!(
+            merge(Some("-dx11 -novid"), MergeMode::Prepend),
+            "/home/user/.local/bin/gamescope -- %command% -dx11 -novid"
+        );
+        // Environment assignments stay in front
+        assert_eq!(
+            merge(Some("PROTON_LOG=1 DXVK_HUD=fps gamemoderun %command%"), MergeMode::Prepend),
+            "PROTON_LOG=1 DXVK_HUD=fps /home/user/.local/bin/gamescope -- gamemoderun %command%"
+        );
     }
 
     #[test]
-    fn test_parse_key_value() {
-        let result = parse_key_value("\"LaunchOptions\"\t\t\"test options\"");
-        assert_eq!(result, Some(("LaunchOptions", "test options".to_string())));
+    fn test_merge_launch_options_does_not_double_wrap() {
+        let ours = "/home/user/.local/bin/gamescope -- %command%";
+        let wrapped = "/home/user/.local/bin/gamescope -- mangohud %command%";
+        assert_eq!(merge_launch_options(Some(wrapped), ours, MergeMode::Prepend), wrapped);
+        assert_eq!(
+            merge_launch_options(Some("gamescope -- %command%"), ours, MergeMode::Append),
+            "gamescope -- %command%"
+        );
+    }
+
+    const LOCALCONFIG: &str = "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t\t\"1850570\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LastPlayed\"\t\t\"1700000000\"\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"value with \\\"quotes\\\"\"\n\t\t\t\t\t}\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
 
-        let result = parse_key_value("\"key\"\t\"value with \\\"quotes\\\"\"");
-        assert_eq!(result, Some(("key", "value with \"quotes\"".to_string())));
+    #[test]
+    fn test_get_launch_options() {
+        let config = LocalConfig::parse(LOCALCONFIG).unwrap();
+        assert_eq!(
+            config.get_launch_options(1850570).as_deref(),
+            Some("value with \"quotes\"")
+        );
+        assert_eq!(config.get_launch_options(10), None);
+        assert_eq!(config.get_app_value(1850570, "LastPlayed"), Some("1700000000"));
+    }
+
+    #[test]
+    fn test_set_and_clear_launch_options() {
+        let mut config = LocalConfig::parse(LOCALCONFIG).unwrap();
+        config.set_launch_options(1850570, Some("gamescope -- %command%"));
+        config.set_launch_options(10, Some("-novid"));
+
+        let reparsed = LocalConfig::parse(&config.content()).unwrap();
+        assert_eq!(
+            reparsed.get_launch_options(1850570).as_deref(),
+            Some("gamescope -- %command%")
+        );
+        assert_eq!(reparsed.get_launch_options(10).as_deref(), Some("-novid"));
+        assert_eq!(reparsed.get_app_value(1850570, "LastPlayed"), Some("1700000000"));
+
+        config.set_launch_options(10, None);
+        assert_eq!(config.get_launch_options(10), None);
+    }
+
+    #[test]
+    fn test_verify_rewrite() {
+        let mut config = LocalConfig::parse(LOCALCONFIG).unwrap();
+        config.set_launch_options(1850570, None);
+        config.set_launch_options(10, Some("-novid"));
+        let rewritten = config.content();
+        assert!(verify_rewrite(LOCALCONFIG, &rewritten, &config.edits).is_ok());
+
+        // Losing an app, or a setting nobody asked to change, fails the check
+        let lost_app = rewritten.replace("\"1850570\"", "\"1850571\"");
+        assert!(verify_rewrite(LOCALCONFIG, &lost_app, &config.edits).is_err());
+        let changed = rewritten.replace("1700000000", "1700000001");
+        assert!(verify_rewrite(LOCALCONFIG, &changed, &config.edits).is_err());
+        assert!(verify_rewrite(LOCALCONFIG, "\"apps\"\n{", &config.edits).is_err());
+        assert!(verify_rewrite(LOCALCONFIG, &rewritten, &[]).is_err());
+    }
+
+    #[test]
+    fn test_write_localconfig_refuses_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("localconfig.vdf");
+        fs::write(&path, LOCALCONFIG).unwrap();
+
+        let mut config = read_localconfig(&path).unwrap();
+        config.set_launch_options(10, Some("-novid"));
+        write_localconfig(&path, &config).unwrap();
+        assert_eq!(read_localconfig(&path).unwrap().get_launch_options(10).as_deref(), Some("-novid"));
+
+        // Written since `config` was read
+        assert!(matches!(
+            write_localconfig(&path, &config),
+            Err(AppError::ChangedOnDisk(_))
+        ));
+    }
+
+    #[test]
+    fn test_diff_launch_options() {
+        let before = LocalConfig::parse(LOCALCONFIG).unwrap();
+        let mut after = before.clone();
+        after.set_launch_options(1850570, None);
+        after.set_launch_options(10, Some("-novid"));
+
+        let changes = diff_launch_options(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].app_id, 10);
+        assert_eq!(changes[0].before, None);
+        assert_eq!(changes[0].after.as_deref(), Some("-novid"));
+        assert_eq!(changes[1].app_id, 1850570);
+        assert_eq!(changes[1].after, None);
+        assert!(diff_launch_options(&before, &before).is_empty());
     }
 
     #[test]
-    fn test_escape_vdf_string() {
-        assert_eq!(escape_vdf_string("test"), "test");
-        assert_eq!(escape_vdf_string("test\"quote"), "test\\\"quote");
-        assert_eq!(escape_vdf_string("test\\slash"), "test\\\\slash");
+    fn test_unchanged_config_round_trips() {
+        let config = LocalConfig::parse(LOCALCONFIG).unwrap();
+        assert_eq!(config.content(), LOCALCONFIG);
     }
 }
@@ -1,10 +1,39 @@
+pub mod appinfo;
+pub mod atomic;
+pub mod backups;
+pub mod binary_vdf;
+pub mod compat_mapping;
+pub mod game_info;
 pub mod installed_games;
 pub mod localconfig;
+pub mod process;
+pub mod shortcuts;
 pub mod userdata;
+pub mod vdf;
 
-pub use installed_games::{find_installed_games, InstalledGame};
+pub use appinfo::{get_appinfo_path, AppInfo, AppInfoCache, LaunchEntry};
+pub use backups::{
+    backup_timestamp, backups_dir, create_backup, create_global_backup, find_backup,
+    global_backups_dir, list_backups,
+};
+pub use binary_vdf::BinaryValue;
+pub use compat_mapping::{
+    assign_compat_tool, assign_compat_tools, get_steam_config_path, parse_compat_tool_mappings,
+    read_compat_tool_mappings, set_compat_tool_mapping,
+};
+pub use game_info::{game_info, GameInfo};
+pub use installed_games::{
+    add_platform_info, cached_game_names, find_installed_games, game_install_dir, game_name, get_library_folders,
+    match_installed_games, name_match_score, refresh_installed_games, InstalledGame,
+};
 pub use localconfig::{
-    generate_default_launch_options, get_launch_options, is_our_launch_options, read_localconfig,
-    set_launch_options, write_localconfig, LocalConfig,
+    generate_default_launch_options, get_launch_options, is_our_launch_options,
+    diff_launch_options, merge_launch_options, read_localconfig, set_launch_options,
+    write_localconfig, LaunchOptionsChange, LocalConfig, MergeMode,
+};
+pub use process::{find_steam_pid, is_steam_running, shutdown_steam, start_steam};
+pub use shortcuts::{read_shortcuts, shortcut_app_id, write_shortcuts, Shortcut, Shortcuts};
+pub use userdata::{
+    default_user_id, find_user_ids, get_grid_dir, get_localconfig_path, get_shortcuts_path,
+    get_steam_root, is_flatpak_steam, steam_root_candidates,
 };
-pub use userdata::{find_user_ids, get_localconfig_path};
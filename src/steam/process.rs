@@ -0,0 +1,132 @@
+use super::userdata::{get_steam_root, is_flatpak_steam, FLATPAK_STEAM_HOME};
+use crate::error::AppError;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// How long to wait for Steam to exit after asking it to shut down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Find the PID of the running Steam client, if any
+///
+/// Steam writes its PID to ~/.steam/steam.pid (inside the sandbox home for
+/// Flatpak Steam), but the file is left behind after a crash, so the process
+/// table is the source of truth.
+pub fn find_steam_pid() -> Option<u32> {
+    let home = dirs::home_dir()?;
+    let pid_files = [
+        home.join(".steam/steam.pid"),
+        home.join(FLATPAK_STEAM_HOME).join(".steam/steam.pid"),
+    ];
+    for pid_file in &pid_files {
+        let Some(pid) = fs::read_to_string(pid_file)
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if is_steam_process(&Path::new("/proc").join(pid.to_string())) {
+            debug!("Steam is running (pid {} from {})", pid, pid_file.display());
+            return Some(pid);
+        }
+    }
+
+    let pid = fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            is_steam_process(&entry.path()).then_some(pid)
+        })
+        .next();
+    if let Some(pid) = pid {
+        debug!("Steam is running (pid {})", pid);
+    }
+    pid
+}
+
+/// Whether Steam is currently running
+pub fn is_steam_running() -> bool {
+    find_steam_pid().is_some()
+}
+
+/// Check a /proc/<pid> entry for the Steam client process
+fn is_steam_process(proc_dir: &Path) -> bool {
+    fs::read_to_string(proc_dir.join("comm"))
+        .map(|comm| is_steam_comm(comm.trim()))
+        .unwrap_or(false)
+}
+
+fn is_steam_comm(comm: &str) -> bool {
+    comm == "steam"
+}
+
+/// Command that runs the Steam client (through flatpak for Flatpak Steam)
+fn steam_command() -> Command {
+    match get_steam_root() {
+        Some(root) if is_flatpak_steam(&root) => {
+            let mut command = Command::new("flatpak");
+            command.args(["run", "com.valvesoftware.Steam"]);
+            command
+        }
+        _ => Command::new("steam"),
+    }
+}
+
+/// Ask Steam to shut down and wait for it to exit
+pub fn shutdown_steam() -> Result<(), AppError> {
+    info!("Asking Steam to shut down");
+    let status = steam_command()
+        .arg("-shutdown")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| AppError::ExecutionFailed(format!("steam -shutdown: {}", e)))?;
+    debug!("steam -shutdown exited with {}", status);
+
+    let start = Instant::now();
+    while is_steam_running() {
+        if start.elapsed() > SHUTDOWN_TIMEOUT {
+            return Err(AppError::SteamRunning(format!(
+                "Steam did not exit within {} seconds",
+                SHUTDOWN_TIMEOUT.as_secs()
+            )));
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    info!("Steam has exited");
+    Ok(())
+}
+
+/// Start Steam in the background, detached from this process
+pub fn start_steam() -> Result<(), AppError> {
+    info!("Starting Steam");
+    let mut command = steam_command();
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    command
+        .spawn()
+        .map_err(|e| AppError::ExecutionFailed(format!("starting steam: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_steam_comm() {
+        assert!(is_steam_comm("steam"));
+        // comm is truncated to 15 characters, so our own binary shows up like this
+        assert!(!is_steam_comm("steam-command-r"));
+        assert!(!is_steam_comm("steamwebhelper"));
+    }
+}
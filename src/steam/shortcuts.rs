@@ -0,0 +1,347 @@
+use super::atomic::{lock_dir, write_atomic};
+use super::binary_vdf::{write_map, BinaryValue, Reader, TYPE_MAP, TYPE_MAP_END};
+use crate::error::AppError;
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+/// A single non-Steam game entry
+///
+/// All fields are kept in their original order so unknown keys survive a rewrite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcut {
+    fields: Vec<(String, BinaryValue)>,
+}
+
+impl Shortcut {
+    /// Create a shortcut with the fields Steam writes for a new non-Steam game
+    pub fn new(name: &str, exe: &Path, start_dir: &Path) -> Self {
+        let exe = quote_path(exe);
+        let app_id = shortcut_app_id(&exe, name);
+        let string = |s: &str| BinaryValue::String(s.to_string());
+
+        let fields = vec![
+            ("appid".to_string(), BinaryValue::Int32(app_id)),
+            ("AppName".to_string(), string(name)),
+            ("Exe".to_string(), BinaryValue::String(exe)),
+            ("StartDir".to_string(), BinaryValue::String(quote_path(start_dir))),
+            ("icon".to_string(), string("")),
+            ("ShortcutPath".to_string(), string("")),
+            ("LaunchOptions".to_string(), string("")),
+            ("IsHidden".to_string(), BinaryValue::Int32(0)),
+            ("AllowDesktopConfig".to_string(), BinaryValue::Int32(1)),
+            ("AllowOverlay".to_string(), BinaryValue::Int32(1)),
+            ("OpenVR".to_string(), BinaryValue::Int32(0)),
+            ("Devkit".to_string(), BinaryValue::Int32(0)),
+            ("DevkitGameID".to_string(), string("")),
+            ("DevkitOverrideAppID".to_string(), BinaryValue::Int32(0)),
+            ("LastPlayTime".to_string(), BinaryValue::Int32(0)),
+            ("FlatpakAppID".to_string(), string("")),
+            ("tags".to_string(), BinaryValue::Map(Vec::new())),
+        ];
+        Self { fields }
+    }
+
+    fn get(&self, key: &str) -> Option<&BinaryValue> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    fn set(&mut self, key: &str, value: BinaryValue) {
+        match self.fields.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key.to_string(), value)),
+        }
+    }
+
+    /// The shortcut's app ID (as used for compat tool mappings and grid art)
+    pub fn app_id(&self) -> u32 {
+        self.get("appid")
+            .and_then(BinaryValue::as_u32)
+            .unwrap_or_else(|| shortcut_app_id(self.exe(), self.name()))
+    }
+
+    pub fn name(&self) -> &str {
+        self.get("AppName").and_then(BinaryValue::as_str).unwrap_or("")
+    }
+
+    /// Executable as stored by Steam (usually quoted)
+    pub fn exe(&self) -> &str {
+        self.get("Exe").and_then(BinaryValue::as_str).unwrap_or("")
+    }
+
+    pub fn start_dir(&self) -> &str {
+        self.get("StartDir").and_then(BinaryValue::as_str).unwrap_or("")
+    }
+
+    pub fn launch_options(&self) -> &str {
+        self.get("LaunchOptions").and_then(BinaryValue::as_str).unwrap_or("")
+    }
+
+    pub fn set_launch_options(&mut self, options: &str) {
+        self.set("LaunchOptions", BinaryValue::String(options.to_string()));
+    }
+}
+
+/// The contents of a user's shortcuts.vdf
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shortcuts {
+    entries: Vec<Shortcut>,
+    /// The file as read from disk (None if there was no file)
+    original: Option<Vec<u8>>,
+}
+
+impl Shortcuts {
+    /// Parse the binary shortcuts.vdf format
+    pub fn parse(data: &[u8]) -> Result<Self, AppError> {
+        Self::parse_entries(data).map_err(|e| match e {
+            AppError::VdfParse(msg) => AppError::ShortcutsParse(msg),
+            other => other,
+        })
+    }
+
+    fn parse_entries(data: &[u8]) -> Result<Self, AppError> {
+        let mut reader = Reader::new(data);
+
+        let root = match reader.byte()? {
+            TYPE_MAP => {
+                let key = reader.cstring()?;
+                if !key.eq_ignore_ascii_case("shortcuts") {
+                    return Err(AppError::ShortcutsParse(format!(
+                        "expected 'shortcuts' root, found '{}'",
+                        key
+                    )));
+                }
+                reader.map()?
+            }
+            other => {
+                return Err(AppError::ShortcutsParse(format!(
+                    "unexpected root type 0x{:02x}",
+                    other
+                )))
+            }
+        };
+
+        let entries = root
+            .into_iter()
+            .filter_map(|(index, value)| match value {
+                BinaryValue::Map(fields) => Some(Shortcut { fields }),
+                _ => {
+                    debug!("Ignoring non-map shortcut entry '{}'", index);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(Self { entries, original: None })
+    }
+
+    /// Serialize back to the binary shortcuts.vdf format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let root: Vec<(String, BinaryValue)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i.to_string(), BinaryValue::Map(s.fields.clone())))
+            .collect();
+
+        let mut out = vec![TYPE_MAP];
+        out.extend_from_slice(b"shortcuts\0");
+        write_map(&mut out, &root);
+        out.push(TYPE_MAP_END);
+        out
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Shortcut> {
+        self.entries.iter()
+    }
+
+    pub fn find_mut(&mut self, app_id: u32) -> Option<&mut Shortcut> {
+        self.entries.iter_mut().find(|s| s.app_id() == app_id)
+    }
+
+    /// Add a shortcut, replacing an existing one with the same app ID
+    pub fn add(&mut self, shortcut: Shortcut) {
+        match self.find_mut(shortcut.app_id()) {
+            Some(existing) => *existing = shortcut,
+            None => self.entries.push(shortcut),
+        }
+    }
+
+    /// Remove a shortcut by app ID, returning it if it existed
+    pub fn remove(&mut self, app_id: u32) -> Option<Shortcut> {
+        let index = self.entries.iter().position(|s| s.app_id() == app_id)?;
+        Some(self.entries.remove(index))
+    }
+}
+
+/// Compute the app ID Steam assigns to a non-Steam game
+///
+/// `exe` is the executable as stored in shortcuts.vdf (including quotes).
+pub fn shortcut_app_id(exe: &str, name: &str) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(exe.as_bytes());
+    hasher.update(name.as_bytes());
+    hasher.finalize() | 0x8000_0000
+}
+
+/// Quote a path the way Steam stores Exe and StartDir
+fn quote_path(path: &Path) -> String {
+    format!("\"{}\"", path.display())
+}
+
+/// Read shortcuts.vdf, returning an empty set if the file doesn't exist yet
+pub fn read_shortcuts<P: AsRef<Path>>(path: P) -> Result<Shortcuts, AppError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        debug!("No shortcuts.vdf at {}", path.display());
+        return Ok(Shortcuts::default());
+    }
+    let data = fs::read(path)?;
+    debug!("Read shortcuts.vdf ({} bytes)", data.len());
+    let mut shortcuts = Shortcuts::parse(&data)?;
+    shortcuts.original = Some(data);
+    Ok(shortcuts)
+}
+
+/// Write shortcuts.vdf back to disk
+///
+/// The file must not have changed since it was read (by Steam, or another
+/// invocation of ours); shortcuts that weren't read from a file can only create
+/// a new one. The file is replaced in one step while holding a lock on its
+/// directory, so an interrupted write can't lose the shortcuts already in it.
+pub fn write_shortcuts<P: AsRef<Path>>(path: P, shortcuts: &Shortcuts) -> Result<(), AppError> {
+    let path = path.as_ref();
+    let data = shortcuts.to_bytes();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let _lock = lock_dir(dir)?;
+    let on_disk = match fs::read(path) {
+        Ok(data) => Some(data),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    if on_disk != shortcuts.original {
+        return Err(AppError::ChangedOnDisk(path.display().to_string()));
+    }
+
+    debug!("Writing shortcuts.vdf ({} bytes)", data.len());
+    write_atomic(path, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::binary_vdf::{TYPE_INT32, TYPE_STRING, TYPE_UINT64};
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut data = vec![TYPE_MAP];
+        data.extend_from_slice(b"shortcuts\0");
+        data.push(TYPE_MAP);
+        data.extend_from_slice(b"0\0");
+        data.push(TYPE_INT32);
+        data.extend_from_slice(b"appid\0");
+        data.extend_from_slice(&0x8123_4567u32.to_le_bytes());
+        data.push(TYPE_STRING);
+        data.extend_from_slice(b"AppName\0My Game\0");
+        data.push(TYPE_STRING);
+        data.extend_from_slice(b"Exe\0\"/games/my game/run.sh\"\0");
+        data.push(TYPE_STRING);
+        data.extend_from_slice(b"LaunchOptions\0\0");
+        data.push(TYPE_UINT64);
+        data.extend_from_slice(b"SomethingNew\0");
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data.push(TYPE_MAP);
+        data.extend_from_slice(b"tags\0");
+        data.push(TYPE_STRING);
+        data.extend_from_slice(b"0\0favorite\0");
+        data.push(TYPE_MAP_END);
+        data.push(TYPE_MAP_END);
+        data.push(TYPE_MAP_END);
+        data.push(TYPE_MAP_END);
+        data
+    }
+
+    #[test]
+    fn test_parse_and_round_trip() {
+        let data = sample();
+        let shortcuts = Shortcuts::parse(&data).unwrap();
+        let shortcut = shortcuts.iter().next().unwrap();
+        assert_eq!(shortcut.app_id(), 0x8123_4567);
+        assert_eq!(shortcut.name(), "My Game");
+        assert_eq!(shortcut.exe(), "\"/games/my game/run.sh\"");
+        assert_eq!(shortcuts.to_bytes(), data);
+    }
+
+    #[test]
+    fn test_add_update_remove() {
+        let mut shortcuts = Shortcuts::parse(&sample()).unwrap();
+        let mut new = Shortcut::new("Other", Path::new("/opt/other/game"), Path::new("/opt/other"));
+        new.set_launch_options("steam-command-runner run -- %command%");
+        let id = new.app_id();
+        assert!(id & 0x8000_0000 != 0);
+        shortcuts.add(new);
+
+        let reparsed = Shortcuts::parse(&shortcuts.to_bytes()).unwrap();
+        assert_eq!(reparsed.iter().count(), 2);
+        let added = reparsed.iter().find(|s| s.app_id() == id).unwrap();
+        assert_eq!(added.start_dir(), "\"/opt/other\"");
+        assert_eq!(added.launch_options(), "steam-command-runner run -- %command%");
+
+        shortcuts.find_mut(0x8123_4567).unwrap().set_launch_options("-novid");
+        assert!(shortcuts.remove(id).is_some());
+        assert!(shortcuts.remove(id).is_none());
+        assert_eq!(shortcuts.iter().next().unwrap().launch_options(), "-novid");
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config/shortcuts.vdf");
+        let shortcuts = Shortcuts::parse(&sample()).unwrap();
+        write_shortcuts(&path, &shortcuts).unwrap();
+        assert_eq!(read_shortcuts(&path).unwrap().to_bytes(), sample());
+        // Only the file itself is left behind
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_changed_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shortcuts.vdf");
+        fs::write(&path, sample()).unwrap();
+        let mut shortcuts = read_shortcuts(&path).unwrap();
+        shortcuts.find_mut(0x8123_4567).unwrap().set_launch_options("-novid");
+
+        // Shortcuts that weren't read from the file can't replace it
+        let parsed = Shortcuts::parse(&sample()).unwrap();
+        assert!(matches!(write_shortcuts(&path, &parsed), Err(AppError::ChangedOnDisk(_))));
+
+        let mut changed = sample();
+        changed.pop();
+        fs::write(&path, &changed).unwrap();
+        assert!(matches!(write_shortcuts(&path, &shortcuts), Err(AppError::ChangedOnDisk(_))));
+        assert_eq!(fs::read(&path).unwrap(), changed);
+
+        fs::write(&path, sample()).unwrap();
+        write_shortcuts(&path, &shortcuts).unwrap();
+        assert_eq!(read_shortcuts(&path).unwrap().iter().next().unwrap().launch_options(), "-novid");
+    }
+
+    #[test]
+    fn test_non_utf8_strings_round_trip() {
+        let mut data = sample();
+        let at = data.windows(7).position(|w| w == b"My Game").unwrap();
+        data.splice(at..at + 7, b"Caf\xe9".iter().copied());
+        assert_eq!(Shortcuts::parse(&data).unwrap().to_bytes(), data);
+    }
+
+    #[test]
+    fn test_truncated_file_is_an_error() {
+        let data = sample();
+        assert!(Shortcuts::parse(&data[..data.len() - 10]).is_err());
+    }
+}
@@ -1,17 +1,37 @@
+use crate::config::configured_steam_root;
 use crate::error::AppError;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
-/// Get the Steam root directory
-pub fn get_steam_root() -> Option<PathBuf> {
-    let candidates = [
-        dirs::home_dir().map(|h| h.join(".steam/steam")),
-        dirs::home_dir().map(|h| h.join(".local/share/Steam")),
+/// Flatpak Steam's sandbox home, relative to the real home directory
+pub(crate) const FLATPAK_STEAM_HOME: &str = ".var/app/com.valvesoftware.Steam";
+
+/// Places Steam may be installed, most likely first
+///
+/// The `steam_root` config setting comes first. Inside the Flatpak sandbox HOME
+/// already points at the sandbox home, so the usual paths cover that case; the
+/// Flatpak paths are for commands run from the host.
+pub fn steam_root_candidates() -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    let flatpak = home.as_ref().map(|h| h.join(FLATPAK_STEAM_HOME));
+    [
+        configured_steam_root(),
+        home.as_ref().map(|h| h.join(".steam/steam")),
+        home.as_ref().map(|h| h.join(".local/share/Steam")),
         dirs::data_dir().map(|d| d.join("Steam")),
-    ];
+        flatpak.as_ref().map(|f| f.join(".steam/steam")),
+        flatpak.as_ref().map(|f| f.join(".local/share/Steam")),
+        flatpak.as_ref().map(|f| f.join("data/Steam")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
 
-    for candidate in candidates.into_iter().flatten() {
+/// Get the Steam root directory
+pub fn get_steam_root() -> Option<PathBuf> {
+    for candidate in steam_root_candidates() {
         if candidate.exists() {
             debug!("Found Steam root at: {}", candidate.display());
             return Some(candidate);
@@ -21,6 +41,15 @@ pub fn get_steam_root() -> Option<PathBuf> {
     None
 }
 
+/// Whether a Steam root belongs to the Flatpak build of Steam
+pub fn is_flatpak_steam(steam_root: &Path) -> bool {
+    let Some(flatpak) = dirs::home_dir().map(|h| h.join(FLATPAK_STEAM_HOME)) else {
+        return false;
+    };
+    steam_root.starts_with(&flatpak)
+        || fs::canonicalize(steam_root).is_ok_and(|root| root.starts_with(&flatpak))
+}
+
 /// Find all Steam user IDs in userdata directory
 pub fn find_user_ids() -> Result<Vec<u64>, AppError> {
     let steam_root = get_steam_root().ok_or_else(|| {
@@ -88,6 +117,24 @@ pub fn get_localconfig_path(user_id: u64) -> Result<PathBuf, AppError> {
     Ok(config_path)
 }
 
+/// Get the path to a user's shortcuts.vdf (which may not exist yet)
+pub fn get_shortcuts_path(user_id: u64) -> Result<PathBuf, AppError> {
+    let steam_root = get_steam_root().ok_or_else(|| {
+        AppError::SteamUserNotFound("Could not find Steam installation".to_string())
+    })?;
+
+    Ok(steam_root
+        .join("userdata")
+        .join(user_id.to_string())
+        .join("config")
+        .join("shortcuts.vdf"))
+}
+
+/// Get the directory holding a user's custom library artwork
+pub fn get_grid_dir(user_id: u64) -> Result<PathBuf, AppError> {
+    Ok(get_shortcuts_path(user_id)?.with_file_name("grid"))
+}
+
 /// Get the path to loginusers.vdf
 pub fn get_login_users_path() -> Result<PathBuf, AppError> {
     let steam_root = get_steam_root().ok_or_else(|| {
@@ -106,6 +153,34 @@ pub fn get_login_users_path() -> Result<PathBuf, AppError> {
     Ok(config_path)
 }
 
+/// SteamID64 of account ID 0 (individual accounts in the public universe)
+const STEAM_ID64_BASE: u64 = 76561197960265728;
+
+/// Account ID of the user loginusers.vdf marks as the most recent login
+pub fn parse_most_recent_user(content: &str) -> Option<u64> {
+    let root = super::vdf::parse(content).ok()?;
+    let steam_id64 = root
+        .get_object("users")?
+        .entries()
+        .filter(|(_, user)| {
+            user.as_object()
+                .and_then(|user| user.get_str("MostRecent"))
+                .is_some_and(|recent| recent == "1")
+        })
+        .find_map(|(steam_id64, _)| steam_id64.parse::<u64>().ok())?;
+    steam_id64.checked_sub(STEAM_ID64_BASE)
+}
+
+/// The user to act for when none is given: the only one, or else the most recent login
+pub fn default_user_id() -> Option<u64> {
+    let user_ids = find_user_ids().ok()?;
+    if let [user_id] = user_ids[..] {
+        return Some(user_id);
+    }
+    let content = fs::read_to_string(get_login_users_path().ok()?).ok()?;
+    parse_most_recent_user(&content).filter(|id| user_ids.contains(id))
+}
+
 /// Get a map of Account ID (32-bit) to Persona Name
 pub fn get_user_names() -> Result<std::collections::HashMap<u64, String>, AppError> {
     let path = get_login_users_path()?;
@@ -163,4 +238,32 @@ mod tests {
         // This test just checks that the function doesn't panic
         let _result = get_steam_root();
     }
+
+    #[test]
+    fn test_parse_most_recent_user() {
+        let content = r#"
+"users"
+{
+	"76561197960265729"
+	{
+		"AccountName"		"first"
+		"MostRecent"		"0"
+	}
+	"76561197960266728"
+	{
+		"AccountName"		"second"
+		"MostRecent"		"1"
+	}
+}
+"#;
+        assert_eq!(parse_most_recent_user(content), Some(1000));
+        assert_eq!(parse_most_recent_user(&content.replace("\"1\"", "\"0\"")), None);
+    }
+
+    #[test]
+    fn test_is_flatpak_steam() {
+        let home = dirs::home_dir().unwrap();
+        assert!(is_flatpak_steam(&home.join(FLATPAK_STEAM_HOME).join(".local/share/Steam")));
+        assert!(!is_flatpak_steam(Path::new("/nonexistent/.local/share/Steam")));
+    }
 }
@@ -0,0 +1,489 @@
+//! Text VDF (Valve KeyValues) parsing and serialization
+//!
+//! Documents are parsed into an ordered tree, so writing one back keeps every key,
+//! including ones we don't understand, in its original order. Output uses Steam's
+//! own layout (tab indentation, braces on their own lines), which means files
+//! written by Steam round-trip unchanged. A string whose escaping we wouldn't
+//! reproduce (such as `C:\games`, where `\g` is no escape) is written back as
+//! it was read, as long as its value wasn't changed.
+
+use crate::error::AppError;
+
+/// A value in a VDF document
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdfValue {
+    String(String),
+    Object(VdfObject),
+}
+
+impl VdfValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(s) => Some(s),
+            VdfValue::Object(_) => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&VdfObject> {
+        match self {
+            VdfValue::Object(o) => Some(o),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut VdfObject> {
+        match self {
+            VdfValue::Object(o) => Some(o),
+            VdfValue::String(_) => None,
+        }
+    }
+}
+
+/// An ordered set of key/value entries
+///
+/// Keys are matched case-insensitively, as Steam does. Duplicate keys are kept.
+/// Objects are equal when their keys and values are, however they were written.
+#[derive(Debug, Clone, Default)]
+pub struct VdfObject {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    value: VdfValue,
+    /// A string value's text as read, when escaping the value wouldn't give it back
+    raw: Option<String>,
+}
+
+impl Entry {
+    fn new(key: &str, value: VdfValue) -> Self {
+        Self {
+            key: key.to_string(),
+            value,
+            raw: None,
+        }
+    }
+}
+
+impl PartialEq for VdfObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries.len() == other.entries.len()
+            && self
+                .entries
+                .iter()
+                .zip(&other.entries)
+                .all(|(a, b)| a.key == b.key && a.value == b.value)
+    }
+}
+
+impl VdfObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &VdfValue)> {
+        self.entries.iter().map(|e| (e.key.as_str(), &e.value))
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = (&str, &mut VdfValue)> {
+        self.entries.iter_mut().map(|e| (e.key.as_str(), &mut e.value))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        self.entries
+            .iter()
+            .find(|e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| &e.value)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut VdfValue> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| &mut e.value)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(VdfValue::as_str)
+    }
+
+    pub fn get_object(&self, key: &str) -> Option<&VdfObject> {
+        self.get(key).and_then(VdfValue::as_object)
+    }
+
+    pub fn get_object_mut(&mut self, key: &str) -> Option<&mut VdfObject> {
+        self.get_mut(key).and_then(VdfValue::as_object_mut)
+    }
+
+    /// Follow a path of nested object keys
+    pub fn get_path(&self, path: &[&str]) -> Option<&VdfObject> {
+        path.iter().try_fold(self, |obj, key| obj.get_object(key))
+    }
+
+    /// Follow a path of nested object keys mutably
+    pub fn get_path_mut(&mut self, path: &[&str]) -> Option<&mut VdfObject> {
+        path.iter().try_fold(self, |obj, key| obj.get_object_mut(key))
+    }
+
+    /// Set a key's value, replacing the first existing entry or appending a new one
+    pub fn set(&mut self, key: &str, value: VdfValue) {
+        match self.get_mut(key) {
+            Some(existing) => *existing = value,
+            None => self.entries.push(Entry::new(key, value)),
+        }
+    }
+
+    /// Set a string value
+    pub fn set_str(&mut self, key: &str, value: &str) {
+        self.set(key, VdfValue::String(value.to_string()));
+    }
+
+    /// Get a nested object, creating it (or replacing a string value) if needed
+    pub fn object_entry(&mut self, key: &str) -> &mut VdfObject {
+        if self.get_object(key).is_none() {
+            self.set(key, VdfValue::Object(VdfObject::new()));
+        }
+        self.get_object_mut(key).expect("object was just inserted")
+    }
+
+    /// Remove every entry with this key, returning the first removed value
+    pub fn remove(&mut self, key: &str) -> Option<VdfValue> {
+        let mut removed = None;
+        self.entries.retain_mut(|entry| {
+            if entry.key.eq_ignore_ascii_case(key) {
+                if removed.is_none() {
+                    removed = Some(std::mem::replace(&mut entry.value, VdfValue::String(String::new())));
+                }
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Find the first object with this key anywhere in the tree (depth-first)
+    pub fn find_object(&self, key: &str) -> Option<&VdfObject> {
+        for Entry { key: k, value, .. } in &self.entries {
+            if let VdfValue::Object(obj) = value {
+                if k.eq_ignore_ascii_case(key) {
+                    return Some(obj);
+                }
+                if let Some(found) = obj.find_object(key) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the first object with this key anywhere in the tree, mutably
+    pub fn find_object_mut(&mut self, key: &str) -> Option<&mut VdfObject> {
+        // Locate first, then borrow mutably, to keep the borrow checker happy
+        let index = self.entries.iter().position(|e| match &e.value {
+            VdfValue::Object(obj) => e.key.eq_ignore_ascii_case(key) || obj.find_object(key).is_some(),
+            VdfValue::String(_) => false,
+        })?;
+        let entry = &mut self.entries[index];
+        let obj = entry.value.as_object_mut()?;
+        if entry.key.eq_ignore_ascii_case(key) {
+            Some(obj)
+        } else {
+            obj.find_object_mut(key)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// A string and, when escaping it wouldn't give it back, its text as written
+    Str(String, Option<String>),
+    Open,
+    Close,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+            line: 1,
+        }
+    }
+
+    fn error(&self, message: &str) -> AppError {
+        AppError::VdfParse(format!("line {}: {}", self.line, message))
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, AppError> {
+        loop {
+            let Some(&(_, c)) = self.chars.peek() else {
+                return Ok(None);
+            };
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.chars.next();
+                }
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '/' => {
+                    self.chars.next();
+                    if self.chars.peek().map(|&(_, c)| c) != Some('/') {
+                        return Err(self.error("unexpected '/'"));
+                    }
+                    while let Some(&(_, c)) = self.chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.chars.next();
+                    }
+                }
+                '[' => {
+                    // Platform conditionals like [$WIN32] are ignored
+                    for (_, c) in self.chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                '{' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Open));
+                }
+                '}' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Close));
+                }
+                '"' => {
+                    self.chars.next();
+                    return self.quoted().map(Some);
+                }
+                _ => return Ok(Some(string_token(self.unquoted()))),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<Token, AppError> {
+        let start = match self.chars.peek() {
+            Some(&(i, _)) => i,
+            None => self.input.len(),
+        };
+        while let Some((i, c)) = self.chars.next() {
+            match c {
+                '"' => return Ok(string_token(self.input[start..i].to_string())),
+                // Skip the escaped character, so \" doesn't end the string
+                '\\' if self.chars.next().is_none() => break,
+                '\n' => self.line += 1,
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn unquoted(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                break;
+            }
+            value.push(c);
+            self.chars.next();
+        }
+        value
+    }
+}
+
+/// A string token from its text as written, keeping the text only when needed
+fn string_token(raw: String) -> Token {
+    let value = unescape(&raw);
+    let raw = (escape_vdf_string(&value) != raw).then_some(raw);
+    Token::Str(value, raw)
+}
+
+/// The value of a string's text: `\n`, `\t`, `\\` and `\"` are escapes, and a
+/// backslash before anything else is kept as it is
+fn unescape(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(c @ ('\\' | '"')) => value.push(c),
+            Some(other) => {
+                value.push('\\');
+                value.push(other);
+            }
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// Parse a text VDF document into its top-level entries
+pub fn parse(input: &str) -> Result<VdfObject, AppError> {
+    let mut lexer = Lexer::new(input);
+    let root = parse_object(&mut lexer, false)?;
+    Ok(root)
+}
+
+fn parse_object(lexer: &mut Lexer, nested: bool) -> Result<VdfObject, AppError> {
+    let mut object = VdfObject::new();
+    loop {
+        let key = match lexer.next_token()? {
+            Some(Token::Str(key, _)) => key,
+            Some(Token::Close) if nested => return Ok(object),
+            Some(Token::Close) => return Err(lexer.error("unexpected '}'")),
+            Some(Token::Open) => return Err(lexer.error("expected a key, found '{'")),
+            None if nested => return Err(lexer.error("unexpected end of file, missing '}'")),
+            None => return Ok(object),
+        };
+        let (value, raw) = match lexer.next_token()? {
+            Some(Token::Str(value, raw)) => (VdfValue::String(value), raw),
+            Some(Token::Open) => (VdfValue::Object(parse_object(lexer, true)?), None),
+            Some(Token::Close) | None => {
+                return Err(lexer.error(&format!("missing value for key '{}'", key)))
+            }
+        };
+        object.entries.push(Entry { key, value, raw });
+    }
+}
+
+/// Serialize entries in Steam's layout
+pub fn to_string(root: &VdfObject) -> String {
+    let mut out = String::new();
+    write_object(&mut out, root, 0);
+    out
+}
+
+fn write_object(out: &mut String, object: &VdfObject, depth: usize) {
+    let indent = "\t".repeat(depth);
+    for Entry { key, value, raw } in &object.entries {
+        match value {
+            VdfValue::String(s) => {
+                // The text as read, unless the value has been changed since
+                let text = match raw {
+                    Some(raw) if unescape(raw) == *s => raw.clone(),
+                    _ => escape_vdf_string(s),
+                };
+                out.push_str(&format!("{}\"{}\"\t\t\"{}\"\n", indent, escape_vdf_string(key), text));
+            }
+            VdfValue::Object(inner) => {
+                out.push_str(&format!("{}\"{}\"\n{}{{\n", indent, escape_vdf_string(key), indent));
+                write_object(out, inner, depth + 1);
+                out.push_str(&format!("{}}}\n", indent));
+            }
+        }
+    }
+}
+
+/// Escape a string for VDF format
+pub fn escape_vdf_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t\t\"1850570\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LastPlayed\"\t\t\"1700000000\"\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"mangohud %command%\"\n\t\t\t\t\t}\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        let root = parse(SAMPLE).unwrap();
+        assert_eq!(to_string(&root), SAMPLE);
+    }
+
+    #[test]
+    fn test_get_path_and_set() {
+        let mut root = parse(SAMPLE).unwrap();
+        let apps = root
+            .get_path(&["UserLocalConfigStore", "software", "valve", "steam", "apps"])
+            .unwrap();
+        assert_eq!(
+            apps.get_object("1850570").unwrap().get_str("launchoptions"),
+            Some("mangohud %command%")
+        );
+
+        root.find_object_mut("apps")
+            .unwrap()
+            .object_entry("10")
+            .set_str("LaunchOptions", "say \"hi\" C:\\games");
+        let reparsed = parse(&to_string(&root)).unwrap();
+        let apps = reparsed.find_object("apps").unwrap();
+        assert_eq!(
+            apps.get_object("10").unwrap().get_str("LaunchOptions"),
+            Some("say \"hi\" C:\\games")
+        );
+    }
+
+    #[test]
+    fn test_unusual_formatting() {
+        let input = "// comment\n\"root\" { \"a\" \"1\" \"b\" { \"c\" \"x\\\"y\" } unquoted value \"cond\" \"1\" [$WIN32] }";
+        let root = parse(input).unwrap();
+        let obj = root.get_object("root").unwrap();
+        assert_eq!(obj.get_str("a"), Some("1"));
+        assert_eq!(obj.get_object("b").unwrap().get_str("c"), Some("x\"y"));
+        assert_eq!(obj.get_str("unquoted"), Some("value"));
+        assert_eq!(obj.get_str("cond"), Some("1"));
+    }
+
+    #[test]
+    fn test_remove_and_errors() {
+        let mut root = parse("\"a\" \"1\"\n\"b\" \"2\"\n\"A\" \"3\"\n").unwrap();
+        assert_eq!(root.remove("a"), Some(VdfValue::String("1".to_string())));
+        assert_eq!(to_string(&root), "\"b\"\t\t\"2\"\n");
+
+        assert!(parse("\"a\" {").is_err());
+        assert!(parse("\"a\"").is_err());
+        assert!(parse("}").is_err());
+        assert!(parse("\"a\" \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_escape_vdf_string() {
+        assert_eq!(escape_vdf_string("test"), "test");
+        assert_eq!(escape_vdf_string("test\"quote"), "test\\\"quote");
+        assert_eq!(escape_vdf_string("test\\slash"), "test\\\\slash");
+        assert_eq!(escape_vdf_string("two\nlines\tand a tab"), "two\\nlines\\tand a tab");
+    }
+
+    #[test]
+    fn test_escapes_round_trip_byte_identical() {
+        let input = "\"root\"\n{\n\t\"Notes\"\t\t\"line one\\nline two\\tindented\"\n\t\"Path\"\t\t\"C:\\games\\steam\"\n\t\"Escaped\"\t\t\"D:\\\\SteamLibrary\"\n}\n";
+        let root = parse(input).unwrap();
+        let obj = root.get_object("root").unwrap();
+        assert_eq!(obj.get_str("Notes"), Some("line one\nline two\tindented"));
+        assert_eq!(obj.get_str("Path"), Some("C:\\games\\steam"));
+        assert_eq!(obj.get_str("Escaped"), Some("D:\\SteamLibrary"));
+        assert_eq!(to_string(&root), input);
+
+        // A changed value is written escaped, without the old text
+        let mut root = root;
+        root.get_path_mut(&["root"]).unwrap().set_str("Path", "C:\\other");
+        assert!(to_string(&root).contains("\"Path\"\t\t\"C:\\\\other\""));
+    }
+}
@@ -0,0 +1,54 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Set by --refresh: ask the web APIs again instead of trusting cached answers
+static REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// Make store searches and ProtonDB lookups ignore cached answers that are still fresh
+pub fn refresh_web_caches() {
+    REFRESH.store(true, Ordering::Relaxed);
+}
+
+/// Whether a cached answer fetched at `fetched` can be used without asking again
+pub(super) fn is_fresh(fetched: u64, ttl: u64, now: u64) -> bool {
+    !REFRESH.load(Ordering::Relaxed) && now.saturating_sub(fetched) <= ttl
+}
+
+pub(super) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// File a web API's answers are cached in
+fn cache_path(name: &str) -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from(".cache"))
+        .join("steam-command-runner")
+        .join(name)
+}
+
+/// Read a cache file, or the default when it's missing or unreadable
+pub(super) fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    fs::read_to_string(cache_path(name))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Write a cache file; failing to is only logged, as the cache is an optimisation
+pub(super) fn save<T: Serialize>(name: &str, value: &T) {
+    let path = cache_path(name);
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(value).unwrap_or_default()));
+    if let Err(e) = result {
+        debug!("Could not write {}: {}", path.display(), e);
+    }
+}
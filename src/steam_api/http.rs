@@ -0,0 +1,43 @@
+use crate::error::AppError;
+use reqwest::blocking::{Client, Response};
+use std::thread;
+use std::time::Duration;
+use tracing::debug;
+
+/// Attempts made for a request before giving up
+const ATTEMPTS: u32 = 3;
+
+/// Client for the web APIs, with timeouts so a flaky connection can't stall a command
+pub(super) fn client() -> Result<Client, AppError> {
+    Ok(Client::builder()
+        .user_agent(concat!("steam-command-runner/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(10))
+        .build()?)
+}
+
+/// GET a URL, retrying with backoff on timeouts, connection errors and server errors
+///
+/// Other error statuses (404 and the like) are returned as they are for the caller
+/// to interpret.
+pub(super) fn get(client: &Client, url: &str) -> Result<Response, reqwest::Error> {
+    let mut attempt = 1;
+    loop {
+        debug!("Fetching: {}", url);
+        let result = client.get(url).send();
+        let retry = match &result {
+            Ok(response) => {
+                let status = response.status();
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => e.is_timeout() || e.is_connect(),
+        };
+        if !retry || attempt == ATTEMPTS {
+            return result;
+        }
+        let delay = Duration::from_millis(500 << (attempt - 1));
+        debug!("Attempt {} of {} failed, retrying in {:?}", attempt, url, delay);
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
@@ -1,3 +1,10 @@
+mod cache;
+mod http;
+mod protondb;
 mod search;
+mod steamgriddb;
 
+pub use cache::refresh_web_caches;
+pub use protondb::protondb_tiers;
 pub use search::search_games;
+pub use steamgriddb::{download_artwork, ArtKind};
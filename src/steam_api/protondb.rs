@@ -0,0 +1,133 @@
+use super::{cache, http};
+use crate::error::AppError;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+const API: &str = "https://www.protondb.com/api/v1/reports/summaries";
+
+/// How long a looked-up rating is trusted before asking ProtonDB again
+const CACHE_TTL: u64 = 7 * 24 * 60 * 60;
+
+const CACHE_FILE: &str = "protondb.json";
+
+#[derive(Deserialize)]
+struct Summary {
+    tier: String,
+}
+
+/// A rating as looked up at a given time; `tier` is None for games without reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedTier {
+    fetched: u64,
+    tier: Option<String>,
+}
+
+/// Look up the ProtonDB tier (platinum, gold, silver, bronze, borked, ...) of each game
+///
+/// Ratings are cached for a week under $XDG_CACHE_HOME (see `refresh_web_caches`). Games without reports are
+/// left out of the result. If ProtonDB can't be reached, the remaining games are
+/// answered from the cache only, however old.
+pub fn protondb_tiers(app_ids: &[u32]) -> HashMap<u32, String> {
+    let mut cache: HashMap<u32, CachedTier> = cache::load(CACHE_FILE);
+    let now = cache::now();
+    let stale = stale_ids(&cache, app_ids, now);
+    if !stale.is_empty() {
+        if let Err(e) = fetch_tiers(&stale, &mut cache, now) {
+            warn!(
+                "Could not reach ProtonDB ({}); showing cached ratings only",
+                e
+            );
+        }
+        cache::save(CACHE_FILE, &cache);
+    }
+
+    app_ids
+        .iter()
+        .filter_map(|app_id| Some((*app_id, cache.get(app_id)?.tier.clone()?)))
+        .collect()
+}
+
+/// Games whose rating isn't cached, or was looked up too long ago
+fn stale_ids(cache: &HashMap<u32, CachedTier>, app_ids: &[u32], now: u64) -> Vec<u32> {
+    app_ids
+        .iter()
+        .copied()
+        .filter(|app_id| {
+            cache
+                .get(app_id)
+                .is_none_or(|entry| !cache::is_fresh(entry.fetched, CACHE_TTL, now))
+        })
+        .collect()
+}
+
+/// Fetch ratings into the cache, stopping at the first network error
+fn fetch_tiers(
+    app_ids: &[u32],
+    cache: &mut HashMap<u32, CachedTier>,
+    now: u64,
+) -> Result<(), AppError> {
+    let client = http::client()?;
+
+    for &app_id in app_ids {
+        let tier = fetch_tier(&client, app_id)?;
+        debug!(
+            "ProtonDB tier of {}: {}",
+            app_id,
+            tier.as_deref().unwrap_or("(no reports)")
+        );
+        cache.insert(app_id, CachedTier { fetched: now, tier });
+    }
+    Ok(())
+}
+
+/// Fetch one game's tier; ProtonDB answers 404 for games nobody has reported on
+fn fetch_tier(client: &Client, app_id: u32) -> Result<Option<String>, AppError> {
+    let url = format!("{}/{}.json", API, app_id);
+    let response = http::get(client, &url)?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let summary: Summary = response.error_for_status()?.json()?;
+    Ok(Some(summary.tier).filter(|tier| !tier.is_empty() && tier != "pending"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_ids() {
+        let now = 10 * CACHE_TTL;
+        let cache = HashMap::from([
+            (
+                440,
+                CachedTier {
+                    fetched: now - 60,
+                    tier: Some("gold".to_string()),
+                },
+            ),
+            (
+                570,
+                CachedTier {
+                    fetched: now - CACHE_TTL - 1,
+                    tier: Some("platinum".to_string()),
+                },
+            ),
+            (
+                620,
+                CachedTier {
+                    fetched: now - 60,
+                    tier: None,
+                },
+            ),
+        ]);
+        // Unrated games count as looked up; only expired and unknown ones are fetched
+        assert_eq!(
+            stale_ids(&cache, &[440, 570, 620, 730], now),
+            vec![570, 730]
+        );
+    }
+}
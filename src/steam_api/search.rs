@@ -1,41 +1,90 @@
+use super::{cache, http};
 use crate::error::AppError;
-use serde::Deserialize;
-use tracing::{debug, info};
+use crate::playtime::format_time;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+const CACHE_FILE: &str = "store-search.json";
+
+/// How long store search results are reused before searching again
+const CACHE_TTL: u64 = 24 * 60 * 60;
+
+/// Results older than this are dropped from the cache rather than kept for offline use
+const CACHE_MAX_AGE: u64 = 30 * 24 * 60 * 60;
+
+/// A store search's results as fetched at a given time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSearch {
+    fetched: u64,
+    results: Vec<(u32, String)>,
+}
 
 /// Search for games by name and return matching App IDs
+///
+/// Results are cached for a day under $XDG_CACHE_HOME, keyed by the lowercased
+/// query. When the store can't be reached, earlier results for the same query are
+/// used however old they are.
 pub fn search_games(query: &str, limit: usize) -> Result<Vec<(u32, String)>, AppError> {
     info!("Searching Steam store for: {}", query);
 
-    let results = search_steam_store(query, limit)?;
+    let key = query.trim().to_lowercase();
+    let mut cache: HashMap<String, CachedSearch> = cache::load(CACHE_FILE);
+    let now = cache::now();
 
+    let results = match cache.get(&key).cloned() {
+        Some(cached) if cache::is_fresh(cached.fetched, CACHE_TTL, now) => {
+            debug!("Using cached store results for '{}'", key);
+            cached.results
+        }
+        cached => match search_steam_store(query) {
+            Ok(results) => {
+                cache.retain(|_, entry| now.saturating_sub(entry.fetched) <= CACHE_MAX_AGE);
+                cache.insert(
+                    key,
+                    CachedSearch {
+                        fetched: now,
+                        results: results.clone(),
+                    },
+                );
+                cache::save(CACHE_FILE, &cache);
+                results
+            }
+            Err(e) => match cached {
+                Some(cached) => {
+                    warn!(
+                        "Steam store search failed ({}); using results from {}",
+                        e,
+                        format_time(cached.fetched)
+                    );
+                    cached.results
+                }
+                None => return Err(e),
+            },
+        },
+    };
+
+    let results: Vec<_> = results.into_iter().take(limit).collect();
+    info!("Found {} results", results.len());
     Ok(results)
 }
 
 /// Search the Steam store for games
-fn search_steam_store(query: &str, limit: usize) -> Result<Vec<(u32, String)>, AppError> {
+fn search_steam_store(query: &str) -> Result<Vec<(u32, String)>, AppError> {
     // Use Steam's storefront search API
     let url = format!(
         "https://store.steampowered.com/api/storesearch/?term={}&l=english&cc=US",
         urlencoding::encode(query)
     );
 
-    debug!("Fetching: {}", url);
-
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("steam-command-runner/0.2.0")
-        .build()?;
-
-    let response: StoreSearchResponse = client.get(&url).send()?.json()?;
+    let client = http::client()?;
+    let response: StoreSearchResponse = http::get(&client, &url)?.error_for_status()?.json()?;
 
-    let results: Vec<_> = response
+    Ok(response
         .items
         .into_iter()
-        .take(limit)
         .map(|item| (item.id, item.name))
-        .collect();
-
-    info!("Found {} results", results.len());
-    Ok(results)
+        .collect())
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,188 @@
+use crate::error::AppError;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+const API: &str = "https://www.steamgriddb.com/api/v2";
+
+/// Artwork Steam shows for a library entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtKind {
+    /// Portrait capsule in the library grid (600x900)
+    Grid,
+    /// Landscape capsule, used for recent games and Big Picture (920x430)
+    WideGrid,
+    /// Banner behind the game's library page
+    Hero,
+    /// Logo drawn over the hero
+    Logo,
+}
+
+impl ArtKind {
+    pub const ALL: [ArtKind; 4] = [ArtKind::Grid, ArtKind::WideGrid, ArtKind::Hero, ArtKind::Logo];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ArtKind::Grid => "grid",
+            ArtKind::WideGrid => "wide grid",
+            ArtKind::Hero => "hero",
+            ArtKind::Logo => "logo",
+        }
+    }
+
+    /// API path and query listing this kind of artwork for a SteamGridDB game
+    fn endpoint(self, game_id: u64) -> String {
+        match self {
+            ArtKind::Grid => format!("grids/game/{}?dimensions=600x900&mimes=image/png,image/jpeg", game_id),
+            ArtKind::WideGrid => format!("grids/game/{}?dimensions=920x430,460x215&mimes=image/png,image/jpeg", game_id),
+            ArtKind::Hero => format!("heroes/game/{}?mimes=image/png,image/jpeg", game_id),
+            ArtKind::Logo => format!("logos/game/{}?mimes=image/png", game_id),
+        }
+    }
+
+    /// File name Steam looks for in the grid directory, without extension
+    fn file_stem(self, app_id: u32) -> String {
+        match self {
+            ArtKind::Grid => format!("{}p", app_id),
+            ArtKind::WideGrid => app_id.to_string(),
+            ArtKind::Hero => format!("{}_hero", app_id),
+            ArtKind::Logo => format!("{}_logo", app_id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    #[serde(default)]
+    success: bool,
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Game {
+    id: u64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Image {
+    url: String,
+}
+
+/// Fetch artwork for a game by name and save it as a library entry's art
+///
+/// The best-rated image of each kind is used; kinds SteamGridDB has nothing for
+/// are skipped. Returns the files written.
+pub fn download_artwork(
+    api_key: &str,
+    name: &str,
+    app_id: u32,
+    grid_dir: &Path,
+) -> Result<Vec<(ArtKind, PathBuf)>, AppError> {
+    let client = Client::builder()
+        .user_agent(concat!("steam-command-runner/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let games: Vec<Game> = get(&client, api_key, &format!("search/autocomplete/{}", encode(name)))?;
+    let game = games
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::SteamGridDb(format!("no game called '{}'", name)))?;
+    info!("Using SteamGridDB game {} ({})", game.name, game.id);
+
+    fs::create_dir_all(grid_dir)?;
+    let mut written = Vec::new();
+    for kind in ArtKind::ALL {
+        let images: Vec<Image> = match get(&client, api_key, &kind.endpoint(game.id)) {
+            Ok(images) => images,
+            Err(e) => {
+                warn!("Could not list {} artwork: {}", kind.label(), e);
+                continue;
+            }
+        };
+        let Some(image) = images.into_iter().next() else {
+            debug!("No {} artwork for {}", kind.label(), game.name);
+            continue;
+        };
+        match save_image(&client, &image.url, &kind.file_stem(app_id), grid_dir) {
+            Ok(path) => written.push((kind, path)),
+            Err(e) => warn!("Could not download {} artwork: {}", kind.label(), e),
+        }
+    }
+    Ok(written)
+}
+
+/// Call an API endpoint returning a list
+fn get<T: DeserializeOwned>(client: &Client, api_key: &str, path: &str) -> Result<Vec<T>, AppError> {
+    let url = format!("{}/{}", API, path);
+    debug!("Fetching: {}", url);
+    let response = client.get(&url).bearer_auth(api_key).send()?;
+    match response.status() {
+        StatusCode::UNAUTHORIZED => return Err(AppError::SteamGridDb("API key rejected".to_string())),
+        StatusCode::NOT_FOUND => return Ok(Vec::new()),
+        _ => {}
+    }
+    let response: Response<T> = response.error_for_status()?.json()?;
+    if !response.success {
+        return Err(AppError::SteamGridDb(response.errors.join("; ")));
+    }
+    Ok(response.data)
+}
+
+/// Download an image into the grid directory as `<stem>.<ext>`, replacing older art of the same kind
+fn save_image(client: &Client, url: &str, stem: &str, grid_dir: &Path) -> Result<PathBuf, AppError> {
+    let bytes = client.get(url).send()?.error_for_status()?.bytes()?;
+    // Steam picks up any of these, so an old file with another extension would shadow the new one
+    for ext in ["png", "jpg", "jpeg"] {
+        let _ = fs::remove_file(grid_dir.join(format!("{}.{}", stem, ext)));
+    }
+    let path = grid_dir.join(format!("{}.{}", stem, image_extension(url)));
+    fs::write(&path, &bytes)?;
+    Ok(path)
+}
+
+/// File extension for an image URL, defaulting to png
+fn image_extension(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        "jpg"
+    } else {
+        "png"
+    }
+}
+
+/// Percent-encode a search term for use as a URL path segment
+fn encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artwork_file_names() {
+        let app_id = 3_000_000_001;
+        assert_eq!(ArtKind::Grid.file_stem(app_id), "3000000001p");
+        assert_eq!(ArtKind::WideGrid.file_stem(app_id), "3000000001");
+        assert_eq!(ArtKind::Hero.file_stem(app_id), "3000000001_hero");
+        assert_eq!(ArtKind::Logo.file_stem(app_id), "3000000001_logo");
+
+        assert_eq!(image_extension("https://cdn2.steamgriddb.com/grid/abc.JPG"), "jpg");
+        assert_eq!(image_extension("https://cdn2.steamgriddb.com/logo/abc.png?v=2"), "png");
+        assert_eq!(encode("Baldur's Gate 3"), "Baldur%27s%20Gate%203");
+    }
+}